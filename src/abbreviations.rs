@@ -0,0 +1,49 @@
+//! Common abbreviations whose periods don't end a sentence.
+//!
+//! While typing, a period that continues a known abbreviation ("e." on the
+//! way to "e.g.") is kept in the word instead of ending it, so "e.g." is one
+//! token rather than the words "e" and "g". When the token is finished, a
+//! known abbreviation doesn't start a new sentence: the "Smith" in
+//! "Dr. Smith" is still mid-sentence.
+//!
+//! Abbreviations that are also common sentence-final words ("no.", "in.")
+//! are deliberately left out.
+
+/// Built-in abbreviations, lowercase, each ending in a period.
+const ABBREVIATIONS: &[&str] = &[
+    "a.m.", "p.m.", "e.g.", "i.e.", "etc.", "vs.", "cf.", "approx.", "dept.", "est.", "fig.",
+    "dr.", "mr.", "mrs.", "ms.", "prof.", "jr.", "sr.",
+];
+
+/// Whether a finished token is a known abbreviation.
+pub fn is_abbreviation(token: &str) -> bool {
+    ABBREVIATIONS
+        .iter()
+        .any(|abbreviation| abbreviation.eq_ignore_ascii_case(token))
+}
+
+/// Whether `partial` (typed so far, ending in a period) is the start of a
+/// known abbreviation, or a whole one.
+pub fn continues_abbreviation(partial: &str) -> bool {
+    let partial = partial.to_ascii_lowercase();
+    ABBREVIATIONS
+        .iter()
+        .any(|abbreviation| abbreviation.starts_with(&partial))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abbreviations() {
+        assert!(continues_abbreviation("e."));
+        assert!(continues_abbreviation("E.g."));
+        assert!(continues_abbreviation("Dr."));
+        assert!(!continues_abbreviation("teh."));
+
+        assert!(is_abbreviation("etc."));
+        assert!(is_abbreviation("Mrs."));
+        assert!(!is_abbreviation("e."));
+    }
+}
@@ -0,0 +1,81 @@
+//! Caret anchoring: a correction is only typed where its word was typed.
+//!
+//! The caret is recorded when a word's first letter is typed and checked
+//! again once the word ends, right before the correction is injected. If
+//! focus moved to another window, or the caret jumped to another line or
+//! back before the word (the user clicked elsewhere while typing it, or the
+//! app moved the selection), the correction is dropped rather than typed
+//! into the wrong place. So is a word that wrapped onto the next line.
+//!
+//! The caret moves forward along the line in between, as the word is typed
+//! and as a busy app catches up with the last keystrokes.
+//! Apps that draw their own caret (browsers, Electron) don't report it, so
+//! corrections there are not anchored.
+
+use crate::ui::positioning::ScreenRect;
+
+/// The foreground window's caret, if the app reports one.
+pub fn snapshot() -> Option<ScreenRect> {
+    #[cfg(windows)]
+    {
+        crate::ui::positioning::system_caret_rect()
+    }
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+/// Whether the caret is still on the line it was on at `anchor`, and not
+/// before it, as far as can be told.
+pub fn caret_stayed(anchor: Option<ScreenRect>, now: Option<ScreenRect>) -> bool {
+    match (anchor, now) {
+        // The app doesn't report its caret
+        (None, _) => true,
+        // Focus moved to a window without a caret
+        (Some(_), None) => false,
+        (Some(anchor), Some(now)) => now.top == anchor.top && now.left >= anchor.left,
+    }
+}
+
+/// Whether the caret is still on the line it was on at `anchor`, as far as
+/// can be told. Used before undoing a correction, which may itself have
+/// moved the caret back along the line.
+pub fn same_line(anchor: Option<ScreenRect>, now: Option<ScreenRect>) -> bool {
+    match (anchor, now) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(anchor), Some(now)) => now.top == anchor.top,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn caret(left: i32, top: i32) -> Option<ScreenRect> {
+        Some(ScreenRect {
+            left,
+            top,
+            right: left + 1,
+            bottom: top + 16,
+        })
+    }
+
+    #[test]
+    fn test_caret_stayed() {
+        assert!(caret_stayed(None, None));
+        assert!(caret_stayed(caret(100, 40), caret(100, 40)));
+        // The app caught up with a keystroke
+        assert!(caret_stayed(caret(100, 40), caret(108, 40)));
+
+        // Clicked earlier on the line, on another line, or in another window
+        assert!(!caret_stayed(caret(100, 40), caret(20, 40)));
+        assert!(!caret_stayed(caret(100, 40), caret(100, 80)));
+        assert!(!caret_stayed(caret(100, 40), None));
+
+        // A shorter correction moves the caret back
+        assert!(same_line(caret(100, 40), caret(92, 40)));
+        assert!(!same_line(caret(100, 40), caret(100, 80)));
+    }
+}
@@ -0,0 +1,100 @@
+//! Pausing corrections during bursts of keystrokes faster than prose.
+//!
+//! Nobody types sentences at 20 keys a second for long, but games, key
+//! mashing and macro playback do. Letters arriving that fast aren't words
+//! being written, and correcting them types into a game or a recorded
+//! sequence. Once more than `burst_max_keys` keys are pressed within
+//! `burst_window_ms`, corrections pause. They resume once the rate has
+//! dropped to half the limit, a rhythm that looks like typing again, so a
+//! burst that hovers around the limit doesn't flip the pause on and off.
+//!
+//! Keys held down and repeating are not counted: holding Backspace is
+//! editing, not a burst.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Default limit: 20 keys a second is about 240 words per minute.
+pub const DEFAULT_MAX_KEYS: u32 = 20;
+
+/// Default window the limit applies to.
+pub const DEFAULT_WINDOW_MS: u64 = 1000;
+
+/// Recent key presses, and whether they are a burst.
+#[derive(Debug)]
+pub struct BurstDetector {
+    /// Keys allowed within the window; 0 means no limit.
+    max_keys: u32,
+    window: Duration,
+    recent: VecDeque<Instant>,
+    bursting: bool,
+}
+
+impl Default for BurstDetector {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_KEYS, DEFAULT_WINDOW_MS)
+    }
+}
+
+impl BurstDetector {
+    pub fn new(max_keys: u32, window_ms: u64) -> Self {
+        Self {
+            max_keys,
+            window: Duration::from_millis(window_ms),
+            recent: VecDeque::new(),
+            bursting: false,
+        }
+    }
+
+    /// Count a key pressed at `now`; returns `true` while in a burst.
+    pub fn on_key(&mut self, now: Instant) -> bool {
+        if self.max_keys == 0 {
+            return false;
+        }
+
+        while self
+            .recent
+            .front()
+            .is_some_and(|&at| now.saturating_duration_since(at) >= self.window)
+        {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(now);
+
+        let count = self.recent.len() as u32;
+        if count > self.max_keys {
+            self.bursting = true;
+        } else if count <= self.max_keys / 2 {
+            self.bursting = false;
+        }
+        self.bursting
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_pauses_and_resumes() {
+        let mut detector = BurstDetector::new(10, 1000);
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        // Prose: 8 keys a second
+        assert!((0..16).all(|key| !detector.on_key(at(key * 125))));
+
+        // Mashing: 40 keys a second
+        let mashing: Vec<bool> = (0..20)
+            .map(|key| detector.on_key(at(2_000 + key * 25)))
+            .collect();
+        assert!(mashing.last().copied().unwrap());
+
+        // Still too fast to resume
+        assert!(detector.on_key(at(2_600)));
+        // Back to prose
+        assert!(!detector.on_key(at(4_000)));
+
+        assert!(!BurstDetector::new(0, 1000).on_key(at(0)));
+    }
+}
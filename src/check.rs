@@ -0,0 +1,275 @@
+//! Batch spellcheck of text files for scripts and CI (`autocorrect check`).
+//!
+//! Reports words the dictionary doesn't know, without changing anything.
+//! Words are judged as while typing: personal dictionary words are known,
+//! and word types set to `skip` under `[word_types]` (names, acronyms,
+//! ordinals) are not reported. Ordinal suffix and number-word slips are
+//! reported with their fix when `fix_numbers` is on. Contractions count as
+//! known if their stem is ("doesn't", "we'll"). A word with no correction
+//! that is several words run together ("#workfromhome") is reported
+//! with them split apart.
+//!
+//! So that docs and READMEs can be checked, some text is left alone:
+//! - Fenced code blocks and inline code in Markdown
+//! - URLs, email addresses and paths (anything containing `://`, `@`, `/`,
+//!   `\` or `_`)
+//! - Words with inner capitals (`iPhone`, `camelCase`) or followed by digits
+//!   (`h264`), and units after numbers (`10px`)
+
+use crate::classify::{self, TokenContext, WordPolicy};
+use crate::config::WordTypesConfig;
+use crate::dictionary::Dictionary;
+use crate::ranking::RankingPolicy;
+
+/// An unknown word in checked text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Misspelling {
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, in characters.
+    pub column: usize,
+    pub word: String,
+    /// The correction the live corrector would make, if any.
+    pub suggestion: Option<String>,
+}
+
+/// What a check needs from the corrector.
+pub struct Checker<'a> {
+    pub dictionary: &'a Dictionary,
+    pub ranking: &'a RankingPolicy,
+    pub word_types: &'a WordTypesConfig,
+}
+
+/// Where the scan is within the text.
+#[derive(Default)]
+struct Position {
+    sentence_start: bool,
+    /// Digits directly before the current word.
+    number: String,
+    /// The word or number before the current one, if only spaces separate them.
+    previous: Option<String>,
+}
+
+impl Checker<'_> {
+    /// Find unknown words in `text`, in order.
+    pub fn check_text(&self, text: &str) -> Vec<Misspelling> {
+        let mut found = Vec::new();
+        let mut in_fence = false;
+        let mut position = Position {
+            sentence_start: true,
+            ..Position::default()
+        };
+
+        for (index, line) in text.lines().enumerate() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                continue;
+            }
+            if in_fence {
+                continue;
+            }
+
+            // Paragraphs, headings, list items and quotes start afresh
+            if trimmed.is_empty() || trimmed.starts_with(['#', '-', '*', '+', '>']) {
+                position.sentence_start = true;
+            }
+            position.previous = None;
+
+            self.check_line(line, index + 1, &mut position, &mut found);
+            if trimmed.starts_with('#') {
+                position.sentence_start = true;
+            }
+        }
+
+        found
+    }
+
+    fn check_line(
+        &self,
+        line: &str,
+        line_number: usize,
+        position: &mut Position,
+        found: &mut Vec<Misspelling>,
+    ) {
+        let chars = without_inline_code(line);
+        let mut column = 0;
+
+        for chunk in chars.split(|c| c.is_whitespace()) {
+            let start = column;
+            column += chunk.len() + 1;
+            if chunk.is_empty() {
+                continue;
+            }
+
+            let text: String = chunk.iter().collect();
+            if is_unchecked(&text) {
+                position.previous = None;
+                position.sentence_start = text.ends_with(['.', '!', '?']);
+                continue;
+            }
+
+            let mut i = 0;
+            while i < chunk.len() {
+                let c = chunk[i];
+                if c.is_alphabetic() {
+                    let word_start = i;
+                    while i < chunk.len()
+                        && (chunk[i].is_alphabetic() || joins_letters(&chunk[i - 1..]))
+                    {
+                        i += 1;
+                    }
+                    let word: String = chunk[word_start..i].iter().collect();
+                    let digit_follows = chunk.get(i).is_some_and(char::is_ascii_digit);
+                    if !digit_follows {
+                        if let Some(suggestion) = self.check_word(&word, position) {
+                            found.push(Misspelling {
+                                line: line_number,
+                                column: start + word_start + 1,
+                                word: word.clone(),
+                                suggestion,
+                            });
+                        }
+                    }
+                    position.number.clear();
+                    position.previous = Some(word);
+                    position.sentence_start = false;
+                } else if c.is_ascii_digit() {
+                    position.number.clear();
+                    while i < chunk.len() && chunk[i].is_ascii_digit() {
+                        position.number.push(chunk[i]);
+                        i += 1;
+                    }
+                    position.previous = Some(position.number.clone());
+                    position.sentence_start = false;
+                } else {
+                    if matches!(c, '.' | '!' | '?') {
+                        position.sentence_start = true;
+                    }
+                    position.number.clear();
+                    position.previous = None;
+                    i += 1;
+                }
+            }
+            position.number.clear();
+        }
+    }
+
+    /// Check one word; returns `Some(suggestion)` if it's misspelled.
+    fn check_word(&self, word: &str, position: &Position) -> Option<Option<String>> {
+        let mut rest = word.chars().skip(1);
+        if rest.any(char::is_uppercase) && !word.chars().all(char::is_uppercase) {
+            return None;
+        }
+
+        if self.word_types.fix_numbers {
+            if let Some(fix) =
+                crate::numbers::fix(&position.number, position.previous.as_deref(), word)
+            {
+                return Some(Some(fix));
+            }
+        }
+        if !position.number.is_empty() {
+            return None;
+        }
+
+        let context = TokenContext {
+            sentence_start: position.sentence_start,
+            after_digits: false,
+        };
+        let class = classify::classify(word, context);
+        if self.word_types.policy(class) == WordPolicy::Skip || self.dictionary.knows(word) {
+            return None;
+        }
+
+        Some(
+            self.dictionary
+                .get_correction(word, self.ranking)
+                .or_else(|| self.dictionary.segment(word)),
+        )
+    }
+}
+
+/// Whether `chars` starts with an apostrophe between two letters
+/// (`chars[1]`), so that it belongs to the word.
+fn joins_letters(chars: &[char]) -> bool {
+    matches!(chars, [before, apostrophe, after, ..]
+        if before.is_alphabetic() && is_apostrophe(*apostrophe) && after.is_alphabetic())
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+/// The line's characters, with Markdown inline code blanked out so columns
+/// stay the same.
+fn without_inline_code(line: &str) -> Vec<char> {
+    let mut in_code = false;
+    line.chars()
+        .map(|c| {
+            if c == '`' {
+                in_code = !in_code;
+                ' '
+            } else if in_code {
+                ' '
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// URLs, email addresses, paths and identifiers.
+fn is_unchecked(chunk: &str) -> bool {
+    chunk.contains("://") || chunk.starts_with("www.") || chunk.contains(['@', '/', '\\', '_'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_text() {
+        let mut dictionary = Dictionary::new();
+        dictionary.load().unwrap();
+        let checker = Checker {
+            dictionary: &dictionary,
+            ranking: &RankingPolicy::default(),
+            word_types: &WordTypesConfig::default(),
+        };
+
+        let text = "# Setup\n\
+                    Run the instaler on the 2end machine, if it doesn't start.\n\
+                    #workfromhome\n\
+                    Ask Anselm, see https://exmaple.com or `confg_path`.\n\
+                    ```\n\
+                    let recieve = 1;\n\
+                    ```\n\
+                    Enable HTTP for iPhone and h264 at 10px.";
+        let found = checker.check_text(text);
+
+        assert_eq!(
+            found,
+            [
+                Misspelling {
+                    line: 2,
+                    column: 9,
+                    word: "instaler".to_string(),
+                    suggestion: Some("installer".to_string()),
+                },
+                Misspelling {
+                    line: 2,
+                    column: 26,
+                    word: "end".to_string(),
+                    suggestion: Some("nd".to_string()),
+                },
+                Misspelling {
+                    line: 3,
+                    column: 2,
+                    word: "workfromhome".to_string(),
+                    suggestion: Some("work from home".to_string()),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Classification of finished words into types with their own correction
+//! policy.
+//!
+//! Some tokens look like misspellings to the dictionary but are deliberate:
+//! - **Names**: capitalized words in the middle of a sentence ("Anselm")
+//! - **Acronyms**: words typed entirely in uppercase ("HTTP", "SKU")
+//! - **Ordinals**: number suffixes typed right after digits (the "st" in "21st")
+//! - **Elongated words**: a letter repeated for emphasis ("soooo", "yesss"),
+//!   which no edit distance reaches the word from
+//!
+//! The classifier only looks at the word and where it was typed; what to do
+//! with each class is configured under `[word_types]`.
+
+use serde::{Deserialize, Serialize};
+
+/// Suffixes that turn a number into an ordinal.
+const ORDINAL_SUFFIXES: &[&str] = &["st", "nd", "rd", "th"];
+
+/// A letter typed this many times in a row or more is drawn out for
+/// emphasis; no English word has three.
+const ELONGATED_RUN: usize = 3;
+
+/// Most runs collapsed when looking for the word an elongated one stands
+/// for; each doubles the spellings tried.
+const MAX_COLLAPSED_RUNS: usize = 4;
+
+/// The kind of token a finished word appears to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    /// An ordinary word.
+    Word,
+    /// A capitalized word in the middle of a sentence, probably a name.
+    Name,
+    /// A word typed entirely in uppercase.
+    Acronym,
+    /// A number suffix typed directly after digits.
+    Ordinal,
+    /// A word with a letter drawn out for emphasis.
+    Elongated,
+}
+
+/// Where a word was typed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TokenContext {
+    /// The word starts a sentence (or the text), so capitalization is expected.
+    pub sentence_start: bool,
+    /// The word directly follows digits, with no separator.
+    pub after_digits: bool,
+}
+
+/// What to do with a class of words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WordPolicy {
+    /// Correct like any other word.
+    Correct,
+    /// Leave the word as typed.
+    Skip,
+}
+
+/// Classify a finished word.
+pub fn classify(word: &str, context: TokenContext) -> TokenClass {
+    if context.after_digits
+        && ORDINAL_SUFFIXES
+            .iter()
+            .any(|suffix| word.eq_ignore_ascii_case(suffix))
+    {
+        return TokenClass::Ordinal;
+    }
+
+    let Some(first) = word.chars().next() else {
+        return TokenClass::Word;
+    };
+    // Letters only: "SKU-ID" and "DON'T" are typed in uppercase too. An
+    // acronym may repeat a letter ("AAA", "XXX"), so it isn't drawn out.
+    let mut letters = word.chars().filter(|c| c.is_alphabetic());
+    if letters.clone().count() >= 2 && letters.all(char::is_uppercase) {
+        TokenClass::Acronym
+    } else if !runs(word).is_empty() {
+        TokenClass::Elongated
+    } else if first.is_uppercase() && !context.sentence_start {
+        TokenClass::Name
+    } else {
+        TokenClass::Word
+    }
+}
+
+/// Byte ranges of the runs of [`ELONGATED_RUN`] or more of a letter, in
+/// any case.
+fn runs(word: &str) -> Vec<(usize, usize)> {
+    let mut runs = Vec::new();
+    let mut chars = word.char_indices().peekable();
+    while let Some((start, ch)) = chars.next() {
+        let mut end = start + ch.len_utf8();
+        let mut count = 1;
+        while let Some(&(index, next)) = chars.peek() {
+            if !ch.is_alphabetic() || !next.to_lowercase().eq(ch.to_lowercase()) {
+                break;
+            }
+            end = index + next.len_utf8();
+            count += 1;
+            chars.next();
+        }
+        if count >= ELONGATED_RUN {
+            runs.push((start, end));
+        }
+    }
+    runs
+}
+
+/// The spellings an elongated word may stand for, with each drawn-out
+/// letter typed twice or once ("cooool" -> "cool", "col").
+pub fn collapse_elongated(word: &str) -> Vec<String> {
+    let runs: Vec<_> = runs(word).into_iter().take(MAX_COLLAPSED_RUNS).collect();
+    if runs.is_empty() {
+        return Vec::new();
+    }
+
+    (0..1u32 << runs.len())
+        .map(|singles| {
+            let mut spelling = String::with_capacity(word.len());
+            let mut last = 0;
+            for (index, &(start, end)) in runs.iter().enumerate() {
+                let keep = if singles & (1 << index) == 0 { 2 } else { 1 };
+                spelling.push_str(&word[last..start]);
+                spelling.extend(word[start..end].chars().take(keep));
+                last = end;
+            }
+            spelling.push_str(&word[last..]);
+            spelling
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MID_SENTENCE: TokenContext = TokenContext {
+        sentence_start: false,
+        after_digits: false,
+    };
+
+    #[test]
+    fn test_classify_names_and_acronyms() {
+        assert_eq!(classify("teh", MID_SENTENCE), TokenClass::Word);
+        assert_eq!(classify("Anselm", MID_SENTENCE), TokenClass::Name);
+        assert_eq!(classify("HTTP", MID_SENTENCE), TokenClass::Acronym);
+        assert_eq!(classify("SKU-ID", MID_SENTENCE), TokenClass::Acronym);
+        assert_eq!(classify("I'M", MID_SENTENCE), TokenClass::Acronym);
+
+        let sentence_start = TokenContext {
+            sentence_start: true,
+            after_digits: false,
+        };
+        assert_eq!(classify("Teh", sentence_start), TokenClass::Word);
+        assert_eq!(classify("NASA", sentence_start), TokenClass::Acronym);
+    }
+
+    #[test]
+    fn test_classify_ordinals() {
+        let after_digits = TokenContext {
+            sentence_start: false,
+            after_digits: true,
+        };
+        assert_eq!(classify("st", after_digits), TokenClass::Ordinal);
+        assert_eq!(classify("TH", after_digits), TokenClass::Ordinal);
+        assert_eq!(classify("px", after_digits), TokenClass::Word);
+        assert_eq!(classify("st", MID_SENTENCE), TokenClass::Word);
+    }
+
+    #[test]
+    fn test_elongated() {
+        assert_eq!(classify("soooo", MID_SENTENCE), TokenClass::Elongated);
+        assert_eq!(classify("YESss", MID_SENTENCE), TokenClass::Elongated);
+        assert_eq!(classify("coffee", MID_SENTENCE), TokenClass::Word);
+        assert_eq!(classify("...", MID_SENTENCE), TokenClass::Word);
+        assert_eq!(classify("AAA", MID_SENTENCE), TokenClass::Acronym);
+        assert_eq!(classify("III", MID_SENTENCE), TokenClass::Acronym);
+
+        assert_eq!(collapse_elongated("yesss"), ["yess", "yes"]);
+        assert_eq!(
+            collapse_elongated("helllooo"),
+            ["helloo", "heloo", "hello", "helo"].map(String::from)
+        );
+        assert!(collapse_elongated("hello").is_empty());
+    }
+}
@@ -0,0 +1,460 @@
+//! Access to the Windows clipboard: Unicode text, plus saving and restoring
+//! everything on it.
+//!
+//! Features that go through the clipboard must leave the user's clipboard as
+//! they found it. [`save`] copies every format stored in global memory (text,
+//! HTML, RTF, file lists, images as DIBs, and formats registered by other
+//! applications), and [`RestoreGuard`] puts them back when dropped, so the
+//! clipboard is restored even if pasting fails midway.
+//!
+//! Formats held as GDI handles (bitmaps, metafiles, palettes), owner-drawn
+//! formats and private formats can't be copied and are not preserved. Images
+//! survive anyway through their DIB form.
+//!
+//! Corrections pasted from the keyboard hook go through [`begin_paste`]
+//! instead, which shares one restore between pastes in quick succession and
+//! starts from a snapshot kept up to date off the hook thread (see
+//! [`keep_snapshot`]).
+
+#[cfg(windows)]
+use parking_lot::Mutex;
+#[cfg(windows)]
+use std::ptr::null_mut;
+#[cfg(windows)]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(windows)]
+use std::sync::Once;
+#[cfg(windows)]
+use std::time::{Duration, Instant};
+#[cfg(windows)]
+use winapi::um::winbase::{
+    GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
+};
+#[cfg(windows)]
+use winapi::um::winuser::{
+    CloseClipboard, EmptyClipboard, EnumClipboardFormats, GetClipboardData,
+    GetClipboardSequenceNumber, OpenClipboard, SetClipboardData, CF_UNICODETEXT,
+};
+
+/// Attempts to open the clipboard, which another application may hold briefly.
+#[cfg(windows)]
+const OPEN_ATTEMPTS: u32 = 10;
+
+/// How often the kept snapshot is checked against the clipboard (see
+/// [`keep_snapshot`]).
+#[cfg(windows)]
+const KEEP_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether to keep a snapshot of the clipboard (see [`keep_snapshot`]).
+#[cfg(windows)]
+static KEEPING: AtomicBool = AtomicBool::new(false);
+
+/// The clipboard as last saved by [`keep_snapshot`], with its sequence
+/// number then.
+#[cfg(windows)]
+static KEPT: Mutex<Option<(u32, Snapshot)>> = Mutex::new(None);
+
+/// The user's clipboard, while pasted corrections hold it (see
+/// [`begin_paste`]).
+#[cfg(windows)]
+static PENDING: Mutex<Option<PendingRestore>> = Mutex::new(None);
+
+/// Standard formats whose data is a GDI or owner-display handle rather than
+/// global memory: `CF_BITMAP`, `CF_METAFILEPICT`, `CF_PALETTE`,
+/// `CF_ENHMETAFILE`, `CF_OWNERDISPLAY`, `CF_DSPBITMAP`, `CF_DSPMETAFILEPICT`
+/// and `CF_DSPENHMETAFILE`.
+const HANDLE_FORMATS: &[u32] = &[2, 3, 9, 14, 0x80, 0x82, 0x83, 0x8E];
+
+/// `CF_PRIVATEFIRST..=CF_GDIOBJLAST`: formats only their owner understands.
+const PRIVATE_FORMATS: std::ops::RangeInclusive<u32> = 0x0200..=0x03FF;
+
+/// Whether a clipboard format's data can be saved as bytes and restored.
+pub fn is_memory_format(format: u32) -> bool {
+    !HANDLE_FORMATS.contains(&format) && !PRIVATE_FORMATS.contains(&format)
+}
+
+/// Open the clipboard, retrying with increasing waits while another
+/// application has it open.
+#[cfg(windows)]
+unsafe fn open() -> Result<(), String> {
+    for attempt in 1..=OPEN_ATTEMPTS {
+        if OpenClipboard(null_mut()) != 0 {
+            return Ok(());
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10 * attempt as u64));
+    }
+    Err("clipboard is in use by another application".to_string())
+}
+
+/// Copy `bytes` into a new movable global memory block.
+#[cfg(windows)]
+unsafe fn global_from_bytes(bytes: &[u8]) -> Result<winapi::shared::minwindef::HGLOBAL, String> {
+    let handle = GlobalAlloc(GMEM_MOVEABLE, bytes.len().max(1));
+    if handle.is_null() {
+        return Err("could not allocate clipboard memory".to_string());
+    }
+
+    let data = GlobalLock(handle) as *mut u8;
+    if data.is_null() {
+        GlobalFree(handle);
+        return Err("could not lock clipboard memory".to_string());
+    }
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len());
+    GlobalUnlock(handle);
+    Ok(handle)
+}
+
+/// The clipboard's contents at one point in time.
+#[cfg(windows)]
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    /// Format and data, in the order the clipboard listed them (most
+    /// descriptive first).
+    formats: Vec<(u32, Vec<u8>)>,
+}
+
+/// Copy every format on the clipboard that can be restored.
+#[cfg(windows)]
+pub fn save() -> Result<Snapshot, String> {
+    let mut snapshot = Snapshot::default();
+
+    unsafe {
+        open()?;
+
+        let mut format = EnumClipboardFormats(0);
+        while format != 0 {
+            if is_memory_format(format) {
+                let handle = GetClipboardData(format);
+                let data = if handle.is_null() {
+                    null_mut()
+                } else {
+                    GlobalLock(handle) as *const u8
+                };
+                if !data.is_null() {
+                    let bytes = std::slice::from_raw_parts(data, GlobalSize(handle)).to_vec();
+                    GlobalUnlock(handle);
+                    snapshot.formats.push((format, bytes));
+                }
+            }
+            format = EnumClipboardFormats(format);
+        }
+
+        CloseClipboard();
+    }
+
+    Ok(snapshot)
+}
+
+#[cfg(windows)]
+impl Snapshot {
+    /// Replace the clipboard contents with this snapshot.
+    ///
+    /// # Errors
+    /// Returns an error if the clipboard can't be opened or a format can't
+    /// be set. Formats set before the error stay on the clipboard.
+    pub fn restore(&self) -> Result<(), String> {
+        unsafe {
+            open()?;
+            EmptyClipboard();
+
+            let mut result = Ok(());
+            for (format, bytes) in &self.formats {
+                let handle = match global_from_bytes(bytes) {
+                    Ok(handle) => handle,
+                    Err(e) => {
+                        result = Err(e);
+                        break;
+                    }
+                };
+                // On success the system owns the memory
+                if SetClipboardData(*format, handle).is_null() {
+                    GlobalFree(handle);
+                    result = Err(format!("could not restore clipboard format {}", format));
+                }
+            }
+
+            CloseClipboard();
+            result
+        }
+    }
+}
+
+/// Restores a saved clipboard when dropped, including on early returns and
+/// errors.
+///
+/// The clipboard is only restored if it changed since the guard was created
+/// and still holds what we last put there (see [`RestoreGuard::track`]):
+/// if the user copied something in the meantime, that wins.
+#[cfg(windows)]
+pub struct RestoreGuard {
+    /// `None` if the clipboard couldn't be saved; it's then left alone.
+    snapshot: Option<Snapshot>,
+    /// Sequence number when the snapshot was taken.
+    saved_sequence: u32,
+    /// Sequence number after our last change.
+    our_sequence: u32,
+}
+
+#[cfg(windows)]
+impl RestoreGuard {
+    /// Save the clipboard so it's restored when the guard is dropped.
+    pub fn new() -> Self {
+        let saved_sequence = sequence_number();
+        let snapshot = save()
+            .map_err(|e| eprintln!("Failed to save clipboard: {}", e))
+            .ok();
+        Self {
+            snapshot,
+            saved_sequence,
+            our_sequence: saved_sequence,
+        }
+    }
+
+    /// Like `new`, but from the kept snapshot if the clipboard hasn't
+    /// changed since it was taken (see [`keep_snapshot`]).
+    fn from_kept() -> Self {
+        let sequence = sequence_number();
+        match KEPT.lock().take() {
+            Some((kept, snapshot)) if kept == sequence => Self {
+                snapshot: Some(snapshot),
+                saved_sequence: sequence,
+                our_sequence: sequence,
+            },
+            _ => Self::new(),
+        }
+    }
+
+    /// Note that the clipboard's current contents were put there by us (or
+    /// by an application at our request, e.g. a copy we sent).
+    pub fn track(&mut self) {
+        self.our_sequence = sequence_number();
+    }
+}
+
+#[cfg(windows)]
+impl Drop for RestoreGuard {
+    fn drop(&mut self) {
+        if self.our_sequence == self.saved_sequence || sequence_number() != self.our_sequence {
+            return;
+        }
+        let Some(snapshot) = &self.snapshot else {
+            return;
+        };
+        // An empty snapshot empties the clipboard, as it was
+        if let Err(e) = snapshot.restore() {
+            eprintln!("Failed to restore clipboard: {}", e);
+        }
+    }
+}
+
+/// The restore shared by pastes in quick succession.
+#[cfg(windows)]
+struct PendingRestore {
+    guard: RestoreGuard,
+    /// When to restore, once a paste has gone through.
+    at: Option<Instant>,
+}
+
+/// Put `text` on the clipboard to be pasted, saving the user's clipboard
+/// to be put back by [`finish_paste`] or [`cancel_paste`].
+///
+/// A paste while another's restore is pending reuses its saved clipboard:
+/// the clipboard then holds our earlier text, not the user's.
+///
+/// # Errors
+/// Returns an error if the clipboard can't be set. It's left as it was.
+#[cfg(windows)]
+pub fn begin_paste(text: &str) -> Result<(), String> {
+    let mut pending = PENDING.lock();
+    let mut restore = pending.take().unwrap_or_else(|| PendingRestore {
+        guard: RestoreGuard::from_kept(),
+        at: None,
+    });
+    let result = set_text(text);
+    if result.is_ok() {
+        restore.guard.track();
+    }
+    // Without an earlier paste to restore after, a failure drops the guard,
+    // restoring the clipboard now
+    if result.is_ok() || restore.at.is_some() {
+        *pending = Some(restore);
+    }
+    result
+}
+
+/// Restore the user's clipboard `settle` after a paste just sent, giving the
+/// application time to read it. A pending restore is pushed back.
+#[cfg(windows)]
+pub fn finish_paste(settle: Duration) {
+    let mut pending = PENDING.lock();
+    let Some(restore) = pending.as_mut() else {
+        return;
+    };
+    let first = restore.at.is_none();
+    restore.at = Some(Instant::now() + settle);
+    if first {
+        std::thread::spawn(restore_when_settled);
+    }
+}
+
+/// Give up on a paste that couldn't be sent: the clipboard is restored now,
+/// unless an earlier paste's restore is still pending.
+#[cfg(windows)]
+pub fn cancel_paste() {
+    let mut pending = PENDING.lock();
+    if pending.as_ref().is_some_and(|restore| restore.at.is_none()) {
+        *pending = None;
+    }
+}
+
+/// Wait until the pending restore is due, however often it's pushed back,
+/// and restore the clipboard.
+#[cfg(windows)]
+fn restore_when_settled() {
+    loop {
+        let wait = {
+            let mut pending = PENDING.lock();
+            let now = Instant::now();
+            match pending.as_ref().and_then(|restore| restore.at) {
+                Some(at) if at > now => at - now,
+                // Dropping the guard restores the clipboard
+                _ => {
+                    *pending = None;
+                    return;
+                }
+            }
+        };
+        std::thread::sleep(wait);
+    }
+}
+
+/// Keep a snapshot of the clipboard while `keep` is set, for the foreground
+/// app taking corrections through the clipboard.
+///
+/// Saving copies every format, which can take a while for a large image, so
+/// it's done on a background thread whenever the clipboard changes, not in
+/// the keyboard hook before a paste. The snapshot is dropped once `keep` is
+/// cleared, so the user's clipboard isn't held on to for longer.
+#[cfg(windows)]
+pub fn keep_snapshot(keep: bool) {
+    static KEEPER: Once = Once::new();
+
+    KEEPING.store(keep, Ordering::Relaxed);
+    if !keep {
+        *KEPT.lock() = None;
+        return;
+    }
+    KEEPER.call_once(|| {
+        std::thread::spawn(|| loop {
+            std::thread::sleep(KEEP_INTERVAL);
+            refresh_kept();
+        });
+    });
+}
+
+/// Save the clipboard again if it changed since the kept snapshot.
+#[cfg(windows)]
+fn refresh_kept() {
+    let sequence = sequence_number();
+    // While a paste's restore is pending, the clipboard holds our text
+    if !KEEPING.load(Ordering::Relaxed)
+        || PENDING.lock().is_some()
+        || KEPT
+            .lock()
+            .as_ref()
+            .is_some_and(|(kept, _)| *kept == sequence)
+    {
+        return;
+    }
+
+    let Ok(snapshot) = save() else {
+        return;
+    };
+    let mut kept = KEPT.lock();
+    // Changed while saving, or no longer wanted
+    if sequence_number() == sequence && KEEPING.load(Ordering::Relaxed) {
+        *kept = Some((sequence, snapshot));
+    }
+}
+
+/// Current clipboard text, or `None` if the clipboard holds no text.
+#[cfg(windows)]
+pub fn get_text() -> Result<Option<String>, String> {
+    unsafe {
+        open()?;
+
+        let handle = GetClipboardData(CF_UNICODETEXT);
+        let text = if handle.is_null() {
+            None
+        } else {
+            let data = GlobalLock(handle) as *const u16;
+            if data.is_null() {
+                None
+            } else {
+                let mut len = 0;
+                while *data.add(len) != 0 {
+                    len += 1;
+                }
+                let text = String::from_utf16_lossy(std::slice::from_raw_parts(data, len));
+                GlobalUnlock(handle);
+                Some(text)
+            }
+        };
+
+        CloseClipboard();
+        Ok(text)
+    }
+}
+
+/// Replace the clipboard contents with `text`.
+#[cfg(windows)]
+pub fn set_text(text: &str) -> Result<(), String> {
+    let bytes: Vec<u8> = text
+        .encode_utf16()
+        .chain(Some(0))
+        .flat_map(u16::to_ne_bytes)
+        .collect();
+
+    unsafe {
+        let handle = global_from_bytes(&bytes)?;
+
+        if let Err(e) = open() {
+            GlobalFree(handle);
+            return Err(e);
+        }
+
+        EmptyClipboard();
+        // On success the system owns the memory
+        let result = if SetClipboardData(CF_UNICODETEXT, handle).is_null() {
+            GlobalFree(handle);
+            Err("could not set clipboard data".to_string())
+        } else {
+            Ok(())
+        };
+
+        CloseClipboard();
+        result
+    }
+}
+
+/// Counter that changes every time the clipboard contents change.
+#[cfg(windows)]
+pub fn sequence_number() -> u32 {
+    unsafe { GetClipboardSequenceNumber() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_memory_format() {
+        assert!(is_memory_format(13)); // CF_UNICODETEXT
+        assert!(is_memory_format(15)); // CF_HDROP
+        assert!(is_memory_format(8)); // CF_DIB
+        assert!(is_memory_format(0xC0A1)); // Registered, e.g. "HTML Format"
+        assert!(!is_memory_format(2)); // CF_BITMAP
+        assert!(!is_memory_format(14)); // CF_ENHMETAFILE
+        assert!(!is_memory_format(0x0250)); // Private
+    }
+}
@@ -1,12 +1,345 @@
+use crate::classify::{TokenClass, WordPolicy};
+use crate::corrector::InputStrategy;
+use crate::dictionary::SpellingVariant;
+use crate::grammar::GrammarMode;
+use crate::keyboard::KeyboardLayout;
+use crate::os_autocorrect::OsAutocorrectPolicy;
+use crate::pipeline::StageKind;
+use crate::ranking::{CorrectionMode, SelectionStrategy};
+use crate::timing::TimingPolicy;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Default `learn_after_undos`: a correction undone twice was unwanted.
+pub const DEFAULT_LEARN_AFTER_UNDOS: u32 = 2;
+
+/// How long the config must go unchanged before a requested save is written.
+const SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub max_edit_distance: i32,
     pub enabled_by_default: bool,
-    pub undo_timeout_seconds: u64,
+    /// Pre-`[timing]` location of `timing.undo_timeout_seconds`, moved there
+    /// on load.
+    #[serde(rename = "undo_timeout_seconds", skip_serializing)]
+    legacy_undo_timeout_seconds: Option<u64>,
+    /// Corrections taking longer than this (lookup plus typing) are logged
+    /// and counted as slow.
+    pub correction_budget_ms: u64,
+    /// Preset for the settings below (`custom` to use them as set).
+    pub correction_mode: CorrectionMode,
+    /// How far to go to find a correction (see `ranking`).
+    pub selection_strategy: SelectionStrategy,
+    /// Typed words shorter than this are only corrected by personal
+    /// corrections.
+    pub min_word_length: usize,
+    /// Corrections to words less frequent than this are not applied.
+    pub min_correction_frequency: u64,
+    /// How many times more frequent a correction must be than the runner-up.
+    pub min_correction_ratio: f64,
     pub hotkey_toggle: String,
     pub auto_check_updates: bool,
+    pub learning_half_life_days: f64,
+    /// Add a word to the personal dictionary after its correction was undone
+    /// this many times (0 to never).
+    pub learn_after_undos: u32,
+    /// Other suggestions the `next-suggestion` hotkey cycles through after a
+    /// correction.
+    pub max_alternatives: usize,
+    pub startup_self_test: bool,
+    pub warn_on_conflicting_software: bool,
+    pub pause_on_conflicting_software: bool,
+    /// Pause while a fullscreen game is in the foreground (see
+    /// `fullscreen`).
+    pub pause_in_fullscreen: bool,
+    /// What to do while Windows' own autocorrect is on (see `os_autocorrect`).
+    pub os_autocorrect: OsAutocorrectPolicy,
+    pub language: String,
+    /// Languages whose words typed without their accents get them back
+    /// ("cafe" -> "café").
+    pub restore_diacritics: Vec<String>,
+    /// Leave alone words that are exact in another installed language pack.
+    pub foreign_word_passthrough: bool,
+    /// Only suggest words of this spelling variant (US or UK).
+    pub spelling_variant: SpellingVariant,
+    /// Layout whose neighboring keys make likely typos (see `keyboard`).
+    pub keyboard_layout: KeyboardLayout,
+    /// Characters that belong to a word between letters ("don't",
+    /// "well-known") instead of ending it.
+    pub word_characters: String,
+    /// Words longer than this many characters are left alone.
+    pub max_word_length: usize,
+    pub tray_toggle_click: TrayToggleClick,
+    /// Show an always-on-top widget with today's corrections and a click
+    /// to pause.
+    pub show_widget: bool,
+    /// Forget the word being typed when a mouse button is pressed.
+    pub clear_word_on_click: bool,
+    /// What to do with keystrokes injected by other software.
+    pub injected_input: InjectedInputPolicy,
+    /// Hold this key while finishing a word to type it literally.
+    pub bypass_key: BypassKey,
+    /// Find corrections but only log and count them, never typing them.
+    pub dry_run: bool,
+    /// Correct words as they are finished; otherwise only when the
+    /// `correct-word` hotkey is pressed.
+    pub auto_correct: bool,
+    /// Correct the word finished by Enter (Space and punctuation always
+    /// correct).
+    pub correct_on_enter: bool,
+    /// Type curly quotes, em dashes and ellipses for straight quotes, `--`
+    /// and `...` (see `typography`).
+    pub typography: bool,
+    /// Tune the delay between injected keys per app from how corrections
+    /// turn out.
+    pub auto_tune_key_delay: bool,
+    /// Corrections per minute after which an app's corrections are paused
+    /// for a while (0 for no limit).
+    pub max_corrections_per_minute: u32,
+    /// Pause corrections while more than this many keys are pressed within
+    /// `burst_window_ms` (0 for no limit, see `burst`).
+    pub burst_max_keys: u32,
+    pub burst_window_ms: u64,
+    /// Append correction, undo and toggle events to `events.jsonl`.
+    pub stream_events: bool,
+    /// Load correction provider plugins from the plugins folder.
+    pub load_plugins: bool,
+    /// Keep a log of corrections and write a weekly report of repeated
+    /// misspellings.
+    pub typing_report: bool,
+    /// Extra hotkeys by action name (see `hotkeys::HotkeyAction`).
+    pub hotkeys: BTreeMap<String, String>,
+    /// Per-application settings.
+    pub apps: AppsConfig,
+    /// Correction policy for names, acronyms and ordinals.
+    pub word_types: WordTypesConfig,
+    /// Confusable-word checks (its/it's, then/than, ...).
+    pub grammar: GrammarConfig,
+    /// Undo window, undo expiry while typing, and correction cooldown.
+    pub timing: TimingPolicy,
+    /// Order of the correction stages.
+    pub pipeline: PipelineConfig,
+    /// When the most recent unsaved change was requested.
+    #[serde(skip)]
+    last_change: Option<Instant>,
+}
+
+/// Which left-click on the tray icon toggles autocorrect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrayToggleClick {
+    /// Toggle on double-click.
+    Double,
+    /// Toggle on every single left-click.
+    Single,
+}
+
+/// How keystrokes injected by other software (macro recorders, text
+/// expanders, remote input tools) are handled. Autocorrect's own injected
+/// keystrokes are unaffected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InjectedInputPolicy {
+    /// Track and correct them like typing (needed for on-screen keyboards).
+    Track,
+    /// Let them through without tracking them.
+    Ignore,
+    /// Pause corrections until the next physical keystroke.
+    Pause,
+}
+
+/// Input method and key delay forced for one app (`[apps.input."app.exe"]`),
+/// where detection picks the wrong one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppInput {
+    /// `sendinput`, `sendmessage` or `clipboard`; detected if unset.
+    pub strategy: Option<InputStrategy>,
+    /// Delay between keys sent as window messages; detected (and
+    /// auto-tuned) if unset. `SendInput` sends every key in one batch and
+    /// the clipboard pastes at once, so neither is paced.
+    pub key_delay_ms: Option<u64>,
+    /// With `clipboard`, how long the app gets to read a pasted correction
+    /// before the user's clipboard is put back; 200 ms if unset.
+    pub paste_delay_ms: Option<u64>,
+}
+
+impl AppInput {
+    /// These settings for `app`, without a `key_delay_ms` that its forced
+    /// strategy would ignore.
+    pub fn checked(mut self, app: &str) -> Self {
+        let paced = matches!(self.strategy, None | Some(InputStrategy::SendMessage));
+        if self.key_delay_ms.is_some() && !paced {
+            eprintln!("Ignoring key_delay_ms for {app}: only window messages are paced");
+            self.key_delay_ms = None;
+        }
+        self
+    }
+}
+
+/// Key that, held down while finishing a word, leaves the word as typed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BypassKey {
+    None,
+    RightAlt,
+    RightCtrl,
+    RightShift,
+}
+
+impl BypassKey {
+    /// Virtual key code as reported by the low-level keyboard hook.
+    pub fn vk_code(self) -> Option<u32> {
+        match self {
+            BypassKey::None => None,
+            BypassKey::RightAlt => Some(0xA5),   // VK_RMENU
+            BypassKey::RightCtrl => Some(0xA3),  // VK_RCONTROL
+            BypassKey::RightShift => Some(0xA1), // VK_RSHIFT
+        }
+    }
+}
+
+/// Settings that depend on the foreground application.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppsConfig {
+    /// Executables (e.g. `keepass.exe`) where corrections still work but
+    /// nothing typed is recorded in learning data, statistics or logs.
+    pub sensitive: Vec<String>,
+    /// Regular expressions on window titles; corrections are suspended while
+    /// a matching window is in the foreground.
+    pub excluded_titles: Vec<String>,
+    /// Remote desktop and VM clients (e.g. `vmconnect.exe`) where corrections
+    /// stay on; they're paused in all other known clients.
+    pub remote_allowed: Vec<String>,
+    /// Executables where corrections pause while Windows' autocorrect is on
+    /// and `os_autocorrect = "yield"`; every app if empty.
+    pub yield_to_os_autocorrect: Vec<String>,
+    /// Executables or window classes where corrections are off.
+    pub disabled: Vec<String>,
+    /// Executables or window classes where corrections are on; every app
+    /// (except `disabled` ones) if empty.
+    pub enabled_only: Vec<String>,
+    /// Executables where a corrected word is deleted with one Ctrl+Backspace
+    /// instead of a backspace per character.
+    pub ctrl_backspace: Vec<String>,
+    /// Executables where Enter sends a message, so it ends a word without
+    /// correcting it.
+    pub enter_sends: Vec<String>,
+    /// Executables where quotes and dashes stay as typed with `typography`.
+    pub no_typography: Vec<String>,
+    /// Executables where corrections stay on while fullscreen.
+    pub fullscreen_allowed: Vec<String>,
+    /// Window classes of controls that take passwords without the
+    /// `ES_PASSWORD` style; corrections pause while one has focus.
+    pub password_fields: Vec<String>,
+    /// Input method and key delay by executable, overriding detection.
+    pub input: BTreeMap<String, AppInput>,
+}
+
+impl Default for AppsConfig {
+    fn default() -> Self {
+        Self {
+            sensitive: crate::privacy::DEFAULT_SENSITIVE_APPS
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+            excluded_titles: Vec::new(),
+            remote_allowed: Vec::new(),
+            yield_to_os_autocorrect: Vec::new(),
+            disabled: Vec::new(),
+            enabled_only: Vec::new(),
+            ctrl_backspace: crate::corrector::DEFAULT_CTRL_BACKSPACE_APPS
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+            enter_sends: crate::corrector::DEFAULT_ENTER_SENDS_APPS
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+            no_typography: crate::typography::DEFAULT_NO_TYPOGRAPHY_APPS
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+            fullscreen_allowed: crate::fullscreen::DEFAULT_FULLSCREEN_ALLOWED_APPS
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+            password_fields: Vec::new(),
+            input: BTreeMap::new(),
+        }
+    }
+}
+
+/// Correction policy per word type (see `classify::TokenClass`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WordTypesConfig {
+    pub names: WordPolicy,
+    pub acronyms: WordPolicy,
+    pub ordinals: WordPolicy,
+    /// Drawn-out words ("soooo"); corrected to the word they stand for.
+    pub elongated: WordPolicy,
+    /// Fix ordinal suffixes and number words ("2end" -> "2nd", see `numbers`).
+    pub fix_numbers: bool,
+}
+
+impl Default for WordTypesConfig {
+    fn default() -> Self {
+        Self {
+            names: WordPolicy::Skip,
+            acronyms: WordPolicy::Skip,
+            ordinals: WordPolicy::Skip,
+            elongated: WordPolicy::Skip,
+            fix_numbers: true,
+        }
+    }
+}
+
+impl WordTypesConfig {
+    /// Policy for a class of word; ordinary words are always corrected.
+    pub fn policy(&self, class: TokenClass) -> WordPolicy {
+        match class {
+            TokenClass::Word => WordPolicy::Correct,
+            TokenClass::Name => self.names,
+            TokenClass::Acronym => self.acronyms,
+            TokenClass::Ordinal => self.ordinals,
+            TokenClass::Elongated => self.elongated,
+        }
+    }
+}
+
+/// Settings for confusable-word rules (see `grammar`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrammarConfig {
+    pub mode: GrammarMode,
+}
+
+impl Default for GrammarConfig {
+    fn default() -> Self {
+        Self {
+            mode: GrammarMode::Suggest,
+        }
+    }
+}
+
+/// Which correction stages run, in order (see `pipeline`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PipelineConfig {
+    pub stages: Vec<StageKind>,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            stages: StageKind::DEFAULT_ORDER.to_vec(),
+        }
+    }
 }
 
 impl Default for Config {
@@ -14,17 +347,64 @@ impl Default for Config {
         Self {
             max_edit_distance: 2,
             enabled_by_default: true,
-            undo_timeout_seconds: 5,
+            legacy_undo_timeout_seconds: None,
+            correction_budget_ms: crate::corrector::DEFAULT_CORRECTION_BUDGET_MS,
+            correction_mode: CorrectionMode::Custom,
+            selection_strategy: SelectionStrategy::Frequency,
+            min_word_length: 1,
+            min_correction_frequency: crate::ranking::DEFAULT_MIN_FREQUENCY,
+            min_correction_ratio: crate::ranking::DEFAULT_MIN_FREQUENCY_RATIO,
             hotkey_toggle: "Ctrl+Shift+A".to_string(),
             auto_check_updates: true,
+            learning_half_life_days: crate::learning::DEFAULT_HALF_LIFE_DAYS,
+            learn_after_undos: DEFAULT_LEARN_AFTER_UNDOS,
+            max_alternatives: crate::corrector::DEFAULT_MAX_ALTERNATIVES,
+            startup_self_test: true,
+            warn_on_conflicting_software: true,
+            pause_on_conflicting_software: false,
+            pause_in_fullscreen: true,
+            os_autocorrect: OsAutocorrectPolicy::Warn,
+            language: crate::languages::BUILTIN_LANGUAGE.to_string(),
+            restore_diacritics: Vec::new(),
+            foreign_word_passthrough: true,
+            spelling_variant: SpellingVariant::Any,
+            keyboard_layout: KeyboardLayout::Qwerty,
+            word_characters: crate::keymap::DEFAULT_WORD_CHARACTERS.to_string(),
+            max_word_length: crate::corrector::MAX_WORD_LEN,
+            tray_toggle_click: TrayToggleClick::Double,
+            show_widget: false,
+            clear_word_on_click: true,
+            injected_input: InjectedInputPolicy::Track,
+            bypass_key: BypassKey::None,
+            dry_run: false,
+            auto_correct: true,
+            correct_on_enter: true,
+            typography: false,
+            auto_tune_key_delay: false,
+            max_corrections_per_minute: crate::ratelimit::DEFAULT_MAX_PER_MINUTE,
+            burst_max_keys: crate::burst::DEFAULT_MAX_KEYS,
+            burst_window_ms: crate::burst::DEFAULT_WINDOW_MS,
+            stream_events: false,
+            load_plugins: false,
+            typing_report: false,
+            hotkeys: BTreeMap::new(),
+            apps: AppsConfig::default(),
+            word_types: WordTypesConfig::default(),
+            grammar: GrammarConfig::default(),
+            timing: TimingPolicy::default(),
+            pipeline: PipelineConfig::default(),
+            last_change: None,
         }
     }
 }
 
 impl Config {
     pub fn load() -> Result<Self, confy::ConfyError> {
-        match confy::load("autocorrect", Some("config")) {
-            Ok(config) => Ok(config),
+        match confy::load::<Self>("autocorrect", Some("config")) {
+            Ok(mut config) => {
+                config.migrate();
+                Ok(config)
+            }
             Err(err) => {
                 eprintln!("Failed to load config, using defaults: {err}");
                 Ok(Self::default())
@@ -32,7 +412,100 @@ impl Config {
         }
     }
 
+    /// Move settings from their old locations, keeping the user's values.
+    fn migrate(&mut self) {
+        if let Some(seconds) = self.legacy_undo_timeout_seconds.take() {
+            self.timing.undo_timeout_seconds = seconds;
+        }
+    }
+
+    /// Location of the config file (`%APPDATA%/autocorrect/config/config.toml` on Windows).
+    pub fn path() -> Result<std::path::PathBuf, confy::ConfyError> {
+        confy::get_configuration_file_path("autocorrect", Some("config"))
+    }
+
+    /// All configured hotkeys as `(action name, hotkey)` pairs, including
+    /// `hotkey_toggle`.
+    pub fn hotkey_bindings(&self) -> impl Iterator<Item = (&str, &str)> {
+        std::iter::once(("toggle", self.hotkey_toggle.as_str())).chain(
+            self.hotkeys
+                .iter()
+                .map(|(action, hotkey)| (action.as_str(), hotkey.as_str())),
+        )
+    }
+
+    /// Write the config file now.
+    ///
+    /// The file is written to a temporary file next to it and then renamed
+    /// over the original, so a crash mid-write never leaves it truncated.
     pub fn save(&self) -> Result<(), confy::ConfyError> {
-        confy::store("autocorrect", Some("config"), self)
+        let path = Self::path()?;
+        let temp_path = path.with_extension("toml.tmp");
+
+        confy::store_path(&temp_path, self)?;
+        std::fs::File::open(&temp_path)
+            .and_then(|file| file.sync_all())
+            .and_then(|()| std::fs::rename(&temp_path, &path))
+            .map_err(confy::ConfyError::WriteConfigurationFileError)
+    }
+
+    /// Ask for the config to be saved soon.
+    ///
+    /// Changes are coalesced: the file is written by `save_if_due` once no
+    /// further change has been requested for a short while.
+    pub fn request_save(&mut self) {
+        self.last_change = Some(Instant::now());
+    }
+
+    /// Whether a requested save has waited long enough to be written.
+    fn save_due(&self, now: Instant) -> bool {
+        self.last_change
+            .is_some_and(|changed| now.duration_since(changed) >= SAVE_DEBOUNCE)
+    }
+
+    /// Write pending changes if they have settled. Returns `true` if saved.
+    pub fn save_if_due(&mut self) -> Result<bool, confy::ConfyError> {
+        if !self.save_due(Instant::now()) {
+            return Ok(false);
+        }
+        self.flush()?;
+        Ok(true)
+    }
+
+    /// Write pending changes immediately (e.g. on shutdown).
+    pub fn flush(&mut self) -> Result<(), confy::ConfyError> {
+        if self.last_change.is_some() {
+            self.save()?;
+            self.last_change = None;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_is_debounced() {
+        let mut config = Config::default();
+        let start = Instant::now();
+        assert!(!config.save_due(start));
+
+        config.request_save();
+        let requested = config.last_change.unwrap();
+        assert!(!config.save_due(requested + Duration::from_millis(500)));
+        assert!(config.save_due(requested + SAVE_DEBOUNCE));
+    }
+
+    #[test]
+    fn test_migrates_undo_timeout() {
+        let mut config: Config = toml::from_str("undo_timeout_seconds = 9").unwrap();
+        config.migrate();
+        assert_eq!(config.timing.undo_timeout_seconds, 9);
+
+        let saved = toml::to_string(&config).unwrap();
+        assert!(saved.contains("[timing]"));
+        assert!(!saved.starts_with("undo_timeout_seconds"));
     }
 }
@@ -0,0 +1,130 @@
+//! Detection of other software that hooks or rewrites keyboard input.
+//!
+//! Tools like AutoHotkey, PowerToys Keyboard Manager, text expanders and other
+//! autocorrectors also watch (and inject) keystrokes. Running them alongside
+//! Autocorrect can cause double corrections or keys arriving out of order, so
+//! we look for their processes, warn the user, and optionally pause while
+//! they run.
+
+use crate::process;
+
+/// A tool known to interfere with keystroke tracking.
+#[derive(Debug)]
+pub struct KnownConflict {
+    /// Executable names (lowercase) that identify the tool.
+    pub process_names: &'static [&'static str],
+    /// Human-readable tool name.
+    pub tool: &'static str,
+    /// What goes wrong when both run, and how to avoid it.
+    pub note: &'static str,
+}
+
+/// Tools known to conflict with Autocorrect.
+pub const KNOWN_CONFLICTS: &[KnownConflict] = &[
+    KnownConflict {
+        process_names: &[
+            "autohotkey.exe",
+            "autohotkeyu64.exe",
+            "autohotkeyu32.exe",
+            "autohotkeya32.exe",
+            "autohotkey64.exe",
+            "autohotkey32.exe",
+        ],
+        tool: "AutoHotkey",
+        note: "Hotstrings may fire on the same word we correct, and remapped keys can \
+arrive out of order. Avoid autocorrect-style hotstrings while both run.",
+    },
+    KnownConflict {
+        process_names: &["powertoys.keyboardmanagerengine.exe"],
+        tool: "PowerToys Keyboard Manager",
+        note: "Remapped keys are re-injected, so remapped letters may be tracked twice \
+or not at all. Remaps that don't produce letters are unaffected.",
+    },
+    KnownConflict {
+        process_names: &["espanso.exe", "espansod.exe"],
+        tool: "espanso",
+        note: "Expansions delete and retype text like corrections do; an expansion \
+trigger that looks like a typo may be corrected first.",
+    },
+    KnownConflict {
+        process_names: &["phraseexpress.exe"],
+        tool: "PhraseExpress",
+        note: "Its autocorrect and text expansion can fix the same word twice. Disable \
+one of the two autocorrect features.",
+    },
+    KnownConflict {
+        process_names: &["textexpander.exe"],
+        tool: "TextExpander",
+        note: "Snippet abbreviations may be corrected before they expand. Add your \
+abbreviations to the personal dictionary.",
+    },
+    KnownConflict {
+        process_names: &["grammarly.desktop.exe"],
+        tool: "Grammarly for Windows",
+        note: "Both tools correct spelling; accepted Grammarly suggestions can race \
+with our corrections.",
+    },
+];
+
+/// Find known conflicting tools among the given process names.
+///
+/// Matching is case-insensitive. Each tool is reported at most once.
+pub fn find_conflicts<S: AsRef<str>>(process_names: &[S]) -> Vec<&'static KnownConflict> {
+    let names: Vec<String> = process_names
+        .iter()
+        .map(|name| name.as_ref().to_lowercase())
+        .collect();
+
+    KNOWN_CONFLICTS
+        .iter()
+        .filter(|conflict| {
+            conflict
+                .process_names
+                .iter()
+                .any(|process| names.iter().any(|name| name == process))
+        })
+        .collect()
+}
+
+/// Scan running processes for known conflicting tools.
+pub fn detect_running() -> Vec<&'static KnownConflict> {
+    find_conflicts(&process::running_process_names())
+}
+
+/// Build the warning shown to the user for detected conflicts.
+pub fn warning_message(conflicts: &[&KnownConflict]) -> String {
+    let mut message = String::from(
+        "Other keyboard software is running. Corrections may be applied twice or \
+keys may arrive out of order:\n",
+    );
+
+    for conflict in conflicts {
+        message.push_str(&format!("\n- {}: {}\n", conflict.tool, conflict.note));
+    }
+
+    message.push_str(
+        "\nSet pause_on_conflicting_software = true in config.toml to pause Autocorrect \
+automatically while these tools run.",
+    );
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_conflicts_case_insensitive() {
+        let running = ["explorer.exe", "AutoHotkeyU64.exe", "notepad.exe"];
+        let conflicts = find_conflicts(&running);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].tool, "AutoHotkey");
+    }
+
+    #[test]
+    fn test_find_conflicts_reports_each_tool_once() {
+        let running = ["espanso.exe", "espansod.exe"];
+        assert_eq!(find_conflicts(&running).len(), 1);
+        assert!(find_conflicts(&["notepad.exe"]).is_empty());
+    }
+}
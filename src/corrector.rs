@@ -17,9 +17,31 @@
 //! - Electron apps (Notion, VS Code, Slack): Use SendMessage fallback
 //! - Browsers (Chrome, Edge): Use SendMessage fallback
 
+use crate::burst::BurstDetector;
+use crate::check::{Checker, Misspelling};
+use crate::classify::TokenContext;
+use crate::config::{AppInput, BypassKey, InjectedInputPolicy, WordTypesConfig};
 use crate::dictionary::Dictionary;
+use crate::grammar::{Grammar, GrammarFix, GrammarMode};
+use crate::keymap::Typed;
+use crate::pacing::KeyPacing;
+use crate::pipeline::{Pipeline, StageContext, WordInput};
+use crate::plugins::Providers;
+use crate::ranking::{CorrectionMode, RankingPolicy};
+use crate::ratelimit::RateLimiter;
+use crate::report::BadCorrection;
+use crate::stats::SessionStats;
+#[cfg(windows)]
+use crate::stats::HOOK_COUNTERS;
+use crate::timing::TimingPolicy;
+#[cfg(windows)]
+use crate::transaction::{held_keys_released, keys_completed, Key, Stroke, Transaction};
+use crate::tutor::MistakeLog;
+use crate::ui::positioning::ScreenRect;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[cfg(windows)]
 use winapi::um::processthreadsapi::GetCurrentThreadId;
@@ -34,6 +56,14 @@ const VK_RETURN: u32 = 0x0D;
 const VK_SPACE: u32 = 0x20;
 /// Virtual key code for Control.
 const VK_CONTROL: u32 = 0x11;
+/// Virtual key code for V, pasting with Control.
+#[cfg(windows)]
+const VK_V: u16 = 0x56;
+
+/// How long to let the application read a pasted replacement before the
+/// clipboard is restored, unless `paste_delay_ms` is set for it.
+#[cfg(windows)]
+pub const PASTE_SETTLE_MS: u64 = 200;
 
 /// Delay between keystrokes in milliseconds.
 /// Increased from 1ms to 5ms for better compatibility with React/Electron apps.
@@ -42,12 +72,177 @@ const KEY_DELAY_MS: u64 = 5;
 /// Delay for problematic applications (Electron, browsers).
 const KEY_DELAY_SLOW_MS: u64 = 10;
 
+/// Default for the most other suggestions kept for "next suggestion" after a
+/// correction (`max_alternatives`).
+pub const DEFAULT_MAX_ALTERNATIVES: usize = 4;
+
 /// Stores information about a correction for potential undo.
 #[derive(Debug, Clone)]
 struct UndoState {
     original_word: String,
     corrected_word: String,
     timestamp: Instant,
+    /// Foreground window the correction was typed into (see
+    /// `process::foreground_window_id`).
+    window: usize,
+    /// Caret when the corrected word ended.
+    caret: Option<ScreenRect>,
+    /// How the correction was typed; undo types the same way.
+    strategy: InputStrategy,
+    /// Other suggestions for the word, best first.
+    alternatives: Vec<String>,
+}
+
+impl UndoState {
+    fn new(
+        original: &str,
+        corrected: &str,
+        caret: Option<ScreenRect>,
+        strategy: InputStrategy,
+    ) -> Self {
+        Self {
+            original_word: original.to_string(),
+            corrected_word: corrected.to_string(),
+            timestamp: Instant::now(),
+            window: crate::process::foreground_window_id(),
+            caret,
+            strategy,
+            alternatives: Vec::new(),
+        }
+    }
+
+    /// Whether the correction is still just before the caret, as far as can
+    /// be told: same window, and the caret on the same line.
+    ///
+    /// The caret is checked against the line only, since the correction
+    /// itself can move it back (a shorter word) as well as forward.
+    fn in_place(&self) -> bool {
+        crate::process::foreground_window_id() == self.window
+            && crate::anchor::same_line(self.caret, crate::anchor::snapshot())
+    }
+}
+
+/// A word left as typed, which the `correct-word` hotkey can still correct
+/// while nothing but `after` has been typed since.
+struct UncorrectedWord {
+    word: String,
+    /// Typed after the word: the space or punctuation that ended it.
+    after: String,
+    number: String,
+    previous_word: Option<String>,
+    sentence_start: bool,
+    /// Foreground window the word was typed into.
+    window: usize,
+    /// Caret when the word ended.
+    caret: Option<ScreenRect>,
+}
+
+impl UncorrectedWord {
+    /// Whether the word is still just before the caret, as far as can be
+    /// told.
+    fn in_place(&self) -> bool {
+        crate::process::foreground_window_id() == self.window
+            && crate::anchor::caret_stayed(self.caret, crate::anchor::snapshot())
+    }
+}
+
+/// Text to type over what's on screen, and how (see
+/// `Corrector::plan_replacement`).
+#[derive(Debug)]
+struct Replacement {
+    original: String,
+    text: String,
+    strategy: InputStrategy,
+    app_type: AppType,
+    process: Option<String>,
+    #[cfg(windows)]
+    key_delay_ms: u64,
+    #[cfg(windows)]
+    paste_delay_ms: u64,
+    #[cfg(windows)]
+    delete_word: bool,
+}
+
+impl Replacement {
+    /// Type it; `false` if injection failed (see `Corrector::replace_text`).
+    fn type_text(&self) -> bool {
+        #[cfg(windows)]
+        unsafe {
+            Corrector::type_replacement(
+                &self.original,
+                &self.text,
+                self.strategy,
+                self.key_delay_ms,
+                self.paste_delay_ms,
+                self.delete_word,
+            )
+        }
+        #[cfg(not(windows))]
+        {
+            let _ = (&self.original, &self.text, self.app_type, &self.process);
+            true
+        }
+    }
+}
+
+/// An edit to the text from a hotkey action: the keys to type, and what to
+/// remember once they've been typed.
+///
+/// The keyboard hook takes the corrector's lock for every key, the edit's
+/// own included, so the edit is planned under the lock and typed after it's
+/// released (see `Corrector::finish_edit`).
+#[derive(Debug)]
+pub struct PendingEdit {
+    replacement: Replacement,
+    kind: EditKind,
+}
+
+impl PendingEdit {
+    /// Type the edit; `false` if injection failed.
+    pub fn type_text(&self) -> bool {
+        self.replacement.type_text()
+    }
+}
+
+#[derive(Debug)]
+enum EditKind {
+    /// Reverting the most recent correction.
+    Undo(UndoState),
+    /// Replacing the most recent correction with `next`.
+    NextSuggestion { undo: UndoState, next: String },
+    /// Correcting the word just finished.
+    Correction(WordCorrection),
+    /// Fixing the word just finished and the one before it.
+    Grammar {
+        fix: GrammarFix,
+        caret: Option<ScreenRect>,
+    },
+}
+
+/// A correction of `word`, just finished, followed by `after`.
+#[derive(Debug)]
+struct WordCorrection {
+    word: String,
+    correction: String,
+    after: String,
+    anchor: Option<ScreenRect>,
+    /// Other suggestions for the word, best first.
+    suggestions: Vec<String>,
+    started: Instant,
+    lookup_time: Duration,
+}
+
+/// How quotes and dashes are typed in the foreground app (see
+/// `Corrector::typography_input`).
+#[derive(Debug, Clone, Copy)]
+struct TypographyInput {
+    /// Listed under `[apps] no_typography`: they stay straight.
+    straight: bool,
+    strategy: InputStrategy,
+    #[cfg(windows)]
+    key_delay_ms: u64,
+    #[cfg(windows)]
+    paste_delay_ms: u64,
 }
 
 /// Detected application type for input method selection.
@@ -78,46 +273,356 @@ impl AppType {
     }
 }
 
+/// Apps known to delete exactly the word before the caret on
+/// Ctrl+Backspace (`[apps] ctrl_backspace`). Classic Win32 edit controls
+/// type a box character instead, so it's opt-in per app.
+pub const DEFAULT_CTRL_BACKSPACE_APPS: &[&str] = &["winword.exe", "outlook.exe"];
+
+/// Chat apps where Enter sends the message (`[apps] enter_sends`): a
+/// correction typed on Enter can race the send and leave half-replaced text
+/// in the message.
+pub const DEFAULT_ENTER_SENDS_APPS: &[&str] = &[
+    "slack.exe",
+    "discord.exe",
+    "ms-teams.exe",
+    "teams.exe",
+    "whatsapp.exe",
+    "telegram.exe",
+    "signal.exe",
+];
+
+/// Default `max_word_length`: the longest run of letters tracked as a word.
+/// Anything longer (a pasted-over URL, a base64 string, letters typed into
+/// an IME that wasn't detected) isn't a word, so the buffer stops growing
+/// one past the limit and the run is left alone, never reaching the
+/// dictionary lookup.
+pub const MAX_WORD_LEN: usize = 48;
+
+/// Longest run of text tracked between spaces, to recognize URLs and paths.
+const MAX_TOKEN_LEN: usize = 256;
+
+/// Default `correction_budget_ms`: most corrections type a handful of keys
+/// with a few milliseconds' delay each.
+pub const DEFAULT_CORRECTION_BUDGET_MS: u64 = 150;
+
+/// Tag in `dwExtraInfo` of keystrokes we inject, so the keyboard hook can
+/// tell them apart from input injected by other software.
+pub const INJECTION_MARKER: usize = 0x4143_5252;
+
+/// How synthesized keystrokes are delivered to the focused window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputStrategy {
+    /// `SendInput` with thread attachment (default for standard apps).
+    SendInput,
+    /// `WM_KEYDOWN`/`WM_CHAR` messages delivered with `SendMessage`.
+    SendMessage,
+    /// Backspaces with `SendInput`, then the text pasted with Ctrl+V; only
+    /// when chosen for an app under `[apps.input]`.
+    Clipboard,
+}
+
+/// Reasons autocorrect can be suspended automatically, independent of the
+/// user's enable/disable toggle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    /// Known conflicting keyboard software is running.
+    ConflictingSoftware,
+    /// The user pressed the pause hotkey.
+    Hotkey,
+    /// A secure desktop or OS credential prompt has the keyboard.
+    SecureInput,
+    /// A password field has keyboard focus.
+    PasswordField,
+    /// The foreground window's title matches `[apps] excluded_titles`.
+    ExcludedWindow,
+    /// The foreground app is off under `[apps] disabled` or `enabled_only`.
+    ExcludedApp,
+    /// A remote desktop or VM client is in the foreground.
+    RemoteSession,
+    /// Another program is injecting keystrokes (`injected_input = "pause"`).
+    InjectedInput,
+    /// The user clicked the widget.
+    Widget,
+    /// Windows' own autocorrect is on (`os_autocorrect = "yield"`).
+    OsAutocorrect,
+    /// The keyboard layout is for a language without spaces between words,
+    /// or typed through an IME (see `segmentation`).
+    UnsupportedScript,
+    /// Keys are coming faster than anyone types prose (see `burst`).
+    TypingBurst,
+    /// The focused window's IME is composing what's typed (see `ime`).
+    ImeComposition,
+    /// The foreground app runs elevated and ignores our input (see
+    /// `elevation`).
+    ElevatedWindow,
+    /// A fullscreen game is in the foreground (see `fullscreen`).
+    FullscreenGame,
+}
+
+impl PauseReason {
+    /// Short description for the tray tooltip.
+    pub fn description(self) -> &'static str {
+        match self {
+            PauseReason::ConflictingSoftware => "conflicting keyboard software",
+            PauseReason::Hotkey => "pause hotkey",
+            PauseReason::SecureInput => "security prompt",
+            PauseReason::PasswordField => "password field",
+            PauseReason::ExcludedWindow => "excluded window",
+            PauseReason::ExcludedApp => "turned off in this app",
+            PauseReason::RemoteSession => "remote desktop or VM",
+            PauseReason::InjectedInput => "input from another program",
+            PauseReason::Widget => "widget",
+            PauseReason::OsAutocorrect => "Windows autocorrect is on",
+            PauseReason::UnsupportedScript => "input language not supported",
+            PauseReason::TypingBurst => "typing too fast to be text",
+            PauseReason::ImeComposition => "input method composing",
+            PauseReason::ElevatedWindow => "app running as administrator",
+            PauseReason::FullscreenGame => "fullscreen game",
+        }
+    }
+}
+
 /// The main autocorrection engine.
 pub struct Corrector {
     dictionary: Dictionary,
     current_word: String,
+    /// Accent of a dead key just pressed, waiting for the next key.
+    dead_key: Option<char>,
+    /// The word has a letter typed after a dead key we can't combine, so
+    /// what's on screen is unknown; it's left as typed.
+    unknown_accent: bool,
+    /// Characters kept in the word between letters (`word_characters`)
+    word_characters: String,
+    /// Longer words are abandoned uncorrected (`max_word_length`)
+    max_word_len: usize,
+    /// Separator typed right after the word; the next key decides whether
+    /// it ends the word or continues a URL or path (see `protected`)
+    pending_separator: Option<char>,
+    /// Everything typed since the last space or Enter
+    token: String,
+    /// A letter of the word was typed by holding its key down ("hellooooo")
+    auto_repeated: bool,
+    /// Caret when the word's first letter was typed (see `anchor`)
+    word_anchor: Option<ScreenRect>,
     enabled: bool,
-    max_edit_distance: i32,
-    undo_timeout_seconds: u64,
+    /// Correct words as they are finished, not only on the hotkey
+    auto_correct: bool,
+    /// The last word, if it was left as typed and is still at the caret
+    uncorrected: Option<UncorrectedWord>,
+    /// Correct the word being finished even with `auto_correct` off
+    forcing: bool,
+    /// Rules for accepting a suggestion as a correction
+    ranking: RankingPolicy,
+    /// Undo window, undo expiry while typing, and correction cooldown
+    timing: TimingPolicy,
     undo_buffer: Option<UndoState>,
-    ctrl_pressed: bool,
     last_correction_time: Option<Instant>,
     /// Cached application type of the current focused window
     last_app_type: Option<AppType>,
+    /// Custom dictionary file, if one was given on the command line
+    dictionary_path: Option<std::path::PathBuf>,
+    /// Half-life of learned word boosts, reapplied when the dictionary is rebuilt
+    learning_half_life_days: f64,
+    /// Undos of the same correction after which the word is learned
+    learn_after_undos: u32,
+    /// Other suggestions kept for "next suggestion" (`max_alternatives`)
+    max_alternatives: usize,
+    /// Input strategy forced for all apps (e.g. after a failed self-test)
+    strategy_override: Option<InputStrategy>,
+    /// Input method and key delay forced per app (`[apps.input]`), by
+    /// lowercase executable name
+    app_input: HashMap<String, AppInput>,
+    /// Active automatic pauses; corrections resume when this is empty
+    pause_reasons: Vec<PauseReason>,
+    /// The most recently finished word, exactly as typed (before correction)
+    last_word: Option<String>,
+    /// Usage counters for this session
+    stats: SessionStats,
+    /// Correction policy for names, acronyms and ordinals
+    word_types: WordTypesConfig,
+    /// Whether the next word starts a sentence
+    sentence_start: bool,
+    /// Digits typed directly before the word being typed, with no separator
+    number: String,
+    /// Confusable-word rules and what to do when one matches
+    grammar: Grammar,
+    grammar_mode: GrammarMode,
+    /// The previous word as it appears in the text, if only a space
+    /// separates it from the word being typed
+    previous_word: Option<String>,
+    /// What to do with keystrokes injected by other software
+    injected_input: InjectedInputPolicy,
+    /// Held while finishing a word, leaves the word as typed
+    bypass_key: BypassKey,
+    /// Corrections slower than this are logged and counted
+    correction_budget: Duration,
+    /// Lowercase words left alone until the application exits
+    session_ignored: HashSet<String>,
+    /// Corrections per day for the weekly typing report
+    mistakes: MistakeLog,
+    typing_report: bool,
+    /// Log corrections instead of typing them
+    dry_run: bool,
+    /// Key delays tuned per process
+    pacing: KeyPacing,
+    auto_tune_key_delay: bool,
+    /// Stages deciding on each word's correction
+    pipeline: Pipeline,
+    /// Per-app cap on corrections per minute
+    rate_limiter: RateLimiter,
+    /// Pauses corrections while keys come faster than prose
+    burst: BurstDetector,
+    /// Plugin correction providers
+    providers: Providers,
+    /// The most recent correction as typed and as corrected, for reporting;
+    /// never one made in a sensitive app
+    last_correction: Option<(String, String)>,
+    /// Executables where words are deleted with Ctrl+Backspace, lowercase
+    ctrl_backspace_apps: Vec<String>,
+    /// Enter finishes a word with a correction (`correct_on_enter`)
+    correct_on_enter: bool,
+    /// Executables where Enter never corrects (`[apps] enter_sends`)
+    enter_sends_apps: Vec<String>,
+    /// Type quotes, dashes and ellipses typographically (`typography`)
+    typography: bool,
+    /// Executables where they stay straight (`[apps] no_typography`)
+    no_typography_apps: Vec<String>,
+    /// How they're typed in the foreground app, until focus changes
+    typography_input: Option<TypographyInput>,
+    /// Languages whose accents are restored (`restore_diacritics`)
+    restore_diacritics: Vec<String>,
 }
 
 impl Corrector {
     pub fn new() -> Self {
-        Self::new_with_settings(2, true, 5)
+        Self::new_with_settings(2, true)
     }
 
     pub fn new_with_config(config: &crate::config::Config) -> Self {
-        Self::new_with_settings(
-            config.max_edit_distance,
-            config.enabled_by_default,
-            config.undo_timeout_seconds,
-        )
+        let mut corrector =
+            Self::new_with_settings(config.max_edit_distance, config.enabled_by_default);
+        corrector.timing = config.timing;
+        corrector.ranking.strategy = config.selection_strategy;
+        corrector.ranking.min_word_length = config.min_word_length;
+        corrector.ranking.min_frequency = config.min_correction_frequency;
+        corrector.ranking.min_frequency_ratio = config.min_correction_ratio;
+        corrector.set_correction_mode(config.correction_mode);
+        corrector.typing_report = config.typing_report;
+        corrector.learning_half_life_days = config.learning_half_life_days;
+        corrector.learn_after_undos = config.learn_after_undos;
+        corrector.max_alternatives = config.max_alternatives;
+        corrector
+            .dictionary
+            .set_learning_half_life_days(config.learning_half_life_days);
+        corrector.dictionary.set_language(&config.language);
+        corrector
+            .dictionary
+            .set_foreign_passthrough(config.foreign_word_passthrough);
+        corrector
+            .dictionary
+            .set_spelling_variant(config.spelling_variant);
+        corrector
+            .dictionary
+            .set_keyboard_layout(config.keyboard_layout);
+        corrector.word_characters = config.word_characters.clone();
+        corrector.max_word_len = config.max_word_length.max(1);
+        corrector.word_types = config.word_types.clone();
+        corrector.injected_input = config.injected_input;
+        corrector.bypass_key = config.bypass_key;
+        corrector.dry_run = config.dry_run;
+        corrector.auto_correct = config.auto_correct;
+        corrector.auto_tune_key_delay = config.auto_tune_key_delay;
+        corrector.pipeline = Pipeline::new(&config.pipeline.stages);
+        corrector.ctrl_backspace_apps = config
+            .apps
+            .ctrl_backspace
+            .iter()
+            .map(|app| app.trim().to_lowercase())
+            .collect();
+        corrector.correct_on_enter = config.correct_on_enter;
+        corrector.enter_sends_apps = config.apps.enter_sends.clone();
+        corrector.typography = config.typography;
+        corrector.no_typography_apps = config.apps.no_typography.clone();
+        corrector.app_input = config
+            .apps
+            .input
+            .iter()
+            .map(|(app, input)| (app.trim().to_lowercase(), input.checked(app)))
+            .collect();
+        corrector.restore_diacritics = config.restore_diacritics.clone();
+        corrector.rate_limiter = RateLimiter::new(config.max_corrections_per_minute);
+        corrector.burst = BurstDetector::new(config.burst_max_keys, config.burst_window_ms);
+        if config.load_plugins {
+            corrector.providers = crate::plugins::load();
+        }
+        corrector.correction_budget = Duration::from_millis(config.correction_budget_ms);
+        corrector.grammar_mode = config.grammar.mode;
+        if corrector.grammar_mode != GrammarMode::Off {
+            corrector.grammar = Grammar::load();
+        }
+        corrector
     }
 
-    fn new_with_settings(max_edit_distance: i32, enabled: bool, undo_timeout_seconds: u64) -> Self {
+    fn new_with_settings(max_edit_distance: i32, enabled: bool) -> Self {
         let max_edit_distance = max_edit_distance.max(0);
 
         Self {
             dictionary: Dictionary::new(),
             current_word: String::new(),
+            dead_key: None,
+            unknown_accent: false,
+            word_characters: crate::keymap::DEFAULT_WORD_CHARACTERS.to_string(),
+            max_word_len: MAX_WORD_LEN,
+            pending_separator: None,
+            token: String::new(),
+            auto_repeated: false,
+            word_anchor: None,
             enabled,
-            max_edit_distance,
-            undo_timeout_seconds,
+            auto_correct: true,
+            uncorrected: None,
+            forcing: false,
+            ranking: RankingPolicy::with_max_edit_distance(max_edit_distance),
+            timing: TimingPolicy::default(),
             undo_buffer: None,
-            ctrl_pressed: false,
             last_correction_time: None,
             last_app_type: None,
+            dictionary_path: None,
+            learning_half_life_days: crate::learning::DEFAULT_HALF_LIFE_DAYS,
+            learn_after_undos: crate::config::DEFAULT_LEARN_AFTER_UNDOS,
+            max_alternatives: DEFAULT_MAX_ALTERNATIVES,
+            strategy_override: None,
+            app_input: HashMap::new(),
+            pause_reasons: Vec::new(),
+            last_word: None,
+            stats: SessionStats::new(),
+            word_types: WordTypesConfig::default(),
+            sentence_start: true,
+            number: String::new(),
+            grammar: Grammar::builtin(),
+            grammar_mode: GrammarMode::Suggest,
+            previous_word: None,
+            injected_input: InjectedInputPolicy::Track,
+            bypass_key: BypassKey::None,
+            correction_budget: Duration::from_millis(DEFAULT_CORRECTION_BUDGET_MS),
+            session_ignored: HashSet::new(),
+            mistakes: MistakeLog::new(),
+            typing_report: false,
+            dry_run: false,
+            pacing: KeyPacing::new(),
+            auto_tune_key_delay: false,
+            pipeline: Pipeline::default(),
+            rate_limiter: RateLimiter::new(crate::ratelimit::DEFAULT_MAX_PER_MINUTE),
+            burst: BurstDetector::default(),
+            providers: Vec::new(),
+            last_correction: None,
+            ctrl_backspace_apps: Vec::new(),
+            correct_on_enter: true,
+            enter_sends_apps: Vec::new(),
+            typography: false,
+            no_typography_apps: Vec::new(),
+            typography_input: None,
+            restore_diacritics: Vec::new(),
         }
     }
 
@@ -129,193 +634,1745 @@ impl Corrector {
         &mut self,
         dictionary_path: Option<&std::path::Path>,
     ) -> Result<(), Box<dyn std::error::Error>> {
+        self.dictionary_path = dictionary_path.map(|path| path.to_path_buf());
         self.dictionary.load_from_path(dictionary_path)?;
+        self.load_stores();
+        Ok(())
+    }
+
+    /// Like `initialize_with_dictionary`, but leaves the built-in words queued
+    /// for [`Corrector::index_dictionary`] so startup doesn't wait for the
+    /// whole delete index to be built.
+    ///
+    /// # Errors
+    /// Returns an error if dictionary files cannot be read.
+    pub fn begin_initialize_with_dictionary(
+        &mut self,
+        dictionary_path: Option<&std::path::Path>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.dictionary_path = dictionary_path.map(|path| path.to_path_buf());
+        self.dictionary.begin_load(dictionary_path)?;
+        self.load_stores();
         Ok(())
     }
 
+    /// Load the mistake log and tuned key delays, if enabled.
+    fn load_stores(&mut self) {
+        if self.typing_report {
+            if let Err(err) = self.mistakes.load() {
+                eprintln!("Failed to load mistake log: {err}");
+            }
+        }
+        if self.auto_tune_key_delay {
+            if let Err(err) = self.pacing.load() {
+                eprintln!("Failed to load key delays: {err}");
+            }
+        }
+    }
+
+    /// Index up to `max_words` queued dictionary words; returns `true` if
+    /// more remain.
+    pub fn index_dictionary(&mut self, max_words: usize) -> bool {
+        self.dictionary.index_queued(max_words)
+    }
+
+    /// Dictionary indexing progress in percent, or `None` when complete.
+    pub fn dictionary_load_progress(&self) -> Option<u8> {
+        self.dictionary.load_progress()
+    }
+
+    /// Delete all personal data files and rebuild the dictionary without them.
+    ///
+    /// Also forgets the in-progress word, undo buffer and session statistics
+    /// so nothing typed before the reset survives in memory (or comes back
+    /// in the status file).
+    ///
+    /// # Errors
+    /// Returns an error if a data file cannot be deleted or the dictionary
+    /// cannot be reloaded.
+    pub fn reset_personal_data(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.current_word.clear();
+        self.previous_word = None;
+        self.undo_buffer = None;
+        self.last_word = None;
+        self.last_correction = None;
+        self.session_ignored.clear();
+        self.mistakes = MistakeLog::new();
+        self.pacing = KeyPacing::new();
+        self.stats = SessionStats::new();
+
+        let removed = crate::paths::remove_personal_data()?;
+        for path in &removed {
+            println!("Removed {}", path.display());
+        }
+
+        let spelling_variant = self.dictionary.spelling_variant();
+        let keyboard_layout = self.dictionary.keyboard_layout();
+        self.dictionary = Dictionary::load_language(
+            self.dictionary_path.as_deref(),
+            self.dictionary.language(),
+            self.learning_half_life_days,
+            self.dictionary.foreign_passthrough(),
+        )?;
+        self.dictionary.set_spelling_variant(spelling_variant);
+        self.dictionary.set_keyboard_layout(keyboard_layout);
+        Ok(())
+    }
+
+    /// Code of the active language pack.
+    pub fn language(&self) -> &str {
+        self.dictionary.language()
+    }
+
+    /// Custom dictionary file given on the command line, if any.
+    pub fn dictionary_path(&self) -> Option<&Path> {
+        self.dictionary_path.as_deref()
+    }
+
+    /// Half-life of learned word boosts, for building replacement dictionaries.
+    pub fn learning_half_life_days(&self) -> f64 {
+        self.learning_half_life_days
+    }
+
+    /// Whether replacement dictionaries load the other packs' words.
+    pub fn foreign_passthrough(&self) -> bool {
+        self.dictionary.foreign_passthrough()
+    }
+
+    /// Swap in a dictionary loaded with `Dictionary::load_language`, and
+    /// return the old one.
+    ///
+    /// Learned boosts carry over from the old dictionary. If the language
+    /// changed, the in-progress word and undo buffer are cleared since they
+    /// were checked against the old language. The old dictionary is returned
+    /// so it can be freed without holding the corrector's lock.
+    pub fn replace_dictionary(&mut self, mut dictionary: Dictionary) -> Dictionary {
+        dictionary.inherit_learning(&mut self.dictionary);
+        dictionary.set_spelling_variant(self.dictionary.spelling_variant());
+        dictionary.set_keyboard_layout(self.dictionary.keyboard_layout());
+        let previous = std::mem::replace(&mut self.dictionary, dictionary);
+        if previous.language() == self.dictionary.language() {
+            println!("Reloaded dictionary '{}'", self.dictionary.language());
+        } else {
+            self.current_word.clear();
+            self.undo_buffer = None;
+            println!("Switched dictionary to '{}'", self.dictionary.language());
+        }
+        previous
+    }
+
+    /// Persist learned word frequencies, undo counts, tuned key delays and
+    /// the mistake log (called on shutdown).
+    pub fn flush_learning(&mut self) {
+        if let Err(err) = self.dictionary.flush_learning() {
+            eprintln!("Failed to save learned frequencies: {err}");
+        }
+        if let Err(err) = self.pacing.flush() {
+            eprintln!("Failed to save key delays: {err}");
+        }
+        if self.typing_report {
+            if let Err(err) = self.mistakes.flush() {
+                eprintln!("Failed to save mistake log: {err}");
+            }
+        }
+    }
+
+    /// Persist what was learned while typing, once enough has changed
+    /// (called on the housekeeping timer).
+    ///
+    /// The keyboard hook only records it in memory: writing a file there
+    /// would hold up every key behind the disk.
+    pub fn flush_learning_if_due(&mut self) {
+        if let Err(err) = self.dictionary.flush_learning_if_due() {
+            eprintln!("Failed to save learned frequencies: {err}");
+        }
+        if let Err(err) = self.pacing.flush() {
+            eprintln!("Failed to save key delays: {err}");
+        }
+        if self.typing_report {
+            if let Err(err) = self.mistakes.flush_if_due() {
+                eprintln!("Failed to save mistake log: {err}");
+            }
+        }
+    }
+
+    /// Corrections per day, if the weekly typing report is enabled.
+    pub fn mistake_log(&self) -> Option<&MistakeLog> {
+        self.typing_report.then_some(&self.mistakes)
+    }
+
+    /// The most recently finished word, exactly as the user typed it.
+    pub fn last_word(&self) -> Option<&str> {
+        self.last_word.as_deref()
+    }
+
+    /// Keep a correction that was just typed for reporting, unless a
+    /// sensitive app is in front.
+    fn remember_correction(&mut self, typed: String, correction: String) {
+        self.last_correction = crate::privacy::recording_allowed().then_some((typed, correction));
+    }
+
+    /// The most recent correction as typed and as corrected.
+    pub fn last_correction(&self) -> Option<(&str, &str)> {
+        self.last_correction
+            .as_ref()
+            .map(|(typed, correction)| (typed.as_str(), correction.as_str()))
+    }
+
+    /// A report of the most recent correction, with the settings that
+    /// chose it.
+    pub fn bad_correction_report(&self) -> Option<BadCorrection> {
+        let (typed, correction) = self.last_correction.clone()?;
+        let app_type = self
+            .last_app_type
+            .map_or("Unknown".to_string(), |app_type| format!("{:?}", app_type));
+        let settings = vec![
+            ("version", env!("CARGO_PKG_VERSION").to_string()),
+            ("language", self.dictionary.language().to_string()),
+            (
+                "max_edit_distance",
+                self.ranking.max_edit_distance.to_string(),
+            ),
+            (
+                "selection_strategy",
+                format!("{:?}", self.ranking.strategy).to_lowercase(),
+            ),
+            (
+                "context_model",
+                self.dictionary.has_context_model().to_string(),
+            ),
+            ("grammar", format!("{:?}", self.grammar_mode).to_lowercase()),
+        ];
+        Some(BadCorrection {
+            typed,
+            correction,
+            app_type,
+            settings,
+        })
+    }
+
+    /// Add the most recently finished word to the personal dictionary.
+    ///
+    /// Returns the word that was added, or `None` if no word has been typed yet.
+    ///
+    /// # Errors
+    /// Returns an error if the personal dictionary file cannot be written.
+    pub fn add_last_word_to_dictionary(
+        &mut self,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let Some(word) = self.last_word.take() else {
+            return Ok(None);
+        };
+
+        self.dictionary.add_personal_word(&word)?;
+        crate::privacy::log_typed(format_args!("Added '{}' to personal dictionary", word));
+        Ok(Some(word))
+    }
+
+    /// Stop correcting the most recently finished word until the
+    /// application exits, without adding it to the personal dictionary.
+    ///
+    /// If the word was just corrected and can still be undone, the
+    /// correction is reverted too: the edit doing so comes back with the
+    /// word, to type with the lock released (see `PendingEdit`). Returns
+    /// `None` if no word has been typed yet.
+    pub fn ignore_last_word_for_session(&mut self) -> Option<(String, Option<PendingEdit>)> {
+        let word = self.last_word.clone()?;
+
+        let undo = if self
+            .undo_buffer
+            .as_ref()
+            .is_some_and(|undo| undo.original_word == word)
+        {
+            self.undo_last_correction()
+        } else {
+            None
+        };
+
+        self.session_ignored.insert(word.to_lowercase());
+        crate::privacy::log_typed(format_args!("Ignoring '{}' for this session", word));
+        Some((word, undo))
+    }
+
+    /// Forget the word being typed and the undo buffer after the foreground
+    /// window or focused control changed, so a half-typed word isn't
+    /// finished (and corrected) in another app, and Ctrl+Z can't retype into
+    /// a different document.
+    ///
+    /// The new control's text is unknown, so the next word is treated as the
+    /// start of a sentence.
+    pub fn on_focus_changed(&mut self) {
+        self.forget_word();
+        self.uncorrected = None;
+        self.sentence_start = true;
+        self.dead_key = None;
+        self.typography_input = None;
+        self.pacing.cancel_pending();
+        if self.undo_buffer.take().is_some() {
+            println!("Focus changed; undo no longer available");
+        }
+    }
+
+    /// Forget the word being typed after a mouse click, which has probably
+    /// moved the caret away from it (see `mouse`).
+    ///
+    /// The undo buffer is kept: undo checks the caret itself.
+    pub fn on_mouse_click(&mut self) {
+        self.forget_word();
+        self.uncorrected = None;
+    }
+
+    /// Forget the word being typed and what came before it.
+    fn forget_word(&mut self) {
+        self.current_word.clear();
+        self.pending_separator = None;
+        self.token.clear();
+        self.number.clear();
+        self.previous_word = None;
+    }
+
+    /// Count a key press (not a repeat, and not one we injected) towards
+    /// burst detection, pausing or resuming corrections (see `burst`).
+    ///
+    /// Called even while paused, so the pause can end.
+    pub fn on_keystroke(&mut self, now: Instant) {
+        let bursting = self.burst.on_key(now);
+        self.set_paused(PauseReason::TypingBurst, bursting);
+    }
+
+    /// Settle key delay tuning with a key the user pressed. Not called for
+    /// keys we injected ourselves.
+    pub fn on_user_key(&mut self, vk_code: u32) {
+        self.pacing.on_user_key(vk_code == VK_BACK, Instant::now());
+    }
+
+    /// How keystrokes injected by other software are handled.
+    pub fn injected_input_policy(&self) -> InjectedInputPolicy {
+        self.injected_input
+    }
+
+    /// Usage counters for this session.
+    pub fn stats(&self) -> &SessionStats {
+        &self.stats
+    }
+
     pub fn set_enabled(&mut self, enabled: bool) {
-        self.enabled = enabled;
+        if enabled != self.enabled {
+            self.toggle_enabled();
+        }
     }
 
     pub fn is_enabled(&self) -> bool {
         self.enabled
     }
 
+    /// Switch to a correction preset; `Custom` keeps the current policy.
+    pub fn set_correction_mode(&mut self, mode: CorrectionMode) {
+        if let Some(policy) = mode.policy() {
+            self.ranking = policy;
+        }
+    }
+
+    /// Find corrections but only log and count them (`--dry-run`).
+    pub fn set_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     pub fn toggle_enabled(&mut self) {
         self.enabled = !self.enabled;
+        if self.enabled {
+            self.rate_limiter.clear();
+        }
+        crate::events::emit(crate::events::Event::Toggle {
+            enabled: self.enabled,
+        });
+    }
+
+    /// Pause or resume corrections for a reason.
+    ///
+    /// Pausing forgets the in-progress word and undo buffer, since keystrokes
+    /// typed while paused aren't tracked. Returns `true` if the state changed.
+    pub fn set_paused(&mut self, reason: PauseReason, paused: bool) -> bool {
+        let was_paused = self.pause_reasons.contains(&reason);
+        if paused == was_paused {
+            return false;
+        }
+
+        if paused {
+            self.pause_reasons.push(reason);
+            self.forget_word();
+            self.uncorrected = None;
+            self.dead_key = None;
+            self.undo_buffer = None;
+        } else {
+            self.pause_reasons.retain(|r| *r != reason);
+        }
+        true
+    }
+
+    /// Whether corrections are currently paused for any reason.
+    pub fn is_paused(&self) -> bool {
+        !self.pause_reasons.is_empty()
+    }
+
+    /// Active pause reasons, oldest first.
+    pub fn pause_reasons(&self) -> &[PauseReason] {
+        &self.pause_reasons
+    }
+
+    /// Track a key press the keyboard hook saw, from the user or injected
+    /// with `extra_info`. Returns `true` if the key should be suppressed.
+    ///
+    /// Keys we inject (marked with [`INJECTION_MARKER`]) come back through
+    /// the hook as well. They're a correction or typographic character
+    /// being typed, whose text is already tracked as it reads, so they're
+    /// left alone: a backspace of a correction would take a letter off the
+    /// next word, or forget the previous one.
+    pub fn handle_hook_key(&mut self, vk_code: u32, repeat: bool, extra_info: usize) -> bool {
+        if extra_info == INJECTION_MARKER {
+            return false;
+        }
+        self.on_user_key(vk_code);
+        self.handle_key(vk_code, repeat)
+    }
+
+    /// Track a key press; `repeat` if it's the key being held down,
+    /// repeating. Returns `true` if the key should be suppressed.
+    fn handle_key(&mut self, vk_code: u32, repeat: bool) -> bool {
+        if vk_code == 0x5A && crate::keymap::ctrl_shortcut_held() {
+            // Ctrl+Z
+            return self.handle_undo();
+        }
+
+        if Some(vk_code) == self.bypass_key.vk_code() {
+            // Held for the separator that follows; doesn't end the word
+            return false;
+        }
+
+        if crate::keymap::is_modifier(vk_code) {
+            // Shift for a capital, AltGr for "ł" or "€": the word goes on
+            return false;
+        }
+
+        if crate::keymap::shortcut_held() && !self.bypass_key_held() {
+            // Ctrl+S, Alt+F, Win+E type nothing, and may have moved the
+            // caret or changed the text (Ctrl+V, Ctrl+Backspace). The
+            // word is kept for the correct-word hotkey, which is one of
+            // these shortcuts and arrives after its keys.
+            if !self.current_word.is_empty() {
+                self.uncorrected = Some(self.uncorrected_word(String::new()));
+            }
+            self.forget_word();
+            self.dead_key = None;
+            return false;
+        }
+
+        let mut unknown_accent = false;
+        let typed = match crate::keymap::translate(vk_code) {
+            Typed::Dead(accent) => {
+                // Nothing is typed until the next key
+                self.dead_key = Some(accent);
+                return false;
+            }
+            // An accent and a letter that don't combine are typed as
+            // two characters; the accent ends the word like any symbol
+            Typed::Char(ch) => match self.dead_key.take() {
+                Some(accent) if !crate::keymap::is_known_accent(accent) => {
+                    unknown_accent = true;
+                    Some(ch)
+                }
+                Some(accent) => crate::keymap::compose(accent, ch),
+                None => Some(ch),
+            },
+            Typed::Nothing => None,
+        };
+
+        let typographic = typed.zip(typed.and_then(|ch| self.typographic(ch)));
+        self.handle_typed(vk_code, typed);
+        if typed.is_some_and(char::is_alphabetic) {
+            self.auto_repeated |= repeat;
+            self.unknown_accent |= unknown_accent;
+        }
+        // The key is swallowed if its typographic form was typed instead
+        typographic.is_some_and(|(ch, (replaced, replacement))| {
+            self.type_typographic(ch, replaced, replacement)
+        })
+    }
+
+    /// Track a key that typed `typed` (`None` if it typed nothing).
+    fn handle_typed(&mut self, vk_code: u32, typed: Option<char>) {
+        // Anything typed after a word left as typed puts it out of reach
+        self.uncorrected = None;
+
+        if crate::keymap::is_navigation(vk_code) {
+            // The caret left the end of the word, or Delete took out a
+            // selection holding it: nothing tracked is at the caret, not
+            // even a correction to undo. Shift+arrows select the same way.
+            self.forget_word();
+            self.dead_key = None;
+            self.undo_buffer = None;
+            return;
+        }
+
+        let handled = self
+            .pending_separator
+            .take()
+            .is_some_and(|separator| self.resolve_separator(separator, vk_code, typed));
+        if !handled {
+            match (vk_code, typed) {
+                (VK_BACK, _) => {
+                    self.dead_key = None;
+                    self.handle_backspace();
+                }
+                (VK_SPACE | VK_RETURN, _) => {
+                    // Only a single space keeps the previous word (or a bare
+                    // number) in context
+                    let had_word = !self.current_word.is_empty();
+                    if self.enter_leaves_word(vk_code) {
+                        self.finish_word_as_typed();
+                    } else {
+                        self.handle_word_end();
+                    }
+                    let number = std::mem::take(&mut self.number);
+                    if vk_code == VK_RETURN {
+                        self.previous_word = None;
+                        self.sentence_start = true;
+                    } else if !had_word {
+                        self.previous_word = Some(number).filter(|n| !n.is_empty());
+                    }
+                }
+                // Period: keep it in the word while it continues an
+                // abbreviation, so "e.g." is one token
+                (_, Some('.'))
+                    if !self.current_word.is_empty()
+                        && crate::abbreviations::continues_abbreviation(&format!(
+                            "{}.",
+                            self.current_word
+                        )) =>
+                {
+                    self.current_word.push('.');
+                }
+                // Separator after a word: the next key tells a sentence end
+                // from "github.com" (see `protected`)
+                (_, Some(ch))
+                    if crate::protected::SEPARATORS.contains(ch)
+                        && !self.current_word.is_empty() =>
+                {
+                    self.pending_separator = Some(ch);
+                }
+                // Apostrophe or hyphen after a letter: part of the word if a
+                // letter follows ("don't", "well-known")
+                (_, Some(ch))
+                    if self.word_characters.contains(ch)
+                        && self.current_word.ends_with(char::is_alphabetic) =>
+                {
+                    self.handle_letter(ch);
+                }
+                (_, Some(ch)) if crate::keymap::ends_word(ch) => {
+                    self.handle_word_end();
+                    self.number.clear();
+                    self.previous_word = None;
+                    if crate::keymap::ends_sentence(ch) {
+                        self.sentence_start = true;
+                    }
+                }
+                (_, Some(ch)) if ch.is_alphabetic() => {
+                    self.handle_letter(ch);
+                }
+                (_, Some(ch)) if ch.is_ascii_digit() => {
+                    self.handle_digit(ch);
+                }
+                (_, typed) => {
+                    self.current_word.clear();
+                    self.previous_word = None;
+                    self.number.clear();
+                    if typed.is_some_and(crate::keymap::ends_sentence) {
+                        self.sentence_start = true;
+                    }
+                }
+            }
+        }
+
+        // A word this key ended is followed by what it types (except Enter,
+        // which apps don't all retype as a line break)
+        if let Some(uncorrected) = self.uncorrected.as_mut() {
+            match typed.filter(|_| vk_code != VK_RETURN) {
+                Some(ch) => uncorrected.after.push(ch),
+                None => self.uncorrected = None,
+            }
+        }
+
+        match (vk_code, typed) {
+            (VK_BACK, _) => {
+                self.token.pop();
+            }
+            (_, Some(ch)) if !ch.is_whitespace() => {
+                if self.token.len() < MAX_TOKEN_LEN {
+                    self.token.push(ch);
+                }
+            }
+            _ => self.token.clear(),
+        }
+    }
+
+    /// Decide on the word before a separator now that the next key is
+    /// known. Returns `true` if that key needs no more handling.
+    fn resolve_separator(&mut self, separator: char, vk_code: u32, typed: Option<char>) -> bool {
+        match (vk_code, typed) {
+            // The separator was deleted; the word goes on
+            (VK_BACK, _) => true,
+            // "github.com", "me@example.org", "C:\Users": the word before is
+            // left alone, and the token is checked when it ends
+            (_, Some(ch)) if ch.is_alphanumeric() => {
+                self.current_word.clear();
+                self.number.clear();
+                self.previous_word = None;
+                false
+            }
+            // The separator ended the word, and is on screen after it
+            _ => {
+                if self.enter_leaves_word(vk_code) {
+                    self.finish_word_as_typed();
+                } else if crate::keymap::ends_word(separator) {
+                    self.finish_word(&separator.to_string());
+                } else {
+                    self.current_word.clear();
+                }
+                self.number.clear();
+                self.previous_word = None;
+                if crate::keymap::ends_sentence(separator) {
+                    self.sentence_start = true;
+                }
+                false
+            }
+        }
+    }
+
+    /// Whether `vk_code` is Enter, and the word before it is to be left as
+    /// typed (see `corrects_on_enter`). The app is only looked up for
+    /// Enter.
+    fn enter_leaves_word(&self, vk_code: u32) -> bool {
+        vk_code == VK_RETURN
+            && !self.corrects_on_enter(crate::process::foreground_process_name().as_deref())
+    }
+
+    /// Finish the word without looking it up: Enter may send the message
+    /// before a correction is typed.
+    fn finish_word_as_typed(&mut self) {
+        if !self.current_word.is_empty() {
+            self.stats.record_word(false);
+            self.last_word = Some(std::mem::take(&mut self.current_word));
+        }
+    }
+
+    fn handle_letter(&mut self, ch: char) {
+        if self.undo_buffer.is_some() {
+            if let Some(correction_time) = self.last_correction_time {
+                if self
+                    .timing
+                    .typing_expires_undo(correction_time, Instant::now())
+                {
+                    self.undo_buffer = None;
+                }
+            }
+        }
+
+        if self.current_word.is_empty() {
+            self.auto_repeated = false;
+            self.unknown_accent = false;
+            self.word_anchor = crate::anchor::snapshot();
+        }
+        // One past the limit is enough to know the word is too long
+        if self.current_word.chars().count() <= self.max_word_len {
+            self.current_word.push(ch);
+        }
     }
 
-    pub fn handle_key(&mut self, vk_code: u32) -> bool {
+    /// Digits start (or continue) a number; a digit after letters starts a
+    /// new one, since "abc1st" isn't an ordinal.
+    fn handle_digit(&mut self, digit: char) {
+        if !self.current_word.is_empty() {
+            self.current_word.clear();
+            self.number.clear();
+        }
+        self.previous_word = None;
+        self.number.push(digit);
+    }
+
+    fn handle_backspace(&mut self) {
+        if self.current_word.is_empty() {
+            // Deleting a digit, or the separator: the previous word's
+            // context is gone
+            if self.number.pop().is_none() {
+                self.previous_word = None;
+            }
+        } else {
+            self.current_word.pop();
+        }
+    }
+
+    fn handle_word_end(&mut self) {
+        self.finish_word("");
+    }
+
+    /// Look up the word just finished, with `after` typed right after it
+    /// (a separator, see `resolve_separator`); a correction retypes both.
+    fn finish_word(&mut self, after: &str) {
+        if let Some(edit) = self.plan_word(after) {
+            self.apply_edit(edit);
+        }
+    }
+
+    /// Look up the word just finished (see `finish_word`), returning the
+    /// correction or grammar fix to type, if any.
+    fn plan_word(&mut self, after: &str) -> Option<PendingEdit> {
+        if self.current_word.is_empty() {
+            return None;
+        }
+
+        if self.current_word.contains('.') {
+            self.finish_abbreviation();
+            return None;
+        }
+
+        // Too long to be a word, or not the word on screen
+        if self.unknown_accent || self.current_word.chars().count() > self.max_word_len {
+            self.current_word.clear();
+            self.previous_word = None;
+            return None;
+        }
+
+        if self
+            .current_word
+            .ends_with(|c| self.word_characters.contains(c))
+            || crate::protected::is_protected(&self.token)
+        {
+            // "dogs'" or "pre-": no letter followed, so the apostrophe or
+            // hyphen is on screen after the word. It's left as typed, like
+            // the end of a URL, email address or path.
+            self.sentence_start = false;
+            self.previous_word = None;
+            self.stats.record_word(false);
+            self.last_word = Some(std::mem::take(&mut self.current_word));
+            return None;
+        }
+
+        if !self.auto_correct && !self.forcing {
+            // Left for the correct-word hotkey
+            self.uncorrected = Some(self.uncorrected_word(after.to_string()));
+            self.sentence_start = false;
+            self.dictionary.record_usage(&self.current_word);
+            self.stats.record_word(false);
+            self.previous_word = Some(self.current_word.clone());
+            self.last_word = Some(std::mem::take(&mut self.current_word));
+            return None;
+        }
+
+        let started = Instant::now();
+        // Where the correction goes, for undo to check it is still there
+        let anchor = crate::anchor::snapshot();
+        let word_lower = self.current_word.to_lowercase();
+        let class = crate::classify::classify(
+            &self.current_word,
+            TokenContext {
+                sentence_start: self.sentence_start,
+                after_digits: !self.number.is_empty(),
+            },
+        );
+        self.sentence_start = false;
+        let bypass = self.bypass_key_held();
+
+        let decision = self.pipeline.decide(
+            &WordInput {
+                word: &self.current_word,
+                word_lower: &word_lower,
+                class,
+                number: &self.number,
+                previous_word: self.previous_word.as_deref(),
+                auto_repeated: self.auto_repeated,
+            },
+            &StageContext {
+                dictionary: &self.dictionary,
+                ranking: &self.ranking,
+                word_types: &self.word_types,
+                session_ignored: &self.session_ignored,
+                timing: &self.timing,
+                last_correction: self.last_correction_time,
+                now: started,
+                bypass,
+                restore_diacritics: self
+                    .restore_diacritics
+                    .iter()
+                    .any(|language| language.eq_ignore_ascii_case(self.dictionary.language())),
+                // Typed words never reach plugins from sensitive apps
+                providers: if crate::privacy::recording_allowed() {
+                    &self.providers
+                } else {
+                    &[]
+                },
+            },
+        );
+        let correction = decision.correction;
+
+        if let Some(correction) = correction.as_ref().filter(|_| self.dry_run) {
+            crate::privacy::log_typed(format_args!(
+                "Dry run: would correct '{}' -> '{}'",
+                self.current_word, correction
+            ));
+            self.dictionary.record_usage(&word_lower);
+            self.stats.record_dry_run_correction();
+            self.previous_word = Some(self.current_word.clone());
+        } else if let Some(correction) = correction
+            .filter(|_| self.caret_anchored(self.word_anchor) && self.within_rate_limit(started))
+        {
+            self.last_correction_time = Some(Instant::now());
+            let correction = if self.typography_active() {
+                crate::typography::curl_apostrophes(&correction)
+            } else {
+                correction
+            };
+            let original = format!("{}{after}", self.current_word);
+            return Some(PendingEdit {
+                replacement: self.plan_replacement(
+                    &original,
+                    &format!("{correction}{after}"),
+                    None,
+                ),
+                kind: EditKind::Correction(WordCorrection {
+                    word: std::mem::take(&mut self.current_word),
+                    correction,
+                    after: after.to_string(),
+                    anchor,
+                    suggestions: decision
+                        .suggestions
+                        .into_iter()
+                        .map(|suggestion| suggestion.display_term)
+                        .collect(),
+                    started,
+                    lookup_time: started.elapsed(),
+                }),
+            });
+        } else {
+            self.dictionary.record_usage(&word_lower);
+            self.stats.record_word(false);
+            if !bypass {
+                if let Some(edit) = self.plan_grammar(after) {
+                    return Some(edit);
+                }
+            }
+            self.previous_word = Some(self.current_word.clone());
+        }
+
+        self.last_word = Some(std::mem::take(&mut self.current_word));
+        None
+    }
+
+    /// Remember a correction just typed, or that it couldn't be, once
+    /// `typed` (see `plan_word`).
+    fn finish_correction(&mut self, correction: WordCorrection, strategy: Option<InputStrategy>) {
+        let WordCorrection {
+            word,
+            correction,
+            after,
+            anchor,
+            suggestions,
+            started,
+            lookup_time,
+        } = correction;
+        self.record_latency(started.elapsed(), lookup_time);
+
+        if let Some(strategy) = strategy {
+            let mut undo = UndoState::new(
+                &format!("{word}{after}"),
+                &format!("{correction}{after}"),
+                anchor,
+                strategy,
+            );
+            for suggestion in suggestions {
+                let alternative = format!("{suggestion}{after}");
+                if undo.alternatives.len() < self.max_alternatives
+                    && alternative != undo.corrected_word
+                    && !undo.alternatives.contains(&alternative)
+                {
+                    undo.alternatives.push(alternative);
+                }
+            }
+            self.undo_buffer = Some(undo);
+
+            crate::privacy::log_typed(format_args!("Corrected: '{}' -> '{}'", word, correction));
+            crate::events::emit(crate::events::Event::Correction {
+                original: &word,
+                replacement: &correction,
+            });
+            // A correction may be several words ("alot" -> "a lot")
+            for word in correction.split_whitespace() {
+                self.dictionary.record_usage(word);
+            }
+            if self.typing_report {
+                self.mistakes.record(&word, &correction);
+            }
+            self.remember_correction(word.clone(), correction.clone());
+            self.stats.record_word(true);
+            self.previous_word = correction.split_whitespace().last().map(str::to_string);
+        } else {
+            // Nothing to undo: the word reads as typed
+            self.undo_buffer = None;
+            self.stats.record_word(false);
+            self.previous_word = Some(word.clone());
+        }
+
+        self.last_word = Some(word);
+    }
+
+    /// The word being finished, as it stands, for the correct-word hotkey.
+    fn uncorrected_word(&self, after: String) -> UncorrectedWord {
+        UncorrectedWord {
+            word: self.current_word.clone(),
+            after,
+            number: self.number.clone(),
+            previous_word: self.previous_word.clone(),
+            sentence_start: self.sentence_start,
+            window: crate::process::foreground_window_id(),
+            caret: crate::anchor::snapshot(),
+        }
+    }
+
+    /// Correct the word just typed, as if it had been finished with
+    /// `auto_correct` on (the `correct-word` hotkey).
+    ///
+    /// Works on the word being typed, or one just ended by a space or
+    /// punctuation, as long as nothing else was typed since. Returns the
+    /// correction to type, with the lock released (see `PendingEdit`).
+    pub fn correct_last_word(&mut self) -> Option<PendingEdit> {
+        let uncorrected = self.uncorrected.take()?;
+        if !uncorrected.in_place() {
+            println!("Correct word refused: the word is no longer at the caret");
+            return None;
+        }
+
+        self.current_word = uncorrected.word;
+        self.number = uncorrected.number;
+        self.previous_word = uncorrected.previous_word;
+        self.sentence_start = uncorrected.sentence_start;
+        self.forcing = true;
+        let edit = self.plan_word(&uncorrected.after);
+        self.forcing = false;
+        edit
+    }
+
+    /// Whether the caret is still past where the word started (see
+    /// `anchor`).
+    fn caret_anchored(&self, anchor: Option<ScreenRect>) -> bool {
+        let stayed = crate::anchor::caret_stayed(anchor, crate::anchor::snapshot());
+        if !stayed {
+            println!("Correction dropped: the caret moved away from the word");
+        }
+        stayed
+    }
+
+    /// Count a correction in the foreground app; `false` if corrections are
+    /// paused there for going over the cap (see `ratelimit`).
+    fn within_rate_limit(&mut self, now: Instant) -> bool {
+        match crate::process::foreground_process_name() {
+            Some(process) => self.rate_limiter.allow(&process, now),
+            None => true,
+        }
+    }
+
+    /// The app where corrections were just paused for going over the
+    /// per-minute cap, once.
+    pub fn take_rate_limit_notice(&mut self) -> Option<String> {
+        self.rate_limiter.take_notice()
+    }
+
+    /// Whether the bypass key is held down right now.
+    fn bypass_key_held(&self) -> bool {
+        #[cfg(windows)]
+        {
+            self.bypass_key
+                .vk_code()
+                .is_some_and(|vk| unsafe { GetAsyncKeyState(vk as i32) } < 0)
+        }
         #[cfg(not(windows))]
         {
-            let _ = vk_code;
+            false
+        }
+    }
+
+    /// Count a correction's latency, warning if it was over budget.
+    ///
+    /// The warning names the app but not the word, so it's safe to log in
+    /// sensitive apps too.
+    fn record_latency(&self, total: Duration, lookup: Duration) {
+        if crate::stats::HOOK_COUNTERS.record_correction(total, self.correction_budget) {
+            eprintln!(
+                "Warning: correction took {} ms (lookup {} ms, budget {} ms) in {}",
+                total.as_millis(),
+                lookup.as_millis(),
+                self.correction_budget.as_millis(),
+                crate::process::foreground_process_name()
+                    .unwrap_or_else(|| "unknown app".to_string())
+            );
+        }
+    }
+
+    /// Finish a word that kept periods because it looked like an
+    /// abbreviation. It's never corrected or learned.
+    ///
+    /// A known abbreviation doesn't end the sentence; anything else ("e."
+    /// followed by a space) is treated as a word followed by a period.
+    fn finish_abbreviation(&mut self) {
+        let word = std::mem::take(&mut self.current_word);
+        self.sentence_start = !crate::abbreviations::is_abbreviation(&word) && word.ends_with('.');
+        self.number.clear();
+        self.stats.record_word(false);
+        self.previous_word = Some(word.clone());
+        self.last_word = Some(word);
+    }
+
+    /// Check the finished word and the one before it against the grammar
+    /// rules, suggesting or planning a fix per the grammar mode.
+    ///
+    /// `after` was typed right after the word, and is retyped with a fix.
+    fn plan_grammar(&mut self, after: &str) -> Option<PendingEdit> {
+        if self.grammar_mode == GrammarMode::Off {
+            return None;
+        }
+
+        let fix = self
+            .grammar
+            .check(self.previous_word.as_deref(), &self.current_word, |word| {
+                self.dictionary.part_of_speech(word)
+            })
+            .or_else(|| {
+                // Real words in the wrong place, judged by the user's writing
+                crate::grammar::check_in_context(
+                    self.previous_word.as_deref(),
+                    &self.current_word,
+                    |previous, word| self.dictionary.pair_count(previous, word),
+                )
+            })?;
+
+        // Dry-run mode downgrades fixes to suggestions
+        if self.grammar_mode == GrammarMode::Suggest || self.dry_run {
+            crate::privacy::log_typed(format_args!(
+                "Grammar suggestion: '{}' -> '{}'",
+                fix.original, fix.replacement
+            ));
+            return None;
+        }
+
+        let caret = crate::anchor::snapshot();
+        self.last_correction_time = Some(Instant::now());
+        let replacement = self.plan_replacement(
+            &format!("{}{after}", fix.original),
+            &format!("{}{after}", fix.replacement),
+            None,
+        );
+        Some(PendingEdit {
+            replacement,
+            kind: EditKind::Grammar { fix, caret },
+        })
+    }
+
+    /// Remember a grammar fix just typed, or that it couldn't be (see
+    /// `plan_grammar`).
+    fn finish_grammar(
+        &mut self,
+        fix: GrammarFix,
+        caret: Option<ScreenRect>,
+        replacement: &Replacement,
+        strategy: Option<InputStrategy>,
+    ) {
+        let word = std::mem::take(&mut self.current_word);
+        let Some(strategy) = strategy else {
+            self.undo_buffer = None;
+            self.previous_word = Some(word.clone());
+            self.last_word = Some(word);
+            return;
+        };
+        self.undo_buffer = Some(UndoState::new(
+            &replacement.original,
+            &replacement.text,
+            caret,
+            strategy,
+        ));
+
+        crate::privacy::log_typed(format_args!(
+            "Grammar: '{}' -> '{}'",
+            fix.original, fix.replacement
+        ));
+        crate::events::emit(crate::events::Event::Correction {
+            original: &fix.original,
+            replacement: &fix.replacement,
+        });
+        self.remember_correction(fix.original.clone(), fix.replacement.clone());
+        // The current word as it now reads
+        self.previous_word = fix.replacement.rsplit(' ').next().map(str::to_string);
+        self.last_word = Some(word);
+    }
+
+    /// Delete `original`, just typed, and type `text` instead, with
+    /// `strategy` or the one detected for the foreground app.
+    ///
+    /// Returns the strategy used, or `None` if injection failed; the original
+    /// text has then been restored, or focus moved to another window (see
+    /// `transaction`).
+    fn replace_text(
+        &mut self,
+        original: &str,
+        text: &str,
+        strategy: Option<InputStrategy>,
+    ) -> Option<InputStrategy> {
+        let replacement = self.plan_replacement(original, text, strategy);
+        self.type_replacement_now(&replacement)
+    }
+
+    /// Work out how to replace `original` with `text` in the foreground app
+    /// (see `replace_text`), without typing anything yet.
+    fn plan_replacement(
+        &self,
+        original: &str,
+        text: &str,
+        strategy: Option<InputStrategy>,
+    ) -> Replacement {
+        #[cfg(windows)]
+        let (app_type, process) = unsafe {
+            (
+                self.detect_app_type(),
+                crate::process::foreground_process_name(),
+            )
+        };
+        #[cfg(not(windows))]
+        let (app_type, process): (AppType, Option<String>) = (self.detect_app_type(), None);
+        let strategy = strategy.unwrap_or_else(|| self.strategy_for(app_type, process.as_deref()));
+
+        Replacement {
+            original: original.to_string(),
+            text: text.to_string(),
+            strategy,
+            app_type,
+            #[cfg(windows)]
+            key_delay_ms: self.key_delay_ms(app_type, process.as_deref()),
+            #[cfg(windows)]
+            paste_delay_ms: self.paste_delay_ms(process.as_deref()),
+            #[cfg(windows)]
+            delete_word: self.deletes_word(original, process.as_deref()),
+            process,
+        }
+    }
+
+    /// Type `replacement`, tuning the key delay for its app by how it went
+    /// if it was typed key by key (`SendInput` and the clipboard don't use
+    /// the delay). Returns the strategy used, or `None` if injection failed.
+    fn type_replacement_now(&mut self, replacement: &Replacement) -> Option<InputStrategy> {
+        #[cfg(windows)]
+        let failures_before = HOOK_COUNTERS.injection_failures();
+        let typed = replacement.type_text();
+        #[cfg(windows)]
+        if let Some(process) = replacement.process.as_ref().filter(|_| {
+            self.auto_tune_key_delay && replacement.strategy == InputStrategy::SendMessage
+        }) {
+            let failed = HOOK_COUNTERS.injection_failures() > failures_before;
+            self.pacing
+                .after_correction(process, replacement.app_type.key_delay_ms(), failed);
+        }
+        typed.then_some(replacement.strategy)
+    }
+
+    /// Type an edit planned with the lock held and remember it (see
+    /// `PendingEdit`); `true` if the text was changed.
+    fn apply_edit(&mut self, edit: PendingEdit) -> bool {
+        let typed = self.type_replacement_now(&edit.replacement).is_some();
+        self.finish_edit(edit, typed)
+    }
+
+    /// Remember an edit once it's been typed, or that it couldn't be, as
+    /// `typed` says. Returns `true` if the text was changed.
+    pub fn finish_edit(&mut self, edit: PendingEdit, typed: bool) -> bool {
+        let strategy = typed.then_some(edit.replacement.strategy);
+        match edit.kind {
+            EditKind::Undo(undo) => self.finish_undo(undo, typed),
+            EditKind::NextSuggestion { undo, next } => {
+                self.finish_next_suggestion(undo, next, typed)
+            }
+            EditKind::Correction(correction) => {
+                self.finish_correction(correction, strategy);
+                typed
+            }
+            EditKind::Grammar { fix, caret } => {
+                self.finish_grammar(fix, caret, &edit.replacement, strategy);
+                typed
+            }
+        }
+    }
+
+    /// Whether Enter finishes a word with a correction in `process`
+    /// (`correct_on_enter`, `[apps] enter_sends`).
+    fn corrects_on_enter(&self, process: Option<&str>) -> bool {
+        self.correct_on_enter
+            && !process.is_some_and(|process| {
+                self.enter_sends_apps
+                    .iter()
+                    .any(|app| app.eq_ignore_ascii_case(process))
+            })
+    }
+
+    /// Whether quotes and dashes are typed typographically in the
+    /// foreground app (`typography`, `[apps] no_typography`); never in dry
+    /// run or while the bypass key is held.
+    fn typography_active(&mut self) -> bool {
+        self.typography
+            && !self.dry_run
+            && !self.bypass_key_held()
+            && !self.typography_input().straight
+    }
+
+    /// How typographic characters are typed in the foreground app.
+    ///
+    /// They're typed from the keyboard hook as the user types, so the app
+    /// is only looked up at the first one after a focus change.
+    fn typography_input(&mut self) -> TypographyInput {
+        if let Some(input) = self.typography_input {
+            return input;
+        }
+        let process = crate::process::foreground_process_name();
+        #[cfg(windows)]
+        let app_type = unsafe { self.detect_app_type() };
+        #[cfg(not(windows))]
+        let app_type = self.detect_app_type();
+        let input = TypographyInput {
+            straight: process.as_deref().is_some_and(|process| {
+                self.no_typography_apps
+                    .iter()
+                    .any(|app| app.eq_ignore_ascii_case(process))
+            }),
+            strategy: self.strategy_for(app_type, process.as_deref()),
+            #[cfg(windows)]
+            key_delay_ms: self.key_delay_ms(app_type, process.as_deref()),
+            #[cfg(windows)]
+            paste_delay_ms: self.paste_delay_ms(process.as_deref()),
+        };
+        self.typography_input = Some(input);
+        input
+    }
+
+    /// The typographic form of `typed`, if it has one here (see
+    /// `typography::replace`).
+    fn typographic(&mut self, typed: char) -> Option<(&'static str, char)> {
+        if !matches!(typed, '"' | '\'' | '-' | '.') || !self.typography_active() {
+            return None;
+        }
+        crate::typography::replace(&self.token, typed)
+    }
+
+    /// Type `replacement` over the straight characters `replaced` before
+    /// `typed`, which has been tracked but not typed. Returns `false` if it
+    /// couldn't be, and `typed` should go through as it is.
+    ///
+    /// Unlike a correction, this doesn't tune the key delay: a quote is at
+    /// most three keys, typed with the app's delay as it was looked up.
+    fn type_typographic(&mut self, typed: char, replaced: &str, replacement: char) -> bool {
+        let input = self.typography_input();
+        #[cfg(windows)]
+        let sent = unsafe {
+            Self::type_replacement(
+                replaced,
+                &replacement.to_string(),
+                input.strategy,
+                input.key_delay_ms,
+                input.paste_delay_ms,
+                false,
+            )
+        };
+        #[cfg(not(windows))]
+        let sent = {
+            let _ = input.strategy;
+            true
+        };
+        if !sent {
+            return false;
+        }
+
+        // Track the text as it now reads: "don’t" is a word like "don't"
+        let straight = format!("{replaced}{typed}");
+        if let Some(kept) = self.token.strip_suffix(&straight) {
+            self.token = format!("{kept}{replacement}");
+        }
+        if let Some(kept) = self.current_word.strip_suffix(&straight) {
+            self.current_word = format!("{kept}{replacement}");
+        }
+        if let Some(uncorrected) = self.uncorrected.as_mut() {
+            if let Some(kept) = uncorrected.after.strip_suffix(&straight) {
+                uncorrected.after = format!("{kept}{replacement}");
+            }
+        }
+        true
+    }
+
+    /// Whether to delete `original` with Ctrl+Backspace in `process`
+    /// (`[apps] ctrl_backspace`).
+    ///
+    /// Only for a plain word, which every app deletes the same way; anything
+    /// with punctuation or spaces (a grammar fix over two words) takes
+    /// backspaces.
+    fn deletes_word(&self, original: &str, process: Option<&str>) -> bool {
+        !original.is_empty()
+            && original.chars().all(char::is_alphanumeric)
+            && process.is_some_and(|process| {
+                self.ctrl_backspace_apps
+                    .iter()
+                    .any(|app| app.eq_ignore_ascii_case(process))
+            })
+    }
+
+    /// Delay between injected keys for an application, tuned per process if
+    /// enabled.
+    #[cfg(windows)]
+    fn key_delay_ms(&self, app_type: AppType, process: Option<&str>) -> u64 {
+        if let Some(delay) = self.app_input(process).and_then(|input| input.key_delay_ms) {
+            return delay;
+        }
+        match process {
+            Some(process) if self.auto_tune_key_delay => {
+                self.pacing.delay_ms(process, app_type.key_delay_ms())
+            }
+            _ => app_type.key_delay_ms(),
+        }
+    }
+
+    /// How long `process` gets to read a pasted replacement before the
+    /// clipboard is restored.
+    #[cfg(windows)]
+    fn paste_delay_ms(&self, process: Option<&str>) -> u64 {
+        self.app_input(process)
+            .and_then(|input| input.paste_delay_ms)
+            .unwrap_or(PASTE_SETTLE_MS)
+    }
+
+    /// Pick the input strategy for an application, honoring any override.
+    fn input_strategy(&self, app_type: AppType) -> InputStrategy {
+        if let Some(strategy) = self.strategy_override {
+            return strategy;
+        }
+
+        if app_type.needs_sendmessage_fallback() {
+            InputStrategy::SendMessage
+        } else {
+            InputStrategy::SendInput
+        }
+    }
+
+    /// The input strategy for an application: the one configured for it
+    /// under `[apps.input]`, or else the detected one.
+    fn strategy_for(&self, app_type: AppType, process: Option<&str>) -> InputStrategy {
+        self.app_input(process)
+            .and_then(|input| input.strategy)
+            .unwrap_or_else(|| self.input_strategy(app_type))
+    }
+
+    /// Whether corrections in the foreground app are pasted through the
+    /// clipboard (see `clipboard::keep_snapshot`).
+    #[cfg(windows)]
+    pub fn pastes_in_foreground(&self) -> bool {
+        let process = crate::process::foreground_process_name();
+        let app_type = unsafe { self.detect_app_type() };
+        self.strategy_for(app_type, process.as_deref()) == InputStrategy::Clipboard
+    }
+
+    /// Input overrides configured for `process`, if any.
+    fn app_input(&self, process: Option<&str>) -> Option<&AppInput> {
+        self.app_input.get(&process?.to_lowercase())
+    }
+
+    /// Force a specific input strategy for all applications (`None` restores detection).
+    pub fn set_strategy_override(&mut self, strategy: Option<InputStrategy>) {
+        self.strategy_override = strategy;
+    }
+
+    /// Replace `original`, just before the caret, with `text` in the focused
+    /// window.
+    ///
+    /// This is the single injection path shared by corrections, undo and the
+    /// startup self-test. With `SendInput`, every key goes out in one batch,
+    /// and `delete_word` deletes `original` with Ctrl+Backspace; with
+    /// `SendMessage`, keys are sent one at a time, `delay_ms` apart; through
+    /// the clipboard, the user's clipboard is restored `paste_delay_ms`
+    /// after the paste. Returns `false` if a key couldn't be sent; the
+    /// original text is then restored as far as possible (see
+    /// `transaction`).
+    #[cfg(windows)]
+    pub unsafe fn type_replacement(
+        original: &str,
+        text: &str,
+        strategy: InputStrategy,
+        delay_ms: u64,
+        paste_delay_ms: u64,
+        delete_word: bool,
+    ) -> bool {
+        if strategy == InputStrategy::Clipboard {
+            return Self::paste_replacement(original, text, paste_delay_ms);
+        }
+
+        // Apps read modifiers from the keyboard state, which messages don't
+        // change, so Ctrl+Backspace only works through SendInput
+        let mut transaction = if delete_word && strategy == InputStrategy::SendInput {
+            Transaction::deleting_word(original, text)
+        } else {
+            Transaction::new(original, text)
+        };
+        let keys = transaction.keys();
+
+        if strategy == InputStrategy::SendInput {
+            let sent = Self::send_batch(&keys);
+            if sent == keys.len() {
+                return true;
+            }
+            for &key in &keys[..sent] {
+                transaction.record(key);
+            }
+            Self::send_batch(&transaction.rollback());
+            eprintln!(
+                "Replacement failed after {} of {} keys; restored the original text",
+                sent,
+                keys.len()
+            );
             return false;
         }
 
-        #[cfg(windows)]
-        {
-            if vk_code == VK_CONTROL {
-                self.ctrl_pressed = true;
+        let target = GetForegroundWindow();
+        for (sent, &key) in keys.iter().enumerate() {
+            if GetForegroundWindow() != target {
+                eprintln!(
+                    "Replacement stopped after {} of {} keys: the foreground window changed",
+                    sent,
+                    keys.len()
+                );
                 return false;
             }
-
-            if self.ctrl_pressed && vk_code == 0x5A {
-                // Z key
-                return self.handle_undo();
-            }
-
-            match vk_code {
-                VK_BACK => {
-                    self.handle_backspace();
-                    false
-                }
-                VK_SPACE | VK_RETURN => {
-                    self.handle_word_end();
-                    false
-                }
-                _ if Self::is_punctuation(vk_code) => {
-                    self.handle_word_end();
-                    false
-                }
-                _ if Self::is_letter(vk_code) => {
-                    self.handle_letter(vk_code);
-                    false
-                }
-                _ => {
-                    self.current_word.clear();
-                    false
+            if !Self::send_key_message(key) {
+                for key in transaction.rollback() {
+                    Self::send_key_message(key);
+                    std::thread::sleep(std::time::Duration::from_millis(delay_ms));
                 }
+                eprintln!(
+                    "Replacement failed after {} of {} keys; restored the original text",
+                    sent,
+                    keys.len()
+                );
+                return false;
             }
+            transaction.record(key);
+            std::thread::sleep(std::time::Duration::from_millis(delay_ms));
         }
+
+        true
     }
 
-    fn handle_letter(&mut self, vk_code: u32) {
-        if self.undo_buffer.is_some() {
-            if let Some(correction_time) = self.last_correction_time {
-                if correction_time.elapsed().as_secs() > 2 {
-                    self.undo_buffer = None;
-                }
-            }
+    /// Delete `original` with backspaces and paste `text` in its place,
+    /// for apps that mangle typed keys (`strategy = "clipboard"`).
+    ///
+    /// The user's clipboard is put back once the app has had `settle_ms` to
+    /// read it, from another thread: the paste only reaches the app after
+    /// the keyboard hook returns. Another paste before then pushes the
+    /// restore back (see `clipboard::begin_paste`).
+    #[cfg(windows)]
+    unsafe fn paste_replacement(original: &str, text: &str, settle_ms: u64) -> bool {
+        let backspaces = Transaction::new(original, "").keys();
+        if Self::send_batch(&backspaces) != backspaces.len() {
+            eprintln!("Replacement failed: could not delete the original text");
+            return false;
         }
 
-        #[cfg(windows)]
-        let uppercase = {
-            let shift_pressed = unsafe { GetAsyncKeyState(VK_SHIFT as i32) < 0 };
-            let caps_lock = unsafe { GetKeyState(VK_CAPITAL as i32) & 1 != 0 };
-            shift_pressed ^ caps_lock
-        };
-        #[cfg(not(windows))]
-        let uppercase = false;
-
-        if let Some(ch) = Self::vk_to_char(vk_code, uppercase) {
-            self.current_word.push(ch);
+        if let Err(e) = crate::clipboard::begin_paste(text) {
+            eprintln!("Replacement failed: {}", e);
+            Self::send_batch(&Transaction::new("", original).keys());
+            return false;
+        }
+        if !Self::send_ctrl_shortcut(VK_V) {
+            eprintln!("Replacement failed: could not paste the replacement");
+            crate::clipboard::cancel_paste();
+            Self::send_batch(&Transaction::new("", original).keys());
+            return false;
         }
+
+        crate::clipboard::finish_paste(Duration::from_millis(settle_ms));
+        true
     }
 
-    fn handle_backspace(&mut self) {
-        if !self.current_word.is_empty() {
-            self.current_word.pop();
+    /// Send one key of a replacement as window messages; `false` if it
+    /// couldn't be sent.
+    #[cfg(windows)]
+    unsafe fn send_key_message(key: Key) -> bool {
+        match key {
+            Key::Backspace => Self::send_key_sendmessage(VK_BACK as u16),
+            Key::Char(ch) => Self::send_char_sendmessage(ch),
+            // Never used with SendMessage (see `type_replacement`)
+            Key::DeleteWord => false,
         }
     }
 
-    fn handle_word_end(&mut self) {
-        if self.current_word.is_empty() {
-            return;
+    /// Submit `keys` with a single `SendInput` call, so none of the user's
+    /// own keystrokes can land between them.
+    ///
+    /// Returns how many leading keys went through completely.
+    #[cfg(windows)]
+    unsafe fn send_batch(keys: &[Key]) -> usize {
+        if keys.is_empty() {
+            return 0;
         }
 
-        let word_lower = self.current_word.to_lowercase();
+        let strokes: Vec<Stroke> = keys.iter().flat_map(|key| key.strokes()).collect();
+        let mut inputs: Vec<INPUT> = strokes.iter().map(|&stroke| Self::input(stroke)).collect();
 
-        if let Some(correction) = self.dictionary.get_correction(&word_lower) {
-            self.undo_buffer = Some(UndoState {
-                original_word: self.current_word.clone(),
-                corrected_word: correction.clone(),
-                timestamp: Instant::now(),
-            });
-            self.last_correction_time = Some(Instant::now());
+        let hwnd = GetForegroundWindow();
+        let mut target_thread_id = 0;
+        let current_thread_id = GetCurrentThreadId();
+        if !hwnd.is_null() {
+            GetWindowThreadProcessId(hwnd, &mut target_thread_id);
+            if target_thread_id != current_thread_id {
+                AttachThreadInput(current_thread_id, target_thread_id, 1);
+            }
+        }
 
-            self.replace_word(&correction);
+        let inserted = SendInput(
+            inputs.len() as u32,
+            inputs.as_mut_ptr(),
+            std::mem::size_of::<INPUT>() as i32,
+        ) as usize;
+        if inserted < inputs.len() {
+            eprintln!(
+                "Warning: SendInput inserted {} of {} key events",
+                inserted,
+                inputs.len()
+            );
+            HOOK_COUNTERS.record_injection_failure();
+
+            let mut releases: Vec<INPUT> = held_keys_released(&strokes[..inserted])
+                .into_iter()
+                .map(|stroke| Self::input(stroke))
+                .collect();
+            if !releases.is_empty() {
+                SendInput(
+                    releases.len() as u32,
+                    releases.as_mut_ptr(),
+                    std::mem::size_of::<INPUT>() as i32,
+                );
+            }
+        }
 
-            println!("Corrected: '{}' -> '{}'", self.current_word, correction);
+        if !hwnd.is_null() && target_thread_id != current_thread_id {
+            AttachThreadInput(current_thread_id, target_thread_id, 0);
         }
 
-        self.current_word.clear();
+        keys_completed(keys, inserted)
     }
 
-    fn replace_word(&self, correction: &str) {
-        #[cfg(windows)]
-        unsafe {
-            let backspace_count = self.current_word.chars().count();
+    /// `stroke` as a `SendInput` event, marked as ours.
+    #[cfg(windows)]
+    unsafe fn input(stroke: Stroke) -> INPUT {
+        let (vk, scan, flags, up) = match stroke {
+            Stroke::Vk { vk, up } => (vk, 0, 0, up),
+            Stroke::Unicode { unit, up } => (0, unit, KEYEVENTF_UNICODE, up),
+        };
+        let mut input = INPUT {
+            type_: INPUT_KEYBOARD,
+            u: std::mem::zeroed(),
+        };
+        *input.u.ki_mut() = KEYBDINPUT {
+            wVk: vk,
+            wScan: scan,
+            dwFlags: if up { flags | KEYEVENTF_KEYUP } else { flags },
+            time: 0,
+            dwExtraInfo: INJECTION_MARKER,
+        };
+        input
+    }
 
-            let app_type = self.detect_app_type();
-            let delay = app_type.key_delay_ms();
+    /// Press `vk` while holding Ctrl (e.g. Ctrl+C), through `SendInput`.
+    ///
+    /// Returns `false` if the shortcut couldn't be pressed. Both keys are
+    /// released either way.
+    #[cfg(windows)]
+    pub unsafe fn send_ctrl_shortcut(vk: u16) -> bool {
+        let pressed = Self::send_key(VK_CONTROL as u16, true) && Self::send_key(vk, true);
+        Self::send_key(vk, false);
+        Self::send_key(VK_CONTROL as u16, false);
+        pressed
+    }
 
-            for _ in 0..backspace_count {
-                if app_type.needs_sendmessage_fallback() {
-                    Self::send_key_sendmessage(VK_BACK as u16);
-                } else {
-                    Self::send_key(VK_BACK as u16, true);
-                    Self::send_key(VK_BACK as u16, false);
-                }
-                std::thread::sleep(std::time::Duration::from_millis(delay));
+    /// Spell-correct every word in a block of text.
+    ///
+    /// Words are runs of ASCII letters; everything else is kept as is.
+    /// Corrections follow the original word's capitalization.
+    pub fn correct_text(&self, text: &str) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut word = String::new();
+
+        for ch in text.chars().chain(std::iter::once('\0')) {
+            if ch.is_ascii_alphabetic() {
+                word.push(ch);
+                continue;
             }
 
-            for ch in correction.chars() {
-                if app_type.needs_sendmessage_fallback() {
-                    Self::send_char_sendmessage(ch);
-                } else {
-                    Self::send_char(ch);
+            if !word.is_empty() {
+                match self.dictionary.get_correction(&word, &self.ranking) {
+                    Some(correction) => result.push_str(&correction),
+                    None => result.push_str(&word),
                 }
-                std::thread::sleep(std::time::Duration::from_millis(delay));
+                word.clear();
+            }
+            if ch != '\0' {
+                result.push(ch);
             }
         }
-        #[cfg(not(windows))]
+
+        result
+    }
+
+    /// Find unknown words in a block of text, without correcting them (see
+    /// `check`).
+    pub fn check_text(&self, text: &str) -> Vec<Misspelling> {
+        Checker {
+            dictionary: &self.dictionary,
+            ranking: &self.ranking,
+            word_types: &self.word_types,
+        }
+        .check_text(text)
+    }
+
+    /// Replace the most recent correction with the next other suggestion
+    /// for the word, while it can still be undone. After the last one, the
+    /// original correction comes back.
+    ///
+    /// Returns the edit to type, with the lock released (see `PendingEdit`).
+    pub fn next_suggestion(&mut self) -> Option<PendingEdit> {
+        let mut undo = self.undo_buffer.take()?;
+        if undo.alternatives.is_empty()
+            || !self.timing.undo_allowed(undo.timestamp, Instant::now())
+            || !self.undo_in_place(&undo)
         {
-            let _ = correction;
+            return None;
+        }
+
+        let next = undo.alternatives.remove(0);
+        Some(PendingEdit {
+            replacement: self.plan_replacement(&undo.corrected_word, &next, Some(undo.strategy)),
+            kind: EditKind::NextSuggestion { undo, next },
+        })
+    }
+
+    fn finish_next_suggestion(&mut self, mut undo: UndoState, next: String, typed: bool) -> bool {
+        if !typed {
+            // The previous suggestion is back in place
+            undo.alternatives.insert(0, next);
+            self.undo_buffer = Some(undo);
+            return false;
         }
+
+        crate::privacy::log_typed(format_args!(
+            "Next suggestion: '{}' -> '{}'",
+            undo.corrected_word, next
+        ));
+        let previous = std::mem::replace(&mut undo.corrected_word, next.clone());
+        undo.alternatives.push(previous);
+        undo.timestamp = Instant::now();
+        self.last_correction_time = Some(undo.timestamp);
+        self.remember_correction(undo.original_word.clone(), next.clone());
+        if self.previous_word.is_some() {
+            self.previous_word = next.split_whitespace().last().map(str::to_string);
+        }
+        self.undo_buffer = Some(undo);
+        true
+    }
+
+    /// Whether `undo` can still be typed over; logs why not.
+    fn undo_in_place(&self, undo: &UndoState) -> bool {
+        let in_place = undo.in_place();
+        if !in_place {
+            println!("Undo refused: the corrected word is no longer at the caret");
+        }
+        in_place
     }
 
     fn handle_undo(&mut self) -> bool {
-        if let Some(undo) = &self.undo_buffer {
-            if undo.timestamp.elapsed().as_secs() < self.undo_timeout_seconds {
-                #[cfg(windows)]
-                {
-                    let correction_len = undo.corrected_word.chars().count();
-                    unsafe {
-                        let app_type = self.detect_app_type();
-                        let delay = app_type.key_delay_ms();
-
-                        for _ in 0..correction_len {
-                            if app_type.needs_sendmessage_fallback() {
-                                Self::send_key_sendmessage(VK_BACK as u16);
-                            } else {
-                                Self::send_key(VK_BACK as u16, true);
-                                Self::send_key(VK_BACK as u16, false);
-                            }
-                            std::thread::sleep(std::time::Duration::from_millis(delay));
-                        }
-
-                        for ch in undo.original_word.chars() {
-                            if app_type.needs_sendmessage_fallback() {
-                                Self::send_char_sendmessage(ch);
-                            } else {
-                                Self::send_char(ch);
-                            }
-                            std::thread::sleep(std::time::Duration::from_millis(delay));
-                        }
-                    }
-                }
+        self.undo_last_correction()
+            .is_some_and(|edit| self.apply_edit(edit))
+    }
 
-                println!(
-                    "Undo: '{}' -> '{}'",
-                    undo.corrected_word, undo.original_word
-                );
+    /// Revert the most recent correction if it is still within the undo timeout.
+    ///
+    /// Returns the edit to type, with the lock released (see `PendingEdit`).
+    pub fn undo_last_correction(&mut self) -> Option<PendingEdit> {
+        let undo = self.undo_buffer.take()?;
+        if !self.timing.undo_allowed(undo.timestamp, Instant::now()) || !self.undo_in_place(&undo) {
+            return None;
+        }
 
-                self.undo_buffer = None;
-                return true;
-            }
+        Some(PendingEdit {
+            replacement: self.plan_replacement(
+                &undo.corrected_word,
+                &undo.original_word,
+                Some(undo.strategy),
+            ),
+            kind: EditKind::Undo(undo),
+        })
+    }
+
+    fn finish_undo(&mut self, undo: UndoState, typed: bool) -> bool {
+        // The correction is back in place; it can be undone again
+        if !typed {
+            self.undo_buffer = Some(undo);
+            return false;
         }
 
-        false
+        crate::privacy::log_typed(format_args!(
+            "Undo: '{}' -> '{}'",
+            undo.corrected_word, undo.original_word
+        ));
+        crate::events::emit(crate::events::Event::Undo {
+            restored: &undo.original_word,
+            removed: &undo.corrected_word,
+        });
+
+        if self.typing_report {
+            self.mistakes
+                .forget(&undo.original_word, &undo.corrected_word);
+        }
+        self.learn_from_undo(&undo);
+        self.stats.record_undo();
+        true
+    }
+
+    /// Count the undo of a one-word correction; learn the word if the same
+    /// correction keeps being undone (see `undos`).
+    fn learn_from_undo(&mut self, undo: &UndoState) {
+        // A separator typed after the word is part of the undo (see
+        // `resolve_separator`); a grammar fix spans two words
+        let word = |text: &str| {
+            text.trim_end_matches(|c: char| !c.is_alphanumeric())
+                .to_string()
+        };
+        let (original, corrected) = (word(&undo.original_word), word(&undo.corrected_word));
+        if original.is_empty() || original.contains(' ') {
+            return;
+        }
+
+        match self
+            .dictionary
+            .record_undo(&original, &corrected, self.learn_after_undos)
+        {
+            Ok(true) => crate::privacy::log_typed(format_args!(
+                "Added '{}' to the personal dictionary after repeated undos",
+                original
+            )),
+            Ok(false) => {}
+            Err(e) => eprintln!("Failed to record undo: {}", e),
+        }
     }
 
     #[cfg(windows)]
@@ -363,8 +2420,9 @@ impl Corrector {
         AppType::Unknown
     }
 
+    /// Press or release `vk` through `SendInput`; `false` if it failed.
     #[cfg(windows)]
-    unsafe fn send_key(vk: u16, key_down: bool) {
+    unsafe fn send_key(vk: u16, key_down: bool) -> bool {
         let hwnd = GetForegroundWindow();
         let mut target_thread_id = 0;
 
@@ -387,13 +2445,14 @@ impl Corrector {
             wScan: 0,
             dwFlags: if key_down { 0 } else { KEYEVENTF_KEYUP },
             time: 0,
-            dwExtraInfo: 0,
+            dwExtraInfo: INJECTION_MARKER,
         };
 
         let result = SendInput(1, &mut input, std::mem::size_of::<INPUT>() as i32);
 
         if result == 0 {
             eprintln!("Warning: SendInput failed for key {}", vk);
+            HOOK_COUNTERS.record_injection_failure();
         }
 
         if !hwnd.is_null() {
@@ -402,85 +2461,33 @@ impl Corrector {
                 AttachThreadInput(current_thread_id, target_thread_id, 0);
             }
         }
-    }
-
-    #[cfg(windows)]
-    unsafe fn send_char(ch: char) {
-        let hwnd = GetForegroundWindow();
-        let mut target_thread_id = 0;
-
-        if !hwnd.is_null() {
-            GetWindowThreadProcessId(hwnd, &mut target_thread_id);
-            let current_thread_id = GetCurrentThreadId();
-
-            if target_thread_id != current_thread_id {
-                AttachThreadInput(current_thread_id, target_thread_id, 1);
-            }
-        }
-
-        if ch.is_ascii_alphabetic() {
-            let vk = ch.to_ascii_uppercase() as u16;
-            let shift = ch.is_uppercase();
-
-            if shift {
-                Self::send_key(VK_SHIFT as u16, true);
-            }
-
-            Self::send_key(vk, true);
-            Self::send_key(vk, false);
-
-            if shift {
-                Self::send_key(VK_SHIFT as u16, false);
-            }
-        } else {
-            let mut input = INPUT {
-                type_: INPUT_KEYBOARD,
-                u: std::mem::zeroed(),
-            };
-
-            *input.u.ki_mut() = KEYBDINPUT {
-                wVk: 0,
-                wScan: ch as u16,
-                dwFlags: KEYEVENTF_UNICODE,
-                time: 0,
-                dwExtraInfo: 0,
-            };
-
-            let result = SendInput(1, &mut input, std::mem::size_of::<INPUT>() as i32);
-            if result == 0 {
-                eprintln!("Warning: SendInput failed for Unicode character '{}'", ch);
-            }
-
-            input.u.ki_mut().dwFlags = KEYEVENTF_UNICODE | KEYEVENTF_KEYUP;
-            SendInput(1, &mut input, std::mem::size_of::<INPUT>() as i32);
-        }
 
-        if !hwnd.is_null() {
-            let current_thread_id = GetCurrentThreadId();
-            if target_thread_id != current_thread_id {
-                AttachThreadInput(current_thread_id, target_thread_id, 0);
-            }
-        }
+        result != 0
     }
 
+    /// Send `vk` to the foreground window; `false` if there is none.
     #[cfg(windows)]
-    unsafe fn send_key_sendmessage(vk: u16) {
+    unsafe fn send_key_sendmessage(vk: u16) -> bool {
         let hwnd = GetForegroundWindow();
         if hwnd.is_null() {
             eprintln!("Warning: No foreground window for SendMessage");
-            return;
+            HOOK_COUNTERS.record_injection_failure();
+            return false;
         }
 
         SendMessageW(hwnd, WM_KEYDOWN, vk as WPARAM, 0);
         SendMessageW(hwnd, WM_KEYUP, vk as WPARAM, 0xC0000000);
+        true
     }
 
+    /// Send `ch` to the foreground window; `false` if there is none.
     #[cfg(windows)]
-    unsafe fn send_char_sendmessage(ch: char) {
+    unsafe fn send_char_sendmessage(ch: char) -> bool {
         let hwnd = GetForegroundWindow();
         if hwnd.is_null() {
             eprintln!("Warning: No foreground window for SendMessage");
-            return;
+            HOOK_COUNTERS.record_injection_failure();
+            return false;
         }
 
         if ch.is_ascii_uppercase() {
@@ -490,55 +2497,465 @@ impl Corrector {
         } else {
             SendMessageW(hwnd, WM_CHAR, ch as WPARAM, 0);
         }
+        true
     }
+}
 
-    fn is_letter(vk_code: u32) -> bool {
-        (0x41..=0x5A).contains(&vk_code)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_change_clears_undo() {
+        let mut corrector = Corrector::new();
+        corrector.undo_buffer = Some(UndoState::new("teh", "the", None, InputStrategy::SendInput));
+        corrector.current_word = "recie".to_string();
+
+        corrector.on_focus_changed();
+        assert!(corrector.current_word.is_empty());
+        assert!(corrector.undo_buffer.is_none());
+        assert!(!apply(&mut corrector, Corrector::undo_last_correction));
     }
 
-    fn is_punctuation(vk_code: u32) -> bool {
-        matches!(
-            vk_code,
-            0xBC | 0xBE | 0xBF | 0xBA | 0xDE | 0xDB | 0xDD | 0xC0 | 0xBD | 0xBB
-        )
+    #[test]
+    fn test_click_forgets_word() {
+        let mut corrector = Corrector::new();
+        corrector.undo_buffer = Some(UndoState::new("teh", "the", None, InputStrategy::SendInput));
+        corrector.current_word = "recie".to_string();
+        corrector.previous_word = Some("the".to_string());
+
+        corrector.on_mouse_click();
+        assert!(corrector.current_word.is_empty());
+        assert!(corrector.previous_word.is_none());
+        assert!(corrector.undo_buffer.is_some());
     }
 
-    fn vk_to_char(vk_code: u32, uppercase: bool) -> Option<char> {
-        if (0x41..=0x5A).contains(&vk_code) {
-            let ch = (vk_code - 0x41 + b'a' as u32) as u8 as char;
-            Some(if uppercase {
-                ch.to_ascii_uppercase()
-            } else {
-                ch
-            })
-        } else {
-            None
+    #[test]
+    fn test_next_suggestion() {
+        let mut corrector = Corrector::new();
+        assert!(!apply(&mut corrector, Corrector::next_suggestion));
+
+        let mut undo = UndoState::new("cta", "cat", None, InputStrategy::SendMessage);
+        undo.alternatives = vec!["act".to_string(), "cut".to_string()];
+        corrector.undo_buffer = Some(undo);
+        corrector.previous_word = Some("cat".to_string());
+
+        // Cycles through the other suggestions, then back to the correction
+        assert!(apply(&mut corrector, Corrector::next_suggestion));
+        assert!(apply(&mut corrector, Corrector::next_suggestion));
+        assert_eq!(corrector.previous_word.as_deref(), Some("cut"));
+        assert!(apply(&mut corrector, Corrector::next_suggestion));
+        let undo = corrector.undo_buffer.as_ref().unwrap();
+        assert_eq!(undo.corrected_word, "cat");
+        assert_eq!(undo.original_word, "cta");
+        assert_eq!(undo.strategy, InputStrategy::SendMessage);
+        assert_eq!(undo.alternatives, vec!["act", "cut"]);
+
+        // A different window: nothing is typed into it
+        corrector.undo_buffer.as_mut().unwrap().window += 1;
+        assert!(!apply(&mut corrector, Corrector::next_suggestion));
+        assert!(!apply(&mut corrector, Corrector::undo_last_correction));
+    }
+
+    #[test]
+    fn test_max_alternatives() {
+        let mut corrector = Corrector::new();
+        corrector.dictionary.load().unwrap();
+        corrector.max_alternatives = 1;
+
+        type_text(&mut corrector, "recieve ");
+        let undo = corrector.undo_buffer.as_ref().unwrap();
+        assert_eq!(undo.corrected_word, "receive");
+        assert_eq!(undo.alternatives.len(), 1);
+    }
+
+    #[test]
+    fn test_grammar_suggest_leaves_text() {
+        let mut corrector = Corrector::new();
+        corrector.previous_word = Some("better".to_string());
+        corrector.current_word = "then".to_string();
+
+        corrector.handle_word_end();
+        assert_eq!(corrector.previous_word.as_deref(), Some("then"));
+        assert!(corrector.undo_buffer.is_none());
+    }
+
+    #[test]
+    fn test_ordinal_fixed_before_lookup() {
+        let mut corrector = Corrector::new();
+        corrector.number = "2".to_string();
+        corrector.current_word = "end".to_string();
+
+        corrector.handle_word_end();
+        let undo = corrector.undo_buffer.as_ref().unwrap();
+        assert_eq!(undo.original_word, "end");
+        assert_eq!(undo.corrected_word, "nd");
+    }
+
+    /// Knows the fix for one run-together typo.
+    struct RunTogether;
+
+    impl crate::plugins::CorrectionProvider for RunTogether {
+        fn name(&self) -> &str {
+            "run-together"
+        }
+
+        fn suggest(&self, word: &str, _previous_word: Option<&str>) -> Option<String> {
+            (word == "alot").then(|| "a lot".to_string())
+        }
+
+        fn veto(&self, _word: &str, _correction: &str) -> bool {
+            false
         }
     }
-}
 
-impl Drop for Corrector {
-    fn drop(&mut self) {
-        self.ctrl_pressed = false;
+    #[test]
+    fn test_multi_word_correction() {
+        let mut corrector = Corrector::new();
+        corrector.providers.push(Box::new(RunTogether));
+        type_text(&mut corrector, "alot ");
+
+        let undo = corrector.undo_buffer.clone().unwrap();
+        assert_eq!(undo.original_word, "alot");
+        assert_eq!(undo.corrected_word, "a lot");
+        assert_eq!(corrector.previous_word.as_deref(), Some("lot"));
+
+        // The correction's backspaces come back through the hook while no
+        // word is being typed; "lot" is still the word before the next
+        replay_injected(&mut corrector, &undo.original_word, &undo.corrected_word);
+        assert_eq!(corrector.previous_word.as_deref(), Some("lot"));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_repeated_word_after_correction() {
+        let mut corrector = Corrector::new();
+        corrector.dictionary.load().unwrap();
+        corrector.grammar_mode = GrammarMode::Fix;
+
+        type_text(&mut corrector, "thw ");
+        let undo = corrector.undo_buffer.clone().unwrap();
+        assert_eq!(undo.corrected_word, "the");
+        replay_injected(&mut corrector, &undo.original_word, &undo.corrected_word);
+
+        // "thw" now reads "the", so typing "the" doubles it
+        type_text(&mut corrector, "the ");
+        let undo = corrector.undo_buffer.as_ref().unwrap();
+        assert_eq!(undo.original_word, "the the");
+        assert_eq!(undo.corrected_word, "the");
+    }
+
+    #[test]
+    fn test_correct_word_on_hotkey() {
+        let mut corrector = Corrector::new();
+        corrector.dictionary.load().unwrap();
+        corrector.auto_correct = false;
+
+        type_text(&mut corrector, "recieve ");
+        assert!(corrector.undo_buffer.is_none());
+        assert!(apply(&mut corrector, Corrector::correct_last_word));
+        let undo = corrector.undo_buffer.as_ref().unwrap();
+        assert_eq!(undo.original_word, "recieve ");
+        assert_eq!(undo.corrected_word, "receive ");
+        assert!(!apply(&mut corrector, Corrector::correct_last_word));
+
+        // Typing on puts the word out of reach
+        type_text(&mut corrector, "becuase, a");
+        assert!(!apply(&mut corrector, Corrector::correct_last_word));
+    }
+
+    #[test]
+    fn test_enter_without_correction() {
+        let mut corrector = Corrector::new();
+        corrector.dictionary.load().unwrap();
+        corrector.enter_sends_apps = vec!["Slack.exe".to_string()];
+        assert!(corrector.corrects_on_enter(Some("notepad.exe")));
+        assert!(!corrector.corrects_on_enter(Some("slack.exe")));
+
+        corrector.correct_on_enter = false;
+        type_text(&mut corrector, "recieve");
+        corrector.handle_typed(VK_RETURN, Some('\r'));
+        assert!(corrector.undo_buffer.is_none());
+        assert_eq!(corrector.last_word(), Some("recieve"));
+
+        // Nor after a separator, which waits for the next key
+        type_text(&mut corrector, "recieve.");
+        corrector.handle_typed(VK_RETURN, Some('\r'));
+        assert!(corrector.undo_buffer.is_none());
+        assert_eq!(corrector.last_word(), Some("recieve"));
+
+        // Space still corrects
+        type_text(&mut corrector, "recieve ");
+        assert!(corrector.undo_buffer.is_some());
+    }
+
+    #[test]
+    fn test_typography() {
+        let mut corrector = Corrector::new();
+        corrector.dictionary.load().unwrap();
+        assert!(corrector.typographic('"').is_none());
+
+        corrector.typography = true;
+        for ch in "don'".chars() {
+            let typographic = corrector.typographic(ch);
+            corrector.handle_typed(0, Some(ch));
+            if let Some((replaced, replacement)) = typographic {
+                assert!(corrector.type_typographic(ch, replaced, replacement));
+                // The ’ comes back through the hook, and isn't typing
+                replay_injected(&mut corrector, replaced, &replacement.to_string());
+            }
+        }
+        assert_eq!(corrector.current_word, "don’");
+        assert_eq!(corrector.token, "don’");
+        assert_eq!(corrector.typographic('"'), Some(("", '”')));
+
+        // A word typed with a curly apostrophe is still known
+        type_text(&mut corrector, "t ");
+        assert!(corrector.undo_buffer.is_none());
+        assert_eq!(corrector.last_word(), Some("don’t"));
+        assert_eq!(corrector.typographic('"'), Some(("", '“')));
+
+        // An app that keeps them straight, until focus moves on
+        corrector.typography_input.as_mut().unwrap().straight = true;
+        assert!(corrector.typographic('"').is_none());
+        corrector.on_focus_changed();
+        assert_eq!(corrector.typographic('"'), Some(("", '“')));
+    }
+
+    #[test]
+    fn test_dry_run_leaves_text() {
+        let mut corrector = Corrector::new();
+        corrector.set_dry_run(true);
+        corrector.number = "2".to_string();
+        corrector.current_word = "end".to_string();
+
+        corrector.handle_word_end();
+        assert!(corrector.undo_buffer.is_none());
+        assert_eq!(corrector.previous_word.as_deref(), Some("end"));
+        assert_eq!(corrector.stats().dry_run_corrections, 1);
+        assert_eq!(corrector.stats().corrections, 0);
+    }
+
+    #[test]
+    fn test_abbreviation_keeps_sentence() {
+        let mut corrector = Corrector::new();
+        corrector.current_word = "e.g.".to_string();
+        corrector.handle_word_end();
+        assert!(!corrector.sentence_start);
+        assert_eq!(corrector.last_word.as_deref(), Some("e.g."));
+
+        corrector.current_word = "e.".to_string();
+        corrector.handle_word_end();
+        assert!(corrector.sentence_start);
+    }
+
+    /// Type `text` as if each character was a key press.
+    fn type_text(corrector: &mut Corrector, text: &str) {
+        for ch in text.chars() {
+            let vk_code = if ch == ' ' { VK_SPACE } else { 0 };
+            corrector.handle_typed(vk_code, Some(ch));
+        }
+    }
+
+    /// Run a hotkey action and type its edit, as `main` does; `true` if the
+    /// text was changed.
+    fn apply(corrector: &mut Corrector, action: fn(&mut Corrector) -> Option<PendingEdit>) -> bool {
+        let Some(edit) = action(corrector) else {
+            return false;
+        };
+        let typed = edit.type_text();
+        corrector.finish_edit(edit, typed)
+    }
+
+    /// Feed the key presses of replacing `original` by `replacement` to the
+    /// corrector, as the hook sees the keys we inject.
+    fn replay_injected(corrector: &mut Corrector, original: &str, replacement: &str) {
+        use crate::transaction::{Stroke, Transaction};
+
+        /// `VK_PACKET`: a character typed as Unicode.
+        const VK_PACKET: u32 = 0xE7;
+
+        for key in Transaction::new(original, replacement).keys() {
+            for stroke in key.strokes() {
+                let vk_code = match stroke {
+                    Stroke::Vk { vk, up: false } => vk as u32,
+                    Stroke::Unicode { up: false, .. } => VK_PACKET,
+                    _ => continue,
+                };
+                assert!(!corrector.handle_hook_key(vk_code, false, INJECTION_MARKER));
+            }
+        }
+    }
+
+    #[test]
+    fn test_separator_waits_for_next_key() {
+        let mut corrector = Corrector::new();
+        corrector.dictionary.load().unwrap();
+
+        // A URL is left alone, and so is the word before its first dot
+        type_text(&mut corrector, "see githbu.com ");
+        assert!(corrector.undo_buffer.is_none());
+        assert_eq!(corrector.last_word.as_deref(), Some("com"));
+
+        // A period then a space: the period is retyped with the correction
+        type_text(&mut corrector, "recieve. ");
+        let undo = corrector.undo_buffer.as_ref().unwrap();
+        assert_eq!(undo.original_word, "recieve.");
+        assert_eq!(undo.corrected_word, "receive.");
+        assert!(corrector.sentence_start);
+    }
+
+    #[test]
+    fn test_navigation_forgets_word() {
+        let mut corrector = Corrector::new();
+        corrector.dictionary.load().unwrap();
+        corrector.undo_buffer = Some(UndoState::new("teh", "the", None, InputStrategy::SendInput));
+
+        // Left arrow, then the word is finished somewhere else
+        type_text(&mut corrector, "recieve");
+        corrector.handle_typed(0x25, None);
+        assert!(corrector.undo_buffer.is_none());
+        type_text(&mut corrector, " ");
+        assert!(corrector.undo_buffer.is_none());
+
+        // Delete while a period waits for the next key
+        type_text(&mut corrector, "recieve.");
+        corrector.handle_typed(0x2E, None);
+        type_text(&mut corrector, " ");
+        assert!(corrector.undo_buffer.is_none());
+        assert!(corrector.current_word.is_empty());
+    }
+
+    #[test]
+    fn test_auto_repeat_left_alone() {
+        let mut corrector = Corrector::new();
+        corrector.dictionary.load().unwrap();
+
+        // "sooooo" typed by holding "o"
+        type_text(&mut corrector, "soo");
+        corrector.auto_repeated = true;
+        type_text(&mut corrector, "ooo ");
+        assert!(corrector.undo_buffer.is_none());
+        assert_eq!(corrector.last_word.as_deref(), Some("sooooo"));
+
+        // Not even looked up when it isn't drawn out
+        type_text(&mut corrector, "reci");
+        corrector.auto_repeated = true;
+        type_text(&mut corrector, "eve ");
+        assert!(corrector.undo_buffer.is_none());
+        assert_eq!(corrector.last_word.as_deref(), Some("recieve"));
+
+        // The next word is checked again
+        type_text(&mut corrector, "recieve ");
+        assert!(corrector.undo_buffer.is_some());
+
+        // Collapsed when drawn-out words are corrected
+        corrector.word_types.elongated = crate::classify::WordPolicy::Correct;
+        type_text(&mut corrector, "soo");
+        corrector.auto_repeated = true;
+        type_text(&mut corrector, "ooo ");
+        assert_eq!(corrector.undo_buffer.as_ref().unwrap().corrected_word, "so");
+    }
+
+    #[test]
+    fn test_trailing_apostrophe_left_alone() {
+        let mut corrector = Corrector::new();
+        corrector.current_word = "teh'".to_string();
+        corrector.handle_word_end();
+        assert!(corrector.undo_buffer.is_none());
+        assert_eq!(corrector.last_word.as_deref(), Some("teh'"));
+    }
+
+    #[test]
+    fn test_short_word_left_alone() {
+        let mut corrector = Corrector::new();
+        corrector.dictionary.load().unwrap();
+        corrector.ranking.min_word_length = 5;
+
+        type_text(&mut corrector, "thsi ");
+        assert!(corrector.undo_buffer.is_none());
+        assert_eq!(corrector.last_word(), Some("thsi"));
+
+        corrector.ranking.min_word_length = 4;
+        type_text(&mut corrector, "thsi ");
+        assert_eq!(
+            corrector.undo_buffer.as_ref().unwrap().original_word,
+            "thsi"
+        );
+    }
+
+    #[test]
+    fn test_overlong_word_left_alone() {
+        let mut corrector = Corrector::new();
+        for _ in 0..MAX_WORD_LEN + 10 {
+            corrector.handle_letter('a');
+        }
+        assert_eq!(corrector.current_word.len(), MAX_WORD_LEN + 1);
+
+        corrector.handle_word_end();
+        assert!(corrector.current_word.is_empty());
+        assert!(corrector.last_word.is_none());
+
+        // A lower cap, counted in characters
+        corrector.max_word_len = 5;
+        for ch in "résumés".chars() {
+            corrector.handle_letter(ch);
+        }
+        assert_eq!(corrector.current_word, "résumé");
+
+        corrector.handle_word_end();
+        assert!(corrector.current_word.is_empty());
+        assert!(corrector.last_word.is_none());
+
+        // A word exactly at the limit is still a word
+        type_text(&mut corrector, "résum ");
+        assert_eq!(corrector.last_word(), Some("résum"));
+    }
 
     #[test]
-    fn test_is_letter() {
-        assert!(Corrector::is_letter(0x41));
-        assert!(Corrector::is_letter(0x5A));
-        assert!(!Corrector::is_letter(0x20));
+    fn test_replace_dictionary() {
+        let mut corrector = Corrector::new();
+        corrector.current_word = "wor".to_string();
+
+        // Reloading the same language keeps the word being typed
+        let previous = corrector.replace_dictionary(Dictionary::new());
+        assert_eq!(previous.language(), "en");
+        assert_eq!(corrector.current_word, "wor");
+
+        let mut french = Dictionary::new();
+        french.set_language("fr");
+        corrector.replace_dictionary(french);
+        assert_eq!(corrector.language(), "fr");
+        assert!(corrector.current_word.is_empty());
     }
 
     #[test]
-    fn test_vk_to_char() {
-        assert_eq!(Corrector::vk_to_char(0x41, false), Some('a'));
-        assert_eq!(Corrector::vk_to_char(0x41, true), Some('A'));
-        assert_eq!(Corrector::vk_to_char(0x5A, false), Some('z'));
+    fn test_ignore_last_word_for_session() {
+        let mut corrector = Corrector::new();
+        let undos = std::env::temp_dir().join(format!(
+            "autocorrect-ignored-undos-{}.txt",
+            std::process::id()
+        ));
+        corrector
+            .dictionary
+            .set_undo_counts(crate::undos::UndoCounts::with_path(undos.clone()));
+        assert!(corrector.ignore_last_word_for_session().is_none());
+
+        corrector.last_word = Some("Teh".to_string());
+        corrector.undo_buffer = Some(UndoState::new("Teh", "The", None, InputStrategy::SendInput));
+
+        let (word, undo) = corrector.ignore_last_word_for_session().unwrap();
+        assert_eq!(word, "Teh");
+        assert!(corrector.session_ignored.contains("teh"));
+        assert!(corrector.undo_buffer.is_none());
+        let undo = undo.unwrap();
+        let typed = undo.type_text();
+        assert!(corrector.finish_edit(undo, typed));
+        // The undo was counted, though not in the user's data
+        corrector.flush_learning();
+        assert!(std::fs::read_to_string(&undos)
+            .unwrap()
+            .contains("teh the 1"));
+        std::fs::remove_file(undos).unwrap();
     }
 
     #[test]
@@ -549,6 +2966,100 @@ mod tests {
         assert!(AppType::Chromium.needs_sendmessage_fallback());
     }
 
+    #[test]
+    fn test_strategy_override() {
+        let mut corrector = Corrector::new();
+        assert_eq!(
+            corrector.input_strategy(AppType::Standard),
+            InputStrategy::SendInput
+        );
+        assert_eq!(
+            corrector.input_strategy(AppType::Electron),
+            InputStrategy::SendMessage
+        );
+
+        corrector.set_strategy_override(Some(InputStrategy::SendMessage));
+        assert_eq!(
+            corrector.input_strategy(AppType::Standard),
+            InputStrategy::SendMessage
+        );
+    }
+
+    #[test]
+    fn test_app_input_override() {
+        let mut corrector = Corrector::new();
+        corrector.app_input.insert(
+            "notion.exe".to_string(),
+            AppInput {
+                strategy: Some(InputStrategy::Clipboard),
+                key_delay_ms: Some(20),
+                paste_delay_ms: None,
+            },
+        );
+
+        assert_eq!(
+            corrector.strategy_for(AppType::Electron, Some("Notion.exe")),
+            InputStrategy::Clipboard
+        );
+        assert_eq!(
+            corrector.strategy_for(AppType::Electron, Some("slack.exe")),
+            InputStrategy::SendMessage
+        );
+        assert_eq!(
+            corrector.strategy_for(AppType::Standard, None),
+            InputStrategy::SendInput
+        );
+
+        let config: crate::config::Config = toml::from_str(
+            "[apps.input.\"notion.exe\"]\nstrategy = \"clipboard\"\npaste_delay_ms = 500\n\
+             [apps.input.\"slack.exe\"]\nstrategy = \"sendmessage\"\nkey_delay_ms = 15\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.apps.input["notion.exe"],
+            AppInput {
+                strategy: Some(InputStrategy::Clipboard),
+                key_delay_ms: None,
+                paste_delay_ms: Some(500),
+            }
+        );
+        assert_eq!(
+            config.apps.input["slack.exe"],
+            AppInput {
+                strategy: Some(InputStrategy::SendMessage),
+                key_delay_ms: Some(15),
+                paste_delay_ms: None,
+            }
+        );
+
+        // Only window messages are paced
+        let slack = config.apps.input["slack.exe"];
+        assert_eq!(slack.checked("slack.exe"), slack);
+        let batched = AppInput {
+            strategy: Some(InputStrategy::SendInput),
+            ..slack
+        };
+        assert_eq!(batched.checked("slack.exe").key_delay_ms, None);
+        let detected = AppInput {
+            strategy: None,
+            ..slack
+        };
+        assert_eq!(detected.checked("slack.exe").key_delay_ms, Some(15));
+    }
+
+    #[test]
+    fn test_pause_reasons() {
+        let mut corrector = Corrector::new();
+        assert!(!corrector.is_paused());
+
+        assert!(corrector.set_paused(PauseReason::ConflictingSoftware, true));
+        assert!(!corrector.set_paused(PauseReason::ConflictingSoftware, true));
+        assert!(corrector.is_paused());
+
+        assert!(corrector.set_paused(PauseReason::ConflictingSoftware, false));
+        assert!(!corrector.is_paused());
+    }
+
     #[test]
     fn test_app_type_key_delay() {
         assert_eq!(AppType::Standard.key_delay_ms(), KEY_DELAY_MS);
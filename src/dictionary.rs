@@ -17,6 +17,22 @@
 //!
 //! If frequency is omitted, it defaults to 1.
 //!
+//! After the frequency (or in its place), a line may carry `key=value`
+//! fields with more about the word; unknown keys are ignored, so files stay
+//! readable by older versions and the other way round:
+//! ```text
+//! iphone 12000 case=iPhone      # canonical casing of corrections
+//! colour 9000 variant=uk        # only suggested for this spelling variant
+//! color 11000 variant=us
+//! bonjour 10 lang=fr            # known, but another language: never suggested
+//! than 80000 pos=conj,prep      # part-of-speech hint for grammar rules
+//! ```
+//!
+//! A file whose first line is `#!sorted` lists words most frequent first;
+//! words without a frequency get one from their rank. Either way, words are
+//! indexed most frequent first, and only the head of the list is indexed
+//! before loading returns (see [`Dictionary::begin_load`]).
+//!
 //! # Fallback Dictionary
 //!
 //! If no dictionary file is found at `dictionary/words.txt`, a built-in
@@ -28,8 +44,54 @@
 //! Personal words are stored in `%APPDATA%/Autocorrect/personal_dictionary.txt`.
 //! These words are given very high frequency (1,000,000) to ensure they are
 //! always preferred over similar dictionary words.
+//!
+//! # Never-Correct List
+//!
+//! Words in `%APPDATA%/Autocorrect/never_correct.txt` (slang, product names,
+//! deliberate misspellings) are never corrected, even when a dictionary word
+//! is close. Unlike personal words they are not suggested as corrections of
+//! other words.
+//!
+//! # Personal Corrections
+//!
+//! `%APPDATA%/Autocorrect/personal_corrections.txt` maps typos to their fix,
+//! one `typo -> replacement` per line (`alot -> a lot`). These are applied
+//! as written, before any lookup, whatever the ranking of dictionary words.
+//!
+//! # Learned Frequencies
+//!
+//! Words the user actually types receive a decaying boost from the
+//! [`LearningStore`], which is added to their dictionary frequency when
+//! ranking suggestions.
+//!
+//! # Undone Corrections
+//!
+//! A correction undone the same way a few times is learned: the typed word
+//! joins the personal dictionary (see [`UndoCounts`]).
+//!
+//! # Language Packs
+//!
+//! When a language other than English is selected, its pack file from
+//! `%LOCALAPPDATA%/Autocorrect/languages/` replaces the embedded dictionary.
+//! A custom dictionary path given on the command line takes precedence.
+//! Words of the other installed packs can be loaded alongside for exact
+//! lookups (see [`ForeignWords`]).
+//!
+//! # Context Model
+//!
+//! If `autocorrect train-lm` has built a model from the user's documents, it
+//! is loaded with the dictionary and used to rerank suggestions by the word
+//! typed before them.
 
+use crate::keyboard::KeyboardLayout;
+use crate::languages::ForeignWords;
+use crate::learning::LearningStore;
+use crate::ranking::RankingPolicy;
 use crate::symspell::SymSpell;
+use crate::trigram::TrigramModel;
+use crate::undos::UndoCounts;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File;
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
@@ -38,16 +100,112 @@ use std::path::{Path, PathBuf};
 // If the file doesn't exist, this will fail at compile time with a clear error
 const EMBEDDED_DICTIONARY: &str = include_str!("../dictionary/words.txt");
 
+/// Canonical casing of built-in words, as `word case=Casing` lines, applied
+/// to the embedded dictionary: the word list itself is a plain list of
+/// words, downloaded separately.
+const BUILTIN_CASING: &str = include_str!("../dictionary/casing.txt");
+
+/// First line marking a dictionary file as sorted, most frequent word first.
+const SORTED_MARKER: &str = "#!sorted";
+
+/// Frequency of the first word in a sorted file without explicit counts;
+/// word `n` gets `SORTED_TOP_FREQUENCY / n` (Zipf's law).
+const SORTED_TOP_FREQUENCY: u64 = 1_000_000;
+
+/// Most frequent built-in words indexed before loading returns; the rest
+/// are indexed in the background.
+const HEAD_WORDS: usize = 2000;
+
+/// Frequency added to a suggestion for each unit of learned boost.
+const LEARNED_BOOST_WEIGHT: f64 = 1000.0;
+
+/// How much a suggestion's frequency grows with its probability in
+/// context: a suggestion certain to follow the previous word gets this many
+/// times its frequency on top.
+const CONTEXT_BOOST_WEIGHT: f64 = 100.0;
+
+/// Spelling variant of English (`spelling_variant`, `variant=` in
+/// dictionary files).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpellingVariant {
+    /// Suggest words of every variant.
+    #[default]
+    Any,
+    /// American spellings (color, organize).
+    Us,
+    /// British spellings (colour, organise).
+    Uk,
+}
+
+impl SpellingVariant {
+    fn from_tag(tag: &str) -> Option<Self> {
+        match tag.to_lowercase().as_str() {
+            "us" => Some(Self::Us),
+            "uk" | "gb" => Some(Self::Uk),
+            _ => None,
+        }
+    }
+}
+
+/// One dictionary line: a word, its frequency and optional fields.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Entry {
+    /// The word, lowercased (the lookup key).
+    pub word: String,
+    pub frequency: u64,
+    /// Canonical casing (`case=iPhone`).
+    pub casing: Option<String>,
+    /// Language tag (`lang=fr`).
+    pub language: Option<String>,
+    /// Spelling variant (`variant=uk`).
+    pub variant: Option<SpellingVariant>,
+    /// Part-of-speech hint (`pos=verb,noun`).
+    pub part_of_speech: Option<String>,
+}
+
+impl Entry {
+    fn has_fields(&self) -> bool {
+        self.casing.is_some() || self.variant.is_some() || self.part_of_speech.is_some()
+    }
+}
+
 /// Manages dictionary loading and word storage.
 ///
 /// The dictionary system consists of:
 /// - A SymSpell instance containing all words and their frequencies
 /// - A path to the personal dictionary file
+/// - A store of learned word boosts
+/// - Undo counts per correction
 pub struct Dictionary {
     /// The SymSpell instance containing all loaded words.
     symspell: SymSpell,
     /// Path to the user's personal dictionary file.
     personal_dict_path: PathBuf,
+    /// Lowercase words never corrected.
+    never_correct: HashSet<String>,
+    /// Replacements for lowercase typos, as written by the user.
+    personal_corrections: HashMap<String, String>,
+    /// Decaying boosts for words the user types.
+    learning: LearningStore,
+    /// How often each correction was undone.
+    undos: UndoCounts,
+    /// Code of the language pack to load (see [`crate::languages`]).
+    language: String,
+    /// Built-in words not yet added to the delete index, most frequent first.
+    unindexed: VecDeque<String>,
+    /// Number of built-in words queued for indexing by the last load.
+    queued_total: usize,
+    /// Load the other installed packs' words with the next call to `load`.
+    foreign_passthrough: bool,
+    /// Words of the other installed packs.
+    foreign: ForeignWords,
+    /// Words with casing, variant or part-of-speech fields, by word.
+    entries: HashMap<String, Entry>,
+    /// Spelling variant whose words are suggested.
+    spelling_variant: SpellingVariant,
+    /// Layout whose neighboring-key slips rank first.
+    keyboard_layout: KeyboardLayout,
 }
 
 impl Dictionary {
@@ -64,7 +222,127 @@ impl Dictionary {
         Self {
             symspell: SymSpell::new(2),
             personal_dict_path: Self::get_personal_dict_path(),
+            never_correct: HashSet::new(),
+            personal_corrections: HashMap::new(),
+            learning: LearningStore::new(),
+            undos: UndoCounts::new(),
+            language: crate::languages::BUILTIN_LANGUAGE.to_string(),
+            unindexed: VecDeque::new(),
+            queued_total: 0,
+            foreign_passthrough: false,
+            foreign: ForeignWords::default(),
+            entries: HashMap::new(),
+            spelling_variant: SpellingVariant::Any,
+            keyboard_layout: KeyboardLayout::Qwerty,
+        }
+    }
+
+    /// Build and load a dictionary for a language pack.
+    ///
+    /// Used to prepare a replacement dictionary without touching the one
+    /// currently in use.
+    ///
+    /// # Errors
+    /// Returns an error if dictionary files cannot be read.
+    pub fn load_language(
+        dictionary_path: Option<&Path>,
+        language: &str,
+        learning_half_life_days: f64,
+        foreign_passthrough: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut dictionary = Self::new();
+        dictionary.set_language(language);
+        dictionary.set_learning_half_life_days(learning_half_life_days);
+        dictionary.set_foreign_passthrough(foreign_passthrough);
+        dictionary.load_from_path(dictionary_path)?;
+        Ok(dictionary)
+    }
+
+    /// Set the language pack loaded by the next call to `load`.
+    pub fn set_language(&mut self, language: &str) {
+        self.language = language.to_lowercase();
+    }
+
+    /// Code of the language this dictionary was built for.
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Set whether the next call to `load` also reads the other installed
+    /// packs' words.
+    pub fn set_foreign_passthrough(&mut self, enabled: bool) {
+        self.foreign_passthrough = enabled;
+    }
+
+    /// Whether the other installed packs' words are loaded.
+    pub fn foreign_passthrough(&self) -> bool {
+        self.foreign_passthrough
+    }
+
+    /// The other installed pack that has `word_lower`, if the active
+    /// dictionary doesn't.
+    pub fn foreign_language(&self, word_lower: &str) -> Option<&str> {
+        if self.symspell.contains(word_lower) {
+            return None;
         }
+        self.foreign.language_of(word_lower)
+    }
+
+    /// Only suggest words of this spelling variant (and untagged words).
+    pub fn set_spelling_variant(&mut self, variant: SpellingVariant) {
+        self.spelling_variant = variant;
+    }
+
+    pub fn spelling_variant(&self) -> SpellingVariant {
+        self.spelling_variant
+    }
+
+    /// Rank suggestions by the neighboring keys of this layout.
+    pub fn set_keyboard_layout(&mut self, layout: KeyboardLayout) {
+        self.keyboard_layout = layout;
+    }
+
+    pub fn keyboard_layout(&self) -> KeyboardLayout {
+        self.keyboard_layout
+    }
+
+    /// Part-of-speech hint of a word from its dictionary line (e.g.
+    /// `"verb,noun"`).
+    pub fn part_of_speech(&self, word: &str) -> Option<&str> {
+        self.entries
+            .get(&word.to_lowercase())?
+            .part_of_speech
+            .as_deref()
+    }
+
+    /// Whether a suggestion is of the preferred spelling variant, or of none.
+    fn variant_allowed(&self, word: &str) -> bool {
+        self.spelling_variant == SpellingVariant::Any
+            || self
+                .entries
+                .get(word)
+                .and_then(|entry| entry.variant)
+                .is_none_or(|variant| variant == self.spelling_variant)
+    }
+
+    /// Take over the learned boosts and undo counts of the dictionary being
+    /// replaced.
+    ///
+    /// Keeps boosts recorded since the replacement was loaded.
+    pub fn inherit_learning(&mut self, previous: &mut Dictionary) {
+        self.learning = std::mem::replace(&mut previous.learning, LearningStore::new());
+        self.undos = std::mem::replace(&mut previous.undos, UndoCounts::new());
+    }
+
+    /// Keep undo counts in `undos` instead of the user's data file.
+    #[cfg(test)]
+    pub fn set_undo_counts(&mut self, undos: UndoCounts) {
+        self.undos = undos;
+    }
+
+    /// Set the half-life (in days) of learned word boosts.
+    pub fn set_learning_half_life_days(&mut self, half_life_days: f64) {
+        self.learning.set_half_life_days(half_life_days);
     }
 
     /// Load both built-in and personal dictionaries.
@@ -93,8 +371,28 @@ impl Dictionary {
         &mut self,
         dictionary_path: Option<&Path>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        // Load built-in dictionary
+        self.begin_load(dictionary_path)?;
+        self.index_queued(usize::MAX);
+        Ok(())
+    }
+
+    /// Read all dictionaries, leaving built-in words queued for indexing.
+    ///
+    /// Every word is known (and never corrected) as soon as this returns,
+    /// but built-in words only become suggestions once
+    /// [`Dictionary::index_queued`] has processed them. Words are indexed in
+    /// file order, so a frequency-sorted file makes common corrections
+    /// available first.
+    ///
+    /// # Errors
+    /// Returns an error if dictionary files cannot be read.
+    pub fn begin_load(
+        &mut self,
+        dictionary_path: Option<&Path>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Load built-in dictionary, indexing its most frequent words right away
         self.load_builtin_dictionary(dictionary_path)?;
+        self.index_queued(HEAD_WORDS);
 
         // Load personal dictionary if it exists
         if self.personal_dict_path.exists() {
@@ -104,13 +402,115 @@ impl Dictionary {
             self.create_personal_dictionary()?;
         }
 
-        println!("Dictionary loaded: {} words", self.symspell.word_count());
+        let never_correct_path = crate::paths::data_file(crate::paths::NEVER_CORRECT_FILE);
+        if never_correct_path.exists() {
+            match load_word_set(&never_correct_path) {
+                Ok(words) => {
+                    self.never_correct = words;
+                    eprintln!("Loaded {} never-correct words", self.never_correct.len());
+                }
+                Err(err) => eprintln!("Failed to load never-correct words: {err}"),
+            }
+        }
+
+        let corrections_path = crate::paths::data_file(crate::paths::PERSONAL_CORRECTIONS_FILE);
+        if corrections_path.exists() {
+            match std::fs::read_to_string(&corrections_path) {
+                Ok(contents) => {
+                    self.personal_corrections = parse_personal_corrections(&contents);
+                    eprintln!(
+                        "Loaded {} personal corrections",
+                        self.personal_corrections.len()
+                    );
+                }
+                Err(err) => eprintln!("Failed to load personal corrections: {err}"),
+            }
+        }
+
+        if let Err(err) = self.learning.load() {
+            eprintln!("Failed to load learned frequencies: {err}");
+        }
+        if let Err(err) = self.undos.load() {
+            eprintln!("Failed to load undone corrections: {err}");
+        }
+
+        let model_path = crate::paths::local_file(crate::paths::LANGUAGE_MODEL_FILE);
+        if model_path.exists() {
+            match TrigramModel::load(&model_path) {
+                Ok(model) => {
+                    eprintln!("Language model loaded: {} words", model.total_words());
+                    self.symspell.trigram_model = Some(model);
+                }
+                Err(err) => eprintln!("Failed to load language model: {err}"),
+            }
+        }
+
+        // Not for a custom dictionary, which isn't tied to a language
+        if self.foreign_passthrough && dictionary_path.is_none() {
+            self.foreign = ForeignWords::load(&self.language);
+        }
+
+        eprintln!("Dictionary loaded: {} words", self.symspell.word_count());
         Ok(())
     }
 
+    /// Index up to `max_words` queued built-in words.
+    ///
+    /// Returns `true` if words remain queued.
+    pub fn index_queued(&mut self, max_words: usize) -> bool {
+        for _ in 0..max_words {
+            let Some(word) = self.unindexed.pop_front() else {
+                break;
+            };
+            self.symspell.index(&word);
+        }
+        !self.unindexed.is_empty()
+    }
+
+    /// Percentage of queued built-in words indexed so far, or `None` once
+    /// indexing is complete.
+    pub fn load_progress(&self) -> Option<u8> {
+        if self.unindexed.is_empty() {
+            return None;
+        }
+        let done = self.queued_total - self.unindexed.len();
+        Some((done * 100 / self.queued_total) as u8)
+    }
+
+    /// Add built-in words and queue them for indexing, in order.
+    ///
+    /// Words tagged with another language are known, so never corrected,
+    /// but not indexed, so never suggested.
+    ///
+    /// Returns the number of words queued.
+    fn queue_words(&mut self, entries: Vec<Entry>) -> usize {
+        let mut count = 0;
+        for entry in entries {
+            let foreign = entry
+                .language
+                .as_ref()
+                .is_some_and(|language| !language.eq_ignore_ascii_case(&self.language));
+            if !foreign {
+                self.unindexed.push_back(entry.word.clone());
+                count += 1;
+            }
+            self.symspell.add_word(entry.word.clone(), entry.frequency);
+            if let Some(casing) = &entry.casing {
+                self.symspell.set_casing(&entry.word, casing);
+            }
+            if entry.has_fields() {
+                self.entries.insert(entry.word.clone(), entry);
+            }
+        }
+        self.queued_total += count;
+        count
+    }
+
     /// Load the built-in dictionary from file or use fallback.
     ///
-    /// Attempts to load the compile-time embedded dictionary. If it is unavailable
+    /// Uses the custom path if given, otherwise the selected language pack.
+    /// For English (or a missing pack) it loads the compile-time embedded
+    /// dictionary. If that is unavailable
     /// or contains no valid words, falls back to a hardcoded list of common English
     /// words.
     ///
@@ -124,75 +524,51 @@ impl Dictionary {
             return self.load_dictionary_file(dict_path);
         }
 
+        if self.language != crate::languages::BUILTIN_LANGUAGE {
+            let pack_path = crate::languages::pack_path(&self.language);
+            if pack_path.exists() {
+                return self.load_dictionary_file(&pack_path);
+            }
+            eprintln!(
+                "Language pack '{}' not found at {}; using English",
+                self.language,
+                pack_path.display()
+            );
+        }
+
         if EMBEDDED_DICTIONARY.trim().is_empty() {
-            println!("Embedded dictionary unavailable; using fallback dictionary");
+            eprintln!("Embedded dictionary unavailable; using fallback dictionary");
             return self.load_fallback_dictionary();
         }
 
-        let mut loaded_words = 0usize;
-        for line in EMBEDDED_DICTIONARY.lines() {
-            let line = line.trim();
-
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
-
-            // Format: word frequency
-            // or just: word (default frequency = 1)
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.is_empty() {
-                continue;
-            }
-
-            let word = parts[0].to_lowercase();
-            let frequency = if parts.len() > 1 {
-                parts[1].parse::<u64>().unwrap_or(1)
-            } else {
-                1
-            };
-
-            self.symspell.insert(word, frequency);
-            loaded_words += 1;
-        }
+        let loaded_words = self.queue_words(parse_word_list(EMBEDDED_DICTIONARY));
 
         if loaded_words == 0 {
-            println!("Embedded dictionary empty or invalid; using fallback dictionary");
+            eprintln!("Embedded dictionary empty or invalid; using fallback dictionary");
             return self.load_fallback_dictionary();
         }
+        self.apply_casing(BUILTIN_CASING);
 
-        println!("Loaded embedded dictionary with {} words", loaded_words);
+        eprintln!("Loaded embedded dictionary with {} words", loaded_words);
         Ok(())
     }
 
-    /// Load a dictionary from a file path.
-    fn load_dictionary_file(&mut self, dict_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        let file = File::open(dict_path)?;
-        let reader = BufReader::new(file);
-
-        for line in reader.lines() {
-            let line = line?;
-            let line = line.trim();
-
-            if line.is_empty() || line.starts_with('#') {
-                continue;
+    /// Give words already loaded the `case=` casing listed for them in
+    /// `contents`; words not loaded are skipped.
+    fn apply_casing(&mut self, contents: &str) {
+        for entry in parse_word_list(contents) {
+            if let Some(casing) = entry.casing.filter(|_| self.symspell.contains(&entry.word)) {
+                self.symspell.set_casing(&entry.word, &casing);
             }
-
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.is_empty() {
-                continue;
-            }
-
-            let word = parts[0].to_lowercase();
-            let frequency = if parts.len() > 1 {
-                parts[1].parse::<u64>().unwrap_or(1)
-            } else {
-                1
-            };
-
-            self.symspell.insert(word, frequency);
         }
+    }
+
+    /// Load a dictionary from a file path.
+    fn load_dictionary_file(&mut self, dict_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let contents = std::fs::read_to_string(dict_path)?;
+        self.queue_words(parse_word_list(&contents));
 
-        println!("Loaded custom dictionary from {}", dict_path.display());
+        eprintln!("Loaded custom dictionary from {}", dict_path.display());
         Ok(())
     }
 
@@ -329,7 +705,7 @@ impl Dictionary {
             self.symspell.insert(word.to_string(), *freq);
         }
 
-        println!(
+        eprintln!(
             "Loaded fallback dictionary with {} common words",
             common_words.len()
         );
@@ -351,16 +727,21 @@ impl Dictionary {
         let mut count = 0;
         for line in reader.lines() {
             let line = line?;
-            let word = line.trim().to_lowercase();
+            let written = line.trim();
+            let word = written.to_lowercase();
 
             if !word.is_empty() && !word.starts_with('#') {
+                // Written with capitals ("GitHub"): that's how it's spelled
+                if written != word {
+                    self.symspell.set_casing(&word, written);
+                }
                 // Personal words get high frequency to prioritize them
                 self.symspell.insert(word, 1000000);
                 count += 1;
             }
         }
 
-        println!("Loaded {} personal words", count);
+        eprintln!("Loaded {} personal words", count);
         Ok(())
     }
 
@@ -386,22 +767,7 @@ impl Dictionary {
     /// or falls back to `personal_dictionary.txt` in the current directory
     /// if the APPDATA environment variable is not set.
     fn get_personal_dict_path() -> PathBuf {
-        // Try to use user's AppData folder
-        if let Ok(appdata) = std::env::var("APPDATA") {
-            let mut path = PathBuf::from(appdata);
-            path.push("Autocorrect");
-
-            // Create directory if it doesn't exist
-            if !path.exists() {
-                let _ = std::fs::create_dir_all(&path);
-            }
-
-            path.push("personal_dictionary.txt");
-            path
-        } else {
-            // Fallback to current directory
-            PathBuf::from("personal_dictionary.txt")
-        }
+        crate::paths::personal_dictionary()
     }
 
     /// Add a word to the personal dictionary.
@@ -415,10 +781,19 @@ impl Dictionary {
     /// # Errors
     /// Returns an error if the personal dictionary file cannot be written.
     pub fn add_personal_word(&mut self, word: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let word = word.trim().to_lowercase();
+        let written = word.trim();
+        let word_lower = written.to_lowercase();
+        // Capitals past the first letter ("GitHub", "iOS") are the word's
+        // own; a capital first letter may just start a sentence
+        let word = if written.chars().skip(1).any(char::is_uppercase) {
+            self.symspell.set_casing(&word_lower, written);
+            written.to_string()
+        } else {
+            word_lower.clone()
+        };
 
         // Add to SymSpell
-        self.symspell.insert(word.clone(), 1000000);
+        self.symspell.insert(word_lower, 1000000);
 
         // Append to file
         let mut file = std::fs::OpenOptions::new()
@@ -431,52 +806,380 @@ impl Dictionary {
         Ok(())
     }
 
+    /// Count an undo of the correction of `original` to `correction`.
+    ///
+    /// Once the same correction has been undone `learn_after` times,
+    /// `original` is added to the personal dictionary and `true` returned.
+    /// With `learn_after` 0, nothing is learned.
+    ///
+    /// # Errors
+    /// Returns an error if the personal dictionary file cannot be written.
+    pub fn record_undo(
+        &mut self,
+        original: &str,
+        correction: &str,
+        learn_after: u32,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        if learn_after == 0 || self.contains(original) {
+            return Ok(false);
+        }
+        if self.undos.record(original, correction) < learn_after {
+            return Ok(false);
+        }
+        self.undos.forget(original);
+        self.add_personal_word(original)?;
+        Ok(true)
+    }
+
+    /// Record that the user typed (or accepted) a word.
+    ///
+    /// Only words already in the dictionary are learned; unknown tokens
+    /// would never be suggested anyway.
+    pub fn record_usage(&mut self, word: &str) {
+        let word = word.to_lowercase();
+        if self.symspell.contains(&word) {
+            self.learning.record(&word);
+        }
+    }
+
+    /// Write learned frequencies and undo counts to disk.
+    ///
+    /// # Errors
+    /// Returns an error if a file cannot be written.
+    pub fn flush_learning(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.undos.flush()?;
+        self.learning.flush()
+    }
+
+    /// Write changed undo counts to disk, and learned frequencies once
+    /// enough are unsaved (see `LearningStore::flush_if_due`).
+    ///
+    /// # Errors
+    /// Returns an error if a file cannot be written.
+    pub fn flush_learning_if_due(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.undos.flush()?;
+        self.learning.flush_if_due()?;
+        Ok(())
+    }
+
     /// Look up spelling corrections for a word.
     ///
     /// Returns a list of suggestions sorted by edit distance (ascending)
-    /// then frequency (descending).
+    /// then frequency (descending). Frequencies include learned boosts, and
+    /// each `display_term` carries the typed word's capitalization. Words on
+    /// the never-correct list have no suggestions.
     ///
     /// # Arguments
-    /// * `word` - The potentially misspelled word
+    /// * `word` - The potentially misspelled word, as typed
     ///
     /// # Returns
     /// A vector of `SuggestItem` containing suggestions.
     pub fn lookup(&self, word: &str) -> Vec<crate::symspell::SuggestItem> {
-        self.symspell.lookup(word, 2, None)
+        let word_lower = word.to_lowercase();
+        if self.never_correct.contains(&word_lower) {
+            return Vec::new();
+        }
+        let mut suggestions = self.symspell.lookup(&word_lower, 2, None);
+
+        for suggestion in &mut suggestions {
+            if suggestion.distance > 0 {
+                suggestion.typing_distance = self
+                    .keyboard_layout
+                    .typing_distance(&word_lower, &suggestion.term);
+            }
+            let boost = self.learning.boost(&suggestion.term);
+            suggestion.frequency += (boost * LEARNED_BOOST_WEIGHT) as u64;
+            suggestion.transfer_case(word);
+        }
+        suggestions.retain(|suggestion| self.variant_allowed(&suggestion.term));
+
+        suggestions.sort_by(crate::symspell::SuggestItem::rank_order);
+
+        suggestions
+    }
+
+    /// Split a word typed without its space ("inthe") into two known words,
+    /// in the typed word's case ("Inthe" -> "In the").
+    pub fn lookup_compound(&self, word: &str) -> Option<crate::symspell::SuggestItem> {
+        let word_lower = word.to_lowercase();
+        if self.never_correct.contains(&word_lower) {
+            return None;
+        }
+        let mut suggestion = self.symspell.lookup_compound(&word_lower, 0)?;
+        suggestion.transfer_case(word);
+        Some(suggestion)
+    }
+
+    /// The accented word typed without its accents ("cafe" -> "café",
+    /// "Uber" -> "Über"), in the typed word's case.
+    pub fn restore_diacritics(&self, word: &str) -> Option<String> {
+        let word_lower = word.to_lowercase();
+        if self.never_correct.contains(&word_lower) {
+            return None;
+        }
+        self.symspell
+            .restore_diacritics(&word_lower)
+            .map(|accented| crate::symspell::match_case(word, accented))
+    }
+
+    /// Split text typed without spaces ("throwbackthursday", a hashtag) into
+    /// dictionary words, in the typed text's case. `None` unless it splits
+    /// into two or more words, all known.
+    pub fn segment(&self, text: &str) -> Option<String> {
+        let text_lower = text.to_lowercase();
+        if self.never_correct.contains(&text_lower) {
+            return None;
+        }
+        let words = self.symspell.word_segmentation(&text_lower);
+        if words.len() < 2 || !words.iter().all(|word| self.symspell.contains(word)) {
+            return None;
+        }
+        Some(crate::symspell::match_case(text, &words.join(" ")))
+    }
+
+    /// Reorder suggestions by how likely each is after `previous_word`.
+    ///
+    /// Likely suggestions are boosted rather than unlikely ones scaled down,
+    /// so words the model never saw keep their dictionary frequency and
+    /// the ranking policy still compares them fairly.
+    ///
+    /// Does nothing unless a context model has been loaded.
+    pub fn rerank_in_context(
+        &self,
+        suggestions: &mut [crate::symspell::SuggestItem],
+        previous_word: Option<&str>,
+    ) {
+        let (Some(model), Some(previous)) = (&self.symspell.trigram_model, previous_word) else {
+            return;
+        };
+
+        let previous = previous.to_lowercase();
+        for suggestion in suggestions.iter_mut() {
+            let probability = model.trigram_probability(&suggestion.term, &previous, "");
+            let boost = suggestion.frequency as f64 * probability * CONTEXT_BOOST_WEIGHT;
+            suggestion.frequency = suggestion.frequency.saturating_add(boost as u64);
+        }
+
+        suggestions.sort_by(crate::symspell::SuggestItem::rank_order);
+    }
+
+    /// Times `word` followed `previous` in the context model's corpus, or
+    /// 0 without a model.
+    pub fn pair_count(&self, previous: &str, word: &str) -> u64 {
+        self.symspell
+            .trigram_model
+            .as_ref()
+            .map_or(0, |model| model.bigram_count(previous, word))
+    }
+
+    /// Whether a context model is loaded (see `rerank_in_context`).
+    pub fn has_context_model(&self) -> bool {
+        self.symspell.trigram_model.is_some()
+    }
+
+    /// Whether a word (in any case) is in the dictionary.
+    pub fn contains(&self, word: &str) -> bool {
+        self.symspell.contains(&word.to_lowercase())
+    }
+
+    /// How common a word (in any case) is, if it's in the dictionary.
+    pub fn frequency(&self, word: &str) -> Option<u64> {
+        self.symspell.frequency(&word.to_lowercase())
+    }
+
+    /// Whether a word is in the dictionary, is a contraction of a word that
+    /// is ("doesn't", "we'll", "Anna's"), or is a compound of known words
+    /// ("well-known").
+    pub fn knows(&self, word: &str) -> bool {
+        if self.contains(word) {
+            return true;
+        }
+        if word.contains('-') {
+            return word
+                .split('-')
+                .all(|part| !part.is_empty() && self.knows(part));
+        }
+        let Some((stem, suffix)) = word.split_once(['\'', '’']) else {
+            return false;
+        };
+        match suffix.to_lowercase().as_str() {
+            "t" => {
+                let without_n = stem.strip_suffix(['n', 'N']).unwrap_or(stem);
+                self.contains(stem) || self.contains(without_n)
+            }
+            "s" | "ll" | "re" | "ve" | "d" | "m" => self.contains(stem),
+            _ => false,
+        }
+    }
+
+    /// A known word typed in other than its canonical casing ("iphone",
+    /// "Github"), in that casing ("iPhone", "GitHub"). All-caps words are
+    /// left as typed.
+    pub fn canonical_case(&self, word: &str) -> Option<String> {
+        let word_lower = word.to_lowercase();
+        if self.never_correct.contains(&word_lower) {
+            return None;
+        }
+        let cased = crate::symspell::match_case(word, self.symspell.casing(&word_lower)?);
+        (cased != word).then_some(cased)
+    }
+
+    /// The user's own replacement for a typo, in the typed word's case.
+    pub fn personal_correction(&self, word: &str) -> Option<String> {
+        self.personal_corrections
+            .get(&word.to_lowercase())
+            .map(|replacement| crate::symspell::match_case(word, replacement))
     }
 
     /// Get the best correction for a word, if one exists.
     ///
-    /// Returns `Some(correction)` only if:
-    /// - There are suggestions
-    /// - The top suggestion is different from the input
-    /// - The edit distance is <= 2
+    /// A personal correction wins outright. Otherwise suggestions are ranked
+    /// by [`Dictionary::lookup`]; `policy` decides which of them (if any) is
+    /// trustworthy enough to apply.
     ///
     /// # Arguments
     /// * `word` - The word to check
+    /// * `policy` - Rules for accepting a suggestion
     ///
     /// # Returns
     /// `Some(corrected_word)` if a correction is available, `None` otherwise.
-    pub fn get_correction(&self, word: &str) -> Option<String> {
+    /// The correction follows the capitalization of `word`.
+    pub fn get_correction(&self, word: &str, policy: &RankingPolicy) -> Option<String> {
+        if let Some(replacement) = self.personal_correction(word) {
+            return Some(replacement);
+        }
         let suggestions = self.lookup(word);
+        policy
+            .resolved(self.has_context_model())
+            .choose(word, &suggestions)
+            .map(|suggestion| suggestion.display_term.clone())
+    }
+}
+
+/// Words of a language pack (the embedded dictionary for English), without
+/// building an index.
+///
+/// # Errors
+/// Returns an error if the pack file cannot be read.
+pub fn pack_words(language: &str) -> std::io::Result<HashSet<String>> {
+    let pack;
+    let contents = if language == crate::languages::BUILTIN_LANGUAGE {
+        EMBEDDED_DICTIONARY
+    } else {
+        pack = std::fs::read_to_string(crate::languages::pack_path(language))?;
+        &pack
+    };
+    Ok(parse_word_list(contents)
+        .into_iter()
+        .map(|entry| entry.word)
+        .collect())
+}
 
-        // Return correction only if:
-        // 1. There are suggestions
-        // 2. The top suggestion is different from input
-        // 3. The distance is <= 2
-        if let Some(suggestion) = suggestions.first() {
-            if suggestion.term.to_lowercase() != word.to_lowercase() && suggestion.distance <= 2 {
-                return Some(suggestion.term.clone());
+/// Read a list of words, one per line, lowercased. Blank lines and `#`
+/// comments are skipped.
+///
+/// # Errors
+/// Returns an error if the file cannot be read.
+fn load_word_set(path: &Path) -> std::io::Result<HashSet<String>> {
+    Ok(parse_word_set(&std::fs::read_to_string(path)?))
+}
+
+fn parse_word_set(contents: &str) -> HashSet<String> {
+    contents
+        .lines()
+        .map(|line| line.trim().to_lowercase())
+        .filter(|word| !word.is_empty() && !word.starts_with('#'))
+        .collect()
+}
+
+/// Parse `typo -> replacement` lines. Blank lines and `#` comments are
+/// skipped, as are lines without an arrow or with nothing on one side of
+/// it.
+fn parse_personal_corrections(contents: &str) -> HashMap<String, String> {
+    let mut corrections = HashMap::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((typo, replacement)) = line.split_once("->") else {
+            eprintln!("Ignoring personal correction without '->': {line}");
+            continue;
+        };
+        let (typo, replacement) = (typo.trim(), replacement.trim());
+        if typo.is_empty() || replacement.is_empty() {
+            eprintln!("Ignoring incomplete personal correction: {line}");
+            continue;
+        }
+        corrections.insert(typo.to_lowercase(), replacement.to_string());
+    }
+    corrections
+}
+
+/// Parse dictionary text into entries, most frequent first.
+///
+/// Lines are `word [frequency] [key=value ...]`; blank lines and `#`
+/// comments are skipped, as is anything after a `#` on a line. Files
+/// starting with [`SORTED_MARKER`] are already in frequency order, and words
+/// without a count get one from their rank. Other files default to a
+/// frequency of 1 and are sorted here, keeping file order among equals.
+fn parse_word_list(contents: &str) -> Vec<Entry> {
+    let sorted = contents.trim_start().starts_with(SORTED_MARKER);
+    let mut entries = Vec::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // Format: word frequency
+        // or just: word (frequency from rank, or 1 if unsorted)
+        let line = line.split(" #").next().unwrap_or(line);
+        let mut parts = line.split_whitespace();
+        let Some(word) = parts.next() else {
+            continue;
+        };
+
+        let default_frequency = if sorted {
+            (SORTED_TOP_FREQUENCY / (entries.len() as u64 + 1)).max(1)
+        } else {
+            1
+        };
+        let mut entry = Entry {
+            word: word.to_lowercase(),
+            frequency: default_frequency,
+            ..Entry::default()
+        };
+        for part in parts {
+            match part.split_once('=') {
+                Some(("case", casing)) => entry.casing = Some(casing.to_string()),
+                Some(("lang", language)) => entry.language = Some(language.to_lowercase()),
+                Some(("variant", variant)) => entry.variant = SpellingVariant::from_tag(variant),
+                Some(("pos", tags)) => entry.part_of_speech = Some(tags.to_lowercase()),
+                Some(_) => {}
+                None => {
+                    if let Ok(count) = part.parse::<u64>() {
+                        entry.frequency = count;
+                    }
+                }
             }
         }
 
-        None
+        entries.push(entry);
     }
+
+    if !sorted {
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.frequency));
+    }
+    entries
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ranking::SelectionStrategy;
 
     #[test]
     fn test_fallback_dictionary() {
@@ -490,7 +1193,222 @@ mod tests {
         let mut dict = Dictionary::new();
         dict.load_fallback_dictionary().unwrap();
 
-        let correction = dict.get_correction("teh");
+        let correction = dict.get_correction("teh", &RankingPolicy::default());
         assert_eq!(correction, Some("the".to_string()));
     }
+
+    #[test]
+    fn test_never_correct() {
+        let mut dict = Dictionary::new();
+        dict.load_fallback_dictionary().unwrap();
+        dict.never_correct = parse_word_set("# Never correct\nTeh\n\nyeet\n");
+
+        let policy = RankingPolicy::default();
+        assert_eq!(dict.get_correction("teh", &policy), None);
+        assert_eq!(dict.get_correction("TEH", &policy), None);
+        assert!(dict.lookup("yeet").is_empty());
+        assert_eq!(dict.get_correction("hte", &policy), Some("the".to_string()));
+    }
+
+    #[test]
+    fn test_personal_corrections() {
+        let mut dict = Dictionary::new();
+        dict.load_fallback_dictionary().unwrap();
+        dict.personal_corrections =
+            parse_personal_corrections("# Mine\nalot -> a lot\nteh->ten\nnonsense\nhte ->\n");
+
+        let policy = RankingPolicy::default();
+        assert_eq!(dict.get_correction("teh", &policy), Some("ten".to_string()));
+        assert_eq!(
+            dict.get_correction("Alot", &policy),
+            Some("A lot".to_string())
+        );
+        assert_eq!(dict.personal_correction("nonsense"), None);
+        // Never replaced with nothing
+        assert_eq!(dict.personal_correction("hte"), None);
+        assert_eq!(dict.personal_corrections.len(), 2);
+    }
+
+    #[test]
+    fn test_queued_words_known_before_indexed() {
+        let mut dict = Dictionary::new();
+        dict.queue_words(parse_word_list("hello 100"));
+        let policy = RankingPolicy::default();
+
+        assert_eq!(dict.load_progress(), Some(0));
+        assert_eq!(dict.get_correction("hello", &policy), None);
+        assert_eq!(dict.get_correction("helo", &policy), None);
+
+        assert!(!dict.index_queued(10));
+        assert_eq!(dict.load_progress(), None);
+        assert_eq!(
+            dict.get_correction("helo", &policy),
+            Some("hello".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_word_list() {
+        let pairs = |entries: Vec<Entry>| {
+            entries
+                .into_iter()
+                .map(|entry| (entry.word, entry.frequency))
+                .collect::<Vec<_>>()
+        };
+        let unsorted = pairs(parse_word_list("# comment\nzebra\nhello 50\nworld 90\n"));
+        assert_eq!(
+            unsorted,
+            [
+                ("world".to_string(), 90),
+                ("hello".to_string(), 50),
+                ("zebra".to_string(), 1)
+            ]
+        );
+
+        let sorted = pairs(parse_word_list("#!sorted\nthe\nof\nand 7\n"));
+        assert_eq!(
+            sorted,
+            [
+                ("the".to_string(), 1_000_000),
+                ("of".to_string(), 500_000),
+                ("and".to_string(), 7)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_entry_fields() {
+        let entries = parse_word_list(
+            "iphone 500 case=iPhone\ncolour variant=uk # British\ncolor 90 variant=us\n\
+             bonjour 10 lang=fr future=1\nthan 80 pos=conj,prep\n",
+        );
+        let iphone = &entries[0];
+        assert_eq!(iphone.word, "iphone");
+        assert_eq!(iphone.casing.as_deref(), Some("iPhone"));
+        assert_eq!(entries[4].word, "colour");
+        assert_eq!(entries[4].frequency, 1);
+        assert_eq!(entries[4].variant, Some(SpellingVariant::Uk));
+
+        let mut dict = Dictionary::new();
+        dict.queue_words(entries);
+        dict.index_queued(usize::MAX);
+        assert_eq!(dict.lookup("iphon")[0].display_term, "iPhone");
+        assert_eq!(dict.lookup("IPHON")[0].display_term, "IPHONE");
+        assert_eq!(dict.canonical_case("iphone").as_deref(), Some("iPhone"));
+        assert_eq!(dict.canonical_case("Iphone").as_deref(), Some("iPhone"));
+        assert_eq!(dict.canonical_case("iPhone"), None);
+        assert_eq!(dict.canonical_case("IPHONE"), None);
+        assert_eq!(dict.canonical_case("than"), None);
+        assert_eq!(dict.part_of_speech("Than"), Some("conj,prep"));
+        // Known, but not suggested
+        assert!(dict.contains("bonjour"));
+        assert!(dict.lookup("bonjur").is_empty());
+
+        let terms = |dict: &Dictionary| {
+            dict.lookup("colur")
+                .into_iter()
+                .map(|suggestion| suggestion.term)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(terms(&dict), ["color", "colour"]);
+        dict.set_spelling_variant(SpellingVariant::Uk);
+        assert_eq!(terms(&dict), ["colour"]);
+    }
+
+    #[test]
+    fn test_builtin_casing() {
+        let mut dict = Dictionary::new();
+        dict.queue_words(parse_word_list("ebay\nshop\n"));
+        dict.apply_casing(BUILTIN_CASING);
+        assert_eq!(dict.canonical_case("ebay").as_deref(), Some("eBay"));
+        assert_eq!(dict.canonical_case("Ebay").as_deref(), Some("eBay"));
+        // Not in the word list: still unknown
+        assert!(!dict.contains("paypal"));
+        assert_eq!(dict.canonical_case("paypal"), None);
+    }
+
+    /// Typos and the correction expected from the fallback dictionary.
+    const GOLDEN_CORPUS: &[(&str, Option<&str>)] = &[
+        ("teh", Some("the")),
+        ("becuase", Some("because")),
+        ("peopel", Some("people")),
+        ("computr", Some("computer")),
+        ("intrenat", Some("internet")),
+        ("sofwtare", Some("software")),
+        ("the", None),
+        ("qx", None),
+        ("xyzzy", None),
+    ];
+
+    #[test]
+    fn test_golden_corpus() {
+        let mut dict = Dictionary::new();
+        dict.load_fallback_dictionary().unwrap();
+
+        let strict = RankingPolicy {
+            strategy: SelectionStrategy::Strict,
+            ..RankingPolicy::default()
+        };
+        for (typo, expected) in GOLDEN_CORPUS {
+            assert_eq!(
+                dict.get_correction(typo, &RankingPolicy::default())
+                    .as_deref(),
+                *expected,
+                "correction for '{}'",
+                typo
+            );
+
+            // Strict may miss corrections, but never makes a wrong one
+            let correction = dict.get_correction(typo, &strict);
+            assert!(
+                correction.is_none() || correction.as_deref() == *expected,
+                "strict correction for '{}'",
+                typo
+            );
+        }
+    }
+
+    #[test]
+    fn test_learned_boost_changes_ranking() {
+        let mut dict = Dictionary::new();
+        dict.learning = LearningStore::with_path(std::env::temp_dir().join(format!(
+            "autocorrect_dictionary_learning_test_{}.txt",
+            std::process::id()
+        )));
+        dict.set_keyboard_layout(KeyboardLayout::None);
+        dict.symspell.insert("cello".to_string(), 100);
+        dict.symspell.insert("hello".to_string(), 50);
+
+        let policy = RankingPolicy::default();
+        assert_eq!(
+            dict.get_correction("jello", &policy),
+            Some("cello".to_string())
+        );
+
+        dict.record_usage("hello");
+        assert_eq!(
+            dict.get_correction("jello", &policy),
+            Some("hello".to_string())
+        );
+    }
+
+    #[test]
+    fn test_neighboring_key_changes_ranking() {
+        let mut dict = Dictionary::new();
+        dict.symspell.insert("cello".to_string(), 100);
+        dict.symspell.insert("hello".to_string(), 50);
+
+        // J is next to H, nowhere near C
+        let policy = RankingPolicy::default();
+        assert_eq!(
+            dict.get_correction("jello", &policy),
+            Some("hello".to_string())
+        );
+
+        dict.set_keyboard_layout(KeyboardLayout::None);
+        assert_eq!(
+            dict.get_correction("jello", &policy),
+            Some("cello".to_string())
+        );
+    }
 }
@@ -0,0 +1,140 @@
+//! Detection of elevated windows, which our keystrokes can't reach.
+//!
+//! Windows blocks input injected by a process into windows of a process
+//! with a higher integrity level (User Interface Privilege Isolation): an
+//! app "run as administrator" ignores `SendInput` from an unelevated
+//! autocorrect, without `SendInput` reporting it. A correction there would
+//! go out as backspaces and letters that are silently dropped, or worse,
+//! partly delivered. While such a window is in the foreground, corrections
+//! pause; run autocorrect elevated to have them corrected too.
+//!
+//! The secure desktop and credential prompts are covered by `secure_input`.
+//! Like it, the result is cached per foreground window, since it's checked
+//! on every key event.
+
+#[cfg(windows)]
+use std::cell::Cell;
+
+/// Whether a window whose process has integrity level `target` is out of
+/// reach of a process at level `own`.
+///
+/// `target` is `None` for a process we could open but whose token we
+/// couldn't read, which only a more privileged process refuses. `own` is
+/// `None` if our own token couldn't be read: nothing is known to outrank
+/// us then, and corrections go on rather than pausing everywhere.
+pub fn outranks(target: Option<u32>, own: Option<u32>) -> bool {
+    own.is_some_and(|own| target.is_none_or(|target| target > own))
+}
+
+#[cfg(windows)]
+thread_local! {
+    /// Last foreground window checked and whether it was elevated.
+    static LAST_CHECK: Cell<(usize, bool)> = const { Cell::new((0, false)) };
+}
+
+/// Whether the foreground window belongs to a process of higher integrity
+/// than ours.
+#[cfg(windows)]
+pub fn is_active() -> bool {
+    use std::sync::OnceLock;
+    use winapi::um::processthreadsapi::{GetCurrentProcessId, OpenProcess};
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+    use winapi::um::winuser::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    static OWN_LEVEL: OnceLock<Option<u32>> = OnceLock::new();
+
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_null() {
+        return false;
+    }
+    let key = hwnd as usize;
+    let (last_hwnd, last_result) = LAST_CHECK.with(Cell::get);
+    if last_hwnd == key {
+        return last_result;
+    }
+
+    let elevated = unsafe {
+        let mut process_id = 0;
+        GetWindowThreadProcessId(hwnd, &mut process_id);
+        if process_id == 0 || process_id == GetCurrentProcessId() {
+            false
+        } else {
+            let own = *OWN_LEVEL.get_or_init(|| {
+                integrity_level(winapi::um::processthreadsapi::GetCurrentProcess())
+            });
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, process_id);
+            if process.is_null() {
+                // Protected system processes can't be opened at all, and
+                // have no windows to type into
+                false
+            } else {
+                let level = integrity_level(process);
+                winapi::um::handleapi::CloseHandle(process);
+                outranks(level, own)
+            }
+        }
+    };
+    LAST_CHECK.with(|cell| cell.set((key, elevated)));
+    elevated
+}
+
+#[cfg(not(windows))]
+pub fn is_active() -> bool {
+    false
+}
+
+/// Mandatory integrity level (e.g. `0x2000` medium, `0x3000` high) of a
+/// process, or `None` if its token can't be read.
+#[cfg(windows)]
+unsafe fn integrity_level(process: winapi::um::winnt::HANDLE) -> Option<u32> {
+    use winapi::um::processthreadsapi::OpenProcessToken;
+    use winapi::um::securitybaseapi::{
+        GetSidSubAuthority, GetSidSubAuthorityCount, GetTokenInformation,
+    };
+    use winapi::um::winnt::{TokenIntegrityLevel, TOKEN_MANDATORY_LABEL, TOKEN_QUERY};
+
+    let mut token = std::ptr::null_mut();
+    if OpenProcessToken(process, TOKEN_QUERY, &mut token) == 0 {
+        return None;
+    }
+
+    // A label is a SID with a few sub-authorities; 64 bytes is plenty
+    let mut buffer = [0u64; 8];
+    let mut needed = 0;
+    let ok = GetTokenInformation(
+        token,
+        TokenIntegrityLevel,
+        buffer.as_mut_ptr() as _,
+        std::mem::size_of_val(&buffer) as u32,
+        &mut needed,
+    );
+    winapi::um::handleapi::CloseHandle(token);
+    if ok == 0 {
+        return None;
+    }
+
+    let label = &*(buffer.as_ptr() as *const TOKEN_MANDATORY_LABEL);
+    let sid = label.Label.Sid;
+    let count = *GetSidSubAuthorityCount(sid);
+    if count == 0 {
+        return None;
+    }
+    Some(*GetSidSubAuthority(sid, count as u32 - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outranks() {
+        // High (administrator) over medium (normal user)
+        assert!(outranks(Some(0x3000), Some(0x2000)));
+        assert!(!outranks(Some(0x2000), Some(0x2000)));
+        assert!(!outranks(Some(0x2000), Some(0x3000)));
+        assert!(outranks(None, Some(0x2000)));
+        // Without our own level, nothing pauses corrections
+        assert!(!outranks(Some(0x3000), None));
+        assert!(!outranks(None, None));
+    }
+}
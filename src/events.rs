@@ -0,0 +1,211 @@
+//! Stream of correction, undo and toggle events for external tools.
+//!
+//! With `stream_events = true`, the running instance appends one JSON object
+//! per line to `events.jsonl` in the local data directory, e.g.
+//!
+//! ```text
+//! {"event":"correction","original":"teh","replacement":"the","time":1760000000000}
+//! {"event":"undo","restored":"teh","removed":"the","time":1760000001200}
+//! {"event":"toggle","enabled":false,"time":1760000005000}
+//! ```
+//!
+//! Like the status file, it's a file rather than a pipe, so any tool that
+//! can follow a file (stream overlays, dashboards, AutoHotkey scripts,
+//! `autocorrect events`) can subscribe without talking to the process.
+//!
+//! The file starts empty each session and is cleared when it grows past
+//! [`MAX_EVENTS_FILE_BYTES`]. Events containing words are not written while
+//! a sensitive app is in the foreground.
+//!
+//! Events are emitted from the keyboard hook, so they're handed to a writer
+//! thread instead of being written in place.
+
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The file is cleared before it grows past this size.
+pub const MAX_EVENTS_FILE_BYTES: u64 = 1024 * 1024;
+
+/// How often `autocorrect events` checks the file for new lines.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Whether events are being written (`stream_events`).
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Lines waiting for the writer thread, started when events are enabled.
+static WRITER: OnceLock<Sender<String>> = OnceLock::new();
+
+/// Something subscribers may want to react to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum Event<'a> {
+    /// A word (or two, for grammar fixes) was replaced.
+    Correction {
+        original: &'a str,
+        replacement: &'a str,
+    },
+    /// A correction was reverted.
+    Undo { restored: &'a str, removed: &'a str },
+    /// Autocorrect was enabled or disabled.
+    Toggle { enabled: bool },
+}
+
+impl Event<'_> {
+    /// Whether the event contains typed text.
+    fn contains_words(&self) -> bool {
+        !matches!(self, Event::Toggle { .. })
+    }
+
+    /// One JSON line, with the time in milliseconds since the Unix epoch.
+    pub fn to_json_line(&self, time_ms: u64) -> String {
+        #[derive(Serialize)]
+        struct Line<'a, 'b> {
+            #[serde(flatten)]
+            event: &'b Event<'a>,
+            time: u64,
+        }
+
+        let mut line = serde_json::to_string(&Line {
+            event: self,
+            time: time_ms,
+        })
+        .expect("events serialize to JSON");
+        line.push('\n');
+        line
+    }
+}
+
+/// Path of the event stream file.
+pub fn events_file() -> PathBuf {
+    crate::paths::local_file(crate::paths::EVENTS_FILE)
+}
+
+/// Start or stop writing events. Starting clears events from earlier
+/// sessions.
+pub fn set_enabled(enabled: bool) {
+    if enabled {
+        if let Err(e) = std::fs::write(events_file(), "") {
+            eprintln!("Failed to create {}: {}", events_file().display(), e);
+        }
+        WRITER.get_or_init(start_writer);
+    }
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Start the thread that appends queued lines to the file.
+fn start_writer() -> Sender<String> {
+    let (sender, lines) = mpsc::channel::<String>();
+    std::thread::spawn(move || {
+        for line in lines {
+            if let Err(e) = append(&line) {
+                eprintln!("Failed to write event: {}", e);
+            }
+        }
+    });
+    sender
+}
+
+/// Queue an event for the stream, if enabled.
+pub fn emit(event: Event) {
+    if !ENABLED.load(Ordering::Relaxed)
+        || (event.contains_words() && !crate::privacy::recording_allowed())
+    {
+        return;
+    }
+
+    let time_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    if let Some(writer) = WRITER.get() {
+        if writer.send(event.to_json_line(time_ms)).is_err() {
+            eprintln!("Failed to write event: the writer thread stopped");
+        }
+    }
+}
+
+fn append(line: &str) -> std::io::Result<()> {
+    let path = events_file();
+    let full = std::fs::metadata(&path)
+        .map(|meta| meta.len() + line.len() as u64 > MAX_EVENTS_FILE_BYTES)
+        .unwrap_or(false);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(!full)
+        .write(true)
+        .truncate(full)
+        .open(path)?;
+    file.write_all(line.as_bytes())
+}
+
+/// Print events from the running instance as they happen (`autocorrect
+/// events`). Runs until interrupted.
+///
+/// # Errors
+/// Returns an error if there is no event stream or it can't be read.
+pub fn follow() -> Result<(), Box<dyn std::error::Error>> {
+    let path = events_file();
+    let mut file = std::fs::File::open(&path).map_err(|_| {
+        "No event stream. Is Autocorrect running with stream_events = true?".to_string()
+    })?;
+    let mut position = file.seek(SeekFrom::End(0))?;
+    let stdout = std::io::stdout();
+
+    loop {
+        let len = file.metadata()?.len();
+        if len < position {
+            // Cleared: a new session started or the file hit its size limit
+            position = 0;
+        }
+
+        if len > position {
+            file.seek(SeekFrom::Start(position))?;
+            let mut reader = BufReader::new(&file);
+            let mut line = String::new();
+            let mut out = stdout.lock();
+            while reader.read_line(&mut line)? > 0 {
+                if !line.ends_with('\n') {
+                    break; // Still being written
+                }
+                position += line.len() as u64;
+                out.write_all(line.as_bytes())?;
+                line.clear();
+            }
+            out.flush()?;
+        }
+
+        std::thread::sleep(FOLLOW_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_json() {
+        let correction = Event::Correction {
+            original: "teh",
+            replacement: "the",
+        };
+        assert_eq!(
+            correction.to_json_line(42),
+            "{\"event\":\"correction\",\"original\":\"teh\",\"replacement\":\"the\",\"time\":42}\n"
+        );
+        assert!(correction.contains_words());
+
+        let toggle = Event::Toggle { enabled: false };
+        assert_eq!(
+            toggle.to_json_line(7),
+            "{\"event\":\"toggle\",\"enabled\":false,\"time\":7}\n"
+        );
+        assert!(!toggle.contains_words());
+    }
+}
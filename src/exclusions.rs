@@ -0,0 +1,246 @@
+//! Suspending corrections in particular foreground windows.
+//!
+//! - **Excluded titles**: Some sensitive contexts share a process with
+//!   everything else (a password manager's browser tab) and can only be told
+//!   apart by window title. Titles matching any regular expression under
+//!   `[apps] excluded_titles` pause corrections.
+//! - **Remote clients**: Remote desktop and VM windows forward keystrokes to
+//!   another machine, which may run its own autocorrect; correcting on both
+//!   sides garbles text. Known clients pause corrections unless listed under
+//!   `[apps] remote_allowed`.
+//! - **Per-app lists**: Corrections are off in apps listed under
+//!   `[apps] disabled` (an IDE, a terminal) and, if `[apps] enabled_only` is
+//!   not empty, in every app not listed there. Entries are executable names
+//!   or window classes.
+//!
+//! Like `privacy`, the state is kept in statics: the focus callback updates
+//! it without the corrector lock, and the keyboard hook reads it on every
+//! key press.
+
+use parking_lot::RwLock;
+use regex::Regex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Compiled `[apps] excluded_titles` patterns.
+static TITLE_PATTERNS: RwLock<Vec<Regex>> = RwLock::new(Vec::new());
+
+/// Whether the foreground window's title is excluded.
+static EXCLUDED: AtomicBool = AtomicBool::new(false);
+
+/// Remote desktop and VM client executables, lowercase.
+const REMOTE_CLIENT_PROCESSES: &[&str] = &[
+    "mstsc.exe",          // Remote Desktop Connection
+    "msrdc.exe",          // Remote Desktop client (Windows App)
+    "vmconnect.exe",      // Hyper-V
+    "vmware.exe",         // VMware Workstation
+    "vmplayer.exe",       // VMware Player
+    "vmware-vmx.exe",     // VMware VM process
+    "vmrc.exe",           // VMware Remote Console
+    "virtualboxvm.exe",   // VirtualBox VM window
+    "prl_client_app.exe", // Parallels Client
+    "wfica32.exe",        // Citrix Workspace session
+    "remote-viewer.exe",  // virt-viewer (SPICE/VNC)
+    "vncviewer.exe",      // TigerVNC, RealVNC and TightVNC viewers
+];
+
+/// Window classes of remote desktop and VM client windows, for clients whose
+/// executable has a different name (portable or embedded copies).
+const REMOTE_CLIENT_CLASSES: &[&str] = &[
+    "TscShellContainerClass",     // Remote Desktop Connection
+    "VMUIFrame",                  // VMware Workstation
+    "VMPlayerFrame",              // VMware Player
+    "Transparent Windows Client", // Citrix
+];
+
+/// Remote clients where corrections stay on (`[apps] remote_allowed`), lowercase.
+static REMOTE_ALLOWED: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Whether the foreground window is a remote desktop or VM client.
+static REMOTE: AtomicBool = AtomicBool::new(false);
+
+/// `[apps] disabled`, lowercase.
+static DISABLED_APPS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// `[apps] enabled_only`, lowercase.
+static ENABLED_ONLY_APPS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Whether the foreground app is turned off by the per-app lists.
+static APP_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Compile title patterns, skipping invalid ones.
+///
+/// Returns a message for each pattern that isn't a valid regular expression.
+pub fn compile_patterns<S: AsRef<str>>(patterns: &[S]) -> (Vec<Regex>, Vec<String>) {
+    let mut compiled = Vec::new();
+    let mut errors = Vec::new();
+
+    for pattern in patterns {
+        match Regex::new(pattern.as_ref()) {
+            Ok(regex) => compiled.push(regex),
+            Err(err) => errors.push(format!(
+                "invalid excluded title pattern '{}': {}",
+                pattern.as_ref(),
+                err
+            )),
+        }
+    }
+
+    (compiled, errors)
+}
+
+/// Set the excluded title patterns.
+///
+/// Returns a message for each pattern that was skipped as invalid.
+pub fn set_title_patterns<S: AsRef<str>>(patterns: &[S]) -> Vec<String> {
+    let (compiled, errors) = compile_patterns(patterns);
+    *TITLE_PATTERNS.write() = compiled;
+    errors
+}
+
+/// Whether a window title matches any of the patterns.
+pub fn title_excluded(title: &str, patterns: &[Regex]) -> bool {
+    patterns.iter().any(|pattern| pattern.is_match(title))
+}
+
+/// Update the exclusion state after the foreground window or its title changed.
+pub fn set_foreground_title(title: Option<&str>) {
+    let excluded = title
+        .map(|title| title_excluded(title, &TITLE_PATTERNS.read()))
+        .unwrap_or(false);
+    EXCLUDED.store(excluded, Ordering::Relaxed);
+}
+
+/// Whether corrections are suspended for the foreground window's title.
+pub fn is_excluded() -> bool {
+    EXCLUDED.load(Ordering::Relaxed)
+}
+
+/// Set the remote clients where corrections stay on (executable names).
+pub fn set_remote_allowed<S: AsRef<str>>(apps: &[S]) {
+    *REMOTE_ALLOWED.write() = apps
+        .iter()
+        .map(|app| app.as_ref().trim().to_lowercase())
+        .filter(|app| !app.is_empty())
+        .collect();
+}
+
+/// Whether a window belongs to a known remote desktop or VM client.
+pub fn is_remote_client(process_name: Option<&str>, class_name: Option<&str>) -> bool {
+    let by_process = process_name.is_some_and(|name| {
+        REMOTE_CLIENT_PROCESSES
+            .iter()
+            .any(|client| client.eq_ignore_ascii_case(name))
+    });
+    let by_class = class_name.is_some_and(|class| REMOTE_CLIENT_CLASSES.contains(&class));
+    by_process || by_class
+}
+
+/// Update the remote client state after the foreground window changed.
+pub fn set_foreground_client(process_name: Option<&str>, class_name: Option<&str>) {
+    let allowed = process_name.is_some_and(|name| {
+        REMOTE_ALLOWED
+            .read()
+            .iter()
+            .any(|app| app.eq_ignore_ascii_case(name))
+    });
+    REMOTE.store(
+        !allowed && is_remote_client(process_name, class_name),
+        Ordering::Relaxed,
+    );
+}
+
+/// Whether a remote desktop or VM client is in the foreground (and not
+/// allowed).
+pub fn is_remote() -> bool {
+    REMOTE.load(Ordering::Relaxed)
+}
+
+/// Set the per-app lists (executable names or window classes).
+pub fn set_app_lists<S: AsRef<str>>(disabled: &[S], enabled_only: &[S]) {
+    let normalize = |apps: &[S]| -> Vec<String> {
+        apps.iter()
+            .map(|app| app.as_ref().trim().to_lowercase())
+            .filter(|app| !app.is_empty())
+            .collect()
+    };
+    *DISABLED_APPS.write() = normalize(disabled);
+    *ENABLED_ONLY_APPS.write() = normalize(enabled_only);
+}
+
+/// Whether the per-app lists turn corrections off for a window.
+pub fn app_disabled<S: AsRef<str>>(
+    process_name: Option<&str>,
+    class_name: Option<&str>,
+    disabled: &[S],
+    enabled_only: &[S],
+) -> bool {
+    let listed = |apps: &[S]| {
+        apps.iter().any(|app| {
+            let app = app.as_ref();
+            process_name.is_some_and(|name| app.eq_ignore_ascii_case(name))
+                || class_name.is_some_and(|class| app.eq_ignore_ascii_case(class))
+        })
+    };
+    listed(disabled) || (!enabled_only.is_empty() && !listed(enabled_only))
+}
+
+/// Update the per-app state after the foreground window changed.
+pub fn set_foreground_app(process_name: Option<&str>, class_name: Option<&str>) {
+    let disabled = app_disabled(
+        process_name,
+        class_name,
+        &DISABLED_APPS.read(),
+        &ENABLED_ONLY_APPS.read(),
+    );
+    APP_DISABLED.store(disabled, Ordering::Relaxed);
+}
+
+/// Whether corrections are off for the foreground app.
+pub fn is_app_disabled() -> bool {
+    APP_DISABLED.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_title_patterns() {
+        let (patterns, errors) =
+            compile_patterns(&[".*— Bitwarden.*", ".* – Remote Desktop", "(unclosed"]);
+        assert_eq!(patterns.len(), 2);
+        assert_eq!(errors.len(), 1);
+
+        assert!(title_excluded("Vault — Bitwarden - Firefox", &patterns));
+        assert!(title_excluded("work-pc – Remote Desktop", &patterns));
+        assert!(!title_excluded("Untitled - Notepad", &patterns));
+    }
+
+    #[test]
+    fn test_is_remote_client() {
+        assert!(is_remote_client(Some("MSTSC.EXE"), None));
+        assert!(is_remote_client(None, Some("VMUIFrame")));
+        assert!(!is_remote_client(Some("notepad.exe"), Some("Notepad")));
+        assert!(!is_remote_client(None, None));
+    }
+
+    #[test]
+    fn test_app_disabled() {
+        let none: [&str; 0] = [];
+        let ide = ["Code.exe", "CASCADIA_HOSTING_WINDOW_CLASS"];
+        assert!(app_disabled(Some("code.exe"), None, &ide, &none));
+        assert!(app_disabled(
+            Some("WindowsTerminal.exe"),
+            Some("CASCADIA_HOSTING_WINDOW_CLASS"),
+            &ide,
+            &none
+        ));
+        assert!(!app_disabled(Some("slack.exe"), None, &ide, &none));
+
+        let slack = ["slack.exe"];
+        assert!(!app_disabled(Some("Slack.exe"), None, &none, &slack));
+        assert!(app_disabled(Some("notepad.exe"), None, &none, &slack));
+        assert!(app_disabled(None, None, &none, &slack));
+        assert!(!app_disabled(None, None, &none, &none));
+    }
+}
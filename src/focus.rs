@@ -0,0 +1,119 @@
+//! Notifications when the foreground window or keyboard focus changes.
+//!
+//! Uses out-of-context `SetWinEventHook` subscriptions, so events are
+//! delivered through the message loop of the thread that subscribed. Events
+//! caused by our own windows (tray menu, dialogs, self-test) are skipped.
+//! Title changes are only reported for the foreground window itself.
+
+#[cfg(windows)]
+use std::ptr::null_mut;
+#[cfg(windows)]
+use std::sync::OnceLock;
+#[cfg(windows)]
+use winapi::shared::windef::{HWINEVENTHOOK, HWND};
+#[cfg(windows)]
+use winapi::um::winuser::{
+    GetForegroundWindow, SetWinEventHook, UnhookWinEvent, EVENT_OBJECT_FOCUS,
+    EVENT_OBJECT_NAMECHANGE, EVENT_SYSTEM_FOREGROUND, OBJID_WINDOW, WINEVENT_OUTOFCONTEXT,
+    WINEVENT_SKIPOWNPROCESS,
+};
+
+/// What kind of focus change happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusChange {
+    /// A different top-level window came to the foreground.
+    Foreground,
+    /// Keyboard focus moved to another control (e.g. a different text field).
+    Control,
+    /// The foreground window's title changed (e.g. a different browser tab).
+    Title,
+}
+
+/// Function called for every focus change.
+#[cfg(windows)]
+static HANDLER: OnceLock<fn(FocusChange)> = OnceLock::new();
+
+/// Active event subscriptions; dropping this unsubscribes.
+#[cfg(windows)]
+pub struct FocusHooks {
+    hooks: Vec<HWINEVENTHOOK>,
+}
+
+#[cfg(windows)]
+impl Drop for FocusHooks {
+    fn drop(&mut self) {
+        for hook in self.hooks.drain(..) {
+            unsafe {
+                UnhookWinEvent(hook);
+            }
+        }
+    }
+}
+
+/// Call `handler` whenever the foreground window, its title or keyboard
+/// focus changes.
+///
+/// Must be called from a thread running a message loop. Only one handler can
+/// be set per process.
+///
+/// # Errors
+/// Returns an error if a handler was already set or Windows refuses the
+/// subscription.
+#[cfg(windows)]
+pub fn subscribe(handler: fn(FocusChange)) -> Result<FocusHooks, String> {
+    HANDLER
+        .set(handler)
+        .map_err(|_| "focus handler already set".to_string())?;
+
+    let mut hooks = FocusHooks { hooks: Vec::new() };
+    for event in [
+        EVENT_SYSTEM_FOREGROUND,
+        EVENT_OBJECT_FOCUS,
+        EVENT_OBJECT_NAMECHANGE,
+    ] {
+        let hook = unsafe {
+            SetWinEventHook(
+                event,
+                event,
+                null_mut(),
+                Some(win_event_proc),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
+            )
+        };
+        if hook.is_null() {
+            return Err(format!("SetWinEventHook failed for event 0x{:04X}", event));
+        }
+        hooks.hooks.push(hook);
+    }
+
+    Ok(hooks)
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn win_event_proc(
+    _hook: HWINEVENTHOOK,
+    event: u32,
+    hwnd: HWND,
+    id_object: i32,
+    _id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    let change = match event {
+        EVENT_SYSTEM_FOREGROUND => FocusChange::Foreground,
+        EVENT_OBJECT_NAMECHANGE => {
+            // Name changes fire for every object in every window
+            if id_object != OBJID_WINDOW || hwnd != GetForegroundWindow() {
+                return;
+            }
+            FocusChange::Title
+        }
+        _ => FocusChange::Control,
+    };
+
+    if let Some(handler) = HANDLER.get() {
+        handler(change);
+    }
+}
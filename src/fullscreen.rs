@@ -0,0 +1,159 @@
+//! Pausing while a fullscreen game is in the foreground.
+//!
+//! Games read letter keys as controls: WASD and hotkeys aren't words, and a
+//! "correction" types backspaces and letters into the game. `burst` catches
+//! frantic play, but a game played at a relaxed pace looks like typing.
+//! While the foreground window fills its monitor, either as an exclusive
+//! fullscreen Direct3D app (as reported by `SHQueryUserNotificationState`)
+//! or as a borderless window the size of the monitor, corrections pause.
+//! They resume once it's closed, minimized or Alt+Tabbed away from.
+//!
+//! Browsers, editors and document apps go fullscreen too (F11, a video, a
+//! focus mode) while text is typed into them; they're listed under
+//! `[apps] fullscreen_allowed`. `pause_in_fullscreen = false` turns the
+//! check off.
+//!
+//! The foreground window is checked when it changes and on the housekeeping
+//! timer, since games often go fullscreen only after their window appears.
+//! Like `exclusions`, the result is kept in a static for the keyboard hook.
+
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Apps typed into while fullscreen: browsers, editors, office apps and
+/// terminals (`[apps] fullscreen_allowed`).
+pub const DEFAULT_FULLSCREEN_ALLOWED_APPS: &[&str] = &[
+    "chrome.exe",
+    "msedge.exe",
+    "firefox.exe",
+    "brave.exe",
+    "opera.exe",
+    "vivaldi.exe",
+    "code.exe",
+    "winword.exe",
+    "excel.exe",
+    "onenote.exe",
+    "obsidian.exe",
+    "notepad.exe",
+    "windowsterminal.exe",
+];
+
+/// Whether `pause_in_fullscreen` is on.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// `[apps] fullscreen_allowed`, lowercase.
+static ALLOWED_APPS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Whether a fullscreen app (not allowed) was in the foreground at the last
+/// check.
+static FULLSCREEN: AtomicBool = AtomicBool::new(false);
+
+/// A window or monitor rectangle: left, top, right, bottom.
+pub type Rect = (i32, i32, i32, i32);
+
+/// Set whether fullscreen apps pause corrections, and the apps they don't.
+pub fn configure<S: AsRef<str>>(enabled: bool, allowed_apps: &[S]) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    *ALLOWED_APPS.write() = allowed_apps
+        .iter()
+        .map(|app| app.as_ref().trim().to_lowercase())
+        .filter(|app| !app.is_empty())
+        .collect();
+}
+
+/// Whether a window rectangle covers the whole monitor it's on.
+pub fn covers_monitor(window: Rect, monitor: Rect) -> bool {
+    window.0 <= monitor.0 && window.1 <= monitor.1 && window.2 >= monitor.2 && window.3 >= monitor.3
+}
+
+/// Check the foreground window again (after it changed, or on a timer).
+pub fn refresh(process_name: Option<&str>) {
+    let allowed = process_name.is_some_and(|name| {
+        ALLOWED_APPS
+            .read()
+            .iter()
+            .any(|app| app.eq_ignore_ascii_case(name))
+    });
+    let fullscreen = ENABLED.load(Ordering::Relaxed) && !allowed && foreground_is_fullscreen();
+    FULLSCREEN.store(fullscreen, Ordering::Relaxed);
+}
+
+/// Whether a fullscreen game (or other fullscreen app not allowed) is in
+/// the foreground.
+pub fn is_active() -> bool {
+    FULLSCREEN.load(Ordering::Relaxed)
+}
+
+/// Whether the foreground window is exclusive fullscreen, or borderless and
+/// the size of its monitor.
+#[cfg(windows)]
+fn foreground_is_fullscreen() -> bool {
+    use winapi::um::shellapi::{SHQueryUserNotificationState, QUNS_RUNNING_D3D_FULL_SCREEN};
+    use winapi::um::winuser::{
+        GetForegroundWindow, GetMonitorInfoW, GetWindowLongW, GetWindowRect, MonitorFromWindow,
+        GWL_STYLE, MONITORINFO, MONITOR_DEFAULTTONEAREST, WS_CAPTION,
+    };
+
+    /// The desktop and taskbar, which cover the monitor without being apps.
+    const SHELL_CLASSES: &[&str] = &["Progman", "WorkerW", "Shell_TrayWnd"];
+
+    unsafe {
+        let mut state = 0;
+        if SHQueryUserNotificationState(&mut state) == 0 && state == QUNS_RUNNING_D3D_FULL_SCREEN {
+            return true;
+        }
+
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return false;
+        }
+        if crate::process::foreground_window_class()
+            .is_some_and(|class| SHELL_CLASSES.contains(&class.as_str()))
+        {
+            return false;
+        }
+        let style = GetWindowLongW(hwnd, GWL_STYLE) as u32;
+        if style & WS_CAPTION == WS_CAPTION {
+            return false;
+        }
+
+        let mut window = std::mem::zeroed();
+        if GetWindowRect(hwnd, &mut window) == 0 {
+            return false;
+        }
+        let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+        let mut info: MONITORINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+        if monitor.is_null() || GetMonitorInfoW(monitor, &mut info) == 0 {
+            return false;
+        }
+        let bounds = info.rcMonitor;
+        covers_monitor(
+            (window.left, window.top, window.right, window.bottom),
+            (bounds.left, bounds.top, bounds.right, bounds.bottom),
+        )
+    }
+}
+
+#[cfg(not(windows))]
+fn foreground_is_fullscreen() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_covers_monitor() {
+        let monitor = (0, 0, 1920, 1080);
+        assert!(covers_monitor((0, 0, 1920, 1080), monitor));
+        // Maximized windows hang a few pixels past the edges
+        assert!(covers_monitor((-8, -8, 1928, 1088), monitor));
+        // Maximized below the taskbar is not the whole monitor
+        assert!(!covers_monitor((0, 0, 1920, 1040), monitor));
+        // Second monitor
+        assert!(covers_monitor((1920, 0, 3840, 1080), (1920, 0, 3840, 1080)));
+        assert!(!covers_monitor((0, 0, 1920, 1080), (1920, 0, 3840, 1080)));
+    }
+}
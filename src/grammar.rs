@@ -0,0 +1,420 @@
+//! Rules for commonly confused words (its/it's, your/you're, then/than, ...).
+//!
+//! Spelling correction can't catch these since both forms are real words.
+//! Instead, small data-driven rules look at the word before or after a
+//! confusable word and decide which form was meant:
+//!
+//! ```toml
+//! [[rule]]
+//! word = "then"
+//! replacement = "than"
+//! after = ["more", "better", "rather"]    # "better then" -> "better than"
+//!
+//! [[rule]]
+//! word = "your"
+//! replacement = "you're"
+//! before = ["welcome", "right"]           # "your welcome" -> "you're welcome"
+//!
+//! [[rule]]
+//! word = "loose"
+//! replacement = "lose"
+//! after_pos = ["modal"]                   # "could loose" -> "could lose"
+//! ```
+//!
+//! `after_pos` and `before_pos` match the neighbouring word's part-of-speech
+//! hint from the dictionary (`pos=` in dictionary files), for rules that
+//! would otherwise need long word lists. `after_pattern` and `before_pattern`
+//! match it against a regular expression, `except_after` and `except_before`
+//! list neighbours that never match, and `repeated` fixes a word typed
+//! twice:
+//!
+//! ```toml
+//! [[rule]]
+//! word = "a"
+//! replacement = "an"
+//! before_pattern = "^[aiu]"               # "a apple" -> "an apple"
+//! except_before = ["unique", "user"]      # but "a user"
+//!
+//! [[rule]]
+//! word = "the"
+//! repeated = true                         # "the the" -> "the"
+//! ```
+//!
+//! Built-in rules live in `dictionary/grammar.toml`; users can add their own
+//! in `%APPDATA%/Autocorrect/grammar.toml`. Depending on `[grammar] mode`,
+//! matches are only logged as suggestions or fixed like spelling mistakes.
+//!
+//! Rules only know the neighbours someone thought to list. When a context
+//! model is loaded (see `trigram`), [`check_in_context`] also compares a
+//! confusable word with its alternatives by how often each was seen next to
+//! the neighbouring word: "their going" is fixed because "they're going" is
+//! common in the user's writing and "their going" isn't.
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Built-in rules, embedded at compile time.
+const BUILTIN_RULES: &str = include_str!("../dictionary/grammar.toml");
+
+/// File in the data directory with the user's own rules.
+const USER_RULES_FILE: &str = "grammar.toml";
+
+/// Words often typed for one another, checked against the context model.
+const CONFUSABLE_SETS: &[&[&str]] = &[
+    &["their", "there", "they're"],
+    &["its", "it's"],
+    &["to", "too", "two"],
+    &["your", "you're"],
+    &["whose", "who's"],
+    &["then", "than"],
+    &["lose", "loose"],
+    &["affect", "effect"],
+    &["were", "we're", "where"],
+];
+
+/// Times the alternative must have been seen next to the neighbouring word
+/// before the typed word is doubted.
+const MIN_CONTEXT_EVIDENCE: u64 = 5;
+
+/// How many times more often than the typed pair the alternative's pair
+/// must have been seen.
+const MIN_CONTEXT_RATIO: u64 = 10;
+
+/// What to do when a rule matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GrammarMode {
+    /// Don't check grammar.
+    Off,
+    /// Log the suggested fix but leave the text alone.
+    Suggest,
+    /// Replace the text, undoable with Ctrl+Z like a spelling correction.
+    Fix,
+}
+
+/// One confusable-word rule.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    /// The word that may be wrong.
+    pub word: String,
+    /// What it should be when the rule matches; not needed with
+    /// `repeated`.
+    #[serde(default)]
+    pub replacement: String,
+    /// Fix `word` when the word typed just before it is one of these.
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// Fix `word` when the word typed just after it is one of these.
+    #[serde(default)]
+    pub before: Vec<String>,
+    /// Fix `word` when the word typed just before it has one of these
+    /// part-of-speech hints.
+    #[serde(default)]
+    pub after_pos: Vec<String>,
+    /// Fix `word` when the word typed just after it has one of these
+    /// part-of-speech hints.
+    #[serde(default)]
+    pub before_pos: Vec<String>,
+    /// Fix `word` when the word typed just before it, in lowercase, matches
+    /// this regular expression.
+    #[serde(default, deserialize_with = "pattern")]
+    pub after_pattern: Option<Regex>,
+    /// Fix `word` when the word typed just after it, in lowercase, matches
+    /// this regular expression.
+    #[serde(default, deserialize_with = "pattern")]
+    pub before_pattern: Option<Regex>,
+    /// Never fix `word` when the word typed just before it is one of
+    /// these, whatever else matches.
+    #[serde(default)]
+    pub except_after: Vec<String>,
+    /// Never fix `word` when the word typed just after it is one of these,
+    /// whatever else matches ("a unanimous" for a pattern on vowels).
+    #[serde(default)]
+    pub except_before: Vec<String>,
+    /// Drop `word` when it's typed twice in a row.
+    #[serde(default)]
+    pub repeated: bool,
+}
+
+/// A rules file: a list of `[[rule]]` tables.
+#[derive(Debug, Deserialize)]
+struct RuleFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<Rule>,
+}
+
+/// A text fix proposed by a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrammarFix {
+    /// The text as typed, ending at the current word (one or two words).
+    pub original: String,
+    /// What the text should read instead.
+    pub replacement: String,
+}
+
+/// A set of confusable-word rules.
+#[derive(Debug, Clone, Default)]
+pub struct Grammar {
+    rules: Vec<Rule>,
+}
+
+impl Grammar {
+    /// The built-in rules only.
+    pub fn builtin() -> Self {
+        Self {
+            rules: parse_rules(BUILTIN_RULES).expect("built-in grammar rules are valid"),
+        }
+    }
+
+    /// The built-in rules plus the user's rules file, if there is one.
+    ///
+    /// An invalid user file is reported and ignored.
+    pub fn load() -> Self {
+        let mut grammar = Self::builtin();
+
+        let path = crate::paths::data_file(USER_RULES_FILE);
+        if let Ok(contents) = std::fs::read_to_string(&path) {
+            match parse_rules(&contents) {
+                Ok(rules) => {
+                    eprintln!(
+                        "Loaded {} grammar rules from {}",
+                        rules.len(),
+                        path.display()
+                    );
+                    grammar.rules.extend(rules);
+                }
+                Err(err) => eprintln!("Ignoring invalid {}: {}", path.display(), err),
+            }
+        }
+
+        grammar
+    }
+
+    /// Check the word just finished against the rules.
+    ///
+    /// `previous` is the word before it, if only a space separates them.
+    /// `part_of_speech` gives a word's part-of-speech hints, comma-separated.
+    pub fn check<'a>(
+        &self,
+        previous: Option<&str>,
+        current: &str,
+        part_of_speech: impl Fn(&str) -> Option<&'a str>,
+    ) -> Option<GrammarFix> {
+        let previous = previous?;
+        let excepted =
+            |list: &[String], word: &str| list.iter().any(|w| w.eq_ignore_ascii_case(word));
+        let listed = |list: &[String], tags: &[String], pattern: &Option<Regex>, word: &str| {
+            list.iter().any(|w| w.eq_ignore_ascii_case(word))
+                || part_of_speech(word).is_some_and(|hints| {
+                    hints
+                        .split(',')
+                        .any(|hint| tags.iter().any(|tag| tag.eq_ignore_ascii_case(hint)))
+                })
+                || pattern
+                    .as_ref()
+                    .is_some_and(|pattern| pattern.is_match(&word.to_lowercase()))
+        };
+
+        for rule in &self.rules {
+            if rule.repeated {
+                if rule.word.eq_ignore_ascii_case(previous)
+                    && rule.word.eq_ignore_ascii_case(current)
+                {
+                    return Some(GrammarFix {
+                        original: format!("{} {}", previous, current),
+                        replacement: previous.to_string(),
+                    });
+                }
+                continue;
+            }
+
+            if rule.word.eq_ignore_ascii_case(current)
+                && listed(&rule.after, &rule.after_pos, &rule.after_pattern, previous)
+                && !excepted(&rule.except_after, previous)
+            {
+                return Some(GrammarFix {
+                    original: current.to_string(),
+                    replacement: crate::symspell::match_case(current, &rule.replacement),
+                });
+            }
+
+            if rule.word.eq_ignore_ascii_case(previous)
+                && listed(
+                    &rule.before,
+                    &rule.before_pos,
+                    &rule.before_pattern,
+                    current,
+                )
+                && !excepted(&rule.except_before, current)
+            {
+                return Some(GrammarFix {
+                    original: format!("{} {}", previous, current),
+                    replacement: format!(
+                        "{} {}",
+                        crate::symspell::match_case(previous, &rule.replacement),
+                        current
+                    ),
+                });
+            }
+        }
+
+        None
+    }
+}
+
+/// Check the word just finished, then the one before it, against the words
+/// they're often confused with, by how often each pair of words was seen
+/// together (`pair_count(first, second)`, lowercase).
+///
+/// `previous` is the word before `current`, if only a space separates them.
+/// An alternative wins only if its pair was seen [`MIN_CONTEXT_EVIDENCE`]
+/// times and [`MIN_CONTEXT_RATIO`] times more often than the pair as typed.
+pub fn check_in_context(
+    previous: Option<&str>,
+    current: &str,
+    pair_count: impl Fn(&str, &str) -> u64,
+) -> Option<GrammarFix> {
+    let previous = previous?;
+    let previous_lower = previous.to_lowercase();
+    let current_lower = current.to_lowercase();
+    let likelier = |typed: &str, count: &dyn Fn(&str) -> u64| {
+        let set = CONFUSABLE_SETS.iter().find(|set| set.contains(&typed))?;
+        let typed_count = count(typed);
+        set.iter()
+            .filter(|&&alternative| alternative != typed)
+            .map(|&alternative| (alternative, count(alternative)))
+            .filter(|&(_, seen)| {
+                seen >= MIN_CONTEXT_EVIDENCE
+                    && seen >= typed_count.saturating_mul(MIN_CONTEXT_RATIO)
+            })
+            .max_by_key(|&(_, seen)| seen)
+            .map(|(alternative, _)| alternative)
+    };
+
+    if let Some(alternative) = likelier(&current_lower, &|word| pair_count(&previous_lower, word)) {
+        return Some(GrammarFix {
+            original: current.to_string(),
+            replacement: crate::symspell::match_case(current, alternative),
+        });
+    }
+
+    let alternative = likelier(&previous_lower, &|word| pair_count(word, &current_lower))?;
+    Some(GrammarFix {
+        original: format!("{} {}", previous, current),
+        replacement: format!(
+            "{} {}",
+            crate::symspell::match_case(previous, alternative),
+            current
+        ),
+    })
+}
+
+/// Parse a rules file.
+///
+/// # Errors
+/// Returns an error for invalid TOML, an invalid pattern, or a rule that
+/// neither has a replacement nor is `repeated`.
+fn parse_rules(contents: &str) -> Result<Vec<Rule>, Box<dyn std::error::Error>> {
+    let rules = toml::from_str::<RuleFile>(contents)?.rules;
+    if let Some(rule) = rules
+        .iter()
+        .find(|rule| rule.replacement.is_empty() && !rule.repeated)
+    {
+        return Err(format!("the rule for '{}' has no replacement", rule.word).into());
+    }
+    Ok(rules)
+}
+
+/// A rule's regular expression, checked when the rules are loaded.
+fn pattern<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Regex>, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern)
+        .map(Some)
+        .map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_rules() {
+        let grammar = Grammar::builtin();
+
+        let fix = grammar.check(Some("better"), "then", |_| None).unwrap();
+        assert_eq!(fix.original, "then");
+        assert_eq!(fix.replacement, "than");
+
+        let fix = grammar.check(Some("Your"), "welcome", |_| None).unwrap();
+        assert_eq!(fix.original, "Your welcome");
+        assert_eq!(fix.replacement, "You're welcome");
+
+        assert_eq!(grammar.check(Some("and"), "then", |_| None), None);
+        assert_eq!(grammar.check(None, "then", |_| None), None);
+    }
+
+    #[test]
+    fn test_pattern_and_repeated_rules() {
+        let grammar = Grammar::builtin();
+
+        let fix = grammar.check(Some("A"), "apple", |_| None).unwrap();
+        assert_eq!(fix.original, "A apple");
+        assert_eq!(fix.replacement, "An apple");
+        assert_eq!(grammar.check(Some("a"), "user", |_| None), None);
+        assert_eq!(grammar.check(Some("a"), "one", |_| None), None);
+        // Words starting with a vowel letter but a "you" sound
+        assert_eq!(grammar.check(Some("a"), "unanimous", |_| None), None);
+        assert_eq!(grammar.check(Some("a"), "ukulele", |_| None), None);
+        let fix = grammar.check(Some("a"), "unusual", |_| None).unwrap();
+        assert_eq!(fix.replacement, "an unusual");
+
+        let fix = grammar.check(Some("The"), "the", |_| None).unwrap();
+        assert_eq!(fix.original, "The the");
+        assert_eq!(fix.replacement, "The");
+        assert_eq!(grammar.check(Some("had"), "had", |_| None), None);
+
+        assert!(parse_rules("[[rule]]\nword = \"a\"\nbefore = [\"b\"]\n").is_err());
+        assert!(parse_rules(
+            "[[rule]]\nword = \"a\"\nreplacement = \"an\"\nbefore_pattern = \"(\"\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_part_of_speech_rules() {
+        let grammar = Grammar {
+            rules: parse_rules(
+                "[[rule]]\nword = \"loose\"\nreplacement = \"lose\"\nafter_pos = [\"modal\"]\n",
+            )
+            .unwrap(),
+        };
+        let hints = |word: &str| (word == "shall").then_some("verb,modal");
+
+        let fix = grammar.check(Some("shall"), "loose", hints).unwrap();
+        assert_eq!(fix.replacement, "lose");
+        assert_eq!(grammar.check(Some("very"), "loose", hints), None);
+    }
+
+    #[test]
+    fn test_check_in_context() {
+        let pairs = |first: &str, second: &str| match (first, second) {
+            ("they're", "going") => 40,
+            ("their", "going") => 1,
+            ("went", "to") => 60,
+            ("me", "too") | ("me", "to") => 30,
+            _ => 0,
+        };
+
+        let fix = check_in_context(Some("Their"), "going", pairs).unwrap();
+        assert_eq!(fix.original, "Their going");
+        assert_eq!(fix.replacement, "They're going");
+
+        let fix = check_in_context(Some("went"), "too", pairs).unwrap();
+        assert_eq!(fix.replacement, "to");
+
+        // Both are common: left alone
+        assert_eq!(check_in_context(Some("me"), "too", pairs), None);
+        assert_eq!(check_in_context(Some("the"), "dog", pairs), None);
+        assert_eq!(check_in_context(None, "too", pairs), None);
+    }
+}
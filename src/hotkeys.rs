@@ -0,0 +1,409 @@
+//! Global hotkeys: parsing, registration and dispatch to named actions.
+//!
+//! Hotkeys are written as modifier and key names joined with `+`, e.g.
+//! `"Ctrl+Shift+A"` or `"Alt+F9"`. Names are case-insensitive. Every hotkey
+//! feature goes through a [`HotkeyRegistry`]:
+//!
+//! 1. Bind each configured string to a [`HotkeyAction`] (parse errors and
+//!    two actions sharing a combination are reported as conflicts)
+//! 2. Register the bindings with `RegisterHotKey` (combinations already
+//!    taken by another application are reported too)
+//! 3. Look up the action for each `WM_HOTKEY` in the message loop
+
+use std::fmt;
+
+#[cfg(windows)]
+use std::ptr::null_mut;
+#[cfg(windows)]
+use winapi::um::winuser::{GetAsyncKeyState, RegisterHotKey, UnregisterHotKey};
+
+/// Modifier flags, using the same values as `RegisterHotKey`.
+pub const MOD_ALT: u32 = 0x0001;
+pub const MOD_CONTROL: u32 = 0x0002;
+pub const MOD_SHIFT: u32 = 0x0004;
+pub const MOD_WIN: u32 = 0x0008;
+/// Don't repeat `WM_HOTKEY` while the combination is held.
+#[cfg(windows)]
+const MOD_NOREPEAT: u32 = 0x4000;
+
+/// How long hotkey actions wait for the user to let go of modifiers before
+/// typing, so held Ctrl/Shift don't combine with injected keys.
+#[cfg(windows)]
+const MODIFIER_RELEASE_TIMEOUT_MS: u64 = 1000;
+
+/// A key combination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    /// Combination of `MOD_*` flags.
+    pub modifiers: u32,
+    /// Virtual key code of the non-modifier key.
+    pub vk_code: u32,
+}
+
+impl Hotkey {
+    /// Parse a hotkey string such as `"Ctrl+Shift+A"`.
+    ///
+    /// # Errors
+    /// Returns a description of the problem if a name is unknown, the
+    /// non-modifier key is missing, or more than one key is given.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut modifiers = 0;
+        let mut vk_code = None;
+
+        for part in spec.split('+') {
+            let name = part.trim().to_lowercase();
+            if name.is_empty() {
+                return Err(format!("invalid hotkey '{}'", spec));
+            }
+
+            if let Some(modifier) = modifier_flag(&name) {
+                modifiers |= modifier;
+                continue;
+            }
+
+            let Some(key) = key_code(&name) else {
+                return Err(format!(
+                    "unknown key '{}' in hotkey '{}'",
+                    part.trim(),
+                    spec
+                ));
+            };
+            if vk_code.replace(key).is_some() {
+                return Err(format!("hotkey '{}' has more than one key", spec));
+            }
+        }
+
+        match vk_code {
+            Some(vk_code) => Ok(Self { modifiers, vk_code }),
+            None => Err(format!("hotkey '{}' has no key besides modifiers", spec)),
+        }
+    }
+}
+
+impl fmt::Display for Hotkey {
+    /// Normalized form, e.g. `Ctrl+Shift+A`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (flag, name) in [
+            (MOD_CONTROL, "Ctrl"),
+            (MOD_ALT, "Alt"),
+            (MOD_SHIFT, "Shift"),
+            (MOD_WIN, "Win"),
+        ] {
+            if self.modifiers & flag != 0 {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", key_name(self.vk_code))
+    }
+}
+
+fn modifier_flag(name: &str) -> Option<u32> {
+    match name {
+        "ctrl" | "control" => Some(MOD_CONTROL),
+        "alt" => Some(MOD_ALT),
+        "shift" => Some(MOD_SHIFT),
+        "win" | "windows" | "super" | "meta" => Some(MOD_WIN),
+        _ => None,
+    }
+}
+
+/// Named keys and their virtual key codes (letters, digits and F-keys are
+/// handled separately).
+const NAMED_KEYS: &[(&str, u32)] = &[
+    ("Space", 0x20),
+    ("Enter", 0x0D),
+    ("Tab", 0x09),
+    ("Esc", 0x1B),
+    ("Backspace", 0x08),
+    ("Delete", 0x2E),
+    ("Insert", 0x2D),
+    ("Home", 0x24),
+    ("End", 0x23),
+    ("PageUp", 0x21),
+    ("PageDown", 0x22),
+    ("Left", 0x25),
+    ("Up", 0x26),
+    ("Right", 0x27),
+    ("Down", 0x28),
+    ("Pause", 0x13),
+];
+
+fn key_code(name: &str) -> Option<u32> {
+    let name = match name {
+        "escape" => "esc",
+        "return" => "enter",
+        "del" => "delete",
+        "ins" => "insert",
+        "pgup" => "pageup",
+        "pgdn" => "pagedown",
+        other => other,
+    };
+
+    let mut chars = name.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        if ch.is_ascii_alphanumeric() {
+            // VK codes for A-Z and 0-9 match their uppercase ASCII values
+            return Some(ch.to_ascii_uppercase() as u32);
+        }
+    }
+
+    if let Some(number) = name.strip_prefix('f').and_then(|n| n.parse::<u32>().ok()) {
+        if (1..=24).contains(&number) {
+            return Some(0x70 + number - 1);
+        }
+    }
+
+    NAMED_KEYS
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, code)| *code)
+}
+
+fn key_name(vk_code: u32) -> String {
+    match vk_code {
+        0x30..=0x39 | 0x41..=0x5A => char::from_u32(vk_code).unwrap_or('?').to_string(),
+        0x70..=0x87 => format!("F{}", vk_code - 0x70 + 1),
+        _ => NAMED_KEYS
+            .iter()
+            .find(|(_, code)| *code == vk_code)
+            .map(|(name, _)| name.to_string())
+            .unwrap_or_else(|| format!("0x{:02X}", vk_code)),
+    }
+}
+
+/// Something a hotkey can trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    /// Enable or disable autocorrect.
+    Toggle,
+    /// Revert the most recent correction.
+    Undo,
+    /// Add the last typed word to the personal dictionary.
+    AddWord,
+    /// Spell-correct the selected text.
+    CorrectSelection,
+    /// Pause or resume corrections without changing the enabled setting.
+    Pause,
+    /// Stop correcting the last typed word until the application exits.
+    IgnoreWord,
+    /// Replace the last correction with the next suggestion.
+    NextSuggestion,
+    /// Correct the word just typed, even with `auto_correct` off.
+    CorrectWord,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 8] = [
+        HotkeyAction::Toggle,
+        HotkeyAction::Undo,
+        HotkeyAction::AddWord,
+        HotkeyAction::CorrectSelection,
+        HotkeyAction::Pause,
+        HotkeyAction::IgnoreWord,
+        HotkeyAction::NextSuggestion,
+        HotkeyAction::CorrectWord,
+    ];
+
+    /// Name used in the `[hotkeys]` config table.
+    pub fn name(self) -> &'static str {
+        match self {
+            HotkeyAction::Toggle => "toggle",
+            HotkeyAction::Undo => "undo",
+            HotkeyAction::AddWord => "add-word",
+            HotkeyAction::CorrectSelection => "correct-selection",
+            HotkeyAction::Pause => "pause",
+            HotkeyAction::IgnoreWord => "ignore-word",
+            HotkeyAction::NextSuggestion => "next-suggestion",
+            HotkeyAction::CorrectWord => "correct-word",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.name() == name.trim().to_lowercase())
+    }
+}
+
+/// Hotkeys bound to actions.
+///
+/// Binding ids (used with `RegisterHotKey`/`WM_HOTKEY`) are the binding's
+/// index plus one.
+#[derive(Debug, Default)]
+pub struct HotkeyRegistry {
+    bindings: Vec<(HotkeyAction, Hotkey)>,
+    registered: Vec<i32>,
+}
+
+impl HotkeyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a registry from `(action name, hotkey)` pairs.
+    ///
+    /// Empty hotkey strings are skipped (the action is unbound). Returns the
+    /// registry and a message for every binding that was rejected.
+    pub fn from_config<'a, I>(entries: I) -> (Self, Vec<String>)
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let mut registry = Self::new();
+        let mut problems = Vec::new();
+
+        for (name, spec) in entries {
+            if spec.trim().is_empty() {
+                continue;
+            }
+            let Some(action) = HotkeyAction::from_name(name) else {
+                problems.push(format!("unknown hotkey action '{}'", name));
+                continue;
+            };
+            if let Err(e) = registry.bind(action, spec) {
+                problems.push(e);
+            }
+        }
+
+        (registry, problems)
+    }
+
+    /// Bind a hotkey string to an action.
+    ///
+    /// # Errors
+    /// Returns a message if the string doesn't parse or the combination is
+    /// already bound to a different action.
+    pub fn bind(&mut self, action: HotkeyAction, spec: &str) -> Result<(), String> {
+        let hotkey = Hotkey::parse(spec)?;
+
+        if let Some((existing, _)) = self.bindings.iter().find(|(_, bound)| *bound == hotkey) {
+            if *existing == action {
+                return Ok(());
+            }
+            return Err(format!(
+                "{} is bound to both '{}' and '{}'",
+                hotkey,
+                existing.name(),
+                action.name()
+            ));
+        }
+
+        self.bindings.push((action, hotkey));
+        Ok(())
+    }
+
+    /// Action for a `WM_HOTKEY` id.
+    pub fn action_for_id(&self, id: i32) -> Option<HotkeyAction> {
+        let index = usize::try_from(id).ok()?.checked_sub(1)?;
+        self.bindings.get(index).map(|(action, _)| *action)
+    }
+
+    /// Register every binding for the calling thread.
+    ///
+    /// `WM_HOTKEY` messages arrive in that thread's message queue. Returns a
+    /// message for every combination another application already owns.
+    #[cfg(windows)]
+    pub fn register_all(&mut self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (index, (action, hotkey)) in self.bindings.iter().enumerate() {
+            let id = index as i32 + 1;
+            let ok = unsafe {
+                RegisterHotKey(
+                    null_mut(),
+                    id,
+                    hotkey.modifiers | MOD_NOREPEAT,
+                    hotkey.vk_code,
+                )
+            };
+            if ok != 0 {
+                self.registered.push(id);
+            } else {
+                problems.push(format!(
+                    "{} ('{}') is already used by another application",
+                    hotkey,
+                    action.name()
+                ));
+            }
+        }
+
+        problems
+    }
+
+    /// Release every registered hotkey.
+    #[cfg(windows)]
+    pub fn unregister_all(&mut self) {
+        for id in self.registered.drain(..) {
+            unsafe {
+                UnregisterHotKey(null_mut(), id);
+            }
+        }
+    }
+}
+
+/// Wait (briefly) until Ctrl, Alt, Shift and Win are all released.
+///
+/// Hotkey actions that type text call this first; otherwise the modifiers
+/// still held from the hotkey would turn injected keys into shortcuts.
+#[cfg(windows)]
+pub fn wait_for_modifiers_released() {
+    const MODIFIER_KEYS: [i32; 5] = [0x11, 0x12, 0x10, 0x5B, 0x5C];
+    let start = std::time::Instant::now();
+
+    while start.elapsed().as_millis() < MODIFIER_RELEASE_TIMEOUT_MS as u128 {
+        let held = MODIFIER_KEYS
+            .iter()
+            .any(|vk| unsafe { GetAsyncKeyState(*vk) } < 0);
+        if !held {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let hotkey = Hotkey::parse("shift + ctrl + a").unwrap();
+        assert_eq!(hotkey.modifiers, MOD_CONTROL | MOD_SHIFT);
+        assert_eq!(hotkey.vk_code, 0x41);
+        assert_eq!(hotkey.to_string(), "Ctrl+Shift+A");
+
+        assert_eq!(Hotkey::parse("Alt+F9").unwrap().to_string(), "Alt+F9");
+        assert_eq!(
+            Hotkey::parse("Win+PgDn").unwrap().to_string(),
+            "Win+PageDown"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Hotkey::parse("Ctrl+Shift").is_err());
+        assert!(Hotkey::parse("Ctrl+A+B").is_err());
+        assert!(Hotkey::parse("Ctrl+Banana").is_err());
+        assert!(Hotkey::parse("Ctrl++").is_err());
+    }
+
+    #[test]
+    fn test_registry_detects_conflicts() {
+        let (registry, problems) = HotkeyRegistry::from_config([
+            ("toggle", "Ctrl+Shift+A"),
+            ("pause", "ctrl+shift+a"),
+            ("add-word", "Ctrl+Shift+D"),
+            ("undo", ""),
+            ("launch-rockets", "Ctrl+R"),
+        ]);
+
+        assert_eq!(problems.len(), 2);
+        assert_eq!(registry.action_for_id(1), Some(HotkeyAction::Toggle));
+        assert_eq!(registry.action_for_id(2), Some(HotkeyAction::AddWord));
+        assert_eq!(registry.action_for_id(3), None);
+        // An empty hotkey leaves the action unbound
+        assert!(!registry
+            .bindings
+            .iter()
+            .any(|(action, _)| *action == HotkeyAction::Undo));
+    }
+}
@@ -0,0 +1,166 @@
+//! Pausing while an input method editor turns keys into text.
+//!
+//! Japanese, Chinese and Korean input methods (and some for Vietnamese)
+//! collect letter keys into a composition string that only becomes text
+//! when it's committed. The letters the hook sees aren't the text on
+//! screen, and backspacing over "a misspelled word" would clobber the
+//! composition instead. While the focused window's IME is open in native
+//! (composing) mode, corrections pause, whatever the keyboard layout says;
+//! with the IME closed or switched to alphanumeric input, keys are words
+//! again.
+//!
+//! The composition string itself can only be read from inside the app's
+//! process. Its IME's open status and conversion mode can be asked from
+//! outside, through the default IME window (`WM_IME_CONTROL`). That means
+//! waiting on the app, which the keyboard hook must not do, so the answer is
+//! asked for from the message loop: when the foreground window changes,
+//! every [`REFRESH_INTERVAL_MS`] since IMEs can be switched on with a key
+//! (or Shift) and no focus change, and right after the hook sees one of the
+//! keys that switch them (see [`is_ime_key`]). Like `fullscreen`, the result
+//! is kept in a static for the keyboard hook.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How often the focused window's IME is checked again.
+pub const REFRESH_INTERVAL_MS: u32 = 500;
+
+/// Message the keyboard hook posts to its own thread's message loop to
+/// have the IME checked again (`WM_APP + 1`).
+pub const REFRESH_MESSAGE: u32 = 0x8001;
+
+/// `IME_CMODE_NATIVE`: letters are composed into the IME's language.
+const IME_CMODE_NATIVE: u32 = 0x0001;
+
+/// Whether the focused window's IME was composing at the last check.
+static COMPOSING: AtomicBool = AtomicBool::new(false);
+
+/// Whether keys go into a composition, given the IME's open status and
+/// conversion mode.
+pub fn is_composing(open: bool, conversion_mode: u32) -> bool {
+    open && conversion_mode & IME_CMODE_NATIVE != 0
+}
+
+/// Whether `vk_code` switches an IME on or off, or is what an IME reports
+/// for a key it takes: Hangul (Kana), Kanji (Hanja) and Process.
+pub fn is_ime_key(vk_code: u32) -> bool {
+    matches!(vk_code, 0x15 | 0x19 | 0xE5)
+}
+
+/// Have the message loop check the IME again once the key being handled
+/// has reached the app. Called from the keyboard hook, which shares the
+/// message loop's thread and mustn't wait for the app itself.
+#[cfg(windows)]
+pub fn request_refresh() {
+    use winapi::um::processthreadsapi::GetCurrentThreadId;
+    use winapi::um::winuser::PostThreadMessageW;
+
+    unsafe {
+        PostThreadMessageW(GetCurrentThreadId(), REFRESH_MESSAGE, 0, 0);
+    }
+}
+
+/// Check the focused window's IME again (after a focus change, an IME key,
+/// or on a timer).
+pub fn refresh() {
+    COMPOSING.store(focused_ime_composing(), Ordering::Relaxed);
+}
+
+/// Whether the focused window's IME was composing what's typed at the last
+/// check.
+pub fn is_active() -> bool {
+    COMPOSING.load(Ordering::Relaxed)
+}
+
+/// Ask the focused window's IME whether it's composing.
+#[cfg(windows)]
+fn focused_ime_composing() -> bool {
+    use winapi::shared::minwindef::{LPARAM, UINT, WPARAM};
+    use winapi::shared::windef::HWND;
+    use winapi::um::winuser::{
+        GetForegroundWindow, GetGUIThreadInfo, GetWindowThreadProcessId, SendMessageTimeoutW,
+        GUITHREADINFO, SMTO_ABORTIFHUNG, SMTO_BLOCK, WM_IME_CONTROL,
+    };
+
+    #[link(name = "imm32")]
+    unsafe extern "system" {
+        fn ImmGetDefaultIMEWnd(hwnd: HWND) -> HWND;
+    }
+
+    /// `WM_IME_CONTROL` commands.
+    const IMC_GETCONVERSIONMODE: WPARAM = 0x0001;
+    const IMC_GETOPENSTATUS: WPARAM = 0x0005;
+    /// Longest wait for the app's answer.
+    const TIMEOUT_MS: UINT = 10;
+
+    unsafe {
+        let foreground = GetForegroundWindow();
+        if foreground.is_null() {
+            return false;
+        }
+        let thread_id = GetWindowThreadProcessId(foreground, std::ptr::null_mut());
+        let mut info: GUITHREADINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<GUITHREADINFO>() as u32;
+        let focus = if GetGUIThreadInfo(thread_id, &mut info) != 0 && !info.hwndFocus.is_null() {
+            info.hwndFocus
+        } else {
+            foreground
+        };
+
+        let ime_window = ImmGetDefaultIMEWnd(focus);
+        if ime_window.is_null() {
+            return false;
+        }
+        let query = |command: WPARAM| {
+            let mut result = 0;
+            let answered = SendMessageTimeoutW(
+                ime_window,
+                WM_IME_CONTROL,
+                command,
+                0 as LPARAM,
+                // Don't handle sent messages (e.g. the keyboard hook's)
+                // while waiting
+                SMTO_BLOCK | SMTO_ABORTIFHUNG,
+                TIMEOUT_MS,
+                &mut result,
+            ) != 0;
+            answered.then_some(result as u32)
+        };
+
+        let Some(open) = query(IMC_GETOPENSTATUS) else {
+            return false;
+        };
+        query(IMC_GETCONVERSIONMODE).is_some_and(|mode| is_composing(open != 0, mode))
+    }
+}
+
+#[cfg(not(windows))]
+fn focused_ime_composing() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_composing() {
+        // Japanese IME in hiragana mode (native | full shape)
+        assert!(is_composing(true, 0x0009));
+        // Open but in alphanumeric mode
+        assert!(!is_composing(true, 0x0000));
+        // Closed
+        assert!(!is_composing(false, 0x0009));
+    }
+
+    #[test]
+    fn test_is_ime_key() {
+        // Hangul, Kanji, Process
+        assert!(is_ime_key(0x15));
+        assert!(is_ime_key(0x19));
+        assert!(is_ime_key(0xE5));
+        // Shift, A, Space
+        assert!(!is_ime_key(0x10));
+        assert!(!is_ime_key(0x41));
+        assert!(!is_ime_key(0x20));
+    }
+}
@@ -0,0 +1,208 @@
+//! End-to-end tests of the keyboard hook, corrector and injection together.
+//!
+//! Each test creates a real edit control (off-screen, like the startup
+//! self-test), installs the actual low-level keyboard hook, types into the
+//! control with `SendInput` as a user would, and checks the control's final
+//! text. This covers the unsafe plumbing the unit tests can't: hook
+//! dispatch, injection markers, batching and undo.
+//!
+//! They need an interactive desktop and take the keyboard focus while they
+//! run, so they're ignored by default. Run them before a release with:
+//!
+//! ```text
+//! cargo test integration_tests -- --ignored
+//! ```
+//!
+//! Don't type while they run: the hook sees your keys too.
+
+use crate::corrector::Corrector;
+use crate::selftest::{create_focused_edit, pump_messages, window_text};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::time::Duration;
+use winapi::shared::windef::HWND;
+use winapi::um::winuser::*;
+
+/// Held by each test: there's one foreground window and one hook.
+static DESKTOP: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Time for the hook and the edit control to process a batch of keys.
+const SETTLE: Duration = Duration::from_millis(150);
+
+/// A focused edit control with the keyboard hook installed.
+struct Harness {
+    hwnd: HWND,
+    _desktop: std::sync::MutexGuard<'static, ()>,
+}
+
+impl Harness {
+    fn new() -> Self {
+        // A failed test poisons the lock; the next one can still run
+        let desktop = DESKTOP.lock().unwrap_or_else(|e| e.into_inner());
+
+        crate::CORRECTOR.get_or_init(|| {
+            let mut corrector = Corrector::new();
+            corrector
+                .initialize_with_dictionary(None)
+                .expect("dictionary loads");
+            Arc::new(Mutex::new(corrector))
+        });
+
+        unsafe {
+            let hwnd = create_focused_edit().expect("test window gets focus");
+            crate::corrector().lock().on_focus_changed();
+            crate::install_hook().expect("keyboard hook installs");
+            Self {
+                hwnd,
+                _desktop: desktop,
+            }
+        }
+    }
+
+    /// Type `text` one key at a time, like a user.
+    fn type_text(&self, text: &str) {
+        for ch in text.chars() {
+            send(&key_strokes(ch));
+            unsafe { pump_messages(Duration::from_millis(20)) };
+        }
+        unsafe { pump_messages(SETTLE) };
+    }
+
+    /// Type `text` as one burst, faster than any user.
+    fn type_burst(&self, text: &str) {
+        let strokes: Vec<INPUT> = text.chars().flat_map(key_strokes).collect();
+        send(&strokes);
+        unsafe { pump_messages(SETTLE * 4) };
+    }
+
+    /// Press Ctrl with `vk`.
+    fn press_ctrl(&self, vk: u16) {
+        send(&[
+            key_input(VK_CONTROL as u16, false),
+            key_input(vk, false),
+            key_input(vk, true),
+            key_input(VK_CONTROL as u16, true),
+        ]);
+        unsafe { pump_messages(SETTLE) };
+    }
+
+    fn text(&self) -> String {
+        unsafe { window_text(self.hwnd) }
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        unsafe {
+            crate::uninstall_hook();
+            DestroyWindow(self.hwnd);
+        }
+    }
+}
+
+/// A key press or release as a user would send it (no injection marker).
+fn key_input(vk: u16, up: bool) -> INPUT {
+    unsafe {
+        let mut input = INPUT {
+            type_: INPUT_KEYBOARD,
+            u: std::mem::zeroed(),
+        };
+        *input.u.ki_mut() = KEYBDINPUT {
+            wVk: vk,
+            wScan: 0,
+            dwFlags: if up { KEYEVENTF_KEYUP } else { 0 },
+            time: 0,
+            dwExtraInfo: 0,
+        };
+        input
+    }
+}
+
+/// The presses and releases typing `ch` in the current keyboard layout.
+fn key_strokes(ch: char) -> Vec<INPUT> {
+    // Low byte: the key; high byte: the shift state needed (1 = Shift)
+    let scan = unsafe { VkKeyScanW(ch as u16) };
+    assert_ne!(scan, -1, "'{}' can't be typed on this keyboard layout", ch);
+    let vk = (scan & 0xFF) as u16;
+    let shift = scan & 0x100 != 0;
+
+    let mut strokes = Vec::new();
+    if shift {
+        strokes.push(key_input(VK_SHIFT as u16, false));
+    }
+    strokes.push(key_input(vk, false));
+    strokes.push(key_input(vk, true));
+    if shift {
+        strokes.push(key_input(VK_SHIFT as u16, true));
+    }
+    strokes
+}
+
+fn send(inputs: &[INPUT]) {
+    let mut inputs = inputs.to_vec();
+    let sent = unsafe {
+        SendInput(
+            inputs.len() as u32,
+            inputs.as_mut_ptr(),
+            std::mem::size_of::<INPUT>() as i32,
+        )
+    };
+    assert_eq!(sent as usize, inputs.len(), "SendInput was blocked");
+}
+
+#[test]
+#[ignore]
+fn test_simple_typo() {
+    let harness = Harness::new();
+    harness.type_text("recieve ");
+    assert_eq!(harness.text(), "receive ");
+}
+
+#[test]
+#[ignore]
+fn test_capitalized_typo() {
+    let harness = Harness::new();
+    harness.type_text("Recieve ");
+    assert_eq!(harness.text(), "Receive ");
+}
+
+#[test]
+#[ignore]
+fn test_undo() {
+    let harness = Harness::new();
+    harness.type_text("recieve ");
+    harness.press_ctrl(0x5A);
+    assert_eq!(harness.text(), "recieve ");
+}
+
+#[test]
+#[ignore]
+fn test_punctuation() {
+    let harness = Harness::new();
+    harness.type_text("recieve, becuase. ");
+    assert_eq!(harness.text(), "receive, because. ");
+}
+
+#[test]
+#[ignore]
+fn test_url_untouched() {
+    let harness = Harness::new();
+    harness.type_text("see githbu.com ");
+    assert_eq!(harness.text(), "see githbu.com ");
+}
+
+#[test]
+#[ignore]
+fn test_fast_typing() {
+    let harness = Harness::new();
+    harness.type_burst("recieve quikc brwon fox ");
+    assert_eq!(harness.text(), "receive quick brown fox ");
+}
+
+#[test]
+#[ignore]
+fn test_correct_words_untouched() {
+    let harness = Harness::new();
+    harness.type_text("the quick brown fox ");
+    assert_eq!(harness.text(), "the quick brown fox ");
+}
@@ -0,0 +1,151 @@
+//! Which keys neighbor each other, for ranking typos by how they're made.
+//!
+//! Most typos hit a key next to the intended one: "jello" is far more
+//! likely "hello" (J is next to H) than "cello" (X, C and V are nowhere
+//! near J). Both are one edit away, so plain edit distance can't tell them
+//! apart. The typing distance counts a substitution between neighboring
+//! keys as half an edit, and candidates the same number of edits away are
+//! ranked by their frequency boosted by how much of that distance is such
+//! near-misses. Which distance is acceptable is still decided on plain edit
+//! distance and frequency (see `ranking`).
+//!
+//! Neighbors are taken from `keyboard_layout` (QWERTY by default): the keys
+//! left and right on the same row, and the two touching keys on the rows
+//! above and below, which are staggered half a key.
+
+use serde::{Deserialize, Serialize};
+
+/// Cost of substituting a key for its neighbor, in edits.
+pub const ADJACENT_KEY_COST: f64 = 0.5;
+
+/// How much a candidate's frequency is multiplied for each whole edit its
+/// typing distance is below its edit distance (so about 3x for one
+/// near-miss).
+const SAVED_EDIT_BOOST: f64 = 10.0;
+
+/// Factor for the frequency of a candidate `distance` edits away, when
+/// ranking it against others at that distance.
+pub fn near_miss_boost(distance: i32, typing_distance: f64) -> f64 {
+    SAVED_EDIT_BOOST.powf((distance as f64 - typing_distance).max(0.0))
+}
+
+/// Physical keyboard layout the user types on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyboardLayout {
+    Qwerty,
+    Qwertz,
+    Azerty,
+    Dvorak,
+    Colemak,
+    /// Rank by edit distance alone.
+    None,
+}
+
+impl KeyboardLayout {
+    /// Letter rows, top to bottom.
+    fn rows(self) -> &'static [&'static str] {
+        match self {
+            KeyboardLayout::Qwerty => &["qwertyuiop", "asdfghjkl", "zxcvbnm"],
+            KeyboardLayout::Qwertz => &["qwertzuiopü", "asdfghjklöä", "yxcvbnm"],
+            KeyboardLayout::Azerty => &["azertyuiop", "qsdfghjklm", "wxcvbn"],
+            // Punctuation keeps the letters in their columns
+            KeyboardLayout::Dvorak => &["',.pyfgcrl", "aoeuidhtns", ";qjkxbmwvz"],
+            KeyboardLayout::Colemak => &["qwfpgjluy", "arstdhneio", "zxcvbkm"],
+            KeyboardLayout::None => &[],
+        }
+    }
+
+    /// Row and column of the key typing `ch`.
+    fn position(self, ch: char) -> Option<(usize, usize)> {
+        self.rows().iter().enumerate().find_map(|(row, keys)| {
+            keys.chars()
+                .position(|key| key == ch)
+                .map(|column| (row, column))
+        })
+    }
+
+    /// Whether `a` and `b` are typed by keys next to each other.
+    pub fn adjacent(self, a: char, b: char) -> bool {
+        let (Some((row_a, col_a)), Some((row_b, col_b))) = (self.position(a), self.position(b))
+        else {
+            return false;
+        };
+        match row_b as isize - row_a as isize {
+            0 => col_a.abs_diff(col_b) == 1,
+            // Each row sits half a key right of the one above it
+            1 => col_b == col_a || col_b + 1 == col_a,
+            -1 => col_a == col_b || col_a + 1 == col_b,
+            _ => false,
+        }
+    }
+
+    /// Damerau-Levenshtein distance from `source` to `target`, with
+    /// substitutions of neighboring keys costing [`ADJACENT_KEY_COST`].
+    pub fn typing_distance(self, source: &str, target: &str) -> f64 {
+        let source: Vec<char> = source.chars().collect();
+        let target: Vec<char> = target.chars().collect();
+
+        let mut matrix = vec![vec![0.0; target.len() + 1]; source.len() + 1];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[0] = i as f64;
+        }
+        for (j, cell) in matrix[0].iter_mut().enumerate() {
+            *cell = j as f64;
+        }
+
+        for i in 1..=source.len() {
+            for j in 1..=target.len() {
+                let (typed, meant) = (source[i - 1], target[j - 1]);
+                let cost = if typed == meant {
+                    0.0
+                } else if self.adjacent(typed, meant) {
+                    ADJACENT_KEY_COST
+                } else {
+                    1.0
+                };
+
+                let mut best = (matrix[i - 1][j] + 1.0)
+                    .min(matrix[i][j - 1] + 1.0)
+                    .min(matrix[i - 1][j - 1] + cost);
+                if i > 1 && j > 1 && typed == target[j - 2] && source[i - 2] == meant {
+                    best = best.min(matrix[i - 2][j - 2] + 1.0);
+                }
+                matrix[i][j] = best;
+            }
+        }
+        matrix[source.len()][target.len()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjacency() {
+        let qwerty = KeyboardLayout::Qwerty;
+        assert!(qwerty.adjacent('j', 'h'));
+        assert!(qwerty.adjacent('g', 't'));
+        assert!(qwerty.adjacent('g', 'y'));
+        assert!(qwerty.adjacent('g', 'b'));
+        assert!(!qwerty.adjacent('g', 'n'));
+        assert!(!qwerty.adjacent('j', 'c'));
+        assert!(KeyboardLayout::Azerty.adjacent('a', 'q'));
+        let dvorak = KeyboardLayout::Dvorak;
+        assert!(dvorak.adjacent('p', 'u'));
+        assert!(dvorak.adjacent('p', 'e'));
+        assert!(dvorak.adjacent('o', 'q'));
+        assert!(dvorak.adjacent('h', 'm'));
+        assert!(!dvorak.adjacent('p', 'a'));
+        assert!(!dvorak.adjacent('a', 'q'));
+        assert!(!KeyboardLayout::None.adjacent('j', 'h'));
+
+        assert_eq!(qwerty.typing_distance("jello", "hello"), 0.5);
+        assert_eq!(qwerty.typing_distance("jello", "cello"), 1.0);
+        assert_eq!(qwerty.typing_distance("teh", "the"), 1.0);
+        assert_eq!(KeyboardLayout::None.typing_distance("jello", "hello"), 1.0);
+        assert!(near_miss_boost(1, 0.5) > near_miss_boost(1, 1.0));
+        assert_eq!(near_miss_boost(1, 1.0), 1.0);
+    }
+}
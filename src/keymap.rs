@@ -0,0 +1,385 @@
+//! What a key press types, in the foreground window's keyboard layout.
+//!
+//! The keyboard hook sees virtual keys, which only match the typed text for
+//! unaccented Latin letters. On AZERTY the number row types "é" and "è"
+//! unshifted and digits with Shift; German types "ü" and "ß" on keys that a
+//! US layout uses for punctuation; Cyrillic and Greek layouts report Latin
+//! letter keys. Each key is translated with `ToUnicodeEx` against the layout
+//! of the foreground window's thread, with the modifier and lock keys as
+//! currently held, so the word being tracked is the word on screen.
+//!
+//! The translation is asked not to change the keyboard state, so a dead key
+//! typed before a letter still combines with it in the app. (Windows before
+//! 10 version 1607 ignores the request.) That also means the translation
+//! doesn't combine them: a dead key is reported on its own, held by the
+//! corrector, and combined with the next letter here (see [`compose`]).
+
+/// `ToUnicodeEx` flag: don't change the keyboard state (dead keys).
+#[cfg(windows)]
+const DONT_CHANGE_KEYBOARD_STATE: u32 = 0x4;
+
+/// Characters that end a word; a word is then looked up.
+const WORD_END_PUNCTUATION: &str = ",./;'[]`-=<>?:\"{}~_+";
+
+/// Default `word_characters`: apostrophes and hyphens, so "don't" and
+/// "well-known" are one word.
+pub const DEFAULT_WORD_CHARACTERS: &str = "'’-";
+
+/// Characters that end a sentence.
+const SENTENCE_END: &str = ".!?";
+
+/// Accents typed by dead keys, by the character the dead key types on its
+/// own, and the letters they combine with.
+const DEAD_KEYS: &[(&[char], &str, &str)] = &[
+    // Acute; US-International types it with the apostrophe key
+    (
+        &['\u{B4}', '\''],
+        "aeiouycnszlrAEIOUYCNSZLR",
+        "áéíóúýćńśźĺŕÁÉÍÓÚÝĆŃŚŹĹŔ",
+    ),
+    (&['`'], "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+    (&['^', '\u{2C6}'], "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+    (&['\u{A8}', '"'], "aeiouyAEIOUY", "äëïöüÿÄËÏÖÜŸ"),
+    (&['~', '\u{2DC}'], "anoANO", "ãñõÃÑÕ"),
+    (&['\u{B8}'], "cstCST", "çşţÇŞŢ"),
+    (&['\u{2DA}', '\u{B0}'], "aAuU", "åÅůŮ"),
+    (&['\u{2C7}'], "cszrendtlCSZRENDTL", "čšžřěňďťľČŠŽŘĚŇĎŤĽ"),
+    // Ogonek, double acute, dot above, breve and macron: Polish,
+    // Hungarian, Lithuanian, Romanian, Turkish and Latvian layouts
+    (&['\u{2DB}'], "aeiuAEIU", "ąęįųĄĘĮŲ"),
+    (&['\u{2DD}'], "ouOU", "őűŐŰ"),
+    (&['\u{2D9}'], "zecgZECGI", "żėċġŻĖĊĠİ"),
+    (&['\u{2D8}'], "aguAGU", "ăğŭĂĞŬ"),
+    (&['\u{AF}'], "aeiouAEIOU", "āēīōūĀĒĪŌŪ"),
+];
+
+/// Accented Latin letters by the letter they are based on, for matching
+/// words typed without their accents. Wider than [`DEAD_KEYS`]: Polish,
+/// Hungarian, Romanian and the like type theirs on dedicated keys or AltGr.
+const ACCENTED: &[(char, &str)] = &[
+    ('a', "àáâãäåāăą"),
+    ('A', "ÀÁÂÃÄÅĀĂĄ"),
+    ('c', "çćĉċč"),
+    ('C', "ÇĆĈĊČ"),
+    ('d', "ďđ"),
+    ('D', "ĎĐ"),
+    ('e', "èéêëēĕėęě"),
+    ('E', "ÈÉÊËĒĔĖĘĚ"),
+    ('g', "ĝğġģ"),
+    ('G', "ĜĞĠĢ"),
+    ('h', "ĥħ"),
+    ('H', "ĤĦ"),
+    ('i', "ìíîïĩīĭįı"),
+    ('I', "ÌÍÎÏĨĪĬĮİ"),
+    ('j', "ĵ"),
+    ('J', "Ĵ"),
+    ('k', "ķ"),
+    ('K', "Ķ"),
+    ('l', "ĺļľŀł"),
+    ('L', "ĹĻĽĿŁ"),
+    ('n', "ñńņň"),
+    ('N', "ÑŃŅŇ"),
+    ('o', "òóôõöøōŏő"),
+    ('O', "ÒÓÔÕÖØŌŎŐ"),
+    ('r', "ŕŗř"),
+    ('R', "ŔŖŘ"),
+    ('s', "śŝşšș"),
+    ('S', "ŚŜŞŠȘ"),
+    ('t', "ţťŧț"),
+    ('T', "ŢŤŦȚ"),
+    ('u', "ùúûüũūŭůűų"),
+    ('U', "ÙÚÛÜŨŪŬŮŰŲ"),
+    ('w', "ŵ"),
+    ('W', "Ŵ"),
+    ('y', "ýÿŷ"),
+    ('Y', "ÝŶŸ"),
+    ('z', "źżž"),
+    ('Z', "ŹŻŽ"),
+];
+
+/// What a key press types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Typed {
+    /// Nothing: arrows, function keys, Ctrl shortcuts, or several characters.
+    Nothing,
+    Char(char),
+    /// A dead key, by the accent it types on its own; it combines with the
+    /// next key.
+    Dead(char),
+}
+
+/// What `vk_code` types with the modifiers currently held.
+pub fn translate(vk_code: u32) -> Typed {
+    #[cfg(windows)]
+    unsafe {
+        use winapi::um::winuser::{
+            GetAsyncKeyState, GetKeyState, MapVirtualKeyExW, ToUnicodeEx, MAPVK_VK_TO_VSC,
+            VK_CAPITAL, VK_CONTROL, VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_MENU, VK_NUMLOCK,
+            VK_RCONTROL, VK_RMENU, VK_RSHIFT, VK_SHIFT,
+        };
+
+        // The hook's own thread has no keyboard state of its own worth
+        // reading; build one from the keys as they are now
+        let mut state = [0u8; 256];
+        for vk in [
+            VK_SHIFT,
+            VK_LSHIFT,
+            VK_RSHIFT,
+            VK_CONTROL,
+            VK_LCONTROL,
+            VK_RCONTROL,
+            VK_MENU,
+            VK_LMENU,
+            VK_RMENU,
+        ] {
+            if GetAsyncKeyState(vk) < 0 {
+                state[vk as usize] = 0x80;
+            }
+        }
+        for vk in [VK_CAPITAL, VK_NUMLOCK] {
+            if GetKeyState(vk) & 1 != 0 {
+                state[vk as usize] = 0x01;
+            }
+        }
+
+        let layout = foreground_layout();
+        let scan_code = MapVirtualKeyExW(vk_code, MAPVK_VK_TO_VSC, layout);
+        let mut buffer = [0u16; 8];
+        let len = ToUnicodeEx(
+            vk_code,
+            scan_code,
+            state.as_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len() as i32,
+            DONT_CHANGE_KEYBOARD_STATE,
+            layout,
+        );
+        match len {
+            // A dead key, with its accent in the buffer
+            ..=-1 => single_char(&buffer[..1]).map_or(Typed::Nothing, Typed::Dead),
+            0 => Typed::Nothing,
+            _ => single_char(&buffer[..len as usize]).map_or(Typed::Nothing, Typed::Char),
+        }
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = vk_code;
+        Typed::Nothing
+    }
+}
+
+/// Whether [`compose`] knows the dead key for `accent`. After any other
+/// dead key, what the app typed for the next letter is unknown.
+pub fn is_known_accent(accent: char) -> bool {
+    DEAD_KEYS
+        .iter()
+        .any(|(accents, _, _)| accents.contains(&accent))
+}
+
+/// The letter typed by a dead key for `accent` followed by `letter`, if
+/// they combine. Otherwise the app types both on their own.
+pub fn compose(accent: char, letter: char) -> Option<char> {
+    let (_, bases, composed) = DEAD_KEYS
+        .iter()
+        .find(|(accents, _, _)| accents.contains(&accent))?;
+    let index = bases.chars().position(|base| base == letter)?;
+    composed.chars().nth(index)
+}
+
+/// The letter an accented one is typed with, or `ch` itself ("é" -> "e").
+pub fn strip_accent(ch: char) -> char {
+    ACCENTED
+        .iter()
+        .find(|(_, accented)| accented.contains(ch))
+        .map_or(ch, |&(base, _)| base)
+}
+
+/// Whether `vk_code` is a modifier or lock key, which types nothing by
+/// itself and doesn't interrupt a word.
+pub fn is_modifier(vk_code: u32) -> bool {
+    matches!(
+        vk_code,
+        // Shift, Ctrl, Alt; Caps Lock; left and right Win; left and right
+        // Shift, Ctrl and Alt
+        0x10..=0x12 | 0x14 | 0x5B | 0x5C | 0xA0..=0xA5
+    )
+}
+
+/// Whether `vk_code` moves the caret or deletes forward: the arrows, Home,
+/// End, Page Up and Page Down, and Delete (which may delete a selection).
+pub fn is_navigation(vk_code: u32) -> bool {
+    // Page Up..Down arrow, Delete
+    matches!(vk_code, 0x21..=0x28 | 0x2E)
+}
+
+/// Whether Ctrl is held for a shortcut (e.g. Ctrl+Z).
+///
+/// AltGr reports itself as Ctrl+Alt (a left Ctrl press the layout makes
+/// up, plus right Alt), so Ctrl with right Alt held is AltGr typing a
+/// character, not a shortcut.
+pub fn ctrl_shortcut_held() -> bool {
+    #[cfg(windows)]
+    unsafe {
+        use winapi::um::winuser::{GetAsyncKeyState, VK_CONTROL, VK_RMENU};
+
+        GetAsyncKeyState(VK_CONTROL) < 0 && GetAsyncKeyState(VK_RMENU) >= 0
+    }
+
+    #[cfg(not(windows))]
+    {
+        false
+    }
+}
+
+/// Whether Ctrl, Alt or Win is held for a shortcut (Ctrl+S, Alt+F, Win+E),
+/// so the key doesn't type into the text.
+///
+/// Right Alt with Ctrl is AltGr (see [`ctrl_shortcut_held`]); right Alt on
+/// its own is plain Alt on layouts without AltGr.
+pub fn shortcut_held() -> bool {
+    #[cfg(windows)]
+    unsafe {
+        use winapi::um::winuser::{
+            GetAsyncKeyState, VK_CONTROL, VK_LMENU, VK_LWIN, VK_RMENU, VK_RWIN,
+        };
+
+        let down = |vk| GetAsyncKeyState(vk) < 0;
+        ctrl_shortcut_held()
+            || down(VK_LMENU)
+            || (down(VK_RMENU) && !down(VK_CONTROL))
+            || down(VK_LWIN)
+            || down(VK_RWIN)
+    }
+
+    #[cfg(not(windows))]
+    {
+        false
+    }
+}
+
+/// The keyboard layout of the foreground window's thread.
+#[cfg(windows)]
+pub fn foreground_layout() -> winapi::shared::minwindef::HKL {
+    use winapi::um::winuser::{GetForegroundWindow, GetKeyboardLayout, GetWindowThreadProcessId};
+
+    unsafe {
+        let thread_id = GetWindowThreadProcessId(GetForegroundWindow(), std::ptr::null_mut());
+        GetKeyboardLayout(thread_id)
+    }
+}
+
+/// The one printable character in `units`, if that's all they hold.
+fn single_char(units: &[u16]) -> Option<char> {
+    let mut chars = char::decode_utf16(units.iter().copied());
+    match (chars.next(), chars.next()) {
+        (Some(Ok(ch)), None) if !ch.is_control() => Some(ch),
+        _ => None,
+    }
+}
+
+/// Whether `ch` ends a word, to be looked up. Characters listed in
+/// `word_characters` only do when they don't come between letters (see
+/// `Corrector::handle_key`).
+pub fn ends_word(ch: char) -> bool {
+    WORD_END_PUNCTUATION.contains(ch)
+}
+
+/// Whether `ch` ends a sentence.
+pub fn ends_sentence(ch: char) -> bool {
+    SENTENCE_END.contains(ch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose() {
+        assert_eq!(compose('\u{B4}', 'e'), Some('é'));
+        assert_eq!(compose('\'', 'E'), Some('É'));
+        assert_eq!(compose('^', 'o'), Some('ô'));
+        assert_eq!(compose('\u{A8}', 'u'), Some('ü'));
+        assert_eq!(compose('~', 'n'), Some('ñ'));
+        assert_eq!(compose('\u{2C7}', 's'), Some('š'));
+        assert_eq!(compose('^', 'x'), None);
+        assert_eq!(compose('x', 'e'), None);
+        assert_eq!(compose('\u{2DB}', 'a'), Some('ą'));
+        assert_eq!(compose('\u{2DD}', 'O'), Some('Ő'));
+        assert!(is_known_accent('^'));
+        assert!(!is_known_accent('x'));
+
+        assert_eq!(strip_accent('é'), 'e');
+        assert_eq!(strip_accent('Ü'), 'U');
+        assert_eq!(strip_accent('x'), 'x');
+        // Letters no dead key types
+        assert_eq!(strip_accent('ő'), 'o');
+        assert_eq!(strip_accent('ą'), 'a');
+        assert_eq!(strip_accent('Ę'), 'E');
+        assert_eq!(strip_accent('ł'), 'l');
+        // Every composed letter strips back to its base
+        for (_, bases, composed) in DEAD_KEYS {
+            for (base, accented) in bases.chars().zip(composed.chars()) {
+                assert_eq!(strip_accent(accented), base);
+            }
+        }
+        for (_, bases, composed) in DEAD_KEYS {
+            assert_eq!(bases.chars().count(), composed.chars().count());
+        }
+    }
+
+    #[test]
+    fn test_ends_word() {
+        for ch in [',', '.', ';', ':', '?', '"', '/', '-', '\''] {
+            assert!(ends_word(ch), "{:?}", ch);
+        }
+        // Letters (accented too), digits, spaces and the curly apostrophe
+        for ch in ['a', 'é', '7', ' ', '’'] {
+            assert!(!ends_word(ch), "{:?}", ch);
+        }
+    }
+
+    #[test]
+    fn test_ends_sentence() {
+        assert!(ends_sentence('.'));
+        assert!(ends_sentence('!'));
+        assert!(ends_sentence('?'));
+        assert!(!ends_sentence(','));
+        assert!(!ends_sentence(';'));
+        assert!(!ends_sentence('a'));
+    }
+
+    #[test]
+    fn test_is_modifier() {
+        // Left Shift, AltGr (left Ctrl + right Alt), Caps Lock
+        assert!(is_modifier(0xA0));
+        assert!(is_modifier(0xA2) && is_modifier(0xA5));
+        assert!(is_modifier(0x14));
+        assert!(!is_modifier(0x41));
+        assert!(!is_modifier(0x20));
+    }
+
+    #[test]
+    fn test_is_navigation() {
+        // Page Up, Home, Left arrow, Down arrow, Delete
+        assert!(is_navigation(0x21));
+        assert!(is_navigation(0x24) && is_navigation(0x25) && is_navigation(0x28));
+        assert!(is_navigation(0x2E));
+        // Space, Insert, Backspace
+        assert!(!is_navigation(0x20));
+        assert!(!is_navigation(0x2D));
+        assert!(!is_navigation(0x08));
+    }
+
+    #[test]
+    fn test_single_char() {
+        assert_eq!(single_char(&[0x61]), Some('a'));
+        assert_eq!(single_char(&[0xE9]), Some('é'));
+        // A surrogate pair is one character
+        assert_eq!(single_char(&[0xD83D, 0xDE00]), Some('😀'));
+        // Ctrl+A, or two characters (a dead key that didn't combine)
+        assert_eq!(single_char(&[0x01]), None);
+        assert_eq!(single_char(&[0x5E, 0x71]), None);
+        assert_eq!(single_char(&[]), None);
+    }
+}
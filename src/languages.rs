@@ -0,0 +1,164 @@
+//! Installed language packs.
+//!
+//! English is always available from the embedded dictionary. Additional
+//! languages are installed by placing a dictionary file named after the
+//! language code (e.g. `fr.txt`) in `%LOCALAPPDATA%/Autocorrect/languages/`.
+//! Pack files use the same `word frequency` format as the built-in dictionary.
+//!
+//! Only the active pack is indexed for suggestions. With
+//! `foreign_word_passthrough`, the words of the other installed packs are
+//! kept as plain word sets, so a word that is misspelled in the active
+//! language but exact in another ("rendezvous", "schadenfreude") is left as
+//! typed.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Code of the language served by the embedded dictionary.
+pub const BUILTIN_LANGUAGE: &str = "en";
+
+/// An installed language pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguagePack {
+    /// Language code, also the pack's file stem (e.g. `fr`).
+    pub code: String,
+    /// Human-readable name shown in the tray menu.
+    pub name: String,
+}
+
+impl LanguagePack {
+    fn new(code: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            name: display_name(code),
+        }
+    }
+}
+
+/// Directory holding language pack files.
+pub fn languages_dir() -> PathBuf {
+    crate::paths::local_file(crate::paths::LANGUAGES_DIR)
+}
+
+/// Dictionary file for a language pack.
+pub fn pack_path(code: &str) -> PathBuf {
+    languages_dir().join(format!("{}.txt", code))
+}
+
+/// List installed language packs, built-in English first, then by code.
+pub fn installed_packs() -> Vec<LanguagePack> {
+    let mut codes: Vec<String> = std::fs::read_dir(languages_dir())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+                .filter_map(|path| {
+                    path.file_stem()
+                        .map(|stem| stem.to_string_lossy().to_lowercase())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    pack_list(&mut codes)
+}
+
+/// Build the sorted, de-duplicated pack list from file stems.
+fn pack_list(codes: &mut Vec<String>) -> Vec<LanguagePack> {
+    codes.retain(|code| !code.is_empty() && code != BUILTIN_LANGUAGE);
+    codes.sort();
+    codes.dedup();
+
+    std::iter::once(LanguagePack::new(BUILTIN_LANGUAGE))
+        .chain(codes.iter().map(|code| LanguagePack::new(code)))
+        .collect()
+}
+
+/// Words of the installed packs other than the active one, for exact
+/// lookups.
+#[derive(Debug, Default)]
+pub struct ForeignWords {
+    /// Language code and lowercase words of each pack.
+    packs: Vec<(String, HashSet<String>)>,
+}
+
+impl ForeignWords {
+    /// Read the words of every installed pack except `active`, skipping
+    /// packs that can't be read.
+    pub fn load(active: &str) -> Self {
+        let packs = installed_packs()
+            .into_iter()
+            .filter(|pack| pack.code != active)
+            .filter_map(|pack| match crate::dictionary::pack_words(&pack.code) {
+                Ok(words) => Some((pack.code, words)),
+                Err(err) => {
+                    eprintln!("Failed to read language pack '{}': {}", pack.code, err);
+                    None
+                }
+            })
+            .collect();
+        Self { packs }
+    }
+
+    /// Code of the first pack containing `word_lower` exactly.
+    pub fn language_of(&self, word_lower: &str) -> Option<&str> {
+        self.packs
+            .iter()
+            .find(|(_, words)| words.contains(word_lower))
+            .map(|(code, _)| code.as_str())
+    }
+}
+
+/// Human-readable name for a language code, or the code itself if unknown.
+pub fn display_name(code: &str) -> String {
+    let name = match code {
+        "en" => "English",
+        "de" => "German",
+        "es" => "Spanish",
+        "fr" => "French",
+        "it" => "Italian",
+        "nl" => "Dutch",
+        "pl" => "Polish",
+        "pt" => "Portuguese",
+        "sv" => "Swedish",
+        _ => return code.to_string(),
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pack_list_puts_builtin_first() {
+        let mut codes = vec!["fr".to_string(), "en".to_string(), "de".to_string()];
+        let codes: Vec<String> = pack_list(&mut codes)
+            .into_iter()
+            .map(|pack| pack.code)
+            .collect();
+        assert_eq!(codes, vec!["en", "de", "fr"]);
+    }
+
+    #[test]
+    fn test_display_name() {
+        assert_eq!(display_name("fr"), "French");
+        assert_eq!(display_name("xx"), "xx");
+    }
+
+    #[test]
+    fn test_foreign_words() {
+        let words = |list: &[&str]| list.iter().map(|word| word.to_string()).collect();
+        let foreign = ForeignWords {
+            packs: vec![
+                ("de".to_string(), words(&["schadenfreude", "und"])),
+                ("fr".to_string(), words(&["rendezvous", "und"])),
+            ],
+        };
+        assert_eq!(foreign.language_of("rendezvous"), Some("fr"));
+        assert_eq!(foreign.language_of("und"), Some("de"));
+        assert_eq!(foreign.language_of("teh"), None);
+        assert_eq!(ForeignWords::default().language_of("und"), None);
+    }
+}
@@ -0,0 +1,280 @@
+//! Learned word frequencies with time decay.
+//!
+//! Every time the user finishes a known word (or accepts a correction), the
+//! word's learned boost grows by one. Boosts decay exponentially with a
+//! configurable half-life, so a word typed heavily long ago doesn't dominate
+//! ranking forever.
+//!
+//! # Lazy Decay
+//!
+//! Decay is not applied continuously. Each entry stores the time it was last
+//! brought up to date; the decay for the elapsed time is applied when the
+//! store is loaded, when a word is recorded, and when the store is flushed.
+//!
+//! # File Format
+//!
+//! Learned boosts are stored in `%APPDATA%/Autocorrect/learned_frequencies.txt`:
+//! ```text
+//! # Comments start with #
+//! word boost last_updated_unix_seconds
+//! hello 12.5 1760000000
+//! ```
+
+use ahash::AHashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default half-life of a learned boost, in days.
+pub const DEFAULT_HALF_LIFE_DAYS: f64 = 90.0;
+
+/// Boosts that decay below this value are dropped from the store.
+const MIN_BOOST: f64 = 0.05;
+
+/// Number of recorded words after which the store is due to be flushed to
+/// disk (see `flush_if_due`).
+const FLUSH_EVERY: u32 = 50;
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// A single learned word.
+#[derive(Debug, Clone, Copy)]
+struct LearnedEntry {
+    /// Decayed usage count as of `last_updated`.
+    boost: f64,
+    /// Unix timestamp (seconds) at which `boost` was last decayed.
+    last_updated: u64,
+}
+
+/// Persistent store of learned word boosts.
+pub struct LearningStore {
+    entries: AHashMap<String, LearnedEntry>,
+    path: PathBuf,
+    half_life_days: f64,
+    /// Words recorded since the last flush.
+    unsaved: u32,
+}
+
+impl LearningStore {
+    /// Create an empty store backed by the default file location.
+    pub fn new() -> Self {
+        Self::with_path(Self::get_learning_path())
+    }
+
+    /// Create an empty store backed by a specific file.
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            entries: AHashMap::new(),
+            path,
+            half_life_days: DEFAULT_HALF_LIFE_DAYS,
+            unsaved: 0,
+        }
+    }
+
+    /// Set the half-life used for decay. Non-positive values disable decay.
+    pub fn set_half_life_days(&mut self, half_life_days: f64) {
+        self.half_life_days = half_life_days;
+    }
+
+    /// Load learned boosts from disk, decaying them to the current time.
+    ///
+    /// A missing file is not an error; the store simply starts empty.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read.
+    pub fn load(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+
+        let file = File::open(&self.path)?;
+        let reader = BufReader::new(file);
+        let now = Self::now();
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 3 {
+                continue;
+            }
+
+            let (Ok(boost), Ok(last_updated)) = (parts[1].parse::<f64>(), parts[2].parse::<u64>())
+            else {
+                continue;
+            };
+
+            let entry = LearnedEntry {
+                boost: Self::decayed(boost, last_updated, now, self.half_life_days),
+                last_updated: now,
+            };
+
+            if entry.boost >= MIN_BOOST {
+                self.entries.insert(parts[0].to_lowercase(), entry);
+            }
+        }
+
+        eprintln!("Loaded {} learned words", self.entries.len());
+        Ok(())
+    }
+
+    /// Record one use of a word.
+    ///
+    /// Only kept in memory: this runs in the keyboard hook, so the store is
+    /// written by `flush_if_due` off it. Nothing is recorded while a
+    /// sensitive app is in the foreground.
+    pub fn record(&mut self, word: &str) {
+        if !crate::privacy::recording_allowed() {
+            return;
+        }
+
+        let now = Self::now();
+        let half_life_days = self.half_life_days;
+        let entry = self
+            .entries
+            .entry(word.to_lowercase())
+            .or_insert(LearnedEntry {
+                boost: 0.0,
+                last_updated: now,
+            });
+
+        entry.boost = Self::decayed(entry.boost, entry.last_updated, now, half_life_days) + 1.0;
+        entry.last_updated = now;
+
+        self.unsaved += 1;
+    }
+
+    /// Write to disk once `FLUSH_EVERY` words have been recorded since the
+    /// last write, so a crash loses little history. Returns `true` if
+    /// written.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn flush_if_due(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        if self.unsaved < FLUSH_EVERY {
+            return Ok(false);
+        }
+        self.flush()?;
+        Ok(true)
+    }
+
+    /// Get the current learned boost for a word (0.0 if never learned).
+    pub fn boost(&self, word: &str) -> f64 {
+        self.entries.get(word).map_or(0.0, |entry| entry.boost)
+    }
+
+    /// Decay all entries to the current time and write them to disk.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let now = Self::now();
+        let half_life_days = self.half_life_days;
+
+        for entry in self.entries.values_mut() {
+            entry.boost = Self::decayed(entry.boost, entry.last_updated, now, half_life_days);
+            entry.last_updated = now;
+        }
+        self.entries.retain(|_, entry| entry.boost >= MIN_BOOST);
+
+        let mut contents = Vec::new();
+        writeln!(
+            contents,
+            "# Learned word frequencies (managed by Autocorrect)"
+        )?;
+        writeln!(contents, "# word boost last_updated")?;
+        for (word, entry) in &self.entries {
+            writeln!(
+                contents,
+                "{} {:.4} {}",
+                word, entry.boost, entry.last_updated
+            )?;
+        }
+        crate::paths::write_atomically(&self.path, &contents)?;
+
+        self.unsaved = 0;
+        Ok(())
+    }
+
+    /// Apply exponential decay: `boost * 0.5^(elapsed_days / half_life_days)`.
+    fn decayed(boost: f64, last_updated: u64, now: u64, half_life_days: f64) -> f64 {
+        if half_life_days <= 0.0 || now <= last_updated {
+            return boost;
+        }
+
+        let elapsed_days = (now - last_updated) as f64 / SECONDS_PER_DAY;
+        boost * 0.5f64.powf(elapsed_days / half_life_days)
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+
+    /// Get the path for the learned frequencies file.
+    fn get_learning_path() -> PathBuf {
+        crate::paths::data_file(crate::paths::LEARNED_FREQUENCIES_FILE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decay_halves_after_half_life() {
+        let day = SECONDS_PER_DAY as u64;
+        let decayed = LearningStore::decayed(8.0, 0, 90 * day, 90.0);
+        assert!((decayed - 4.0).abs() < 1e-9);
+
+        let decayed = LearningStore::decayed(8.0, 0, 180 * day, 90.0);
+        assert!((decayed - 2.0).abs() < 1e-9);
+
+        // Non-positive half-life disables decay
+        assert_eq!(LearningStore::decayed(8.0, 0, 180 * day, 0.0), 8.0);
+    }
+
+    #[test]
+    fn test_record_and_reload() {
+        let path = std::env::temp_dir().join(format!(
+            "autocorrect_learning_test_{}.txt",
+            std::process::id()
+        ));
+
+        let mut store = LearningStore::with_path(path.clone());
+        store.record("Hello");
+        store.record("hello");
+        assert!((store.boost("hello") - 2.0).abs() < 1e-6);
+        store.flush().unwrap();
+
+        let mut reloaded = LearningStore::with_path(path.clone());
+        reloaded.load().unwrap();
+        assert!((reloaded.boost("hello") - 2.0).abs() < 1e-3);
+        assert_eq!(reloaded.boost("world"), 0.0);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_old_entries_are_dropped_on_load() {
+        let path = std::env::temp_dir().join(format!(
+            "autocorrect_learning_old_test_{}.txt",
+            std::process::id()
+        ));
+        std::fs::write(&path, "ancient 1.0 0\n").unwrap();
+
+        let mut store = LearningStore::with_path(path.clone());
+        store.load().unwrap();
+        assert_eq!(store.boost("ancient"), 0.0);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
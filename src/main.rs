@@ -7,7 +7,7 @@
 //! - Tracking words as they are typed
 //! - Using the SymSpell algorithm to suggest corrections
 //! - Replacing misspelled words automatically
-//! - Providing an undo mechanism (Ctrl+Z within 5 seconds)
+//! - Providing an undo mechanism (Ctrl+Z within 5 seconds by default)
 //!
 //! # Architecture
 //!
@@ -41,15 +41,62 @@
 //!
 //! - `main.rs`: Entry point, Windows message loop, and system tray
 //! - `corrector.rs`: Word tracking, correction logic, and undo buffer
+//! - `abbreviations.rs`: Abbreviations whose periods don't end a sentence
+//! - `anchor.rs`: Dropping corrections when the caret moved after the word
+//! - `burst.rs`: Pausing during bursts of keystrokes faster than prose
+//! - `check.rs`: Batch spellcheck of files (`autocorrect check`)
 //! - `symspell.rs`: Fast spell correction using the SymSpell algorithm
 //! - `dictionary.rs`: Dictionary loading (built-in + personal)
+//! - `elevation.rs`: Pause in elevated windows our input can't reach
+//! - `events.rs`: Correction, undo and toggle events for external tools
+//! - `exclusions.rs`: Suspend corrections in windows matched by title
+//! - `languages.rs`: Installed language packs
+//! - `grammar.rs`: Rules for confusable words (its/it's, then/than)
+//! - `focus.rs`: Foreground and focus change notifications
+//! - `fullscreen.rs`: Pause while a fullscreen game is in the foreground
+//! - `hotkeys.rs`: Hotkey parsing, registration and dispatch
+//! - `ime.rs`: Pause while an input method editor composes text
+//! - `integration_tests.rs`: End-to-end tests on a real edit control (ignored by default)
+//! - `keyboard.rs`: Neighboring keys, for ranking typos by how they're made
+//! - `keymap.rs`: Characters typed by keys in the foreground keyboard layout
+//! - `mouse.rs`: Mouse click notifications, which end the word being typed
+//! - `classify.rs`: Word types (names, acronyms, ordinals) and their policies
+//! - `numbers.rs`: Ordinal suffix and number-word fixes (2end -> 2nd)
+//! - `os_autocorrect.rs`: Coexistence with Windows' own autocorrect
+//! - `password_field.rs`: Pause while a password field has keyboard focus
+//! - `clipboard.rs`: Clipboard text access
+//! - `selection.rs`: Spell-correct the selected text
+//! - `ranking.rs`: Which suggestion (if any) is trusted as a correction
+//! - `report.rs`: Reports of bad corrections (GitHub issue or local file)
+//! - `ratelimit.rs`: Per-app cap on corrections per minute
+//! - `learning.rs`: Learned word frequencies with time decay
+//! - `pacing.rs`: Per-app tuning of the delay between injected keys
+//! - `pipeline.rs`: Ordered stages deciding each word's correction
+//! - `plugins.rs`: Correction providers loaded from plugin DLLs
+//! - `paths.rs`: Locations of per-user data files
+//! - `privacy.rs`: No recording while sensitive apps are in the foreground
+//! - `process.rs`: Queries about running processes
+//! - `protected.rs`: URLs, email addresses and paths left uncorrected
+//! - `conflicts.rs`: Detection of conflicting keyboard software
+//! - `secure_input.rs`: Detection of UAC and credential prompts
+//! - `segmentation.rs`: Word boundaries per script; pause for unsupported ones
+//! - `selftest.rs`: Startup check that keystroke injection works
+//! - `stats.rs`: Per-session usage statistics
+//! - `timing.rs`: Undo window, undo expiry and correction cooldown
+//! - `transaction.rs`: Rolling back a replacement that failed halfway
+//! - `tutor.rs`: Weekly report of repeated misspellings
+//! - `typography.rs`: Curly quotes, em dashes and ellipses typed as you go
+//! - `undos.rs`: Undo counts per correction; learning words undone repeatedly
 //! - `trigram.rs`: Context-based language model (optional enhancement)
+//! - `watchdog.rs`: Reinstalling a keyboard hook that stopped receiving input
+//! - `ui/`: Shared helpers for popups (DPI-aware positioning) and the widget
 //!
 //! # System Tray
 //!
 //! The application runs minimized in the system tray with:
 //! - Toggle to enable/disable autocorrection
-//! - Visual indicator (green icon = enabled)
+//! - Visual indicator (green icon = running, red = keyboard hook lost)
+//! - Optionally, an always-on-top widget with today's corrections
 //!
 //! # Keyboard Hook
 //!
@@ -59,17 +106,23 @@
 
 #![windows_subsystem = "windows"]
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use parking_lot::Mutex;
 #[cfg(windows)]
 use std::ffi::OsStr;
 use std::ptr::null_mut;
+#[cfg(windows)]
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 
 #[cfg(windows)]
 use std::os::windows::ffi::OsStrExt;
+use std::time::{Duration, Instant};
 #[cfg(windows)]
-use tray_icon::{menu::Menu, menu::MenuItem, TrayIconBuilder};
+use tray_icon::{
+    menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, Submenu},
+    MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent,
+};
 #[cfg(windows)]
 use winapi::shared::minwindef::{LPARAM, LRESULT, WPARAM};
 #[cfg(windows)]
@@ -84,16 +137,76 @@ use winapi::um::winuser::{MessageBoxW, MB_ICONERROR, MB_ICONINFORMATION, MB_ICON
 #[cfg(not(windows))]
 type HHOOK = *mut std::ffi::c_void;
 
+mod abbreviations;
+mod anchor;
+mod burst;
+mod check;
+mod classify;
+mod clipboard;
 mod config;
+mod conflicts;
 mod corrector;
 mod dictionary;
+mod elevation;
+mod events;
+mod exclusions;
+mod focus;
+mod fullscreen;
+mod grammar;
+mod hotkeys;
+mod ime;
+#[cfg(all(test, windows))]
+mod integration_tests;
+mod keyboard;
+mod keymap;
+mod languages;
+mod learning;
+mod mouse;
+mod numbers;
+mod os_autocorrect;
+mod pacing;
+mod password_field;
+mod paths;
+mod pipeline;
+mod plugins;
+mod privacy;
+mod process;
+mod protected;
+mod ranking;
+mod ratelimit;
+mod report;
+mod secure_input;
+mod segmentation;
+mod selection;
+mod selftest;
+mod stats;
 mod symspell;
+mod timing;
+mod transaction;
 mod trigram;
+mod tutor;
+mod typography;
+mod ui;
+mod undos;
 mod updater;
+mod watchdog;
 
 use config::Config;
-use corrector::Corrector;
+#[cfg(windows)]
+use config::{InjectedInputPolicy, TrayToggleClick};
+use corrector::{Corrector, PauseReason};
+#[cfg(windows)]
+use hotkeys::{HotkeyAction, HotkeyRegistry};
+#[cfg(windows)]
+use os_autocorrect::OsAutocorrectPolicy;
+#[cfg(windows)]
+use segmentation::WordBoundaries;
+use selftest::SelfTestOutcome;
+#[cfg(windows)]
+use ui::widget::{Widget, WidgetState, WidgetStatus};
 use updater::Updater;
+#[cfg(windows)]
+use watchdog::{HookWatchdog, WatchdogAction};
 
 #[derive(Parser, Debug)]
 #[command(name = "autocorrect")]
@@ -108,6 +221,10 @@ struct Args {
     #[arg(short, long)]
     dictionary: Option<std::path::PathBuf>,
 
+    /// Find corrections but only log and count them, without typing them
+    #[arg(long)]
+    dry_run: bool,
+
     /// Run in console mode (don't hide console)
     #[arg(long)]
     console: bool,
@@ -115,14 +232,56 @@ struct Args {
     /// Check for updates
     #[arg(long)]
     check_update: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show counters from the running instance
+    Status,
+    /// Print correction, undo and toggle events from the running instance as
+    /// JSON lines (needs `stream_events = true`)
+    Events,
+    /// Report misspelled words in files as path:line:column, without fixing
+    /// them; exits with code 1 if any are found
+    Check {
+        /// Files to check
+        #[arg(required = true)]
+        files: Vec<std::path::PathBuf>,
+    },
+    /// Reload dictionaries in the running instance, after editing them
+    Reload,
+    /// Train the context model on your own .txt and .md files
+    TrainLm {
+        /// Folder to read documents from (including subfolders)
+        #[arg(long)]
+        input: std::path::PathBuf,
+    },
+    /// Delete stored data and exit
+    Reset {
+        /// Delete the personal dictionary, learned frequencies and all other per-user data
+        #[arg(long)]
+        personal_data: bool,
+
+        /// Don't ask for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
 }
 
 #[cfg(windows)]
 #[link(name = "kernel32")]
 unsafe extern "system" {
     fn GetConsoleWindow() -> *mut std::ffi::c_void;
+    fn AttachConsole(process_id: u32) -> i32;
 }
 
+/// `AttachConsole` argument for the console of the parent process.
+#[cfg(windows)]
+const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
 /// Global handle to the low-level keyboard hook.
 ///
 /// # Safety
@@ -130,12 +289,50 @@ unsafe extern "system" {
 /// It's set during `install_hook()` and cleared in `uninstall_hook()`.
 static mut HOOK_HANDLE: HHOOK = null_mut();
 
+/// Interval of the timer that drives periodic checks in the message loop.
+const HOUSEKEEPING_INTERVAL_MS: u32 = 5000;
+
+/// Age past which the status file is left over from an instance that
+/// crashed or was killed. It's rewritten on every housekeeping tick.
+const STATUS_STALE_AFTER: Duration = Duration::from_millis(3 * HOUSEKEEPING_INTERVAL_MS as u64);
+
+/// Tray icon color while running normally (green).
+#[cfg(windows)]
+const ICON_COLOR: [u8; 3] = [50, 200, 50];
+
+/// Tray icon color while the keyboard hook is lost (red).
+#[cfg(windows)]
+const LOST_ICON_COLOR: [u8; 3] = [220, 50, 40];
+
+/// How often the tray tooltip shows dictionary loading progress.
+#[cfg(windows)]
+const LOADING_REFRESH_MS: u32 = 250;
+
+/// Dictionary words indexed per corrector lock, keeping each hold short
+/// enough not to delay keystrokes noticeably.
+const DICTIONARY_INDEX_CHUNK: usize = 256;
+
 /// Global autocorrector instance, lazily initialized.
 ///
 /// Uses `parking_lot::Mutex` for fast, compact locking without poisoning.
 /// The `once_cell::sync::Lazy` ensures thread-safe one-time initialization.
 static CORRECTOR: std::sync::OnceLock<Arc<Mutex<Corrector>>> = std::sync::OnceLock::new();
 
+/// A focus change that arrived while the corrector was locked, applied at
+/// the next key press.
+#[cfg(windows)]
+static MISSED_FOCUS_CHANGE: AtomicBool = AtomicBool::new(false);
+
+/// The key last pressed and not yet released, to tell auto-repeat (see
+/// `handle_key_event`).
+#[cfg(windows)]
+static HELD_KEY: AtomicU32 = AtomicU32::new(0);
+
+/// A mouse click that arrived while the corrector was locked, applied at the
+/// next key press.
+#[cfg(windows)]
+static MISSED_CLICK: AtomicBool = AtomicBool::new(false);
+
 fn corrector() -> &'static Arc<Mutex<Corrector>> {
     CORRECTOR
         .get()
@@ -143,20 +340,28 @@ fn corrector() -> &'static Arc<Mutex<Corrector>> {
 }
 
 /// Convert UTF-8 Rust strings to UTF-16 and display a modal Windows message box.
+///
+/// Returns the button the user pressed (`IDOK`, `IDYES`, ...).
+#[cfg(windows)]
+fn message_box(title: &str, message: &str, flags: u32) -> i32 {
+    let title_wide: Vec<u16> = OsStr::new(title).encode_wide().chain(Some(0)).collect();
+    let message_wide: Vec<u16> = OsStr::new(message).encode_wide().chain(Some(0)).collect();
+
+    unsafe {
+        MessageBoxW(
+            std::ptr::null_mut(),
+            message_wide.as_ptr(),
+            title_wide.as_ptr(),
+            flags,
+        )
+    }
+}
+
+/// Display a modal message box with an OK button.
 fn show_dialog(title: &str, message: &str, _icon_flag: u32) {
     #[cfg(windows)]
     {
-        let title_wide: Vec<u16> = OsStr::new(title).encode_wide().chain(Some(0)).collect();
-        let message_wide: Vec<u16> = OsStr::new(message).encode_wide().chain(Some(0)).collect();
-
-        unsafe {
-            MessageBoxW(
-                std::ptr::null_mut(),
-                message_wide.as_ptr(),
-                title_wide.as_ptr(),
-                MB_OK | _icon_flag,
-            );
-        }
+        message_box(title, message, MB_OK | _icon_flag);
     }
     #[cfg(not(windows))]
     {
@@ -178,7 +383,6 @@ fn show_warning_dialog(title: &str, message: &str) {
     show_dialog(title, message, 0);
 }
 
-#[allow(dead_code)]
 fn show_info_dialog(title: &str, message: &str) {
     #[cfg(windows)]
     show_dialog(title, message, MB_ICONINFORMATION);
@@ -186,6 +390,899 @@ fn show_info_dialog(title: &str, message: &str) {
     show_dialog(title, message, 0);
 }
 
+/// Ask the user a yes/no question. Returns `true` if they answered yes.
+fn confirm_dialog(title: &str, message: &str) -> bool {
+    #[cfg(windows)]
+    {
+        message_box(title, message, MB_YESNO | MB_ICONWARNING | MB_DEFBUTTON2) == IDYES
+    }
+    #[cfg(not(windows))]
+    {
+        use std::io::Write;
+
+        print!("{}: {} [y/N] ", title, message);
+        let _ = std::io::stdout().flush();
+
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).is_err() {
+            return false;
+        }
+        matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+    }
+}
+
+/// Confirmation text shown before deleting personal data.
+const RESET_CONFIRMATION: &str = "This permanently deletes your personal dictionary, learned word \
+frequencies and all other Autocorrect data stored for your account.\n\nContinue?";
+
+/// Longest wait for a running instance to delete its personal data.
+const RESET_WAIT: Duration = Duration::from_secs(15);
+
+/// Handle `autocorrect reset`.
+fn run_reset(personal_data: bool, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if !personal_data {
+        return Err("Nothing to reset. Pass --personal-data to delete your stored data.".into());
+    }
+
+    if !yes && !confirm_dialog("Reset Personal Data", RESET_CONFIRMATION) {
+        println!("Reset cancelled.");
+        return Ok(());
+    }
+
+    // A running instance would write what it holds in memory back, so it
+    // resets first
+    let running = instance_running();
+    let reset_in_memory = running && request_reset();
+
+    let removed = paths::remove_personal_data()?;
+    for path in &removed {
+        println!("Removed {}", path.display());
+    }
+
+    let message = if reset_in_memory {
+        "Personal data deleted, including the data Autocorrect held in memory.".to_string()
+    } else if running {
+        format!(
+            "Personal data deleted ({} files removed), but Autocorrect didn't respond.\n\nRestart it so it forgets data already in memory.",
+            removed.len()
+        )
+    } else {
+        format!("Personal data deleted ({} files removed).", removed.len())
+    };
+    show_info_dialog("Autocorrect", &message);
+    Ok(())
+}
+
+/// Ask the running instance to delete its personal data, and wait until it
+/// has. Returns `false` if it didn't within [`RESET_WAIT`].
+fn request_reset() -> bool {
+    let request = paths::local_file(paths::RESET_REQUEST_FILE);
+    if let Err(e) = std::fs::write(&request, "") {
+        eprintln!("Failed to ask Autocorrect to reset: {}", e);
+        return false;
+    }
+
+    println!("Waiting for Autocorrect to forget the data it holds in memory...");
+    let started = Instant::now();
+    while started.elapsed() < RESET_WAIT {
+        if !request.exists() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+
+    let _ = std::fs::remove_file(&request);
+    false
+}
+
+/// Exit code of `check` when misspellings were found.
+const CHECK_FOUND_EXIT_CODE: i32 = 1;
+/// Exit code of `check` when a file could not be read.
+const CHECK_ERROR_EXIT_CODE: i32 = 2;
+
+/// Spellcheck files and print each unknown word, exiting with
+/// [`CHECK_FOUND_EXIT_CODE`] if there were any.
+fn run_check(
+    files: &[std::path::PathBuf],
+    dictionary: Option<&std::path::Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let mut corrector = Corrector::new_with_config(&config);
+    corrector.initialize_with_dictionary(dictionary)?;
+
+    let mut found = 0;
+    let mut unreadable = false;
+    for file in files {
+        let text = match std::fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("{}: {}", file.display(), e);
+                unreadable = true;
+                continue;
+            }
+        };
+
+        for misspelling in corrector.check_text(&text) {
+            let suggestion = misspelling
+                .suggestion
+                .map(|s| format!(" (did you mean \"{}\"?)", s))
+                .unwrap_or_default();
+            println!(
+                "{}:{}:{}: {}{}",
+                file.display(),
+                misspelling.line,
+                misspelling.column,
+                misspelling.word,
+                suggestion
+            );
+            found += 1;
+        }
+    }
+
+    if unreadable {
+        std::process::exit(CHECK_ERROR_EXIT_CODE);
+    }
+    if found > 0 {
+        eprintln!("{} misspelled words found", found);
+        std::process::exit(CHECK_FOUND_EXIT_CODE);
+    }
+    Ok(())
+}
+
+/// Train the context model on a folder of documents and save it.
+fn run_train_lm(input: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+    let files = trigram::corpus_files(input)
+        .map_err(|e| format!("Cannot read {}: {}", input.display(), e))?;
+
+    let mut model = trigram::TrigramModel::new();
+    let mut trained = 0;
+    for file in &files {
+        let text = match std::fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let sentences = trigram::sentences(&text);
+        model.train(&sentences.iter().map(String::as_str).collect::<Vec<_>>());
+        trained += 1;
+    }
+
+    if model.total_words() == 0 {
+        return Err(format!(
+            "No words found in .txt or .md files under {}",
+            input.display()
+        )
+        .into());
+    }
+
+    let path = paths::local_file(paths::LANGUAGE_MODEL_FILE);
+    model.save(&path)?;
+    println!(
+        "Trained on {} words from {} files; saved to {}",
+        model.total_words(),
+        trained,
+        path.display()
+    );
+    println!("Restart Autocorrect to use the new model.");
+    Ok(())
+}
+
+/// Run the injection self-test and adapt the corrector to the result.
+///
+/// Returns a warning to show the user once the tray icon is up.
+fn run_startup_self_test() -> Option<String> {
+    match selftest::run() {
+        SelfTestOutcome::Passed => {
+            println!("Injection self-test passed");
+            None
+        }
+        SelfTestOutcome::Fallback(strategy) => {
+            println!("Injection self-test: falling back to {:?}", strategy);
+            corrector().lock().set_strategy_override(Some(strategy));
+            Some(format!(
+                "Simulated typing appears to be blocked on this system, so Autocorrect \
+switched to the {:?} input method. Corrections may not work in every application.",
+                strategy
+            ))
+        }
+        SelfTestOutcome::Failed => {
+            println!("Injection self-test failed; disabling autocorrect");
+            corrector().lock().set_enabled(false);
+            Some(
+                "Autocorrect could not type into a test window, so corrections would likely be \
+left half-applied. Autocorrect has been disabled.\n\nThis can happen in virtual machines, \
+kiosk setups, or with security software that blocks simulated input."
+                    .to_string(),
+            )
+        }
+        SelfTestOutcome::Skipped(reason) => {
+            println!("Injection self-test skipped: {}", reason);
+            None
+        }
+    }
+}
+
+/// Tooltip text describing the corrector's current state.
+#[cfg(windows)]
+fn tray_tooltip(corrector: &Corrector) -> String {
+    if watchdog::hook_lost() {
+        return "Autocorrect - Inactive (keyboard hook lost)".to_string();
+    }
+
+    if !corrector.is_enabled() {
+        return "Autocorrect - Disabled".to_string();
+    }
+
+    if let Some(reason) = corrector.pause_reasons().first() {
+        return format!("Autocorrect - Paused ({})", reason.description());
+    }
+
+    match corrector.dictionary_load_progress() {
+        Some(percent) => format!("Autocorrect - Loading dictionary… {}%", percent),
+        None if corrector.is_dry_run() => "Autocorrect - Dry run (not correcting)".to_string(),
+        None => "Autocorrect - Enabled".to_string(),
+    }
+}
+
+/// What the widget should show for the corrector's current state.
+#[cfg(windows)]
+fn widget_state(corrector: &Corrector) -> WidgetState {
+    let status = if !corrector.is_enabled() {
+        WidgetStatus::Off
+    } else if watchdog::hook_lost() || !corrector.pause_reasons().is_empty() {
+        WidgetStatus::Paused
+    } else {
+        WidgetStatus::Active
+    };
+    WidgetState {
+        status,
+        corrections_today: corrector.stats().corrections_today(),
+    }
+}
+
+/// A click on the widget enables autocorrect if it's off, and otherwise
+/// pauses or resumes it.
+#[cfg(windows)]
+fn on_widget_click(config: &mut Config, toggle_item: &MenuItem, tray_icon: &tray_icon::TrayIcon) {
+    if !corrector().lock().is_enabled() {
+        toggle_autocorrect(config, toggle_item, tray_icon);
+        return;
+    }
+    {
+        let mut corrector = corrector().lock();
+        let paused = corrector.pause_reasons().contains(&PauseReason::Widget);
+        corrector.set_paused(PauseReason::Widget, !paused);
+    }
+    refresh_tray_tooltip(tray_icon);
+}
+
+/// Look for conflicting keyboard software, warning and/or pausing as configured.
+///
+/// Returns `true` if the corrector's pause state changed.
+fn check_conflicting_software(config: &Config, at_startup: bool) -> bool {
+    let warn = at_startup && config.warn_on_conflicting_software;
+    if !warn && !config.pause_on_conflicting_software {
+        return false;
+    }
+
+    let conflicts = conflicts::detect_running();
+
+    if warn && !conflicts.is_empty() {
+        show_warning_dialog(
+            "Autocorrect Warning",
+            &conflicts::warning_message(&conflicts),
+        );
+    }
+
+    if config.pause_on_conflicting_software {
+        corrector()
+            .lock()
+            .set_paused(PauseReason::ConflictingSoftware, !conflicts.is_empty())
+    } else {
+        false
+    }
+}
+
+/// Re-read Windows' autocorrect setting, warning when it's found turned on.
+#[cfg(windows)]
+fn check_os_autocorrect(config: &Config) {
+    if os_autocorrect::refresh() && config.os_autocorrect == OsAutocorrectPolicy::Warn {
+        show_warning_dialog("Autocorrect Warning", os_autocorrect::WARNING);
+    }
+}
+
+/// Open a file in its default editor, reporting failures to the user.
+#[cfg(windows)]
+fn open_file(path: &std::path::Path) {
+    if let Err(e) = process::open_with_default_app(path) {
+        show_error_dialog(
+            "Autocorrect Error",
+            &format!("Failed to open {}: {}", path.display(), e),
+        );
+    }
+}
+
+/// Label of the "Add last word" tray item when there is no word to add.
+#[cfg(windows)]
+const ADD_WORD_LABEL: &str = "Add last word to dictionary";
+
+/// Label of the "Ignore last word" tray item when there is no word to ignore.
+#[cfg(windows)]
+const IGNORE_WORD_LABEL: &str = "Don't correct last word this session";
+
+/// Show the corrector's last finished word in the "Add last word" and
+/// "Ignore last word" menu items.
+#[cfg(windows)]
+fn refresh_last_word_items(
+    add_item: &MenuItem,
+    ignore_item: &MenuItem,
+    shown_last_word: &mut Option<String>,
+) {
+    let last_word = corrector().lock().last_word().map(str::to_string);
+    if last_word == *shown_last_word {
+        return;
+    }
+
+    match &last_word {
+        Some(word) => {
+            add_item.set_text(format!("Add last word ('{}') to dictionary", word));
+            ignore_item.set_text(format!("Don't correct '{}' this session", word));
+        }
+        None => {
+            add_item.set_text(ADD_WORD_LABEL);
+            ignore_item.set_text(IGNORE_WORD_LABEL);
+        }
+    }
+    add_item.set_enabled(last_word.is_some());
+    ignore_item.set_enabled(last_word.is_some());
+    *shown_last_word = last_word;
+}
+
+/// Label of the "Report bad correction" tray item when there is nothing to
+/// report.
+#[cfg(windows)]
+const REPORT_LABEL: &str = "Report bad correction...";
+
+/// Show the corrector's last correction in the "Report bad correction" menu
+/// item.
+#[cfg(windows)]
+fn refresh_report_item(report_item: &MenuItem, shown_correction: &mut Option<(String, String)>) {
+    let last_correction = corrector()
+        .lock()
+        .last_correction()
+        .map(|(typed, correction)| (typed.to_string(), correction.to_string()));
+    if last_correction == *shown_correction {
+        return;
+    }
+
+    match &last_correction {
+        Some((typed, correction)) => report_item.set_text(format!(
+            "Report bad correction ('{}' -> '{}')...",
+            typed, correction
+        )),
+        None => report_item.set_text(REPORT_LABEL),
+    }
+    report_item.set_enabled(last_correction.is_some());
+    *shown_correction = last_correction;
+}
+
+/// Show the last correction's report and, if the user agrees, open it as a
+/// GitHub issue or save it locally.
+#[cfg(windows)]
+fn report_bad_correction() {
+    let Some(report) = corrector().lock().bad_correction_report() else {
+        return;
+    };
+
+    let message = format!(
+        "{}\n\n{}\nYes: open it as a new GitHub issue (nothing is sent until you \
+         submit it)\nNo: save it to a local file\nCancel: don't report it",
+        report.title(),
+        report.body()
+    );
+    match message_box(
+        "Report Bad Correction",
+        &message,
+        MB_YESNOCANCEL | MB_ICONQUESTION,
+    ) {
+        IDYES => {
+            if let Err(e) = process::open_url(&report.issue_url()) {
+                show_error_dialog("Autocorrect Error", &format!("Failed to open issue: {}", e));
+            }
+        }
+        IDNO => match report.save() {
+            Ok(path) => open_file(&path),
+            Err(e) => show_error_dialog(
+                "Autocorrect Error",
+                &format!("Failed to save report: {}", e),
+            ),
+        },
+        _ => {}
+    }
+}
+
+/// Flip the enabled state, then update the menu label, tooltip and config.
+#[cfg(windows)]
+fn toggle_autocorrect(
+    config: &mut Config,
+    toggle_item: &MenuItem,
+    tray_icon: &tray_icon::TrayIcon,
+) {
+    let (enabled, tooltip) = {
+        let mut corrector = corrector().lock();
+        corrector.toggle_enabled();
+        (corrector.is_enabled(), tray_tooltip(&corrector))
+    };
+
+    config.enabled_by_default = enabled;
+    config.request_save();
+
+    toggle_item.set_text(if enabled {
+        "Disable Autocorrect"
+    } else {
+        "Enable Autocorrect"
+    });
+
+    if let Err(e) = tray_icon.set_tooltip(Some(tooltip)) {
+        println!("Failed to update tray tooltip: {}", e);
+        show_warning_dialog(
+            "Autocorrect Warning",
+            &format!(
+                "Autocorrect state changed, but tray tooltip could not be updated: {}",
+                e
+            ),
+        );
+    }
+
+    println!(
+        "Autocorrect {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+}
+
+/// Whether a left-click on the tray icon should toggle autocorrect.
+///
+/// In double-click mode, `last_click` remembers an unpaired click; a second
+/// click within the system double-click time completes the pair.
+#[cfg(windows)]
+fn is_toggle_click(mode: TrayToggleClick, last_click: &mut Option<Instant>) -> bool {
+    match mode {
+        TrayToggleClick::Single => true,
+        TrayToggleClick::Double => {
+            let double_click_time = Duration::from_millis(unsafe { GetDoubleClickTime() } as u64);
+            let now = Instant::now();
+
+            if last_click.is_some_and(|previous| now.duration_since(previous) <= double_click_time)
+            {
+                *last_click = None;
+                true
+            } else {
+                *last_click = Some(now);
+                false
+            }
+        }
+    }
+}
+
+/// Load a language pack's dictionary in the background and swap it in.
+///
+/// Loading a large pack takes a moment, so it runs off the main thread to
+/// keep the keyboard hook responsive. The old dictionary stays active until
+/// the new one is ready, and is freed after the lock is released.
+#[cfg(windows)]
+fn switch_language(language: String) {
+    let (dictionary_path, half_life_days, foreign_passthrough) = {
+        let corrector = corrector().lock();
+        (
+            corrector.dictionary_path().map(|path| path.to_path_buf()),
+            corrector.learning_half_life_days(),
+            corrector.foreign_passthrough(),
+        )
+    };
+
+    std::thread::spawn(move || {
+        match dictionary::Dictionary::load_language(
+            dictionary_path.as_deref(),
+            &language,
+            half_life_days,
+            foreign_passthrough,
+        ) {
+            Ok(dictionary) => {
+                let previous = corrector().lock().replace_dictionary(dictionary);
+                drop(previous);
+            }
+            Err(e) => show_error_dialog(
+                "Autocorrect Error",
+                &format!(
+                    "Failed to load {} dictionary: {}",
+                    languages::display_name(&language),
+                    e
+                ),
+            ),
+        }
+    });
+}
+
+/// Reload the active language's dictionary files (after editing them, or
+/// installing a newer pack), without a restart.
+#[cfg(windows)]
+fn reload_dictionaries() {
+    let language = corrector().lock().language().to_string();
+    switch_language(language);
+}
+
+/// Reload the dictionaries if `autocorrect reload` asked for it.
+#[cfg(windows)]
+fn check_reload_request() {
+    if std::fs::remove_file(paths::local_file(paths::RELOAD_REQUEST_FILE)).is_ok() {
+        reload_dictionaries();
+    }
+}
+
+/// Delete personal data, in memory and on disk, if `autocorrect reset`
+/// asked for it.
+#[cfg(windows)]
+fn check_reset_request() {
+    let request = paths::local_file(paths::RESET_REQUEST_FILE);
+    if !request.exists() {
+        return;
+    }
+
+    let result = corrector().lock().reset_personal_data();
+    if let Err(e) = result {
+        eprintln!("Failed to delete personal data: {}", e);
+    }
+    // Removed last: `reset` waits for it to go
+    let _ = std::fs::remove_file(request);
+}
+
+/// Build the rest of the dictionary's delete index after startup.
+///
+/// Corrections work from the start, using whichever (most frequent) words are
+/// already indexed. The corrector is locked one chunk at a time so the
+/// keyboard hook never waits long. Stops early if the dictionary is replaced
+/// (e.g. by a language switch), since the replacement is fully indexed.
+fn index_dictionary_in_background() {
+    let started = std::time::Instant::now();
+    while corrector().lock().index_dictionary(DICTIONARY_INDEX_CHUNK) {
+        std::thread::yield_now();
+    }
+    println!("Dictionary indexed in {:?}", started.elapsed());
+}
+
+/// Update the tray tooltip while the dictionary loads, stopping the refresh
+/// timer once loading is done.
+#[cfg(windows)]
+fn refresh_loading_progress(tray_icon: &tray_icon::TrayIcon, loading_timer: &mut usize) {
+    let (tooltip, loading) = {
+        let corrector = corrector().lock();
+        (
+            tray_tooltip(&corrector),
+            corrector.dictionary_load_progress().is_some(),
+        )
+    };
+    let _ = tray_icon.set_tooltip(Some(tooltip));
+
+    if !loading {
+        unsafe {
+            KillTimer(null_mut(), *loading_timer);
+        }
+        *loading_timer = 0;
+    }
+}
+
+/// Periodic work driven by the housekeeping timer.
+#[cfg(windows)]
+fn on_housekeeping_tick(
+    config: &mut Config,
+    tray_icon: &tray_icon::TrayIcon,
+    watchdog: &mut HookWatchdog,
+) {
+    if check_conflicting_software(config, false) {
+        refresh_tray_tooltip(tray_icon);
+    }
+    check_os_autocorrect(config);
+    // Games often go fullscreen after their window is in the foreground
+    fullscreen::refresh(process::foreground_process_name().as_deref());
+    check_hook(watchdog, tray_icon);
+    check_rate_limit();
+    check_reset_request();
+    corrector().lock().flush_learning_if_due();
+    write_status_file();
+    check_typing_report();
+    check_reload_request();
+
+    if let Err(err) = config.save_if_due() {
+        eprintln!("Failed to save config: {err}");
+    }
+}
+
+/// Reinstall the keyboard hook if it stopped receiving input, and warn when
+/// it can't be brought back (see `watchdog`).
+#[cfg(windows)]
+fn check_hook(watchdog: &mut HookWatchdog, tray_icon: &tray_icon::TrayIcon) {
+    // Input to a secure desktop never reaches the hook
+    if secure_input::is_active() {
+        return;
+    }
+
+    let action = watchdog.tick(
+        stats::HOOK_COUNTERS.key_events(),
+        watchdog::last_input_time(),
+    );
+    match action {
+        WatchdogAction::None => {}
+        WatchdogAction::Probe => watchdog::send_probe(),
+        WatchdogAction::Reinstall | WatchdogAction::Lost => {
+            println!("Keyboard hook is not receiving input, reinstalling it");
+            unsafe {
+                uninstall_hook();
+                if let Err(e) = install_hook() {
+                    eprintln!("{}", e);
+                }
+            }
+        }
+        WatchdogAction::Recovered => {
+            println!("Keyboard hook is receiving input again");
+            set_tray_icon(tray_icon, ICON_COLOR);
+            refresh_tray_tooltip(tray_icon);
+        }
+    }
+
+    if action == WatchdogAction::Lost {
+        set_tray_icon(tray_icon, LOST_ICON_COLOR);
+        refresh_tray_tooltip(tray_icon);
+        show_warning_dialog(
+            "Autocorrect Warning",
+            "Autocorrect's keyboard hook was removed by Windows or another program and \
+             could not be reinstalled, so corrections are inactive.\n\n\
+             Autocorrect keeps trying and will resume on its own if it succeeds. \
+             Restarting Autocorrect usually helps; if this keeps happening, security \
+             or anti-cheat software may be blocking keyboard hooks.",
+        );
+    }
+}
+
+/// Tell the user when corrections were paused in an app for going over
+/// `max_corrections_per_minute`.
+#[cfg(windows)]
+fn check_rate_limit() {
+    let Some(process) = corrector().lock().take_rate_limit_notice() else {
+        return;
+    };
+    println!("Corrections paused in {}: too many per minute", process);
+    ui::notify::show(
+        "Autocorrect Paused in This App",
+        &format!(
+            "Unusually many corrections in {}, so none are made there for the next {} minutes. \
+             Add words you meant with \"Add last word to dictionary\", or exclude the app.",
+            process,
+            ratelimit::PAUSE.as_secs() / 60
+        ),
+        None,
+    );
+}
+
+/// Write the weekly typing report if one is due, and say so with a
+/// notification that opens it.
+#[cfg(windows)]
+fn check_typing_report() {
+    let today = tutor::today();
+    let path = tutor::report_path();
+    let mistakes = {
+        let corrector = corrector().lock();
+        match corrector.mistake_log() {
+            Some(log) if tutor::report_due(&path, log, today) => log.weekly_mistakes(today),
+            _ => return,
+        }
+    };
+
+    // Written even when empty: it marks this week's report as done
+    if let Err(e) = std::fs::write(&path, tutor::render_html(&mistakes)) {
+        eprintln!("Failed to write typing report: {}", e);
+        return;
+    }
+    if mistakes.is_empty() {
+        return;
+    }
+
+    let message = format!(
+        "{} words you often misspell, with their correct spelling. Click to open it.",
+        mistakes.len()
+    );
+    ui::notify::show(
+        "Your weekly typing report is ready",
+        &message,
+        Some(Box::new(move || open_file(&path))),
+    );
+}
+
+/// Publish the current counters for `autocorrect status`.
+fn write_status_file() {
+    let session = corrector().lock().stats().clone();
+    if let Err(e) = stats::write_status_file(&session) {
+        eprintln!("Failed to write status file: {}", e);
+    }
+}
+
+/// Whether an instance is running, going by how recently it wrote its
+/// status file.
+fn instance_running() -> bool {
+    stats::read_status_file().is_some_and(|(_, age)| age < STATUS_STALE_AFTER)
+}
+
+/// Print the counters published by a running instance.
+fn run_status() -> Result<(), Box<dyn std::error::Error>> {
+    match stats::read_status_file() {
+        Some((_, age)) if age >= STATUS_STALE_AFTER => Err(format!(
+            "Autocorrect is not running (last seen {}s ago).",
+            age.as_secs()
+        )
+        .into()),
+        Some((status, age)) => {
+            println!("Autocorrect is running (updated {}s ago)\n", age.as_secs());
+            println!("{}", status);
+            Ok(())
+        }
+        None => Err("Autocorrect is not running.".into()),
+    }
+}
+
+/// Ask the running instance to reload its dictionaries.
+///
+/// Like `status`, this goes through a file: the instance looks for the
+/// request on its next housekeeping tick.
+fn run_reload() -> Result<(), Box<dyn std::error::Error>> {
+    if !instance_running() {
+        return Err("Autocorrect is not running.".into());
+    }
+    std::fs::write(paths::local_file(paths::RELOAD_REQUEST_FILE), "")?;
+    println!("Reload requested; dictionaries reload within a few seconds.");
+    Ok(())
+}
+
+/// Show the corrector's current state in the tray tooltip.
+#[cfg(windows)]
+fn refresh_tray_tooltip(tray_icon: &tray_icon::TrayIcon) {
+    let tooltip = tray_tooltip(&corrector().lock());
+    println!("{}", tooltip);
+    if let Err(e) = tray_icon.set_tooltip(Some(tooltip)) {
+        println!("Failed to update tray tooltip: {}", e);
+    }
+}
+
+/// Add the last typed word to the personal dictionary, reporting failures.
+#[cfg(windows)]
+fn add_last_word_to_dictionary() {
+    let result = corrector().lock().add_last_word_to_dictionary();
+    if let Err(e) = result {
+        show_error_dialog(
+            "Autocorrect Error",
+            &format!("Failed to add word to personal dictionary: {}", e),
+        );
+    }
+}
+
+/// Bind and register the configured hotkeys, warning about any that can't be used.
+#[cfg(windows)]
+fn register_hotkeys(config: &Config) -> HotkeyRegistry {
+    let (mut registry, mut problems) = HotkeyRegistry::from_config(config.hotkey_bindings());
+    problems.extend(registry.register_all());
+
+    if !problems.is_empty() {
+        let list: Vec<String> = problems.iter().map(|p| format!("- {}", p)).collect();
+        show_warning_dialog(
+            "Autocorrect Warning",
+            &format!(
+                "Some hotkeys could not be set up:\n\n{}\n\nCheck the [hotkeys] section of the settings file.",
+                list.join("\n")
+            ),
+        );
+    }
+
+    registry
+}
+
+/// Run the action bound to a pressed hotkey.
+///
+/// Actions that type into the focused app run on a worker thread: they wait
+/// for the hotkey's modifiers to be released, and the keys they inject must
+/// reach the keyboard hook on this thread meanwhile.
+#[cfg(windows)]
+fn run_hotkey_action(
+    action: HotkeyAction,
+    config: &mut Config,
+    toggle_item: &MenuItem,
+    tray_icon: &tray_icon::TrayIcon,
+) {
+    match action {
+        HotkeyAction::Toggle => toggle_autocorrect(config, toggle_item, tray_icon),
+        HotkeyAction::AddWord => add_last_word_to_dictionary(),
+        HotkeyAction::Pause => {
+            {
+                let mut corrector = corrector().lock();
+                let paused = corrector.pause_reasons().contains(&PauseReason::Hotkey);
+                corrector.set_paused(PauseReason::Hotkey, !paused);
+            }
+            refresh_tray_tooltip(tray_icon);
+        }
+        HotkeyAction::IgnoreWord => {
+            std::thread::spawn(|| {
+                // May revert the correction, which types into the app
+                hotkeys::wait_for_modifiers_released();
+                ignore_last_word();
+            });
+        }
+        HotkeyAction::Undo => {
+            std::thread::spawn(|| {
+                hotkeys::wait_for_modifiers_released();
+                let edit = corrector().lock().undo_last_correction();
+                type_edit(edit);
+            });
+        }
+        HotkeyAction::NextSuggestion => {
+            std::thread::spawn(|| {
+                hotkeys::wait_for_modifiers_released();
+                let edit = corrector().lock().next_suggestion();
+                type_edit(edit);
+            });
+        }
+        HotkeyAction::CorrectWord => {
+            std::thread::spawn(|| {
+                hotkeys::wait_for_modifiers_released();
+                let edit = corrector().lock().correct_last_word();
+                type_edit(edit);
+            });
+        }
+        HotkeyAction::CorrectSelection => {
+            std::thread::spawn(|| {
+                let result =
+                    selection::correct_selection(|text| corrector().lock().correct_text(text));
+                if let Err(e) = result {
+                    eprintln!("Failed to correct selection: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// Type a hotkey action's edit with the corrector unlocked, then let it
+/// remember the edit (see `PendingEdit`).
+///
+/// The edit must be taken in a statement of its own: a lock guard in the
+/// same expression would be held while typing, and the keyboard hook, which
+/// sees every key typed, would wait for it.
+#[cfg(windows)]
+fn type_edit(edit: Option<corrector::PendingEdit>) {
+    if let Some(edit) = edit {
+        let typed = edit.type_text();
+        corrector().lock().finish_edit(edit, typed);
+    }
+}
+
+/// Ignore the last word for the session, reverting its correction if it was
+/// just corrected.
+#[cfg(windows)]
+fn ignore_last_word() {
+    let ignored = corrector().lock().ignore_last_word_for_session();
+    if let Some((_, undo)) = ignored {
+        type_edit(undo);
+    }
+}
+
+/// Write output to the console the command was run from, if any.
+///
+/// The binary is built for the GUI subsystem so the tray app doesn't open a
+/// console window; command-line commands would otherwise print nothing.
+fn attach_parent_console() {
+    #[cfg(windows)]
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
 fn hide_console_window() {
     #[cfg(windows)]
     unsafe {
@@ -218,25 +1315,112 @@ fn hide_console_window() {
 /// * Other - Result from `CallNextHookEx` (pass through)
 #[cfg(windows)]
 unsafe extern "system" fn keyboard_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
-    if code >= 0 {
-        let kb_struct = *(lparam as *const KBDLLHOOKSTRUCT);
-        let vk_code = kb_struct.vkCode;
-        let is_key_down = wparam == WM_KEYDOWN as usize || wparam == WM_SYSKEYDOWN as usize;
+    if code < 0 {
+        return CallNextHookEx(HOOK_HANDLE, code, wparam, lparam);
+    }
+
+    let started = Instant::now();
+    let result = handle_key_event(code, wparam, lparam);
+    stats::HOOK_COUNTERS.record_callback(started.elapsed());
+    result
+}
 
-        if is_key_down {
-            let mut corrector = corrector().lock();
+/// Update the state that depends on the foreground window: sensitive apps,
+/// remote clients and excluded titles.
+fn refresh_foreground_window() {
+    let process_name = process::foreground_process_name();
+    privacy::set_foreground_app(process_name.as_deref());
+    let class_name = process::foreground_window_class();
+    exclusions::set_foreground_client(process_name.as_deref(), class_name.as_deref());
+    exclusions::set_foreground_app(process_name.as_deref(), class_name.as_deref());
+    exclusions::set_foreground_title(process::foreground_window_title().as_deref());
+    os_autocorrect::set_foreground_app(process_name.as_deref());
+    fullscreen::refresh(process_name.as_deref());
+    ime::refresh();
+    password_field::refresh();
+    // Skipped before startup is done, or if the hook thread is inside a
+    // correction (see `focus::subscribe`)
+    #[cfg(windows)]
+    if let Some(corrector) = CORRECTOR.get().and_then(|corrector| corrector.try_lock()) {
+        clipboard::keep_snapshot(corrector.pastes_in_foreground());
+    }
+}
+
+/// Feed a key event to the corrector (the body of `keyboard_proc`).
+///
+/// Only key presses are tracked. Modifier and lock keys are read as they
+/// are when each key is pressed (see `keymap`), not kept from the hook's
+/// own press and release events: a release the hook never sees (Ctrl+Alt+Del,
+/// Win+L, a key let go on the secure desktop) would otherwise leave a
+/// modifier stuck down. Releases only end auto-repeat: the low-level hook
+/// gets no repeat flag, so a press of the key already held is a repeat. A
+/// missed release makes at most the next press of that key look repeated.
+#[cfg(windows)]
+unsafe fn handle_key_event(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let kb_struct = *(lparam as *const KBDLLHOOKSTRUCT);
+    let vk_code = kb_struct.vkCode;
+    let is_key_down = wparam == WM_KEYDOWN as usize || wparam == WM_SYSKEYDOWN as usize;
 
-            // Check if autocorrect is enabled
-            if !corrector.is_enabled() {
+    if !is_key_down {
+        let _ = HELD_KEY.compare_exchange(vk_code, 0, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    if is_key_down {
+        if ime::is_ime_key(vk_code) {
+            ime::request_refresh();
+        }
+        let repeat = HELD_KEY.swap(vk_code, Ordering::Relaxed) == vk_code;
+        // Never track keys typed into OS security prompts, not even in memory
+        let secure = secure_input::is_active();
+        // Keystrokes injected by other software, not by us
+        let foreign = kb_struct.flags & LLKHF_INJECTED != 0
+            && kb_struct.dwExtraInfo != corrector::INJECTION_MARKER;
+
+        let mut corrector = corrector().lock();
+        if MISSED_FOCUS_CHANGE.swap(false, Ordering::Relaxed) {
+            corrector.on_focus_changed();
+        }
+        if MISSED_CLICK.swap(false, Ordering::Relaxed) {
+            corrector.on_mouse_click();
+        }
+        corrector.set_paused(PauseReason::SecureInput, secure);
+        corrector.set_paused(PauseReason::ElevatedWindow, elevation::is_active());
+        corrector.set_paused(PauseReason::PasswordField, password_field::is_active());
+        corrector.set_paused(PauseReason::ExcludedWindow, exclusions::is_excluded());
+        corrector.set_paused(PauseReason::ExcludedApp, exclusions::is_app_disabled());
+        corrector.set_paused(PauseReason::RemoteSession, exclusions::is_remote());
+        corrector.set_paused(PauseReason::FullscreenGame, fullscreen::is_active());
+        corrector.set_paused(PauseReason::OsAutocorrect, os_autocorrect::should_yield());
+        corrector.set_paused(
+            PauseReason::UnsupportedScript,
+            segmentation::foreground_boundaries() == WordBoundaries::Unsupported,
+        );
+        corrector.set_paused(PauseReason::ImeComposition, ime::is_active());
+        if !repeat && kb_struct.dwExtraInfo != corrector::INJECTION_MARKER {
+            corrector.on_keystroke(Instant::now());
+        }
+
+        match corrector.injected_input_policy() {
+            InjectedInputPolicy::Track => {}
+            InjectedInputPolicy::Ignore if foreign => {
                 return CallNextHookEx(HOOK_HANDLE, code, wparam, lparam);
             }
-
-            // Handle the key press
-            if corrector.handle_key(vk_code) {
-                // Key was handled (correction was made), suppress it
-                return 1;
+            InjectedInputPolicy::Ignore => {}
+            InjectedInputPolicy::Pause => {
+                corrector.set_paused(PauseReason::InjectedInput, foreign);
             }
         }
+
+        // Check if autocorrect is enabled and not paused
+        if !corrector.is_enabled() || corrector.is_paused() {
+            return CallNextHookEx(HOOK_HANDLE, code, wparam, lparam);
+        }
+
+        // Handle the key press
+        if corrector.handle_hook_key(vk_code, repeat, kb_struct.dwExtraInfo) {
+            // Key was handled (correction was made), suppress it
+            return 1;
+        }
     }
 
     CallNextHookEx(HOOK_HANDLE, code, wparam, lparam)
@@ -265,6 +1449,7 @@ unsafe fn install_hook() -> Result<(), String> {
         if HOOK_HANDLE.is_null() {
             return Err("Failed to install keyboard hook".to_string());
         }
+        stats::HOOK_COUNTERS.record_hook_install();
     }
 
     Ok(())
@@ -288,9 +1473,10 @@ unsafe fn uninstall_hook() {
 ///
 /// # Initialization Sequence
 /// 1. Initialize the corrector (load dictionaries)
-/// 2. Install the low-level keyboard hook
-/// 3. Create the system tray icon and menu
-/// 4. Enter the Windows message loop
+/// 2. Self-test keystroke injection (optional)
+/// 3. Install the low-level keyboard hook
+/// 4. Create the system tray icon and menu
+/// 5. Enter the Windows message loop
 ///
 /// # Shutdown
 /// - Menu "Quit" selection breaks the message loop
@@ -300,7 +1486,15 @@ unsafe fn uninstall_hook() {
 /// # Errors
 /// Returns an error if dictionary loading fails or the hook cannot be installed.
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+    // Help, version and usage errors are printed by clap
+    let args = Args::try_parse().unwrap_or_else(|e| {
+        attach_parent_console();
+        e.exit()
+    });
+
+    if args.check_update || args.command.is_some() {
+        attach_parent_console();
+    }
 
     if args.check_update {
         match Updater::check_and_update() {
@@ -317,30 +1511,68 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    if !args.console {
+    match args.command {
+        Some(Command::Reset { personal_data, yes }) => return run_reset(personal_data, yes),
+        Some(Command::Status) => return run_status(),
+        Some(Command::Events) => return events::follow(),
+        Some(Command::Reload) => return run_reload(),
+        Some(Command::TrainLm { input }) => return run_train_lm(&input),
+        Some(Command::Check { files }) => return run_check(&files, args.dictionary.as_deref()),
+        None => {}
+    }
+
+    if args.console {
+        attach_parent_console();
+    } else {
         hide_console_window();
     }
 
+    // Before any window exists, so popups can be placed in physical pixels
+    #[cfg(windows)]
+    ui::positioning::enable_per_monitor_dpi_awareness();
+
     let mut config = Config::load()?;
 
+    // Older versions kept everything in the roaming profile
+    for error in paths::migrate_local_data() {
+        eprintln!("Warning: {}", error);
+    }
+
     if args.disabled {
         config.enabled_by_default = false;
     }
 
+    privacy::set_sensitive_apps(&config.apps.sensitive);
+    events::set_enabled(config.stream_events);
+    exclusions::set_remote_allowed(&config.apps.remote_allowed);
+    exclusions::set_app_lists(&config.apps.disabled, &config.apps.enabled_only);
+    for error in exclusions::set_title_patterns(&config.apps.excluded_titles) {
+        eprintln!("Warning: {}", error);
+    }
+    os_autocorrect::configure(config.os_autocorrect, &config.apps.yield_to_os_autocorrect);
+    fullscreen::configure(config.pause_in_fullscreen, &config.apps.fullscreen_allowed);
+    password_field::set_password_classes(&config.apps.password_fields);
+    refresh_foreground_window();
+
     // Persist defaults so users get a concrete config.toml on first run.
     if let Err(err) = config.save() {
         eprintln!("Failed to persist config defaults: {err}");
     }
 
-    let configured_corrector = Arc::new(Mutex::new(Corrector::new_with_config(&config)));
+    let mut configured_corrector = Corrector::new_with_config(&config);
+    // Not saved to the config, so it only lasts for this run
+    if args.dry_run {
+        configured_corrector.set_dry_run(true);
+    }
+    let configured_corrector = Arc::new(Mutex::new(configured_corrector));
     CORRECTOR
         .set(configured_corrector)
         .map_err(|_| "Corrector was already initialized")?;
 
-    // Initialize the corrector (loads dictionaries)
+    // Initialize the corrector (reads dictionaries; indexing continues below)
     {
         let mut corrector = corrector().lock();
-        if let Err(e) = corrector.initialize_with_dictionary(args.dictionary.as_deref()) {
+        if let Err(e) = corrector.begin_initialize_with_dictionary(args.dictionary.as_deref()) {
             println!("Failed to initialize corrector: {}", e);
             show_error_dialog(
                 "Autocorrect Error",
@@ -350,6 +1582,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    std::thread::spawn(index_dictionary_in_background);
+
+    // Verify keystroke injection works before the hook starts correcting.
+    // Runs before the hook is installed so the test keys aren't seen by it.
+    let startup_warning = if config.startup_self_test {
+        run_startup_self_test()
+    } else {
+        None
+    };
+
     // Install keyboard hook
     unsafe {
         if let Err(e) = install_hook() {
@@ -364,10 +1606,57 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     #[cfg(windows)]
     {
+        // A word started in one window must not be finished in another, and
+        // undo must not retype into a window other than the one corrected.
+        // try_lock: events can be delivered while the hook thread is inside
+        // a correction (e.g. during SendMessage), and the lock isn't reentrant;
+        // a change missed then is applied at the next key.
+        let _focus_hooks = match focus::subscribe(|change| {
+            match change {
+                focus::FocusChange::Title => {
+                    exclusions::set_foreground_title(process::foreground_window_title().as_deref());
+                    return;
+                }
+                focus::FocusChange::Foreground => refresh_foreground_window(),
+                focus::FocusChange::Control => {
+                    password_field::refresh();
+                    ime::refresh();
+                }
+            }
+            match corrector().try_lock() {
+                Some(mut corrector) => corrector.on_focus_changed(),
+                None => MISSED_FOCUS_CHANGE.store(true, Ordering::Relaxed),
+            }
+        }) {
+            Ok(hooks) => Some(hooks),
+            Err(e) => {
+                println!("Failed to subscribe to focus changes: {}", e);
+                None
+            }
+        };
+
+        // A click moves the caret away from the word being typed
+        let _mouse_hook = if config.clear_word_on_click {
+            match mouse::subscribe(|| match corrector().try_lock() {
+                Some(mut corrector) => corrector.on_mouse_click(),
+                None => MISSED_CLICK.store(true, Ordering::Relaxed),
+            }) {
+                Ok(hook) => Some(hook),
+                Err(e) => {
+                    println!("Failed to subscribe to mouse clicks: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let enabled = corrector().lock().is_enabled();
+
         // Create tray icon menu
         let menu = Menu::new();
         let toggle_item = MenuItem::new(
-            if config.enabled_by_default {
+            if enabled {
                 "Disable Autocorrect"
             } else {
                 "Enable Autocorrect"
@@ -375,40 +1664,88 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             true,
             None,
         );
-        let quit_item = MenuItem::new("Quit", true, None);
+        let add_word_item = MenuItem::new(ADD_WORD_LABEL, false, None);
+        let ignore_word_item = MenuItem::new(IGNORE_WORD_LABEL, false, None);
+        let report_item = MenuItem::new(REPORT_LABEL, false, None);
 
-        if let Err(e) = menu.append(&toggle_item) {
-            println!("Failed to append toggle menu item: {}", e);
-            show_error_dialog(
-                "Autocorrect Error",
-                &format!("Failed to create tray menu: {}", e),
-            );
-            unsafe {
-                uninstall_hook();
+        // One checkable item per installed language pack. A custom
+        // dictionary replaces every pack, so the submenu is disabled then.
+        let active_language = corrector().lock().language().to_string();
+        let language_menu = Submenu::new("Language", args.dictionary.is_none());
+        let language_items: Vec<(languages::LanguagePack, CheckMenuItem)> =
+            languages::installed_packs()
+                .into_iter()
+                .map(|pack| {
+                    let item =
+                        CheckMenuItem::new(&pack.name, true, pack.code == active_language, None);
+                    (pack, item)
+                })
+                .collect();
+        for (_, item) in &language_items {
+            if let Err(e) = language_menu.append(item) {
+                println!("Failed to append language menu item: {}", e);
             }
-            return Err(e.into());
         }
-        if let Err(e) = menu.append(&quit_item) {
-            println!("Failed to append quit menu item: {}", e);
-            show_error_dialog(
-                "Autocorrect Error",
-                &format!("Failed to create tray menu: {}", e),
-            );
-            unsafe {
-                uninstall_hook();
+
+        // Quick switching between correction presets; none is checked while
+        // the individual settings are in use (`correction_mode = "custom"`).
+        let mode_menu = Submenu::new("Correction Mode", true);
+        let mode_items: Vec<(ranking::CorrectionMode, CheckMenuItem)> =
+            ranking::CorrectionMode::PRESETS
+                .into_iter()
+                .map(|mode| {
+                    let item = CheckMenuItem::new(
+                        mode.label(),
+                        true,
+                        mode == config.correction_mode,
+                        None,
+                    );
+                    (mode, item)
+                })
+                .collect();
+        for (_, item) in &mode_items {
+            if let Err(e) = mode_menu.append(item) {
+                println!("Failed to append correction mode menu item: {}", e);
+            }
+        }
+
+        let open_settings_item = MenuItem::new("Open Settings File", true, None);
+        let open_dictionary_item = MenuItem::new("Open Personal Dictionary", true, None);
+        let reload_item = MenuItem::new("Reload Dictionaries", true, None);
+        let reset_item = MenuItem::new("Reset Personal Data...", true, None);
+        let quit_item = MenuItem::new("Quit", true, None);
+
+        for item in [
+            &toggle_item as &dyn IsMenuItem,
+            &add_word_item,
+            &ignore_word_item,
+            &report_item,
+            &language_menu,
+            &mode_menu,
+            &open_settings_item,
+            &open_dictionary_item,
+            &reload_item,
+            &reset_item,
+            &quit_item,
+        ] {
+            if let Err(e) = menu.append(item) {
+                println!("Failed to append menu item: {}", e);
+                show_error_dialog(
+                    "Autocorrect Error",
+                    &format!("Failed to create tray menu: {}", e),
+                );
+                unsafe {
+                    uninstall_hook();
+                }
+                return Err(e.into());
             }
-            return Err(e.into());
         }
 
         // Create tray icon
-        let icon = load_icon();
+        let icon = load_icon(ICON_COLOR);
         let _tray_icon = TrayIconBuilder::new()
             .with_menu(Box::new(menu))
-            .with_tooltip(if config.enabled_by_default {
-                "Autocorrect - Enabled"
-            } else {
-                "Autocorrect - Disabled"
-            })
+            .with_tooltip(tray_tooltip(&corrector().lock()))
             .with_icon(icon)
             .build()
             .map_err(|e| {
@@ -423,58 +1760,155 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 e
             })?;
 
+        if let Some(warning) = &startup_warning {
+            show_warning_dialog("Autocorrect Warning", warning);
+        }
+        check_conflicting_software(&config, true);
+        check_os_autocorrect(&config);
+
+        write_status_file();
+
+        let mut widget = if config.show_widget {
+            match Widget::create() {
+                Ok(widget) => Some(widget),
+                Err(e) => {
+                    println!("Failed to create widget: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // WM_HOTKEY messages arrive in this thread's queue
+        let mut hotkey_registry = register_hotkeys(&config);
+
         println!("Autocorrect started. Running in system tray.");
         println!("Press Ctrl+C to quit.");
 
-        // Menu event handling
+        // Menu and tray icon event handling
         let menu_channel = tray_icon::menu::MenuEvent::receiver();
+        let tray_channel = TrayIconEvent::receiver();
+        let mut last_left_click: Option<Instant> = None;
+
+        // Periodic checks (e.g. conflicting software) run on a thread timer
+        let housekeeping_timer = unsafe { SetTimer(null_mut(), 0, HOUSEKEEPING_INTERVAL_MS, None) };
+
+        // IMEs switch on and off without a focus change
+        let ime_timer = unsafe { SetTimer(null_mut(), 0, ime::REFRESH_INTERVAL_MS, None) };
+
+        // Loading progress in the tooltip, until the dictionary is indexed
+        let mut loading_timer = if corrector().lock().dictionary_load_progress().is_some() {
+            unsafe { SetTimer(null_mut(), 0, LOADING_REFRESH_MS, None) }
+        } else {
+            0
+        };
+
+        // Notices when the keyboard hook stops receiving input
+        let mut hook_watchdog = HookWatchdog::new(
+            stats::HOOK_COUNTERS.key_events(),
+            watchdog::last_input_time(),
+        );
+
+        // Last word shown in the "Add last word" menu item
+        let mut shown_last_word: Option<String> = None;
+        // Last correction shown in the "Report bad correction" menu item
+        let mut shown_correction: Option<(String, String)> = None;
 
         // Message loop
-        let mut msg = std::mem::MaybeUninit::uninit();
+        let mut msg = std::mem::MaybeUninit::<MSG>::uninit();
         unsafe {
             loop {
+                // Left-click toggles (single or double, per config);
+                // middle-click shows session statistics
+                while let Ok(event) = tray_channel.try_recv() {
+                    let TrayIconEvent::Click {
+                        button,
+                        button_state: MouseButtonState::Up,
+                        ..
+                    } = event
+                    else {
+                        continue;
+                    };
+
+                    match button {
+                        MouseButton::Left => {
+                            if is_toggle_click(config.tray_toggle_click, &mut last_left_click) {
+                                toggle_autocorrect(&mut config, &toggle_item, &_tray_icon);
+                            }
+                        }
+                        MouseButton::Middle => {
+                            let summary = format!(
+                                "{}\n\n{}",
+                                corrector().lock().stats().summary(),
+                                stats::HOOK_COUNTERS.snapshot().summary()
+                            );
+                            show_info_dialog("Autocorrect Statistics", &summary);
+                        }
+                        MouseButton::Right => {}
+                    }
+                }
+
                 // Check for menu events
                 if let Ok(event) = menu_channel.try_recv() {
                     if event.id == toggle_item.id() {
-                        let mut corrector = corrector().lock();
-                        corrector.toggle_enabled();
-                        config.enabled_by_default = corrector.is_enabled();
-
-                        if let Err(err) = config.save() {
-                            eprintln!("Failed to save config: {err}");
+                        toggle_autocorrect(&mut config, &toggle_item, &_tray_icon);
+                    } else if event.id == add_word_item.id() {
+                        add_last_word_to_dictionary();
+                    } else if event.id == ignore_word_item.id() {
+                        ignore_last_word();
+                    } else if event.id == report_item.id() {
+                        report_bad_correction();
+                    } else if let Some((pack, _)) = language_items
+                        .iter()
+                        .find(|(_, item)| event.id == *item.id())
+                    {
+                        // Clicking toggles the item itself, so re-apply the
+                        // radio-style checks from the selection
+                        for (other, item) in &language_items {
+                            item.set_checked(other.code == pack.code);
                         }
 
-                        let new_label = if corrector.is_enabled() {
-                            "Disable Autocorrect"
-                        } else {
-                            "Enable Autocorrect"
-                        };
-                        toggle_item.set_text(new_label);
-
-                        let tooltip = if corrector.is_enabled() {
-                            "Autocorrect - Enabled"
-                        } else {
-                            "Autocorrect - Disabled"
-                        };
-                        if let Err(e) = _tray_icon.set_tooltip(Some(tooltip)) {
-                            println!("Failed to update tray tooltip: {}", e);
-                            show_warning_dialog(
-                                "Autocorrect Warning",
-                                &format!(
-                                    "Autocorrect state changed, but tray tooltip could not be updated: {}",
-                                    e
-                                ),
-                            );
+                        if pack.code != config.language {
+                            config.language = pack.code.clone();
+                            config.request_save();
+                            switch_language(pack.code.clone());
+                        }
+                    } else if let Some((mode, _)) =
+                        mode_items.iter().find(|(_, item)| event.id == *item.id())
+                    {
+                        for (other, item) in &mode_items {
+                            item.set_checked(other == mode);
                         }
 
-                        println!(
-                            "Autocorrect {}",
-                            if corrector.is_enabled() {
-                                "enabled"
-                            } else {
-                                "disabled"
+                        if *mode != config.correction_mode {
+                            config.correction_mode = *mode;
+                            config.request_save();
+                            corrector().lock().set_correction_mode(*mode);
+                        }
+                    } else if event.id == open_settings_item.id() {
+                        match Config::path() {
+                            Ok(path) => open_file(&path),
+                            Err(e) => show_error_dialog(
+                                "Autocorrect Error",
+                                &format!("Could not locate the settings file: {}", e),
+                            ),
+                        }
+                    } else if event.id == open_dictionary_item.id() {
+                        open_file(&paths::personal_dictionary());
+                    } else if event.id == reload_item.id() {
+                        reload_dictionaries();
+                    } else if event.id == reset_item.id() {
+                        if confirm_dialog("Reset Personal Data", RESET_CONFIRMATION) {
+                            let result = corrector().lock().reset_personal_data();
+                            match result {
+                                Ok(()) => show_info_dialog("Autocorrect", "Personal data deleted."),
+                                Err(e) => show_error_dialog(
+                                    "Autocorrect Error",
+                                    &format!("Failed to delete personal data: {}", e),
+                                ),
                             }
-                        );
+                        }
                     } else if event.id == quit_item.id() {
                         break;
                     }
@@ -486,17 +1920,57 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     break;
                 }
 
+                // Refresh before dispatching, so a tray click that opens the
+                // menu already sees the latest word
+                refresh_last_word_items(&add_word_item, &ignore_word_item, &mut shown_last_word);
+                refresh_report_item(&report_item, &mut shown_correction);
+
+                let message = &*msg.as_ptr();
+                if let Some(widget) = &mut widget {
+                    if widget.is_click(message) {
+                        on_widget_click(&mut config, &toggle_item, &_tray_icon);
+                    }
+                    widget.update(widget_state(&corrector().lock()));
+                }
+                if message.message == WM_TIMER && message.wParam == housekeeping_timer {
+                    on_housekeeping_tick(&mut config, &_tray_icon, &mut hook_watchdog);
+                } else if (message.message == WM_TIMER && message.wParam == ime_timer)
+                    || message.message == ime::REFRESH_MESSAGE
+                {
+                    ime::refresh();
+                } else if message.message == WM_TIMER
+                    && loading_timer != 0
+                    && message.wParam == loading_timer
+                {
+                    refresh_loading_progress(&_tray_icon, &mut loading_timer);
+                } else if message.message == WM_HOTKEY {
+                    if let Some(action) = hotkey_registry.action_for_id(message.wParam as i32) {
+                        run_hotkey_action(action, &mut config, &toggle_item, &_tray_icon);
+                    }
+                }
+
                 TranslateMessage(msg.as_ptr());
                 DispatchMessageW(msg.as_ptr());
             }
 
             // Cleanup
+            hotkey_registry.unregister_all();
             uninstall_hook();
+            corrector().lock().flush_learning();
+            stats::remove_status_file();
+            if let Err(err) = config.flush() {
+                eprintln!("Failed to save config: {err}");
+            }
         }
     }
 
     #[cfg(not(windows))]
     {
+        if let Some(warning) = &startup_warning {
+            show_warning_dialog("Autocorrect Warning", warning);
+        }
+        check_conflicting_software(&config, true);
+
         println!("Autocorrect started (no-op on non-Windows).");
         println!("Press Ctrl+C to quit.");
         // We could use a signal handler here, but for a no-op build loop is fine.
@@ -506,9 +1980,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Show the tray icon in a different color.
+#[cfg(windows)]
+fn set_tray_icon(tray_icon: &tray_icon::TrayIcon, color: [u8; 3]) {
+    if let Err(e) = tray_icon.set_icon(Some(load_icon(color))) {
+        println!("Failed to update tray icon: {}", e);
+    }
+}
+
 /// Create the application icon for the system tray.
 ///
-/// Generates a simple 16x16 RGBA icon with a green circle pattern.
+/// Generates a simple 16x16 RGBA icon: a circle in `color`.
 /// This is used as the visual indicator in the Windows system tray.
 ///
 /// # Returns
@@ -517,13 +1999,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// # Panics
 /// Panics if the icon data is invalid (should never happen with valid RGBA).
 #[cfg(windows)]
-fn load_icon() -> tray_icon::Icon {
-    // Create a simple 16x16 RGBA icon (green checkmark-ish)
+fn load_icon(color: [u8; 3]) -> tray_icon::Icon {
+    // Create a simple 16x16 RGBA icon
     let width = 16;
     let height = 16;
     let mut rgba = vec![0u8; (width * height * 4) as usize];
 
-    // Simple green circle pattern
+    // Simple filled circle
     for y in 0..height {
         for x in 0..width {
             let idx = ((y * width + x) * 4) as usize;
@@ -532,9 +2014,7 @@ fn load_icon() -> tray_icon::Icon {
             let dist_sq = dx * dx + dy * dy;
 
             if dist_sq < 36 {
-                rgba[idx] = 50; // R
-                rgba[idx + 1] = 200; // G
-                rgba[idx + 2] = 50; // B
+                rgba[idx..idx + 3].copy_from_slice(&color);
                 rgba[idx + 3] = 255; // A
             } else {
                 rgba[idx + 3] = 0; // Transparent
@@ -0,0 +1,92 @@
+//! Notifications when a mouse button is pressed.
+//!
+//! A click almost always moves the caret, so the letters typed before it
+//! aren't the word at the caret any more: backspacing over them to correct
+//! the word would delete unrelated text. Uses a low-level mouse hook
+//! (`WH_MOUSE_LL`), which runs on the installing thread's message loop.
+//!
+//! Clicks on windows that never take focus (the on-screen and touch
+//! keyboards) don't move the caret, and are skipped.
+
+#[cfg(windows)]
+use std::ptr::null_mut;
+#[cfg(windows)]
+use std::sync::OnceLock;
+#[cfg(windows)]
+use winapi::shared::minwindef::{LPARAM, LRESULT, WPARAM};
+#[cfg(windows)]
+use winapi::shared::windef::HHOOK;
+#[cfg(windows)]
+use winapi::um::libloaderapi::GetModuleHandleW;
+#[cfg(windows)]
+use winapi::um::winuser::{
+    CallNextHookEx, GetAncestor, GetWindowLongW, SetWindowsHookExW, UnhookWindowsHookEx,
+    WindowFromPoint, GA_ROOT, GWL_EXSTYLE, MSLLHOOKSTRUCT, WH_MOUSE_LL, WM_LBUTTONDOWN,
+    WM_MBUTTONDOWN, WM_RBUTTONDOWN, WM_XBUTTONDOWN, WS_EX_NOACTIVATE,
+};
+
+/// Function called for every click.
+#[cfg(windows)]
+static HANDLER: OnceLock<fn()> = OnceLock::new();
+
+/// The installed mouse hook; dropping this removes it.
+#[cfg(windows)]
+pub struct MouseHook {
+    hook: HHOOK,
+}
+
+#[cfg(windows)]
+impl Drop for MouseHook {
+    fn drop(&mut self) {
+        unsafe {
+            UnhookWindowsHookEx(self.hook);
+        }
+    }
+}
+
+/// Call `handler` whenever a mouse button is pressed over a window that can
+/// take focus.
+///
+/// Must be called from a thread running a message loop. Only one handler can
+/// be set per process.
+///
+/// # Errors
+/// Returns an error if a handler was already set or Windows refuses the
+/// hook.
+#[cfg(windows)]
+pub fn subscribe(handler: fn()) -> Result<MouseHook, String> {
+    HANDLER
+        .set(handler)
+        .map_err(|_| "mouse handler already set".to_string())?;
+
+    let hook = unsafe {
+        SetWindowsHookExW(
+            WH_MOUSE_LL,
+            Some(mouse_proc),
+            GetModuleHandleW(null_mut()),
+            0,
+        )
+    };
+    if hook.is_null() {
+        return Err("Failed to install mouse hook".to_string());
+    }
+    Ok(MouseHook { hook })
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let button_down = matches!(
+        wparam as u32,
+        WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN
+    );
+    if code >= 0 && button_down {
+        let info = *(lparam as *const MSLLHOOKSTRUCT);
+        let window = GetAncestor(WindowFromPoint(info.pt), GA_ROOT);
+        let takes_focus =
+            window.is_null() || GetWindowLongW(window, GWL_EXSTYLE) as u32 & WS_EX_NOACTIVATE == 0;
+        if let Some(handler) = HANDLER.get().filter(|_| takes_focus) {
+            handler();
+        }
+    }
+    CallNextHookEx(null_mut(), code, wparam, lparam)
+}
@@ -0,0 +1,110 @@
+//! Fixes for ordinal suffixes and misspelled number words.
+//!
+//! Checked before the dictionary, since SymSpell can't know that "2end"
+//! should be "2nd" or that "forth" after "third" means "fourth":
+//! - **Ordinal suffixes** typed right after digits get the suffix the number
+//!   needs, in lowercase ("1ST" → "1st", "2end" → "2nd", "11st" → "11th").
+//! - **Number words** from [`NUMBER_WORD_FIXES`] are fixed, some of them only
+//!   when the previous word is a number.
+
+/// Typed suffixes recognized as an attempt at an ordinal suffix.
+const ORDINAL_SUFFIX_ATTEMPTS: &[&str] = &[
+    "st", "nd", "rd", "th", // correct suffixes, possibly on the wrong number
+    "end", "ond", "ird", "rst", "nth", // common slips
+];
+
+/// Misspelled number words: (typed, fix, only after a number).
+///
+/// Words that are also real words ("forth") are only fixed in numeric
+/// context, e.g. "third forth" or "3 forth".
+pub const NUMBER_WORD_FIXES: &[(&str, &str, bool)] = &[
+    ("forth", "fourth", true),
+    ("fourty", "forty", false),
+    ("fith", "fifth", false),
+    ("nineth", "ninth", false),
+    ("ninty", "ninety", false),
+    ("twelth", "twelfth", false),
+];
+
+/// Number and ordinal words that make the next word numeric context.
+const NUMBER_WORDS: &[&str] = &[
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "first",
+    "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth", "tenth",
+];
+
+/// The ordinal suffix for a number written in digits.
+pub fn ordinal_suffix(number: &str) -> &'static str {
+    let tens = number
+        .len()
+        .checked_sub(2)
+        .and_then(|start| number.get(start..start + 1));
+    if tens == Some("1") {
+        return "th";
+    }
+
+    match number.chars().last() {
+        Some('1') => "st",
+        Some('2') => "nd",
+        Some('3') => "rd",
+        _ => "th",
+    }
+}
+
+/// Fix a word using its numeric context.
+///
+/// `number` holds the digits typed directly before `word` (empty if none);
+/// `previous` is the word before it, if only a space separates them.
+/// Returns the replacement for `word`, or `None` if nothing needs fixing.
+pub fn fix(number: &str, previous: Option<&str>, word: &str) -> Option<String> {
+    if !number.is_empty() {
+        let typed = word.to_lowercase();
+        if !ORDINAL_SUFFIX_ATTEMPTS.contains(&typed.as_str()) {
+            return None;
+        }
+
+        let suffix = ordinal_suffix(number);
+        return (word != suffix).then(|| suffix.to_string());
+    }
+
+    let numeric_context = previous.is_some_and(|previous| {
+        previous.chars().all(|ch| ch.is_ascii_digit())
+            || NUMBER_WORDS
+                .iter()
+                .any(|number_word| number_word.eq_ignore_ascii_case(previous))
+    });
+
+    NUMBER_WORD_FIXES
+        .iter()
+        .find(|(typed, _, needs_context)| {
+            typed.eq_ignore_ascii_case(word) && (numeric_context || !needs_context)
+        })
+        .map(|(_, fixed, _)| crate::symspell::match_case(word, fixed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ordinal_suffix() {
+        assert_eq!(ordinal_suffix("1"), "st");
+        assert_eq!(ordinal_suffix("22"), "nd");
+        assert_eq!(ordinal_suffix("103"), "rd");
+        assert_eq!(ordinal_suffix("11"), "th");
+        assert_eq!(ordinal_suffix("112"), "th");
+        assert_eq!(ordinal_suffix("4"), "th");
+    }
+
+    #[test]
+    fn test_fix() {
+        assert_eq!(fix("1", None, "ST"), Some("st".to_string()));
+        assert_eq!(fix("2", None, "end"), Some("nd".to_string()));
+        assert_eq!(fix("11", None, "st"), Some("th".to_string()));
+        assert_eq!(fix("21", None, "st"), None);
+        assert_eq!(fix("5", None, "px"), None);
+
+        assert_eq!(fix("", Some("third"), "forth"), Some("fourth".to_string()));
+        assert_eq!(fix("", Some("back"), "forth"), None);
+        assert_eq!(fix("", None, "Fourty"), Some("Forty".to_string()));
+    }
+}
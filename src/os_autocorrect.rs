@@ -0,0 +1,155 @@
+//! Coexistence with Windows' own autocorrect for hardware keyboards.
+//!
+//! Windows 10 and 11 can autocorrect misspelled words typed on a physical
+//! keyboard (Settings > Time & language > Typing > "Autocorrect misspelled
+//! words as I type"). In apps that support it, a typo is then fixed twice:
+//! once by Windows, and again by us on the already corrected text, which
+//! duplicates letters or undoes the fix. Windows' text suggestions only show
+//! a list to pick from and don't interfere, so only autocorrect matters here.
+//!
+//! What happens while Windows' autocorrect is on is set by `os_autocorrect`:
+//! warn once, yield (pause corrections in the apps listed under
+//! `[apps] yield_to_os_autocorrect`, or in every app if the list is empty),
+//! or ignore it. The setting is read from the registry at startup and on
+//! every housekeeping tick, so changes in Settings apply without a restart.
+//!
+//! Like `exclusions`, the state is kept in statics for the keyboard hook.
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// What to do while Windows' autocorrect is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OsAutocorrectPolicy {
+    /// Tell the user once, and keep correcting.
+    Warn,
+    /// Pause corrections in the configured apps and let Windows correct.
+    Yield,
+    /// Keep correcting without a warning.
+    Ignore,
+}
+
+/// Registry key holding the Settings > Typing switches.
+#[cfg(windows)]
+const SETTINGS_KEY: &str = r"Software\Microsoft\Input\Settings";
+
+/// "Autocorrect misspelled words as I type" for hardware keyboards.
+#[cfg(windows)]
+const AUTOCORRECT_VALUE: &str = "EnableHwkbAutocorrection";
+
+/// Whether Windows' autocorrect was on at the last check.
+static OS_AUTOCORRECT: AtomicBool = AtomicBool::new(false);
+
+/// Whether the policy is `yield`.
+static YIELD: AtomicBool = AtomicBool::new(false);
+
+/// `[apps] yield_to_os_autocorrect`, lowercase.
+static YIELD_APPS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Whether the foreground app is one to yield in.
+static YIELD_APP_IN_FRONT: AtomicBool = AtomicBool::new(false);
+
+/// Set the policy and the apps to yield in (all if empty).
+pub fn configure<S: AsRef<str>>(policy: OsAutocorrectPolicy, apps: &[S]) {
+    YIELD.store(policy == OsAutocorrectPolicy::Yield, Ordering::Relaxed);
+    *YIELD_APPS.write() = apps
+        .iter()
+        .map(|app| app.as_ref().trim().to_lowercase())
+        .filter(|app| !app.is_empty())
+        .collect();
+}
+
+/// Whether corrections yield in `process_name` given the configured apps.
+pub fn yields_in<S: AsRef<str>>(process_name: Option<&str>, apps: &[S]) -> bool {
+    if apps.is_empty() {
+        return true;
+    }
+    process_name.is_some_and(|name| {
+        apps.iter()
+            .any(|app| app.as_ref().eq_ignore_ascii_case(name))
+    })
+}
+
+/// Update the yield state after the foreground app changed.
+pub fn set_foreground_app(process_name: Option<&str>) {
+    let yields = yields_in(process_name, &YIELD_APPS.read());
+    YIELD_APP_IN_FRONT.store(yields, Ordering::Relaxed);
+}
+
+/// Read Windows' autocorrect switch again.
+///
+/// Returns `true` if it was just turned on (or found on at the first check).
+pub fn refresh() -> bool {
+    let enabled = os_autocorrect_enabled();
+    !OS_AUTOCORRECT.swap(enabled, Ordering::Relaxed) && enabled
+}
+
+/// Whether corrections should pause in the foreground app to let Windows
+/// correct instead.
+pub fn should_yield() -> bool {
+    YIELD.load(Ordering::Relaxed)
+        && OS_AUTOCORRECT.load(Ordering::Relaxed)
+        && YIELD_APP_IN_FRONT.load(Ordering::Relaxed)
+}
+
+/// The warning shown when Windows' autocorrect is found on.
+pub const WARNING: &str = "Windows' own autocorrect is on (Settings > Time & language > \
+Typing > \"Autocorrect misspelled words as I type\"). In apps where it works, typos may be \
+corrected twice, duplicating or undoing the fix.\n\nTurn one of the two off, or set \
+os_autocorrect = \"yield\" in the settings file to let Windows correct.";
+
+#[cfg(windows)]
+fn os_autocorrect_enabled() -> bool {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::winreg::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+    let key: Vec<u16> = OsStr::new(SETTINGS_KEY)
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let value_name: Vec<u16> = OsStr::new(AUTOCORRECT_VALUE)
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key.as_ptr(),
+            value_name.as_ptr(),
+            RRF_RT_REG_DWORD,
+            std::ptr::null_mut(),
+            &mut value as *mut u32 as *mut _,
+            &mut size,
+        )
+    };
+    // A missing value means the switch was never turned on
+    status == 0 && value != 0
+}
+
+#[cfg(not(windows))]
+fn os_autocorrect_enabled() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yields_in() {
+        let none: [&str; 0] = [];
+        assert!(yields_in(Some("notepad.exe"), &none));
+        assert!(yields_in(None, &none));
+
+        let apps = ["notepad.exe"];
+        assert!(yields_in(Some("Notepad.exe"), &apps));
+        assert!(!yields_in(Some("winword.exe"), &apps));
+        assert!(!yields_in(None, &apps));
+    }
+}
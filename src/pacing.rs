@@ -0,0 +1,247 @@
+//! Per-app tuning of the delay between injected keystrokes.
+//!
+//! Fast native apps accept corrections with no delay at all, while some
+//! Electron apps drop keys unless they're spaced out. Instead of one delay
+//! per app type, each process starts at its app type's delay and is tuned
+//! from how its corrections turn out:
+//!
+//! - **Failure**: a keystroke could not be injected, or the user pressed
+//!   Backspace right after the correction (cleaning up garbled text). The
+//!   delay doubles, up to [`MAX_DELAY_MS`].
+//! - **Success**: the user carried on typing. After [`SPEEDUP_AFTER`]
+//!   successes in a row, the delay drops by a millisecond.
+//!
+//! Ctrl+Z is not a failure: it means the user didn't want the word, not that
+//! it arrived garbled.
+//!
+//! # File Format
+//!
+//! Tuned delays are machine-specific and stored in
+//! `%LOCALAPPDATA%/Autocorrect/key_delays.txt`:
+//! ```text
+//! # process delay_ms successes
+//! notepad.exe 0 7
+//! ```
+
+use ahash::AHashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Longest delay tuning will go to, in milliseconds.
+pub const MAX_DELAY_MS: u64 = 40;
+
+/// Successes in a row before the delay is lowered.
+pub const SPEEDUP_AFTER: u32 = 20;
+
+/// How long after a correction a Backspace counts as cleaning it up.
+const VERIFY_WINDOW: Duration = Duration::from_millis(1500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TunedDelay {
+    delay_ms: u64,
+    /// Successes since the delay last changed.
+    successes: u32,
+}
+
+/// A correction waiting to be judged by the user's next key.
+#[derive(Debug, Clone)]
+struct PendingCheck {
+    process: String,
+    default_ms: u64,
+    at: Instant,
+}
+
+/// Learned per-process key delays.
+pub struct KeyPacing {
+    delays: AHashMap<String, TunedDelay>,
+    path: PathBuf,
+    pending: Option<PendingCheck>,
+    /// A delay changed since the last flush.
+    unsaved: bool,
+}
+
+impl KeyPacing {
+    /// Create an empty store backed by the default file location.
+    pub fn new() -> Self {
+        Self::with_path(crate::paths::local_file(crate::paths::KEY_DELAYS_FILE))
+    }
+
+    /// Create an empty store backed by a specific file.
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            delays: AHashMap::new(),
+            path,
+            pending: None,
+            unsaved: false,
+        }
+    }
+
+    /// Load tuned delays from disk. A missing file is not an error.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read.
+    pub fn load(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+
+        let reader = BufReader::new(File::open(&self.path)?);
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [process, delay_ms, successes] = parts[..] else {
+                continue;
+            };
+            let (Ok(delay_ms), Ok(successes)) = (delay_ms.parse::<u64>(), successes.parse()) else {
+                continue;
+            };
+            self.delays.insert(
+                process.to_lowercase(),
+                TunedDelay {
+                    delay_ms: delay_ms.min(MAX_DELAY_MS),
+                    successes,
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Write tuned delays to disk, if any changed since the last flush.
+    ///
+    /// Tuning runs in the keyboard hook, so it only marks the store changed,
+    /// and this is called off it.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.unsaved {
+            return Ok(());
+        }
+
+        let mut contents = Vec::new();
+        writeln!(contents, "# Tuned key delays (managed by Autocorrect)")?;
+        writeln!(contents, "# process delay_ms successes")?;
+        for (process, tuned) in &self.delays {
+            writeln!(
+                contents,
+                "{} {} {}",
+                process, tuned.delay_ms, tuned.successes
+            )?;
+        }
+        crate::paths::write_atomically(&self.path, &contents)?;
+        self.unsaved = false;
+        Ok(())
+    }
+
+    /// Delay to use for a process, or `default_ms` if it hasn't been tuned.
+    pub fn delay_ms(&self, process: &str, default_ms: u64) -> u64 {
+        self.delays
+            .get(&process.to_lowercase())
+            .map_or(default_ms, |tuned| tuned.delay_ms)
+    }
+
+    /// Judge a correction just typed into `process`.
+    ///
+    /// If injection already failed it's a failure right away; otherwise the
+    /// user's next key decides (see [`KeyPacing::on_user_key`]).
+    pub fn after_correction(&mut self, process: &str, default_ms: u64, injection_failed: bool) {
+        if injection_failed {
+            self.pending = None;
+            self.record(process, default_ms, false);
+        } else {
+            self.pending = Some(PendingCheck {
+                process: process.to_lowercase(),
+                default_ms,
+                at: Instant::now(),
+            });
+        }
+    }
+
+    /// Settle a pending correction with a key the user pressed (not one we
+    /// injected).
+    pub fn on_user_key(&mut self, is_backspace: bool, now: Instant) {
+        let Some(check) = self.pending.take() else {
+            return;
+        };
+        let cleaned_up = is_backspace && now.saturating_duration_since(check.at) < VERIFY_WINDOW;
+        self.record(&check.process, check.default_ms, !cleaned_up);
+    }
+
+    /// Forget a pending correction (e.g. focus moved elsewhere).
+    pub fn cancel_pending(&mut self) {
+        self.pending = None;
+    }
+
+    fn record(&mut self, process: &str, default_ms: u64, success: bool) {
+        let tuned = self
+            .delays
+            .entry(process.to_lowercase())
+            .or_insert(TunedDelay {
+                delay_ms: default_ms,
+                successes: 0,
+            });
+        let before = tuned.delay_ms;
+
+        if success {
+            tuned.successes += 1;
+            if tuned.successes >= SPEEDUP_AFTER {
+                tuned.delay_ms = tuned.delay_ms.saturating_sub(1);
+                tuned.successes = 0;
+            }
+        } else {
+            tuned.delay_ms = (tuned.delay_ms * 2).clamp(2, MAX_DELAY_MS);
+            tuned.successes = 0;
+        }
+
+        if tuned.delay_ms != before {
+            println!(
+                "Key delay for {} tuned from {} ms to {} ms",
+                process, before, tuned.delay_ms
+            );
+            self.unsaved = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tuning() {
+        let path =
+            std::env::temp_dir().join(format!("autocorrect-pacing-{}.txt", std::process::id()));
+        let mut pacing = KeyPacing::with_path(path.clone());
+        assert_eq!(pacing.delay_ms("Slack.exe", 10), 10);
+
+        // Backspace right after a correction doubles the delay
+        pacing.after_correction("Slack.exe", 10, false);
+        pacing.on_user_key(true, Instant::now());
+        assert_eq!(pacing.delay_ms("slack.exe", 10), 20);
+
+        // A run of successes lowers it again
+        for _ in 0..SPEEDUP_AFTER {
+            pacing.after_correction("slack.exe", 10, false);
+            pacing.on_user_key(false, Instant::now());
+        }
+        assert_eq!(pacing.delay_ms("slack.exe", 10), 19);
+
+        pacing.after_correction("slack.exe", 10, true);
+        assert_eq!(pacing.delay_ms("slack.exe", 10), 38);
+
+        pacing.flush().unwrap();
+        let mut reloaded = KeyPacing::with_path(path.clone());
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.delay_ms("slack.exe", 10), 38);
+
+        let _ = std::fs::remove_file(path);
+    }
+}
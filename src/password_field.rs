@@ -0,0 +1,269 @@
+//! Detection of password fields inside ordinary apps.
+//!
+//! Standard Win32 edit controls (and rich edit and WinForms text boxes,
+//! which are built on them) mark password input with the `ES_PASSWORD`
+//! style. While such a control has keyboard focus, the corrector is paused,
+//! so nothing typed into it is tracked or replaced. The focused control is
+//! checked again on every focus change, since tabbing from a user name to a
+//! password field doesn't change the foreground window.
+//!
+//! Some toolkits draw their own password boxes as windows of a class of
+//! their own, without the style; those classes are listed under
+//! `[apps] password_fields`. Controls drawn inside a window of the app
+//! (browsers, WPF, UWP) only report password fields through UI Automation,
+//! so the focused element's `IsPassword` property is asked for too. That
+//! call can block on a hung app, so it's made on a thread of its own and
+//! its answer arrives a moment after the focus change.
+//!
+//! Like `secure_input`, the state is kept in statics for the keyboard hook.
+
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// `ES_PASSWORD`, in the edit control style bits.
+const ES_PASSWORD: u32 = 0x0020;
+
+/// Whether the focused control is a password field.
+static PASSWORD_FIELD: AtomicBool = AtomicBool::new(false);
+
+/// Whether UI Automation reported the focused element as a password field.
+static UIA_PASSWORD_FIELD: AtomicBool = AtomicBool::new(false);
+
+/// `[apps] password_fields`, lowercase.
+static PASSWORD_CLASSES: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Set the window classes that are password fields whatever their style.
+pub fn set_password_classes<S: AsRef<str>>(classes: &[S]) {
+    *PASSWORD_CLASSES.write() = classes
+        .iter()
+        .map(|class| class.as_ref().trim().to_lowercase())
+        .filter(|class| !class.is_empty())
+        .collect();
+}
+
+/// Whether a control with this class and style is a password field, given
+/// the classes listed as password fields.
+///
+/// The style bit means something else for other controls (`BS_LEFTTEXT` on
+/// buttons), so only edit controls count by style.
+pub fn is_password_control(class_name: &str, style: u32, password_classes: &[String]) -> bool {
+    let class_name = class_name.to_lowercase();
+    (class_name.contains("edit") && style & ES_PASSWORD != 0)
+        || password_classes.contains(&class_name)
+}
+
+/// Check the control with keyboard focus again (after a focus change).
+pub fn refresh() {
+    let password = focused_control().is_some_and(|(class_name, style)| {
+        is_password_control(&class_name, style, &PASSWORD_CLASSES.read())
+    });
+    PASSWORD_FIELD.store(password, Ordering::Relaxed);
+    #[cfg(windows)]
+    uia::request();
+}
+
+/// Whether a password field has keyboard focus.
+pub fn is_active() -> bool {
+    PASSWORD_FIELD.load(Ordering::Relaxed) || UIA_PASSWORD_FIELD.load(Ordering::Relaxed)
+}
+
+/// Class name and style of the control with keyboard focus in the
+/// foreground window.
+#[cfg(windows)]
+fn focused_control() -> Option<(String, u32)> {
+    use winapi::um::winuser::{
+        GetClassNameW, GetForegroundWindow, GetGUIThreadInfo, GetWindowLongW,
+        GetWindowThreadProcessId, GUITHREADINFO, GWL_STYLE,
+    };
+
+    unsafe {
+        let foreground = GetForegroundWindow();
+        if foreground.is_null() {
+            return None;
+        }
+        let thread_id = GetWindowThreadProcessId(foreground, std::ptr::null_mut());
+
+        let mut info: GUITHREADINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<GUITHREADINFO>() as u32;
+        if GetGUIThreadInfo(thread_id, &mut info) == 0 || info.hwndFocus.is_null() {
+            return None;
+        }
+
+        let mut buffer = [0u16; 256];
+        let len = GetClassNameW(info.hwndFocus, buffer.as_mut_ptr(), buffer.len() as i32);
+        if len <= 0 {
+            return None;
+        }
+        let class_name = String::from_utf16_lossy(&buffer[..len as usize]);
+        let style = GetWindowLongW(info.hwndFocus, GWL_STYLE) as u32;
+        Some((class_name, style))
+    }
+}
+
+#[cfg(not(windows))]
+fn focused_control() -> Option<(String, u32)> {
+    None
+}
+
+/// The UI Automation client. winapi has no bindings for it, so the two
+/// interfaces are declared here up to the last method used.
+#[cfg(windows)]
+#[allow(non_snake_case, dead_code)]
+mod uia {
+    use super::UIA_PASSWORD_FIELD;
+    use std::ptr::null_mut;
+    use std::sync::atomic::Ordering;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::sync::OnceLock;
+    use winapi::ctypes::c_void;
+    use winapi::shared::minwindef::BOOL;
+    use winapi::shared::windef::POINT;
+    use winapi::shared::wtypes::VT_BOOL;
+    use winapi::shared::wtypesbase::CLSCTX_INPROC_SERVER;
+    use winapi::um::combaseapi::{CoCreateInstance, CoInitializeEx};
+    use winapi::um::oaidl::VARIANT;
+    use winapi::um::objbase::COINIT_MULTITHREADED;
+    use winapi::um::oleauto::VariantClear;
+    use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+    use winapi::um::winnt::HRESULT;
+    use winapi::{Class, Interface, RIDL};
+
+    /// `UIA_IsPasswordPropertyId`.
+    const IS_PASSWORD_PROPERTY: i32 = 30019;
+
+    RIDL! {#[uuid(0xff48dba4, 0x60ef, 0x4201, 0xaa, 0x87, 0x54, 0x10, 0x3e, 0xef, 0x59, 0x4e)]
+    class CUIAutomation;}
+
+    RIDL! {#[uuid(0xd22108aa, 0x8ac5, 0x49a5, 0x83, 0x7b, 0x37, 0xbb, 0xb3, 0xd7, 0x59, 0x1e)]
+    interface IUIAutomationElement(IUIAutomationElementVtbl): IUnknown(IUnknownVtbl) {
+        fn SetFocus() -> HRESULT,
+        fn GetRuntimeId(runtime_id: *mut *mut c_void,) -> HRESULT,
+        fn FindFirst(
+            scope: i32,
+            condition: *mut c_void,
+            found: *mut *mut IUIAutomationElement,
+        ) -> HRESULT,
+        fn FindAll(scope: i32, condition: *mut c_void, found: *mut *mut c_void,) -> HRESULT,
+        fn FindFirstBuildCache(
+            scope: i32,
+            condition: *mut c_void,
+            cache_request: *mut c_void,
+            found: *mut *mut IUIAutomationElement,
+        ) -> HRESULT,
+        fn FindAllBuildCache(
+            scope: i32,
+            condition: *mut c_void,
+            cache_request: *mut c_void,
+            found: *mut *mut c_void,
+        ) -> HRESULT,
+        fn BuildUpdatedCache(
+            cache_request: *mut c_void,
+            updated: *mut *mut IUIAutomationElement,
+        ) -> HRESULT,
+        fn GetCurrentPropertyValue(property_id: i32, value: *mut VARIANT,) -> HRESULT,
+    }}
+
+    RIDL! {#[uuid(0x30cbe57d, 0xd9d0, 0x452a, 0xab, 0x13, 0x7a, 0xc5, 0xac, 0x48, 0x25, 0xee)]
+    interface IUIAutomation(IUIAutomationVtbl): IUnknown(IUnknownVtbl) {
+        fn CompareElements(
+            first: *mut IUIAutomationElement,
+            second: *mut IUIAutomationElement,
+            are_same: *mut BOOL,
+        ) -> HRESULT,
+        fn CompareRuntimeIds(
+            first: *mut c_void,
+            second: *mut c_void,
+            are_same: *mut BOOL,
+        ) -> HRESULT,
+        fn GetRootElement(root: *mut *mut IUIAutomationElement,) -> HRESULT,
+        fn ElementFromHandle(
+            hwnd: *mut c_void,
+            element: *mut *mut IUIAutomationElement,
+        ) -> HRESULT,
+        fn ElementFromPoint(
+            point: POINT,
+            element: *mut *mut IUIAutomationElement,
+        ) -> HRESULT,
+        fn GetFocusedElement(element: *mut *mut IUIAutomationElement,) -> HRESULT,
+    }}
+
+    /// Requests to check the focused element, read by the UI Automation
+    /// thread.
+    static REQUESTS: OnceLock<Sender<()>> = OnceLock::new();
+
+    /// Have the focused element checked, starting the UI Automation thread
+    /// the first time.
+    pub fn request() {
+        let requests = REQUESTS.get_or_init(|| {
+            let (sender, receiver) = channel();
+            std::thread::spawn(move || unsafe { run(receiver) });
+            sender
+        });
+        let _ = requests.send(());
+    }
+
+    unsafe fn run(requests: Receiver<()>) {
+        CoInitializeEx(null_mut(), COINIT_MULTITHREADED);
+        let mut automation: *mut IUIAutomation = null_mut();
+        let created = CoCreateInstance(
+            &CUIAutomation::uuidof(),
+            null_mut(),
+            CLSCTX_INPROC_SERVER,
+            &IUIAutomation::uuidof(),
+            &mut automation as *mut *mut IUIAutomation as *mut *mut c_void,
+        );
+        if created < 0 || automation.is_null() {
+            eprintln!("UI Automation is unavailable; only standard password boxes are detected");
+            return;
+        }
+
+        while requests.recv().is_ok() {
+            // Only the element focused last matters
+            while requests.try_recv().is_ok() {}
+            let password = focused_is_password(&*automation);
+            UIA_PASSWORD_FIELD.store(password, Ordering::Relaxed);
+        }
+    }
+
+    unsafe fn focused_is_password(automation: &IUIAutomation) -> bool {
+        let mut element: *mut IUIAutomationElement = null_mut();
+        if automation.GetFocusedElement(&mut element) < 0 || element.is_null() {
+            return false;
+        }
+        let mut value: VARIANT = std::mem::zeroed();
+        let password = (*element).GetCurrentPropertyValue(IS_PASSWORD_PROPERTY, &mut value) >= 0
+            && value.n1.n2().vt == VT_BOOL as u16
+            && *value.n1.n2().n3.boolVal() != 0;
+        VariantClear(&mut value);
+        (*element).Release();
+        password
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_password_control() {
+        assert!(is_password_control("Edit", 0x5001_00A0, &[]));
+        assert!(is_password_control("RICHEDIT50W", ES_PASSWORD, &[]));
+        assert!(is_password_control(
+            "WindowsForms10.EDIT.app.0.141b42a_r6_ad1",
+            ES_PASSWORD,
+            &[]
+        ));
+        assert!(!is_password_control("Edit", 0x5001_0080, &[]));
+        // BS_LEFTTEXT on a check box
+        assert!(!is_password_control("Button", 0x5001_0023, &[]));
+
+        // A listed class counts without the style
+        let listed = vec!["securepasswordbox".to_string()];
+        assert!(is_password_control(
+            "SecurePasswordBox",
+            0x5001_0000,
+            &listed
+        ));
+        assert!(!is_password_control("SecureTextBox", 0x5001_0000, &listed));
+    }
+}
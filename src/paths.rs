@@ -0,0 +1,307 @@
+//! Locations of per-user data files.
+//!
+//! Data is split by whether it should follow the user between machines:
+//!
+//! - **Roaming** (`%APPDATA%/Autocorrect/`): the personal dictionary, the
+//!   never-correct list, personal corrections, learned frequencies, undone
+//!   corrections and custom grammar rules. These are small and are what the
+//!   user would miss on another machine.
+//! - **Local** (`%LOCALAPPDATA%/Autocorrect/`): language packs, the status
+//!   file, the event stream, the mistake log and the typing report. These are
+//!   large, change constantly or can be regenerated, so roaming them only
+//!   slows down sign-in.
+//!
+//! If an environment variable is not set, files are placed in the current
+//! directory (or the roaming directory, for local files) instead. Files
+//! written to the roaming directory by older versions are moved by
+//! [`migrate_local_data`].
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Name of the per-user data folder inside `%APPDATA%` and `%LOCALAPPDATA%`.
+const DATA_DIR_NAME: &str = "Autocorrect";
+
+/// Words the user never wants corrected.
+pub const PERSONAL_DICTIONARY_FILE: &str = "personal_dictionary.txt";
+/// Words that are never corrected, though not suggested either.
+pub const NEVER_CORRECT_FILE: &str = "never_correct.txt";
+/// Typos and the exact text that replaces them.
+pub const PERSONAL_CORRECTIONS_FILE: &str = "personal_corrections.txt";
+/// Decaying usage counts of typed words.
+pub const LEARNED_FREQUENCIES_FILE: &str = "learned_frequencies.txt";
+/// How often each correction was undone (see `undos`).
+pub const UNDONE_CORRECTIONS_FILE: &str = "undone_corrections.txt";
+/// Folder holding language pack dictionaries (local).
+pub const LANGUAGES_DIR: &str = "languages";
+/// Counters for `autocorrect status` (local).
+pub const STATUS_FILE: &str = "status.txt";
+/// Stream of corrections for external tools (`stream_events`, local).
+pub const EVENTS_FILE: &str = "events.jsonl";
+/// Per-day counts of corrections, for the typing report (local).
+pub const MISTAKES_FILE: &str = "mistakes.txt";
+/// The weekly typing report (local).
+pub const TYPING_REPORT_FILE: &str = "typing_report.html";
+/// Key delays tuned per app (local).
+pub const KEY_DELAYS_FILE: &str = "key_delays.txt";
+/// Context model trained on the user's documents (`train-lm`, local).
+pub const LANGUAGE_MODEL_FILE: &str = "language_model.bin";
+/// Folder holding correction provider plugins (`load_plugins`, local).
+pub const PLUGINS_DIR: &str = "plugins";
+/// Asks the running instance to reload its dictionaries (`autocorrect
+/// reload`, local).
+pub const RELOAD_REQUEST_FILE: &str = "reload_request";
+/// Asks the running instance to delete personal data (`autocorrect reset`,
+/// local).
+pub const RESET_REQUEST_FILE: &str = "reset_request";
+/// Bad corrections the user chose to keep on disk (local).
+pub const CORRECTION_REPORTS_FILE: &str = "correction_reports.md";
+
+/// Files and folders that belong in the local directory.
+const LOCAL_DATA: &[&str] = &[
+    LANGUAGES_DIR,
+    STATUS_FILE,
+    EVENTS_FILE,
+    MISTAKES_FILE,
+    TYPING_REPORT_FILE,
+    KEY_DELAYS_FILE,
+];
+
+/// `%<var>%/Autocorrect`, created if it doesn't exist yet.
+fn app_dir(var: &str) -> Option<PathBuf> {
+    let mut path = env_dir(var)?;
+    path.push(DATA_DIR_NAME);
+
+    // Create directory if it doesn't exist
+    if !path.exists() {
+        let _ = std::fs::create_dir_all(&path);
+    }
+
+    Some(path)
+}
+
+/// The folder in environment variable `var`.
+///
+/// Unit tests get a folder of their own in the temp directory instead, so
+/// loading a dictionary or counting an undo never reads or writes the
+/// user's data, nor leaves files in the current directory.
+fn env_dir(var: &str) -> Option<PathBuf> {
+    if cfg!(test) {
+        let root = format!("autocorrect-test-{}", std::process::id());
+        return Some(std::env::temp_dir().join(root).join(var));
+    }
+    std::env::var_os(var).map(PathBuf::from)
+}
+
+/// Get the directory holding roaming per-user data files.
+///
+/// Creates `%APPDATA%/Autocorrect` if it doesn't exist yet.
+pub fn data_dir() -> PathBuf {
+    // Fallback to current directory
+    app_dir("APPDATA").unwrap_or_default()
+}
+
+/// Get the full path of a named file in the roaming data directory.
+pub fn data_file(file_name: &str) -> PathBuf {
+    data_dir().join(file_name)
+}
+
+/// Get the directory holding machine-local data files.
+///
+/// Creates `%LOCALAPPDATA%/Autocorrect` if it doesn't exist yet.
+pub fn local_dir() -> PathBuf {
+    app_dir("LOCALAPPDATA").unwrap_or_else(data_dir)
+}
+
+/// Get the full path of a named file in the local data directory.
+pub fn local_file(file_name: &str) -> PathBuf {
+    local_dir().join(file_name)
+}
+
+/// Move local data left in the roaming directory by older versions.
+///
+/// Returns a message for each file that could not be moved.
+pub fn migrate_local_data() -> Vec<String> {
+    move_entries(&data_dir(), &local_dir(), LOCAL_DATA)
+}
+
+/// Move the named files or folders from `from` to `to`, leaving any that
+/// already exist in `to` alone.
+fn move_entries(from: &Path, to: &Path, names: &[&str]) -> Vec<String> {
+    let mut errors = Vec::new();
+    if from == to {
+        return errors;
+    }
+
+    for name in names {
+        let (old, new) = (from.join(name), to.join(name));
+        if !old.exists() || new.exists() {
+            continue;
+        }
+        if let Err(e) = std::fs::rename(&old, &new) {
+            errors.push(format!(
+                "Failed to move {} to {}: {}",
+                old.display(),
+                new.display(),
+                e
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Path of the personal dictionary file.
+pub fn personal_dictionary() -> PathBuf {
+    data_file(PERSONAL_DICTIONARY_FILE)
+}
+
+/// Replace a file's contents without ever leaving it half-written.
+///
+/// The contents go to a temporary file next to `path`, which then replaces
+/// it, so a crash mid-write leaves either the old file or the new one.
+///
+/// # Errors
+/// Returns an error if the temporary file cannot be written or renamed.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+
+    let mut file = std::fs::File::create(&temp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&temp, path)
+}
+
+/// Every per-user data file that may contain personal information.
+///
+/// Anything added here is removed by `autocorrect reset --personal-data`.
+pub fn personal_data_files() -> Vec<PathBuf> {
+    personal_data_files_in(&data_dir(), &local_dir())
+}
+
+/// The personal data files in a roaming and a local directory.
+fn personal_data_files_in(roaming: &Path, local: &Path) -> Vec<PathBuf> {
+    let roaming = [
+        PERSONAL_DICTIONARY_FILE,
+        NEVER_CORRECT_FILE,
+        PERSONAL_CORRECTIONS_FILE,
+        LEARNED_FREQUENCIES_FILE,
+        UNDONE_CORRECTIONS_FILE,
+    ]
+    .map(|name| roaming.join(name));
+    let local = [
+        STATUS_FILE,
+        EVENTS_FILE,
+        MISTAKES_FILE,
+        TYPING_REPORT_FILE,
+        KEY_DELAYS_FILE,
+        LANGUAGE_MODEL_FILE,
+        CORRECTION_REPORTS_FILE,
+    ]
+    .map(|name| local.join(name));
+    roaming.into_iter().chain(local).collect()
+}
+
+/// Delete all personal data files.
+///
+/// Missing files are skipped. Returns the files that were actually removed.
+///
+/// # Errors
+/// Returns an error if an existing file cannot be deleted.
+pub fn remove_personal_data() -> std::io::Result<Vec<PathBuf>> {
+    remove_files(personal_data_files())
+}
+
+/// Delete the files that exist of `paths`, returning those.
+fn remove_files(paths: Vec<PathBuf>) -> std::io::Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+
+    for path in paths {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+            removed.push(path);
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_entries() {
+        let root = std::env::temp_dir().join(format!("autocorrect-paths-{}", std::process::id()));
+        let (roaming, local) = (root.join("roaming"), root.join("local"));
+        std::fs::create_dir_all(roaming.join(LANGUAGES_DIR)).unwrap();
+        std::fs::create_dir_all(&local).unwrap();
+        std::fs::write(roaming.join(LANGUAGES_DIR).join("fr.txt"), "bonjour 1").unwrap();
+        std::fs::write(roaming.join(STATUS_FILE), "old").unwrap();
+        std::fs::write(local.join(STATUS_FILE), "new").unwrap();
+        std::fs::write(roaming.join(PERSONAL_DICTIONARY_FILE), "myname").unwrap();
+
+        let errors = move_entries(&roaming, &local, LOCAL_DATA);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(local.join(LANGUAGES_DIR).join("fr.txt").exists());
+        assert!(!roaming.join(LANGUAGES_DIR).exists());
+        // Newer local files win; roaming data stays put
+        assert_eq!(
+            std::fs::read_to_string(local.join(STATUS_FILE)).unwrap(),
+            "new"
+        );
+        assert!(roaming.join(PERSONAL_DICTIONARY_FILE).exists());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_remove_personal_data() {
+        let root = std::env::temp_dir().join(format!("autocorrect-reset-{}", std::process::id()));
+        let (roaming, local) = (root.join("roaming"), root.join("local"));
+        std::fs::create_dir_all(local.join(LANGUAGES_DIR)).unwrap();
+        std::fs::create_dir_all(&roaming).unwrap();
+        std::fs::write(roaming.join(LEARNED_FREQUENCIES_FILE), "teh 1").unwrap();
+        std::fs::write(local.join(STATUS_FILE), "updated 0").unwrap();
+        std::fs::write(local.join(MISTAKES_FILE), "teh the 1").unwrap();
+        std::fs::write(local.join(LANGUAGES_DIR).join("fr.txt"), "bonjour 1").unwrap();
+
+        let removed = remove_files(personal_data_files_in(&roaming, &local)).unwrap();
+        assert_eq!(removed.len(), 3);
+        assert!(!roaming.join(LEARNED_FREQUENCIES_FILE).exists());
+        // Session and hook counters go with the rest
+        assert!(!local.join(STATUS_FILE).exists());
+        assert!(!local.join(MISTAKES_FILE).exists());
+        // Language packs aren't personal
+        assert!(local.join(LANGUAGES_DIR).join("fr.txt").exists());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_tests_use_temp_dirs() {
+        assert!(data_dir().starts_with(std::env::temp_dir()));
+        assert!(local_file(STATUS_FILE).starts_with(std::env::temp_dir()));
+        assert_ne!(data_dir(), local_dir());
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let path =
+            std::env::temp_dir().join(format!("autocorrect-atomic-{}.txt", std::process::id()));
+        std::fs::write(&path, "old contents").unwrap();
+
+        write_atomically(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+        assert!(!PathBuf::from(temp).exists());
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,616 @@
+//! The stages that decide whether, and how, a finished word is corrected.
+//!
+//! Each stage implements [`Stage`] and runs in the order given by
+//! `[pipeline] stages`:
+//!
+//! - **rules**: explicit fixes that need no lookup: the user's personal
+//!   corrections ("alot" -> "a lot"), canonical casing ("github" ->
+//!   "GitHub"), then ordinal suffixes ("2end" -> "2nd")
+//! - **ignore**: leave alone words ignored this session, word types
+//!   configured to be skipped (names, acronyms, ordinals), and words of
+//!   another installed language
+//! - **providers**: ask plugin correction providers (see `plugins`), in
+//!   order, for the word's replacement
+//! - **elongated**: the word a drawn-out one stands for ("soooo" -> "so"),
+//!   with its repeated letters collapsed, when `[word_types] elongated` is
+//!   set to be corrected
+//! - **diacritics**: the accented word typed without its accents ("cafe"
+//!   -> "café"), for languages listed in `restore_diacritics`
+//! - **lookup**: SymSpell suggestions for the word
+//! - **rerank**: reorder suggestions using the surrounding words, when a
+//!   context model is loaded
+//! - **gate**: accept a suggestion only if the ranking policy trusts it, and
+//!   nothing during the correction cooldown
+//! - **compound** (not run by default): when no suggestion was accepted,
+//!   split a word typed without its space into two words ("inthe" -> "in
+//!   the")
+//! - **veto**: let plugin providers refuse the chosen correction
+//!
+//! Stages share a [`PipelineState`]: once one proposes a correction, later
+//! ones that look for corrections leave it be, while filters can still
+//! veto it. No stage runs while the bypass key is held, so the word isn't
+//! even looked up or shown to plugins. Typing the result (or logging it, in
+//! dry-run mode) is done by the corrector after the last stage.
+//!
+//! New checks (snippets, phonetic matching) are added as a [`StageKind`]
+//! and a [`Stage`] implementation rather than more branches in the corrector.
+
+use crate::classify::{TokenClass, WordPolicy};
+use crate::config::WordTypesConfig;
+use crate::dictionary::Dictionary;
+use crate::plugins::CorrectionProvider;
+use crate::ranking::RankingPolicy;
+use crate::symspell::SuggestItem;
+use crate::timing::TimingPolicy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Instant;
+
+/// The built-in stages, as named in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StageKind {
+    Rules,
+    Ignore,
+    Providers,
+    Elongated,
+    Diacritics,
+    Lookup,
+    Rerank,
+    Gate,
+    Compound,
+    Veto,
+}
+
+impl StageKind {
+    /// Default order: cheap explicit rules and filters before the lookup.
+    ///
+    /// `Compound` is left out: real words missing from the dictionary
+    /// ("doorknob", "hashtag", "blogpost") split into two known ones, so
+    /// it's opt-in.
+    pub const DEFAULT_ORDER: [StageKind; 9] = [
+        StageKind::Rules,
+        StageKind::Ignore,
+        StageKind::Providers,
+        StageKind::Elongated,
+        StageKind::Diacritics,
+        StageKind::Lookup,
+        StageKind::Rerank,
+        StageKind::Gate,
+        StageKind::Veto,
+    ];
+
+    fn build(self) -> Box<dyn Stage + Send> {
+        match self {
+            StageKind::Rules => Box::new(Rules),
+            StageKind::Ignore => Box::new(Ignore),
+            StageKind::Providers => Box::new(Providers),
+            StageKind::Elongated => Box::new(Elongated),
+            StageKind::Diacritics => Box::new(Diacritics),
+            StageKind::Lookup => Box::new(Lookup),
+            StageKind::Rerank => Box::new(Rerank),
+            StageKind::Gate => Box::new(Gate),
+            StageKind::Compound => Box::new(Compound),
+            StageKind::Veto => Box::new(Veto),
+        }
+    }
+}
+
+/// A word that just ended.
+pub struct WordInput<'a> {
+    /// As typed.
+    pub word: &'a str,
+    pub word_lower: &'a str,
+    pub class: TokenClass,
+    /// Digits typed directly before the word.
+    pub number: &'a str,
+    pub previous_word: Option<&'a str>,
+    /// A letter was typed by holding its key down.
+    pub auto_repeated: bool,
+}
+
+/// Corrector state the stages may consult.
+pub struct StageContext<'a> {
+    pub dictionary: &'a Dictionary,
+    pub ranking: &'a RankingPolicy,
+    pub word_types: &'a WordTypesConfig,
+    pub session_ignored: &'a HashSet<String>,
+    pub timing: &'a TimingPolicy,
+    pub last_correction: Option<Instant>,
+    pub now: Instant,
+    /// The bypass key was held while finishing the word.
+    pub bypass: bool,
+    /// Restore accents to words of the active language typed without them.
+    pub restore_diacritics: bool,
+    /// Plugin correction providers; empty while a sensitive app is in front.
+    pub providers: &'a [Box<dyn CorrectionProvider + Send>],
+}
+
+/// What the stages have worked out so far.
+#[derive(Debug, Default)]
+pub struct PipelineState {
+    /// Dictionary suggestions, best first.
+    pub suggestions: Vec<SuggestItem>,
+    /// The replacement, once a stage has settled on one.
+    pub correction: Option<String>,
+}
+
+/// Whether to run the remaining stages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    /// Leave the word as typed; later stages don't run.
+    Keep,
+}
+
+/// One step of the correction pipeline.
+pub trait Stage {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow;
+}
+
+/// An ordered list of stages.
+pub struct Pipeline {
+    stages: Vec<Box<dyn Stage + Send>>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new(&StageKind::DEFAULT_ORDER)
+    }
+}
+
+impl Pipeline {
+    pub fn new(order: &[StageKind]) -> Self {
+        Self {
+            stages: order.iter().map(|kind| kind.build()).collect(),
+        }
+    }
+
+    /// Run every stage in order; returns the correction, if any.
+    #[cfg(test)]
+    pub fn run(&self, input: &WordInput, context: &StageContext) -> Option<String> {
+        self.decide(input, context).correction
+    }
+
+    /// Run every stage in order; returns the correction along with the
+    /// suggestions it was chosen from. Empty if a stage kept the word, or
+    /// the bypass key was held.
+    pub fn decide(&self, input: &WordInput, context: &StageContext) -> PipelineState {
+        let mut state = PipelineState::default();
+        if context.bypass {
+            return state;
+        }
+        for stage in &self.stages {
+            if stage.run(input, context, &mut state) == Flow::Keep {
+                return PipelineState::default();
+            }
+        }
+        state
+    }
+}
+
+/// Personal corrections, canonical casing of known words, then ordinal
+/// suffix and number-word fixes (see `numbers`).
+struct Rules;
+
+impl Stage for Rules {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow {
+        if state.correction.is_none() {
+            state.correction = context.dictionary.personal_correction(input.word);
+        }
+        if state.correction.is_none() {
+            state.correction = context.dictionary.canonical_case(input.word);
+        }
+        if state.correction.is_none() && context.word_types.fix_numbers {
+            state.correction = crate::numbers::fix(input.number, input.previous_word, input.word);
+        }
+        Flow::Continue
+    }
+}
+
+/// Session-ignored words, skipped word types, foreign words (exact in
+/// another installed language pack), contractions and compounds of known
+/// words ("don't", "well-known"), and words with a held-down key that aren't
+/// drawn out (those are left to `Elongated`). Explicit rule fixes still
+/// apply to them: the dictionary can't tell "2end" is wrong, but a rule can.
+struct Ignore;
+
+impl Stage for Ignore {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow {
+        if state.correction.is_some() {
+            return Flow::Continue;
+        }
+        let skipped = context.session_ignored.contains(input.word_lower)
+            || (input.auto_repeated && input.class != TokenClass::Elongated)
+            || context.word_types.policy(input.class) == WordPolicy::Skip
+            || context
+                .dictionary
+                .foreign_language(input.word_lower)
+                .is_some()
+            || (!input.word.chars().all(char::is_alphabetic)
+                && context.dictionary.knows(input.word));
+        if skipped {
+            Flow::Keep
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+/// The first plugin provider that knows a replacement for the word.
+struct Providers;
+
+impl Stage for Providers {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow {
+        if state.correction.is_none() {
+            state.correction = context
+                .providers
+                .iter()
+                .find_map(|provider| provider.suggest(input.word, input.previous_word))
+                .filter(|suggestion| suggestion != input.word);
+        }
+        Flow::Continue
+    }
+}
+
+/// The most common dictionary word a drawn-out one becomes with each
+/// repeated letter typed twice or once ("yesss" -> "yes", "helllooo" ->
+/// "hello"). SymSpell can't get there itself: "soooo" is three edits from
+/// "so". One drawn out by holding a key down is never looked up: the
+/// letters are as meant, just too many of them.
+struct Elongated;
+
+impl Stage for Elongated {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow {
+        if state.correction.is_none() && input.class == TokenClass::Elongated {
+            state.correction = crate::classify::collapse_elongated(input.word)
+                .into_iter()
+                .filter_map(|spelling| {
+                    let frequency = context.dictionary.frequency(&spelling)?;
+                    Some((frequency, spelling))
+                })
+                .max_by_key(|(frequency, _)| *frequency)
+                .map(|(_, spelling)| spelling);
+            if state.correction.is_none() && input.auto_repeated {
+                return Flow::Keep;
+            }
+        }
+        Flow::Continue
+    }
+}
+
+/// The accented word typed plain on a keyboard without its accents
+/// ("cafe" -> "café"). SymSpell would find it one edit away, but as one
+/// candidate among many; the accented spelling of the very same letters is
+/// the one meant.
+struct Diacritics;
+
+impl Stage for Diacritics {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow {
+        if state.correction.is_none() && context.restore_diacritics {
+            state.correction = context.dictionary.restore_diacritics(input.word);
+        }
+        Flow::Continue
+    }
+}
+
+/// SymSpell suggestions, boosted by learned frequencies, in the typed word's
+/// case ("Teh" -> "The", "TEH" -> "THE").
+struct Lookup;
+
+impl Stage for Lookup {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow {
+        if state.correction.is_none() {
+            // The suggestions take on the word's capitalization
+            state.suggestions = context.dictionary.lookup(input.word);
+        }
+        Flow::Continue
+    }
+}
+
+/// Reorder suggestions by how well they fit after the previous word.
+struct Rerank;
+
+impl Stage for Rerank {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow {
+        if state.correction.is_none() {
+            context
+                .dictionary
+                .rerank_in_context(&mut state.suggestions, input.previous_word);
+        }
+        Flow::Continue
+    }
+}
+
+/// The ranking policy picks the suggestion to apply; nothing is applied
+/// during the correction cooldown.
+struct Gate;
+
+impl Stage for Gate {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow {
+        if context
+            .timing
+            .in_cooldown(context.last_correction, context.now)
+        {
+            return Flow::Keep;
+        }
+        if state.correction.is_none() {
+            state.correction = context
+                .ranking
+                .resolved(context.dictionary.has_context_model())
+                .choose(input.word_lower, &state.suggestions)
+                .map(|suggestion| suggestion.display_term.clone());
+        }
+        Flow::Continue
+    }
+}
+
+/// Two words run together, when nothing else corrected the word. Only
+/// splits into exact dictionary words are made, so a misspelled part is left
+/// alone.
+struct Compound;
+
+impl Stage for Compound {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow {
+        if state.correction.is_none() && !context.dictionary.contains(input.word_lower) {
+            state.correction = context
+                .dictionary
+                .lookup_compound(input.word)
+                .map(|suggestion| suggestion.display_term);
+        }
+        Flow::Continue
+    }
+}
+
+/// Any plugin provider can refuse the correction.
+struct Veto;
+
+impl Stage for Veto {
+    fn run(&self, input: &WordInput, context: &StageContext, state: &mut PipelineState) -> Flow {
+        let vetoed = state.correction.as_ref().is_some_and(|correction| {
+            context
+                .providers
+                .iter()
+                .any(|provider| provider.veto(input.word, correction))
+        });
+        if vetoed {
+            Flow::Keep
+        } else {
+            Flow::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The settings a test's stage context borrows, at their defaults.
+    #[derive(Default)]
+    struct Defaults {
+        ranking: RankingPolicy,
+        word_types: WordTypesConfig,
+        session_ignored: HashSet<String>,
+        timing: TimingPolicy,
+    }
+
+    impl Defaults {
+        /// A context for a word typed now, with no plugins loaded.
+        fn context<'a>(&'a self, dictionary: &'a Dictionary) -> StageContext<'a> {
+            StageContext {
+                dictionary,
+                ranking: &self.ranking,
+                word_types: &self.word_types,
+                session_ignored: &self.session_ignored,
+                timing: &self.timing,
+                last_correction: None,
+                now: Instant::now(),
+                bypass: false,
+                restore_diacritics: false,
+                providers: &[],
+            }
+        }
+    }
+
+    /// A company glossary: knows one product name, and protects another.
+    struct Glossary;
+
+    impl CorrectionProvider for Glossary {
+        fn name(&self) -> &str {
+            "glossary"
+        }
+
+        fn suggest(&self, word: &str, _previous_word: Option<&str>) -> Option<String> {
+            (word == "acmee").then(|| "Acme".to_string())
+        }
+
+        fn veto(&self, word: &str, _correction: &str) -> bool {
+            word == "teh"
+        }
+    }
+
+    #[test]
+    fn test_stage_order() {
+        let dictionary = Dictionary::new();
+        let mut session_ignored = HashSet::new();
+        session_ignored.insert("end".to_string());
+        let defaults = Defaults::default();
+        let context = StageContext {
+            session_ignored: &session_ignored,
+            ..defaults.context(&dictionary)
+        };
+        let input = WordInput {
+            word: "end",
+            word_lower: "end",
+            class: TokenClass::Word,
+            number: "2",
+            previous_word: None,
+            auto_repeated: false,
+        };
+
+        // Rule fixes apply even to ignored words...
+        let pipeline = Pipeline::default();
+        assert_eq!(pipeline.run(&input, &context).as_deref(), Some("nd"));
+
+        // ...unless the filter runs first
+        let filter_first = Pipeline::new(&[StageKind::Ignore, StageKind::Rules, StageKind::Gate]);
+        assert_eq!(filter_first.run(&input, &context), None);
+
+        // Holding the bypass key keeps even rule fixes out
+        let bypass = StageContext {
+            bypass: true,
+            ..context
+        };
+        assert_eq!(pipeline.run(&input, &bypass), None);
+        // ...whether or not a gate stage is configured
+        let rules_only = Pipeline::new(&[StageKind::Rules]);
+        assert_eq!(rules_only.run(&input, &context).as_deref(), Some("nd"));
+        assert_eq!(rules_only.run(&input, &bypass), None);
+
+        // Without the rules stage there is nothing to apply
+        let no_rules = Pipeline::new(&[StageKind::Lookup, StageKind::Gate]);
+        assert_eq!(no_rules.run(&input, &context), None);
+    }
+
+    #[test]
+    fn test_compound_opt_in() {
+        let mut dictionary = Dictionary::new();
+        dictionary.load().unwrap();
+        let defaults = Defaults::default();
+        let context = defaults.context(&dictionary);
+        let mut order = StageKind::DEFAULT_ORDER.to_vec();
+        order.insert(order.len() - 1, StageKind::Compound);
+        let compound = Pipeline::new(&order);
+        let corrected = |pipeline: &Pipeline, word: &str| {
+            let word_lower = word.to_lowercase();
+            pipeline.run(
+                &WordInput {
+                    word,
+                    word_lower: &word_lower,
+                    class: TokenClass::Word,
+                    number: "",
+                    previous_word: None,
+                    auto_repeated: false,
+                },
+                &context,
+            )
+        };
+
+        // A real word missing from the dictionary isn't split by default
+        assert_eq!(corrected(&Pipeline::default(), "doorknob"), None);
+        assert_eq!(
+            corrected(&compound, "doorknob").as_deref(),
+            Some("door knob")
+        );
+        assert_eq!(
+            corrected(&compound, "Helloworld").as_deref(),
+            Some("Hello world")
+        );
+    }
+
+    #[test]
+    fn test_providers() {
+        let mut dictionary = Dictionary::new();
+        dictionary.load().unwrap();
+        let providers: Vec<Box<dyn CorrectionProvider + Send>> = vec![Box::new(Glossary)];
+        let defaults = Defaults::default();
+        let context = StageContext {
+            providers: &providers,
+            ..defaults.context(&dictionary)
+        };
+        let input = |word| WordInput {
+            word,
+            word_lower: word,
+            class: TokenClass::Word,
+            number: "",
+            previous_word: None,
+            auto_repeated: false,
+        };
+        let pipeline = Pipeline::default();
+
+        assert_eq!(
+            pipeline.run(&input("acmee"), &context).as_deref(),
+            Some("Acme")
+        );
+        assert_eq!(pipeline.run(&input("teh"), &context), None);
+        assert_eq!(
+            pipeline.run(&input("recieve"), &context).as_deref(),
+            Some("receive")
+        );
+    }
+
+    #[test]
+    fn test_case_preserved() {
+        let mut dictionary = Dictionary::new();
+        dictionary.load().unwrap();
+        let defaults = Defaults::default();
+        let context = defaults.context(&dictionary);
+        let corrected = |word: &str| {
+            let word_lower = word.to_lowercase();
+            Pipeline::default().run(
+                &WordInput {
+                    word,
+                    word_lower: &word_lower,
+                    class: TokenClass::Word,
+                    number: "",
+                    previous_word: None,
+                    auto_repeated: false,
+                },
+                &context,
+            )
+        };
+
+        assert_eq!(corrected("recieve").as_deref(), Some("receive"));
+        assert_eq!(corrected("Recieve").as_deref(), Some("Receive"));
+        assert_eq!(corrected("RECIEVE").as_deref(), Some("RECEIVE"));
+
+        // Contractions and compounds of known words are left alone
+        assert_eq!(corrected("don't"), None);
+        assert_eq!(corrected("It's"), None);
+        assert_eq!(corrected("well-known"), None);
+    }
+
+    #[test]
+    fn test_elongated() {
+        let mut dictionary = Dictionary::new();
+        dictionary.load().unwrap();
+        let correct = WordTypesConfig {
+            elongated: WordPolicy::Correct,
+            ..WordTypesConfig::default()
+        };
+        let defaults = Defaults::default();
+        let context = StageContext {
+            word_types: &correct,
+            ..defaults.context(&dictionary)
+        };
+        let input = |word| WordInput {
+            word,
+            word_lower: word,
+            class: TokenClass::Elongated,
+            number: "",
+            previous_word: None,
+            auto_repeated: false,
+        };
+        let pipeline = Pipeline::default();
+
+        assert_eq!(
+            pipeline.run(&input("soooo"), &context).as_deref(),
+            Some("so")
+        );
+        assert_eq!(
+            pipeline.run(&input("yesss"), &context).as_deref(),
+            Some("yes")
+        );
+        assert_eq!(
+            pipeline.run(&input("helllooo"), &context).as_deref(),
+            Some("hello")
+        );
+
+        // Left as typed by default
+        let skip = StageContext {
+            word_types: &defaults.word_types,
+            ..context
+        };
+        assert_eq!(pipeline.run(&input("soooo"), &skip), None);
+    }
+}
@@ -0,0 +1,210 @@
+//! Correction providers from plugins: extra suggestions and vetoes without
+//! forking the crate.
+//!
+//! A [`CorrectionProvider`] can suggest the replacement for a word (a
+//! company glossary, a domain word list) and veto a correction the other
+//! stages chose. They are consulted by the `providers` and `veto` pipeline
+//! stages, and never for words typed in sensitive apps.
+//!
+//! # Dynamic Library Plugins
+//!
+//! With `load_plugins = true`, every DLL in `%LOCALAPPDATA%/Autocorrect/plugins/`
+//! is loaded at startup. Strings are NUL-terminated UTF-8:
+//!
+//! ```c
+//! // Required: the plugin API version, currently 1.
+//! uint32_t autocorrect_plugin_api_version(void);
+//!
+//! // Optional: write the replacement for `word` into `out` (at most
+//! // `out_len` bytes including the NUL) and return 1, or return 0.
+//! // `previous_word` is NULL at the start of the text.
+//! int32_t autocorrect_suggest(const char *word, const char *previous_word,
+//!                             char *out, size_t out_len);
+//!
+//! // Optional: return 1 to keep `word` from being replaced by `correction`.
+//! int32_t autocorrect_veto(const char *word, const char *correction);
+//! ```
+//!
+//! Plugins run inside the keyboard hook, so they must answer in well under a
+//! millisecond: from memory, never over the network or from disk per word.
+//! A crashing plugin takes Autocorrect down with it.
+
+/// Version of the plugin functions described above.
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// Something that can contribute to the correction of a word.
+pub trait CorrectionProvider {
+    /// Name shown in logs.
+    fn name(&self) -> &str;
+
+    /// The replacement for `word`, if this provider knows better.
+    fn suggest(&self, word: &str, previous_word: Option<&str>) -> Option<String>;
+
+    /// Whether `word` must not be replaced by `correction`.
+    fn veto(&self, word: &str, correction: &str) -> bool;
+}
+
+/// Loaded providers, consulted in order.
+pub type Providers = Vec<Box<dyn CorrectionProvider + Send>>;
+
+/// Load every plugin in the plugins folder, skipping any that fail to load.
+pub fn load() -> Providers {
+    let dir = crate::paths::local_file(crate::paths::PLUGINS_DIR);
+    let mut providers: Providers = Vec::new();
+
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return providers;
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("dll"))
+        })
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        match load_library(&path) {
+            Ok(provider) => {
+                eprintln!("Loaded plugin {}", provider.name());
+                providers.push(provider);
+            }
+            Err(err) => eprintln!("Ignoring plugin {}: {}", path.display(), err),
+        }
+    }
+
+    providers
+}
+
+#[cfg(windows)]
+fn load_library(path: &std::path::Path) -> Result<Box<dyn CorrectionProvider + Send>, String> {
+    Ok(Box::new(dll::DllProvider::load(path)?))
+}
+
+#[cfg(not(windows))]
+fn load_library(_path: &std::path::Path) -> Result<Box<dyn CorrectionProvider + Send>, String> {
+    Err("plugins are only supported on Windows".to_string())
+}
+
+#[cfg(windows)]
+mod dll {
+    use super::{CorrectionProvider, PLUGIN_API_VERSION};
+    use std::ffi::{c_char, CStr, CString, OsStr};
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use winapi::shared::minwindef::{FARPROC, HMODULE};
+    use winapi::um::libloaderapi::{FreeLibrary, GetProcAddress, LoadLibraryW};
+
+    /// Longest suggestion accepted from a plugin, in bytes.
+    const SUGGESTION_CAPACITY: usize = 256;
+
+    type VersionFn = unsafe extern "C" fn() -> u32;
+    type SuggestFn = unsafe extern "C" fn(*const c_char, *const c_char, *mut c_char, usize) -> i32;
+    type VetoFn = unsafe extern "C" fn(*const c_char, *const c_char) -> i32;
+
+    /// A plugin DLL and its exported functions.
+    pub struct DllProvider {
+        name: String,
+        module: HMODULE,
+        suggest: Option<SuggestFn>,
+        veto: Option<VetoFn>,
+    }
+
+    // The module handle and function pointers may be used from any thread
+    unsafe impl Send for DllProvider {}
+
+    impl DllProvider {
+        pub fn load(path: &Path) -> Result<Self, String> {
+            let wide: Vec<u16> = OsStr::new(path)
+                .encode_wide()
+                .chain(std::iter::once(0))
+                .collect();
+
+            unsafe {
+                let module = LoadLibraryW(wide.as_ptr());
+                if module.is_null() {
+                    return Err("could not load library".to_string());
+                }
+
+                let version = GetProcAddress(module, c"autocorrect_plugin_api_version".as_ptr());
+                if version.is_null() {
+                    FreeLibrary(module);
+                    return Err("not an Autocorrect plugin".to_string());
+                }
+                let version = std::mem::transmute::<FARPROC, VersionFn>(version)();
+                if version != PLUGIN_API_VERSION {
+                    FreeLibrary(module);
+                    return Err(format!("unsupported plugin API version {}", version));
+                }
+
+                let suggest = GetProcAddress(module, c"autocorrect_suggest".as_ptr());
+                let veto = GetProcAddress(module, c"autocorrect_veto".as_ptr());
+                Ok(Self {
+                    name: path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    module,
+                    suggest: (!suggest.is_null())
+                        .then(|| std::mem::transmute::<FARPROC, SuggestFn>(suggest)),
+                    veto: (!veto.is_null()).then(|| std::mem::transmute::<FARPROC, VetoFn>(veto)),
+                })
+            }
+        }
+    }
+
+    impl CorrectionProvider for DllProvider {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn suggest(&self, word: &str, previous_word: Option<&str>) -> Option<String> {
+            let suggest = self.suggest?;
+            let word = CString::new(word).ok()?;
+            let previous = previous_word.and_then(|previous| CString::new(previous).ok());
+            let mut out = [0 as c_char; SUGGESTION_CAPACITY];
+
+            let found = unsafe {
+                suggest(
+                    word.as_ptr(),
+                    previous
+                        .as_ref()
+                        .map_or(std::ptr::null(), |previous| previous.as_ptr()),
+                    out.as_mut_ptr(),
+                    out.len(),
+                )
+            };
+            // Never trust the plugin to have terminated the string
+            out[SUGGESTION_CAPACITY - 1] = 0;
+            if found != 1 {
+                return None;
+            }
+
+            let suggestion = unsafe { CStr::from_ptr(out.as_ptr()) };
+            suggestion
+                .to_str()
+                .ok()
+                .filter(|suggestion| !suggestion.is_empty())
+                .map(str::to_string)
+        }
+
+        fn veto(&self, word: &str, correction: &str) -> bool {
+            let Some(veto) = self.veto else {
+                return false;
+            };
+            let (Ok(word), Ok(correction)) = (CString::new(word), CString::new(correction)) else {
+                return false;
+            };
+            unsafe { veto(word.as_ptr(), correction.as_ptr()) == 1 }
+        }
+    }
+
+    impl Drop for DllProvider {
+        fn drop(&mut self) {
+            unsafe {
+                FreeLibrary(self.module);
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Suppression of recording while a sensitive app is in the foreground.
+//!
+//! Apps listed under `[apps] sensitive` in the config (password managers,
+//! banking apps) still get corrections, but nothing about what is typed in
+//! them is kept: no learned frequencies, no session statistics, and no log
+//! lines containing words.
+//!
+//! The check lives in the storage layer. Stores and the typed-text logger
+//! call [`recording_allowed`] themselves, so callers never need to remember
+//! to check.
+
+use parking_lot::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Default `[apps] sensitive` list.
+pub const DEFAULT_SENSITIVE_APPS: &[&str] = &[
+    "1password.exe",
+    "bitwarden.exe",
+    "dashlane.exe",
+    "keepass.exe",
+    "keepassxc.exe",
+    "lastpass.exe",
+];
+
+/// Configured sensitive executable names, lowercase.
+static SENSITIVE_APPS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Whether the foreground app is sensitive.
+static SUPPRESSED: AtomicBool = AtomicBool::new(false);
+
+/// Set the sensitive app list (executable names such as `keepass.exe`).
+pub fn set_sensitive_apps<S: AsRef<str>>(apps: &[S]) {
+    *SENSITIVE_APPS.write() = apps
+        .iter()
+        .map(|app| app.as_ref().trim().to_lowercase())
+        .filter(|app| !app.is_empty())
+        .collect();
+}
+
+/// Whether an executable name is in a sensitive app list.
+pub fn is_sensitive<S: AsRef<str>>(process_name: &str, sensitive_apps: &[S]) -> bool {
+    sensitive_apps
+        .iter()
+        .any(|app| app.as_ref().eq_ignore_ascii_case(process_name))
+}
+
+/// Update the recording state after the foreground app changed.
+pub fn set_foreground_app(process_name: Option<&str>) {
+    let sensitive = process_name
+        .map(|name| is_sensitive(name, &SENSITIVE_APPS.read()))
+        .unwrap_or(false);
+    SUPPRESSED.store(sensitive, Ordering::Relaxed);
+}
+
+/// Whether usage may be recorded (false while a sensitive app is in front).
+pub fn recording_allowed() -> bool {
+    !SUPPRESSED.load(Ordering::Relaxed)
+}
+
+/// Print a log line that contains typed text, unless recording is suppressed.
+pub fn log_typed(message: std::fmt::Arguments) {
+    if recording_allowed() {
+        println!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_sensitive() {
+        assert!(is_sensitive("KeePass.exe", DEFAULT_SENSITIVE_APPS));
+        assert!(!is_sensitive("notepad.exe", DEFAULT_SENSITIVE_APPS));
+    }
+}
@@ -0,0 +1,246 @@
+//! Process and window queries used to adapt behavior to what's running.
+
+use std::path::Path;
+
+#[cfg(windows)]
+use std::ffi::OsStr;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+#[cfg(windows)]
+use winapi::shared::windef::HWND;
+#[cfg(windows)]
+use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+#[cfg(windows)]
+use winapi::um::processthreadsapi::OpenProcess;
+#[cfg(windows)]
+use winapi::um::shellapi::ShellExecuteW;
+#[cfg(windows)]
+use winapi::um::tlhelp32::{
+    CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+};
+
+/// Get the executable names (e.g. `notepad.exe`) of all running processes.
+///
+/// Returns an empty list if the process snapshot cannot be taken.
+pub fn running_process_names() -> Vec<String> {
+    #[cfg(windows)]
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Vec::new();
+        }
+
+        let mut names = Vec::new();
+        let mut entry: PROCESSENTRY32W = std::mem::zeroed();
+        entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+
+        if Process32FirstW(snapshot, &mut entry) != 0 {
+            loop {
+                names.push(wide_to_string(&entry.szExeFile));
+                if Process32NextW(snapshot, &mut entry) == 0 {
+                    break;
+                }
+            }
+        }
+
+        CloseHandle(snapshot);
+        names
+    }
+
+    #[cfg(not(windows))]
+    {
+        Vec::new()
+    }
+}
+
+/// Executable name (e.g. `notepad.exe`) of the process owning a window.
+///
+/// Returns `None` if the window is null or the process can't be queried
+/// (e.g. it runs elevated and we don't).
+#[cfg(windows)]
+pub fn window_process_name(hwnd: HWND) -> Option<String> {
+    if hwnd.is_null() {
+        return None;
+    }
+
+    unsafe {
+        let mut process_id = 0;
+        winapi::um::winuser::GetWindowThreadProcessId(hwnd, &mut process_id);
+        if process_id == 0 {
+            return None;
+        }
+
+        let process = OpenProcess(
+            winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION,
+            0,
+            process_id,
+        );
+        if process.is_null() {
+            return None;
+        }
+
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        let ok = winapi::um::winbase::QueryFullProcessImageNameW(
+            process,
+            0,
+            buffer.as_mut_ptr(),
+            &mut len,
+        );
+        CloseHandle(process);
+
+        if ok == 0 {
+            return None;
+        }
+
+        let path = String::from_utf16_lossy(&buffer[..len as usize]);
+        Path::new(&path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+}
+
+/// Executable name of the process owning the foreground window.
+pub fn foreground_process_name() -> Option<String> {
+    #[cfg(windows)]
+    unsafe {
+        window_process_name(winapi::um::winuser::GetForegroundWindow())
+    }
+
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+/// Handle of the foreground window, as a number (0 if there is none).
+///
+/// Kept as a number so it can be stored in state shared across threads.
+pub fn foreground_window_id() -> usize {
+    #[cfg(windows)]
+    unsafe {
+        winapi::um::winuser::GetForegroundWindow() as usize
+    }
+
+    #[cfg(not(windows))]
+    {
+        0
+    }
+}
+
+/// Window class name of the foreground window (e.g. `Notepad`).
+pub fn foreground_window_class() -> Option<String> {
+    #[cfg(windows)]
+    unsafe {
+        let hwnd = winapi::um::winuser::GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+
+        let mut buffer = [0u16; 256];
+        let len =
+            winapi::um::winuser::GetClassNameW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
+        if len <= 0 {
+            return None;
+        }
+        Some(String::from_utf16_lossy(&buffer[..len as usize]))
+    }
+
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+/// Title of the foreground window.
+///
+/// Returns `None` if there is no foreground window or it has no title.
+pub fn foreground_window_title() -> Option<String> {
+    #[cfg(windows)]
+    unsafe {
+        let hwnd = winapi::um::winuser::GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+
+        let len = winapi::um::winuser::GetWindowTextLengthW(hwnd);
+        if len <= 0 {
+            return None;
+        }
+
+        let mut buffer = vec![0u16; len as usize + 1];
+        let copied =
+            winapi::um::winuser::GetWindowTextW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
+        Some(String::from_utf16_lossy(&buffer[..copied.max(0) as usize]))
+    }
+
+    #[cfg(not(windows))]
+    {
+        None
+    }
+}
+
+/// Open a file with its default application (e.g. a text file in Notepad).
+///
+/// # Errors
+/// Returns an error if the shell cannot launch an application for the file.
+pub fn open_with_default_app(path: &Path) -> Result<(), String> {
+    shell_open(path.as_os_str()).map_err(|code| match code {
+        Some(code) => format!("could not open {} (error {})", path.display(), code),
+        None => format!(
+            "opening {} is not supported on this platform",
+            path.display()
+        ),
+    })
+}
+
+/// Open a URL in the default browser.
+///
+/// # Errors
+/// Returns an error if the shell cannot launch a browser.
+pub fn open_url(url: &str) -> Result<(), String> {
+    shell_open(url.as_ref()).map_err(|code| match code {
+        Some(code) => format!("could not open the browser (error {})", code),
+        None => "opening a browser is not supported on this platform".to_string(),
+    })
+}
+
+/// `ShellExecute` a file or URL with its "open" verb.
+///
+/// Fails with the shell's error code, or `None` where it isn't supported.
+fn shell_open(target: &std::ffi::OsStr) -> Result<(), Option<isize>> {
+    #[cfg(windows)]
+    unsafe {
+        let operation: Vec<u16> = OsStr::new("open").encode_wide().chain(Some(0)).collect();
+        let file: Vec<u16> = target.encode_wide().chain(Some(0)).collect();
+
+        let result = ShellExecuteW(
+            std::ptr::null_mut(),
+            operation.as_ptr(),
+            file.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            winapi::um::winuser::SW_SHOWNORMAL,
+        );
+
+        // ShellExecute returns a value greater than 32 on success
+        if result as isize > 32 {
+            Ok(())
+        } else {
+            Err(Some(result as isize))
+        }
+    }
+
+    #[cfg(not(windows))]
+    {
+        let _ = target;
+        Err(None)
+    }
+}
+
+/// Convert a NUL-terminated UTF-16 buffer to a `String`.
+#[cfg(windows)]
+fn wide_to_string(buffer: &[u16]) -> String {
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    String::from_utf16_lossy(&buffer[..len])
+}
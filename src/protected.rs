@@ -0,0 +1,66 @@
+//! Tokens that are addresses, not words: URLs, email addresses and file
+//! paths.
+//!
+//! A word ending in `.`, `:`, `/`, `@` or `\` is usually the end of a
+//! sentence or clause, but may be the start of "github.com",
+//! "me@example.org" or "C:\Users". The corrector waits for the next key: if
+//! a letter or digit follows, the whole run up to the next space is one
+//! token, checked here when it ends. Protected tokens are left as typed;
+//! otherwise only the word at the caret is looked up.
+
+/// Characters that may continue a token past a word.
+pub const SEPARATORS: &str = ".:/@\\";
+
+/// Top-level domains recognized after a dot ("example.com").
+const TOP_LEVEL_DOMAINS: &[&str] = &[
+    "app", "au", "biz", "ca", "ch", "cn", "co", "com", "de", "dev", "edu", "es", "eu", "fr", "gov",
+    "info", "io", "it", "jp", "me", "net", "nl", "org", "ru", "se", "uk", "us",
+];
+
+/// Whether `token` (everything typed since the last space) looks like a
+/// URL, email address or Windows path.
+pub fn is_protected(token: &str) -> bool {
+    let token = token.trim_end_matches(|c: char| !c.is_alphanumeric());
+    if token.contains("://") || token.contains('\\') || token.starts_with("www.") {
+        return true;
+    }
+    // "name@example.org", not "@mention"
+    if let Some((user, domain)) = token.split_once('@') {
+        return !user.is_empty() && domain.contains('.');
+    }
+    has_top_level_domain(token)
+}
+
+/// Whether the host part of `token` ends in a known top-level domain.
+fn has_top_level_domain(token: &str) -> bool {
+    let host = token.split(['/', ':']).next().unwrap_or(token);
+    match host.rsplit_once('.') {
+        Some((name, tld)) => {
+            !name.is_empty() && TOP_LEVEL_DOMAINS.contains(&tld.to_ascii_lowercase().as_str())
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_protected() {
+        assert!(is_protected("github.com"));
+        assert!(is_protected("https://exmaple.org/pth"));
+        assert!(is_protected("crates.io/crates/serde"));
+        assert!(is_protected("Example.CO.UK,"));
+        assert!(is_protected("anselm@exmaple.com"));
+        assert!(is_protected("C:\\Users\\anselm"));
+        assert!(is_protected("www.exmaple"));
+
+        // A missing space, a clause, a handle
+        assert!(!is_protected("end.teh"));
+        assert!(!is_protected("and/or"));
+        assert!(!is_protected("Note:"));
+        assert!(!is_protected("@teh"));
+        assert!(!is_protected("main.rs"));
+    }
+}
@@ -0,0 +1,398 @@
+//! Choosing which suggestion, if any, replaces a typed word.
+//!
+//! Distance-2 corrections are the most error-prone, especially for short
+//! words where almost every string is within two edits of something. The
+//! policy only accepts one when either:
+//! - the typed word is long enough that two typos are plausible, or
+//! - the candidate is much more common than every distance-1 candidate.
+//!
+//! Within those rules, `selection_strategy` trades precision for recall:
+//! `strict` only ever applies the top distance-1 suggestion, `frequency`
+//! (the default) applies the rules above, and `context` lets a distance-2
+//! candidate of a long word beat distance-1 ones when it is more frequent
+//! once the context model has boosted it. Without a context model loaded,
+//! `context` behaves like `frequency`.
+//!
+//! Short words are the most error-prone of all: "im", "ot" and variable
+//! names are rarely typos of the dictionary word nearest to them. Words
+//! shorter than `min_word_length` are only ever corrected by a personal
+//! correction.
+//!
+//! Whatever the strategy picks must then be confident enough to apply:
+//! - `min_correction_frequency`: rare dictionary words (a surname, an archaic
+//!   spelling) are never typed over another rare token;
+//! - `min_correction_ratio`: the pick must be this many times more frequent
+//!   than the runner-up at the same distance, so a coin toss between two
+//!   equally likely words is left to the user.
+//!
+//! Both are off by default.
+//!
+//! `correction_mode` sets all of the above at once: `conservative`,
+//! `balanced` (the defaults) or `aggressive`. Left at `custom`, the
+//! individual settings apply.
+
+use crate::symspell::SuggestItem;
+use serde::{Deserialize, Serialize};
+
+/// Typed words at least this long may be corrected at distance 2.
+const DISTANCE_TWO_MIN_LENGTH: usize = 6;
+
+/// How many times more frequent a distance-2 candidate must be than the best
+/// distance-1 candidate to win.
+const DISTANCE_TWO_FREQUENCY_RATIO: f64 = 10.0;
+
+/// Default minimum frequency of an applied correction: any.
+pub const DEFAULT_MIN_FREQUENCY: u64 = 0;
+
+/// Default frequency advantage over the runner-up: none.
+pub const DEFAULT_MIN_FREQUENCY_RATIO: f64 = 1.0;
+
+/// How far the policy goes to find a correction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionStrategy {
+    /// Only the top suggestion, and only at distance 1.
+    Strict,
+    /// Distance 1 first; distance 2 for long words or much more common words.
+    Frequency,
+    /// Like `frequency`, but the most likely candidate in context wins across
+    /// distances when a context model is loaded.
+    Context,
+}
+
+/// Presets for how readily words are corrected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CorrectionMode {
+    /// One-typo corrections of words of 4+ letters, to common words only.
+    Conservative,
+    /// The default policy.
+    Balanced,
+    /// Two-typo corrections from 4 letters, chosen in context.
+    Aggressive,
+    /// `max_edit_distance`, `selection_strategy`, `min_word_length` and the
+    /// confidence thresholds as configured.
+    Custom,
+}
+
+impl CorrectionMode {
+    /// Presets offered in the tray, in order.
+    pub const PRESETS: [CorrectionMode; 3] = [
+        CorrectionMode::Conservative,
+        CorrectionMode::Balanced,
+        CorrectionMode::Aggressive,
+    ];
+
+    /// The policy this preset stands for; `None` for `Custom`.
+    pub fn policy(self) -> Option<RankingPolicy> {
+        let balanced = RankingPolicy::default();
+        match self {
+            CorrectionMode::Conservative => Some(RankingPolicy {
+                max_edit_distance: 1,
+                min_word_length: 4,
+                strategy: SelectionStrategy::Strict,
+                min_frequency: 100,
+                min_frequency_ratio: 2.0,
+                ..balanced
+            }),
+            CorrectionMode::Balanced => Some(balanced),
+            CorrectionMode::Aggressive => Some(RankingPolicy {
+                distance_two_min_length: 4,
+                distance_two_frequency_ratio: 3.0,
+                strategy: SelectionStrategy::Context,
+                ..balanced
+            }),
+            CorrectionMode::Custom => None,
+        }
+    }
+
+    /// Name shown in the tray menu.
+    pub fn label(self) -> &'static str {
+        match self {
+            CorrectionMode::Conservative => "Conservative",
+            CorrectionMode::Balanced => "Balanced",
+            CorrectionMode::Aggressive => "Aggressive",
+            CorrectionMode::Custom => "Custom",
+        }
+    }
+}
+
+/// Rules for picking a correction from ranked suggestions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RankingPolicy {
+    /// Largest edit distance ever corrected.
+    pub max_edit_distance: i32,
+    /// Shorter typed words are never corrected from the dictionary
+    /// (personal corrections still apply).
+    pub min_word_length: usize,
+    /// Minimum typed word length for distance-2 corrections without a
+    /// frequency advantage.
+    pub distance_two_min_length: usize,
+    /// Frequency advantage a distance-2 candidate needs over distance-1 ones.
+    pub distance_two_frequency_ratio: f64,
+    pub strategy: SelectionStrategy,
+    /// Candidates less frequent than this are never applied.
+    pub min_frequency: u64,
+    /// Frequency advantage the chosen candidate needs over the next most
+    /// frequent one at the same distance (1.0 or less: none).
+    pub min_frequency_ratio: f64,
+}
+
+impl Default for RankingPolicy {
+    fn default() -> Self {
+        Self::with_max_edit_distance(2)
+    }
+}
+
+impl RankingPolicy {
+    pub fn with_max_edit_distance(max_edit_distance: i32) -> Self {
+        Self {
+            max_edit_distance,
+            min_word_length: 1,
+            distance_two_min_length: DISTANCE_TWO_MIN_LENGTH,
+            distance_two_frequency_ratio: DISTANCE_TWO_FREQUENCY_RATIO,
+            strategy: SelectionStrategy::Frequency,
+            min_frequency: DEFAULT_MIN_FREQUENCY,
+            min_frequency_ratio: DEFAULT_MIN_FREQUENCY_RATIO,
+        }
+    }
+
+    /// The policy to apply, given whether a context model is loaded.
+    pub fn resolved(&self, context_model: bool) -> Self {
+        let mut policy = *self;
+        if policy.strategy == SelectionStrategy::Context && !context_model {
+            policy.strategy = SelectionStrategy::Frequency;
+        }
+        policy
+    }
+
+    /// Pick the correction for `word` from suggestions sorted by distance,
+    /// then frequency (as returned by `Dictionary::lookup`).
+    ///
+    /// Returns `None` if the word is itself a dictionary word or no
+    /// candidate is acceptable.
+    pub fn choose<'a>(
+        &self,
+        word: &str,
+        suggestions: &'a [SuggestItem],
+    ) -> Option<&'a SuggestItem> {
+        if suggestions.iter().any(|s| s.distance == 0)
+            || word.chars().count() < self.min_word_length
+        {
+            return None;
+        }
+
+        let chosen = match self.strategy {
+            SelectionStrategy::Strict => suggestions.first().filter(|s| s.distance == 1),
+            SelectionStrategy::Frequency => self.choose_by_frequency(word, suggestions),
+            SelectionStrategy::Context => self.choose_in_context(word, suggestions),
+        };
+        chosen.filter(|chosen| self.is_confident(chosen, suggestions))
+    }
+
+    /// Whether `chosen` is common enough, and far enough ahead of the other
+    /// candidates at its distance, to be applied.
+    fn is_confident(&self, chosen: &SuggestItem, suggestions: &[SuggestItem]) -> bool {
+        if chosen.frequency < self.min_frequency {
+            return false;
+        }
+        let runner_up = suggestions
+            .iter()
+            .filter(|s| s.distance == chosen.distance && s.term != chosen.term)
+            .map(SuggestItem::score)
+            .max_by(f64::total_cmp);
+        runner_up.is_none_or(|runner_up| chosen.score() >= runner_up * self.min_frequency_ratio)
+    }
+
+    fn choose_by_frequency<'a>(
+        &self,
+        word: &str,
+        suggestions: &'a [SuggestItem],
+    ) -> Option<&'a SuggestItem> {
+        let best_at = |distance: i32| {
+            if distance > self.max_edit_distance {
+                None
+            } else {
+                suggestions.iter().find(|s| s.distance == distance)
+            }
+        };
+        let distance_one = best_at(1);
+        let distance_two = best_at(2);
+
+        if let (Some(one), Some(two)) = (distance_one, distance_two) {
+            if two.frequency as f64 >= one.frequency as f64 * self.distance_two_frequency_ratio {
+                return Some(two);
+            }
+        }
+
+        distance_one.or_else(|| {
+            distance_two.filter(|_| word.chars().count() >= self.distance_two_min_length)
+        })
+    }
+
+    /// The frequency choice, unless an acceptable candidate at another
+    /// distance is more frequent after reranking in context.
+    fn choose_in_context<'a>(
+        &self,
+        word: &str,
+        suggestions: &'a [SuggestItem],
+    ) -> Option<&'a SuggestItem> {
+        let by_frequency = self.choose_by_frequency(word, suggestions);
+        let long_enough = word.chars().count() >= self.distance_two_min_length;
+        let most_likely = suggestions
+            .iter()
+            .filter(|s| s.distance <= self.max_edit_distance)
+            .filter(|s| s.distance == 1 || (s.distance == 2 && long_enough))
+            .reduce(|best, s| {
+                if s.frequency > best.frequency {
+                    s
+                } else {
+                    best
+                }
+            });
+
+        match (by_frequency, most_likely) {
+            (Some(chosen), Some(likely)) if likely.frequency > chosen.frequency => Some(likely),
+            (None, likely) => likely,
+            (chosen, _) => chosen,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggestion(term: &str, distance: i32, frequency: u64) -> SuggestItem {
+        SuggestItem {
+            term: term.to_string(),
+            display_term: term.to_string(),
+            distance,
+            frequency,
+            typing_distance: distance as f64,
+        }
+    }
+
+    #[test]
+    fn test_distance_two_needs_length_or_frequency() {
+        let policy = RankingPolicy::default();
+
+        // Short word, only a distance-2 candidate: left alone
+        let short = [suggestion("cat", 2, 100)];
+        assert!(policy.choose("xq", &short).is_none());
+
+        // Long enough for two typos
+        let long = [suggestion("because", 2, 100)];
+        assert_eq!(policy.choose("becuze", &long).unwrap().term, "because");
+
+        // Much more common than the distance-1 candidate
+        let common = [suggestion("rare", 1, 10), suggestion("common", 2, 5000)];
+        assert_eq!(policy.choose("word", &common).unwrap().term, "common");
+
+        // Not common enough: distance 1 wins
+        let close = [suggestion("rare", 1, 10), suggestion("common", 2, 50)];
+        assert_eq!(policy.choose("word", &close).unwrap().term, "rare");
+    }
+
+    #[test]
+    fn test_strategies() {
+        let policy = |strategy| RankingPolicy {
+            strategy,
+            ..RankingPolicy::default()
+        };
+        // A long word whose distance-2 candidate fits the context better
+        let suggestions = [suggestion("fromage", 1, 50), suggestion("formula", 2, 400)];
+
+        let strict = policy(SelectionStrategy::Strict);
+        assert_eq!(
+            strict.choose("formage", &suggestions).unwrap().term,
+            "fromage"
+        );
+        assert!(strict
+            .choose("becuze", &[suggestion("because", 2, 100)])
+            .is_none());
+
+        let frequency = policy(SelectionStrategy::Frequency);
+        assert_eq!(
+            frequency.choose("formage", &suggestions).unwrap().term,
+            "fromage"
+        );
+
+        let context = policy(SelectionStrategy::Context);
+        assert_eq!(
+            context.choose("formage", &suggestions).unwrap().term,
+            "formula"
+        );
+        assert_eq!(context.resolved(false), frequency);
+        assert_eq!(context.resolved(true), context);
+    }
+
+    #[test]
+    fn test_confidence_threshold() {
+        let policy = RankingPolicy {
+            min_frequency: 100,
+            min_frequency_ratio: 3.0,
+            ..RankingPolicy::default()
+        };
+
+        // A rare word doesn't attract corrections
+        assert!(policy
+            .choose("zorp", &[suggestion("zarp", 1, 20)])
+            .is_none());
+        assert_eq!(
+            policy
+                .choose("teh", &[suggestion("the", 1, 5000)])
+                .unwrap()
+                .term,
+            "the"
+        );
+
+        // Too close to call
+        let close = [suggestion("bat", 1, 900), suggestion("cat", 1, 800)];
+        assert!(policy.choose("bta", &close).is_none());
+        assert!(RankingPolicy::default().choose("bta", &close).is_some());
+
+        // Clear winner; a candidate at another distance isn't a rival
+        let clear = [
+            suggestion("bat", 1, 900),
+            suggestion("cat", 1, 200),
+            suggestion("but", 2, 5000),
+        ];
+        assert_eq!(policy.choose("bta", &clear).unwrap().term, "bat");
+    }
+
+    #[test]
+    fn test_correction_modes() {
+        let policy = |mode: CorrectionMode| mode.policy().unwrap();
+        let conservative = policy(CorrectionMode::Conservative);
+        let aggressive = policy(CorrectionMode::Aggressive);
+        assert_eq!(policy(CorrectionMode::Balanced), RankingPolicy::default());
+        assert!(CorrectionMode::Custom.policy().is_none());
+
+        // Short words are left alone unless aggressive
+        let short = [suggestion("then", 2, 5000)];
+        assert!(conservative
+            .choose("thme", &[suggestion("them", 1, 5000)])
+            .is_some());
+        assert!(conservative
+            .choose("ot", &[suggestion("to", 1, 5000)])
+            .is_none());
+        assert!(RankingPolicy::default().choose("thay", &short).is_none());
+        assert_eq!(aggressive.choose("thay", &short).unwrap().term, "then");
+
+        // Only common words for conservative
+        assert!(conservative
+            .choose("zarq", &[suggestion("zarp", 1, 20)])
+            .is_none());
+    }
+
+    #[test]
+    fn test_respects_max_distance_and_exact_matches() {
+        let policy = RankingPolicy::with_max_edit_distance(1);
+        let suggestions = [suggestion("because", 2, 100)];
+        assert!(policy.choose("becuze", &suggestions).is_none());
+
+        let exact = [suggestion("the", 0, 1), suggestion("then", 1, 1000)];
+        assert!(RankingPolicy::default().choose("the", &exact).is_none());
+    }
+}
@@ -0,0 +1,124 @@
+//! A per-app cap on corrections per minute.
+//!
+//! Normal typing needs a handful of corrections a minute. An app that gets
+//! many more is usually one where the engine misreads input, such as an IDE
+//! completing identifiers or a spreadsheet moving between cells. Once an app
+//! goes over `max_corrections_per_minute`, corrections stop there for
+//! [`PAUSE`] (or until autocorrect is toggled off and on), and the user is
+//! told so they can add the words or exclude the app.
+
+use ahash::AHashMap;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Window the cap applies to.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// How long corrections stay paused in an app that went over the cap.
+pub const PAUSE: Duration = Duration::from_secs(10 * 60);
+
+/// Default cap, far above what normal typing needs.
+pub const DEFAULT_MAX_PER_MINUTE: u32 = 30;
+
+/// Recent corrections per process.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    /// Corrections allowed per app per minute; 0 means no limit.
+    max_per_minute: u32,
+    recent: AHashMap<String, VecDeque<Instant>>,
+    /// Apps over the cap, by when corrections resume there.
+    limited: AHashMap<String, Instant>,
+    /// App that just went over the cap, for the notification.
+    notice: Option<String>,
+}
+
+impl RateLimiter {
+    pub fn new(max_per_minute: u32) -> Self {
+        Self {
+            max_per_minute,
+            ..Self::default()
+        }
+    }
+
+    /// Count a correction about to be made in `process`.
+    ///
+    /// Returns `false` if corrections are paused there, including when this
+    /// one would go over the cap.
+    pub fn allow(&mut self, process: &str, now: Instant) -> bool {
+        if self.max_per_minute == 0 {
+            return true;
+        }
+        let process = process.to_lowercase();
+        match self.limited.get(&process) {
+            Some(&until) if now < until => return false,
+            Some(_) => {
+                self.limited.remove(&process);
+            }
+            None => {}
+        }
+
+        let recent = self.recent.entry(process.clone()).or_default();
+        while recent
+            .front()
+            .is_some_and(|&at| now.saturating_duration_since(at) >= WINDOW)
+        {
+            recent.pop_front();
+        }
+
+        if recent.len() >= self.max_per_minute as usize {
+            self.recent.remove(&process);
+            self.limited.insert(process.clone(), now + PAUSE);
+            self.notice = Some(process);
+            return false;
+        }
+        recent.push_back(now);
+        true
+    }
+
+    /// The app that last went over the cap, once.
+    pub fn take_notice(&mut self) -> Option<String> {
+        self.notice.take()
+    }
+
+    /// Resume corrections in every app.
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.limited.clear();
+        self.notice = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_per_app() {
+        let mut limiter = RateLimiter::new(3);
+        let start = Instant::now();
+
+        for second in 0..3 {
+            assert!(limiter.allow("Excel.exe", start + Duration::from_secs(second * 30)));
+        }
+        // The first correction has left the window
+        assert!(limiter.allow("excel.exe", start + Duration::from_secs(61)));
+        assert_eq!(limiter.take_notice(), None);
+
+        // A fourth within a minute pauses the app, and only that app
+        assert!(!limiter.allow("excel.exe", start + Duration::from_secs(62)));
+        assert_eq!(limiter.take_notice().as_deref(), Some("excel.exe"));
+        assert_eq!(limiter.take_notice(), None);
+        assert!(!limiter.allow("excel.exe", start + Duration::from_secs(600)));
+        assert!(limiter.allow("notepad.exe", start + Duration::from_secs(62)));
+
+        // The pause wears off, with a fresh count
+        let resumed = start + Duration::from_secs(62) + PAUSE;
+        for second in 0..3 {
+            assert!(limiter.allow("excel.exe", resumed + Duration::from_secs(second)));
+        }
+        assert!(!limiter.allow("excel.exe", resumed + Duration::from_secs(3)));
+
+        limiter.clear();
+        assert!(limiter.allow("excel.exe", start + Duration::from_secs(600)));
+    }
+}
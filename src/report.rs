@@ -0,0 +1,111 @@
+//! Reports of bad corrections, to tune the ranking defaults.
+//!
+//! After an unwanted correction, "Report bad correction..." in the tray menu
+//! shows what would be reported: the word as typed, the correction, the kind
+//! of app it happened in, and the engine settings that chose it. Nothing
+//! leaves the machine unless the user agrees: the report either opens as a
+//! prefilled GitHub issue, which they can still edit or discard before
+//! submitting, or is appended to a local file.
+//!
+//! Corrections made in `[apps] sensitive` apps are never offered for
+//! reporting.
+
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Where new issues for the project are filed.
+const NEW_ISSUE_URL: &str = "https://github.com/anselmlong/autocorrect/issues/new";
+
+/// A correction the user considers wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadCorrection {
+    /// The word as typed.
+    pub typed: String,
+    /// What it was replaced with.
+    pub correction: String,
+    /// Kind of app it happened in (e.g. `Electron`).
+    pub app_type: String,
+    /// Engine settings, as `(name, value)` pairs.
+    pub settings: Vec<(&'static str, String)>,
+}
+
+impl BadCorrection {
+    /// Issue title.
+    pub fn title(&self) -> String {
+        format!("Bad correction: '{}' -> '{}'", self.typed, self.correction)
+    }
+
+    /// Report text, in Markdown.
+    pub fn body(&self) -> String {
+        let mut body = format!(
+            "- Typed: `{}`\n- Corrected to: `{}`\n- Expected: \n- App type: {}\n\n\
+             Settings:\n",
+            self.typed, self.correction, self.app_type
+        );
+        for (name, value) in &self.settings {
+            body.push_str(&format!("- {}: {}\n", name, value));
+        }
+        body
+    }
+
+    /// URL of a new GitHub issue prefilled with the report.
+    pub fn issue_url(&self) -> String {
+        format!(
+            "{}?title={}&body={}",
+            NEW_ISSUE_URL,
+            percent_encode(&self.title()),
+            percent_encode(&self.body())
+        )
+    }
+
+    /// Append the report to the local reports file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self) -> std::io::Result<PathBuf> {
+        let path = crate::paths::local_file(crate::paths::CORRECTION_REPORTS_FILE);
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?;
+        writeln!(file, "## {}\n\n{}", self.title(), self.body())?;
+        Ok(path)
+    }
+}
+
+/// Percent-encode text for a URL query value.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_issue_url() {
+        let report = BadCorrection {
+            typed: "teh".to_string(),
+            correction: "tea".to_string(),
+            app_type: "Standard".to_string(),
+            settings: vec![("max_edit_distance", "2".to_string())],
+        };
+        assert!(report.body().contains("- Typed: `teh`\n"));
+        assert!(report.body().ends_with("- max_edit_distance: 2\n"));
+
+        let url = report.issue_url();
+        assert!(url.starts_with(NEW_ISSUE_URL));
+        assert!(url.contains("?title=Bad%20correction%3A%20%27teh%27%20-%3E%20%27tea%27&body="));
+        assert!(!url.contains(' ') && !url.contains('\n'));
+        assert_eq!(percent_encode("é"), "%C3%A9");
+    }
+}
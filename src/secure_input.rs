@@ -0,0 +1,115 @@
+//! Detection of OS security prompts whose keystrokes must never be tracked.
+//!
+//! Two situations are covered:
+//! - **Secure desktop**: UAC prompts (by default), Ctrl+Alt+Del and the lock
+//!   screen run on a separate desktop. Our hook normally can't see those keys
+//!   at all, but if it does, the input desktop is no longer `Default`.
+//! - **Credential prompts on the normal desktop**: Windows Security
+//!   credential dialogs and UAC prompts with the secure desktop disabled run
+//!   in dedicated system processes.
+//!
+//! While either is active, the corrector is paused, which also drops the
+//! in-progress word and undo buffer, so nothing typed is held in memory.
+//! Password fields inside ordinary apps are detected by `password_field`.
+
+#[cfg(windows)]
+use std::cell::Cell;
+#[cfg(windows)]
+use winapi::um::winuser::{
+    CloseDesktop, GetForegroundWindow, GetUserObjectInformationW, OpenInputDesktop,
+    DESKTOP_READOBJECTS, UOI_NAME,
+};
+
+/// Processes that host OS credential and elevation prompts.
+pub const SECURE_PROCESSES: &[&str] = &[
+    // UAC prompt (when not shown on the secure desktop)
+    "consent.exe",
+    // "Windows Security" credential dialogs
+    "credentialuibroker.exe",
+    // Sign-in and lock screens
+    "logonui.exe",
+    "lockapp.exe",
+];
+
+/// Whether an executable name belongs to an OS security prompt.
+pub fn is_secure_process(process_name: &str) -> bool {
+    SECURE_PROCESSES
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(process_name))
+}
+
+#[cfg(windows)]
+thread_local! {
+    /// Last foreground window checked and whether it was secure.
+    static LAST_CHECK: Cell<(usize, bool)> = const { Cell::new((0, false)) };
+}
+
+/// Whether keystrokes are currently going to a secure desktop or OS
+/// credential prompt.
+///
+/// Called for every key event, so the process lookup is cached per
+/// foreground window.
+#[cfg(windows)]
+pub fn is_active() -> bool {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_null() {
+        // No foreground window on our desktop: check which desktop has input
+        return !input_desktop_is_default();
+    }
+
+    let key = hwnd as usize;
+    let (last_hwnd, last_result) = LAST_CHECK.with(Cell::get);
+    if last_hwnd == key {
+        return last_result;
+    }
+
+    let secure = crate::process::window_process_name(hwnd)
+        .map(|name| is_secure_process(&name))
+        .unwrap_or(false);
+    LAST_CHECK.with(|cell| cell.set((key, secure)));
+    secure
+}
+
+/// Whether the desktop receiving input is the normal `Default` desktop.
+///
+/// The secure desktop (`Winlogon`) can't be opened by normal processes, so a
+/// failure to open the input desktop also counts as not default.
+#[cfg(windows)]
+fn input_desktop_is_default() -> bool {
+    unsafe {
+        let desktop = OpenInputDesktop(0, 0, DESKTOP_READOBJECTS);
+        if desktop.is_null() {
+            return false;
+        }
+
+        let mut name = [0u16; 64];
+        let mut needed = 0;
+        let ok = GetUserObjectInformationW(
+            desktop as _,
+            UOI_NAME as i32,
+            name.as_mut_ptr() as _,
+            (name.len() * std::mem::size_of::<u16>()) as u32,
+            &mut needed,
+        );
+        CloseDesktop(desktop);
+
+        if ok == 0 {
+            return false;
+        }
+
+        let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+        String::from_utf16_lossy(&name[..len]).eq_ignore_ascii_case("Default")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_secure_process() {
+        assert!(is_secure_process("consent.exe"));
+        assert!(is_secure_process("CredentialUIBroker.exe"));
+        assert!(!is_secure_process("notepad.exe"));
+    }
+}
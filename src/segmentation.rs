@@ -0,0 +1,83 @@
+//! Where words end, depending on the script being typed.
+//!
+//! Words are tracked from key presses and end at a space, Enter or
+//! punctuation. That doesn't work for every script:
+//!
+//! - Chinese, Japanese, Thai, Lao, Khmer, Burmese and Tibetan are written
+//!   without spaces between words, so a word end never comes.
+//! - Chinese, Japanese and Korean are composed in an IME from letter keys
+//!   that don't end up in the text as typed, so the tracked letters aren't
+//!   the word on screen.
+//!
+//! While the foreground window's keyboard layout is for one of these
+//! languages, corrections pause instead of looking up the letter keys as one
+//! long misspelled word.
+//!
+//! Support for such a script (e.g. a dictionary-based segmenter) is added as
+//! a [`WordBoundaries`] variant for its languages, rather than as special
+//! cases in the corrector.
+
+/// How words are delimited in a language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordBoundaries {
+    /// Spaces and punctuation end words.
+    Spaces,
+    /// No spaces between words, or composed in an IME; not supported yet.
+    Unsupported,
+}
+
+/// Primary language IDs (the low 10 bits of a `LANGID`) without spaces
+/// between words or typed through an IME.
+const UNSUPPORTED_LANGUAGES: &[u16] = &[
+    0x04, // Chinese
+    0x11, // Japanese
+    0x12, // Korean
+    0x1E, // Thai
+    0x51, // Tibetan
+    0x53, // Khmer
+    0x54, // Lao
+    0x55, // Burmese
+];
+
+/// Word boundaries for a keyboard layout's language.
+pub fn boundaries_for(language_id: u16) -> WordBoundaries {
+    if UNSUPPORTED_LANGUAGES.contains(&(language_id & 0x3FF)) {
+        WordBoundaries::Unsupported
+    } else {
+        WordBoundaries::Spaces
+    }
+}
+
+/// Word boundaries for the foreground window's keyboard layout.
+///
+/// The layout can change with Alt+Shift without a focus change, so this is
+/// checked on every key press; it's a few cheap calls.
+pub fn foreground_boundaries() -> WordBoundaries {
+    #[cfg(windows)]
+    {
+        // The low word of the layout handle is its language
+        boundaries_for(crate::keymap::foreground_layout() as usize as u16)
+    }
+
+    #[cfg(not(windows))]
+    {
+        WordBoundaries::Spaces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boundaries_for() {
+        // en-US, fr-FR
+        assert_eq!(boundaries_for(0x0409), WordBoundaries::Spaces);
+        assert_eq!(boundaries_for(0x040C), WordBoundaries::Spaces);
+        // ja-JP, zh-CN, zh-TW, th-TH
+        assert_eq!(boundaries_for(0x0411), WordBoundaries::Unsupported);
+        assert_eq!(boundaries_for(0x0804), WordBoundaries::Unsupported);
+        assert_eq!(boundaries_for(0x0404), WordBoundaries::Unsupported);
+        assert_eq!(boundaries_for(0x041E), WordBoundaries::Unsupported);
+    }
+}
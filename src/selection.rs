@@ -0,0 +1,81 @@
+//! Spell-correct the text selected in the focused application.
+//!
+//! The selection is read and replaced through the clipboard:
+//!
+//! 1. Copy the selection with Ctrl+C
+//! 2. Correct every word in the copied text
+//! 3. Paste the result with Ctrl+V (only if something changed)
+//! 4. Put the user's previous clipboard contents back, in every format,
+//!    even if a step fails
+//!
+//! Must run off the thread that owns the keyboard hook, since the copy and
+//! paste keystrokes pass through the hook while we wait for them.
+
+#[cfg(windows)]
+use crate::clipboard;
+#[cfg(windows)]
+use crate::corrector::Corrector;
+#[cfg(windows)]
+use std::time::{Duration, Instant};
+
+/// How long to wait for the application to copy the selection.
+#[cfg(windows)]
+const COPY_TIMEOUT_MS: u64 = 500;
+/// How long to let the application read the clipboard before restoring it.
+#[cfg(windows)]
+const PASTE_SETTLE_MS: u64 = 200;
+
+/// Virtual key codes for C and V.
+#[cfg(windows)]
+const VK_C: u16 = 0x43;
+#[cfg(windows)]
+const VK_V: u16 = 0x56;
+
+/// Correct the current selection using `correct` to fix the copied text.
+///
+/// Returns `Ok(false)` if nothing was selected or nothing needed fixing.
+///
+/// # Errors
+/// Returns an error if the clipboard cannot be read or written.
+#[cfg(windows)]
+pub fn correct_selection(correct: impl FnOnce(&str) -> String) -> Result<bool, String> {
+    crate::hotkeys::wait_for_modifiers_released();
+
+    let mut guard = clipboard::RestoreGuard::new();
+    let sequence = clipboard::sequence_number();
+
+    unsafe { Corrector::send_ctrl_shortcut(VK_C) };
+
+    let start = Instant::now();
+    while clipboard::sequence_number() == sequence {
+        if start.elapsed() > Duration::from_millis(COPY_TIMEOUT_MS) {
+            // Nothing selected (or the app ignored Ctrl+C)
+            return Ok(false);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    guard.track();
+
+    let result = match clipboard::get_text()? {
+        Some(text) => {
+            let corrected = correct(&text);
+            if corrected != text {
+                clipboard::set_text(&corrected)?;
+                guard.track();
+                unsafe { Corrector::send_ctrl_shortcut(VK_V) };
+                std::thread::sleep(Duration::from_millis(PASTE_SETTLE_MS));
+                crate::privacy::log_typed(format_args!(
+                    "Corrected selection: '{}' -> '{}'",
+                    text, corrected
+                ));
+                true
+            } else {
+                false
+            }
+        }
+        None => false,
+    };
+
+    // Dropping the guard restores the clipboard
+    Ok(result)
+}
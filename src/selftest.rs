@@ -0,0 +1,187 @@
+//! Startup self-test of the keystroke injection pipeline.
+//!
+//! Some environments (VMs, kiosks, security software) silently block or
+//! partially drop `SendInput`. If that happens mid-correction the user is
+//! left with a half-deleted word, so on launch we check injection works
+//! before trusting it:
+//!
+//! 1. Create a small edit control off-screen and give it focus
+//! 2. Type a misspelling through `Corrector::type_replacement`
+//! 3. Apply the correction through the same path
+//! 4. Read the control's text back and compare
+//!
+//! `SendInput` is tried first; if it fails, the `SendMessage` strategy is
+//! tried so the caller can fall back to it. The test runs before the keyboard
+//! hook is installed so the injected keys are not seen by the corrector.
+
+use crate::corrector::InputStrategy;
+
+#[cfg(windows)]
+use crate::corrector::Corrector;
+#[cfg(windows)]
+use std::ffi::OsStr;
+#[cfg(windows)]
+use std::os::windows::ffi::OsStrExt;
+#[cfg(windows)]
+use std::ptr::null_mut;
+#[cfg(windows)]
+use std::time::{Duration, Instant};
+#[cfg(windows)]
+use winapi::shared::windef::HWND;
+#[cfg(windows)]
+use winapi::um::libloaderapi::GetModuleHandleW;
+#[cfg(windows)]
+use winapi::um::winuser::*;
+
+/// Misspelling typed into the test control.
+#[cfg(windows)]
+const TYPED: &str = "teh";
+/// Text the control must contain after the correction is applied.
+#[cfg(windows)]
+const EXPECTED: &str = "the";
+/// Delay between injected keys during the test.
+#[cfg(windows)]
+const TEST_KEY_DELAY_MS: u64 = 5;
+
+/// Result of the injection self-test.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelfTestOutcome {
+    /// The default strategy works.
+    Passed,
+    /// The default strategy failed, but this one works.
+    Fallback(InputStrategy),
+    /// No strategy produced the expected text.
+    Failed,
+    /// The test could not run (e.g. the test window could not be focused).
+    Skipped(String),
+}
+
+/// Run the injection self-test.
+pub fn run() -> SelfTestOutcome {
+    #[cfg(windows)]
+    unsafe {
+        run_windows()
+    }
+
+    #[cfg(not(windows))]
+    {
+        SelfTestOutcome::Skipped("not supported on this platform".to_string())
+    }
+}
+
+#[cfg(windows)]
+unsafe fn run_windows() -> SelfTestOutcome {
+    let previous_foreground = GetForegroundWindow();
+
+    let outcome = match create_focused_edit() {
+        Ok(hwnd) => {
+            let outcome = if try_strategy(hwnd, InputStrategy::SendInput) {
+                SelfTestOutcome::Passed
+            } else if try_strategy(hwnd, InputStrategy::SendMessage) {
+                SelfTestOutcome::Fallback(InputStrategy::SendMessage)
+            } else {
+                SelfTestOutcome::Failed
+            };
+            DestroyWindow(hwnd);
+            outcome
+        }
+        Err(reason) => SelfTestOutcome::Skipped(reason),
+    };
+
+    if !previous_foreground.is_null() {
+        SetForegroundWindow(previous_foreground);
+    }
+
+    outcome
+}
+
+/// Create a small edit control off-screen and give it keyboard focus.
+///
+/// # Errors
+/// Returns the reason if the control couldn't be created or focused.
+#[cfg(windows)]
+pub(crate) unsafe fn create_focused_edit() -> Result<HWND, String> {
+    let class_name: Vec<u16> = OsStr::new("EDIT").encode_wide().chain(Some(0)).collect();
+    let hwnd = CreateWindowExW(
+        WS_EX_TOOLWINDOW,
+        class_name.as_ptr(),
+        null_mut(),
+        WS_POPUP | WS_VISIBLE | ES_AUTOHSCROLL,
+        -32000,
+        -32000,
+        400,
+        24,
+        null_mut(),
+        null_mut(),
+        GetModuleHandleW(null_mut()),
+        null_mut(),
+    );
+
+    if hwnd.is_null() {
+        return Err("could not create test window".to_string());
+    }
+
+    SetForegroundWindow(hwnd);
+    SetFocus(hwnd);
+    pump_messages(Duration::from_millis(50));
+
+    if GetForegroundWindow() != hwnd {
+        DestroyWindow(hwnd);
+        return Err("could not focus test window".to_string());
+    }
+    Ok(hwnd)
+}
+
+/// Type the misspelling, correct it, and check the control's final text.
+#[cfg(windows)]
+unsafe fn try_strategy(hwnd: HWND, strategy: InputStrategy) -> bool {
+    let empty: [u16; 1] = [0];
+    SetWindowTextW(hwnd, empty.as_ptr());
+
+    Corrector::type_replacement(
+        "",
+        TYPED,
+        strategy,
+        TEST_KEY_DELAY_MS,
+        crate::corrector::PASTE_SETTLE_MS,
+        false,
+    );
+    pump_messages(Duration::from_millis(50));
+
+    Corrector::type_replacement(
+        TYPED,
+        EXPECTED,
+        strategy,
+        TEST_KEY_DELAY_MS,
+        crate::corrector::PASTE_SETTLE_MS,
+        false,
+    );
+    pump_messages(Duration::from_millis(100));
+
+    window_text(hwnd) == EXPECTED
+}
+
+/// Dispatch pending messages for `duration` so the edit control processes input.
+#[cfg(windows)]
+pub(crate) unsafe fn pump_messages(duration: Duration) {
+    let start = Instant::now();
+    let mut msg = std::mem::MaybeUninit::<MSG>::uninit();
+
+    while start.elapsed() < duration {
+        while PeekMessageW(msg.as_mut_ptr(), null_mut(), 0, 0, PM_REMOVE) != 0 {
+            TranslateMessage(msg.as_ptr());
+            DispatchMessageW(msg.as_ptr());
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[cfg(windows)]
+pub(crate) unsafe fn window_text(hwnd: HWND) -> String {
+    let mut buffer = [0u16; 256];
+    let len = GetWindowTextW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
+    if len <= 0 {
+        return String::new();
+    }
+    String::from_utf16_lossy(&buffer[..len as usize])
+}
@@ -0,0 +1,358 @@
+//! Per-session usage statistics and keyboard hook health counters.
+//!
+//! Counters start at zero each time the application launches. They are
+//! shown from the tray (middle-click the icon), and a snapshot is written to
+//! a status file while the application runs so `autocorrect status` can
+//! report on it from another process.
+//!
+//! Hook counters are atomics in a static so the hook callback and the
+//! keystroke injection code can update them without taking the corrector lock.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Health and performance counters for the keyboard hook.
+pub struct HookCounters {
+    key_events: AtomicU64,
+    callback_nanos: AtomicU64,
+    max_callback_nanos: AtomicU64,
+    injection_failures: AtomicU64,
+    hook_installs: AtomicU64,
+    corrections: AtomicU64,
+    correction_nanos: AtomicU64,
+    max_correction_nanos: AtomicU64,
+    slow_corrections: AtomicU64,
+}
+
+/// Counters for the running process.
+pub static HOOK_COUNTERS: HookCounters = HookCounters::new();
+
+impl HookCounters {
+    const fn new() -> Self {
+        Self {
+            key_events: AtomicU64::new(0),
+            callback_nanos: AtomicU64::new(0),
+            max_callback_nanos: AtomicU64::new(0),
+            injection_failures: AtomicU64::new(0),
+            hook_installs: AtomicU64::new(0),
+            corrections: AtomicU64::new(0),
+            correction_nanos: AtomicU64::new(0),
+            max_correction_nanos: AtomicU64::new(0),
+            slow_corrections: AtomicU64::new(0),
+        }
+    }
+
+    /// Count one hook callback and how long it took.
+    pub fn record_callback(&self, elapsed: Duration) {
+        let nanos = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+        self.key_events.fetch_add(1, Ordering::Relaxed);
+        self.callback_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.max_callback_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    /// Key events seen by the hook so far.
+    pub fn key_events(&self) -> u64 {
+        self.key_events.load(Ordering::Relaxed)
+    }
+
+    /// Count a keystroke that could not be injected.
+    pub fn record_injection_failure(&self) {
+        self.injection_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Keystrokes that could not be injected so far.
+    pub fn injection_failures(&self) -> u64 {
+        self.injection_failures.load(Ordering::Relaxed)
+    }
+
+    /// Count a successful hook installation (the first is not a reinstall).
+    pub fn record_hook_install(&self) {
+        self.hook_installs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count one correction (lookup plus typing the replacement) and how
+    /// long it took.
+    ///
+    /// Returns `true` if it took longer than `budget`.
+    pub fn record_correction(&self, elapsed: Duration, budget: Duration) -> bool {
+        let nanos = elapsed.as_nanos().min(u64::MAX as u128) as u64;
+        self.corrections.fetch_add(1, Ordering::Relaxed);
+        self.correction_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.max_correction_nanos
+            .fetch_max(nanos, Ordering::Relaxed);
+
+        let slow = elapsed > budget;
+        if slow {
+            self.slow_corrections.fetch_add(1, Ordering::Relaxed);
+        }
+        slow
+    }
+
+    /// Current counter values.
+    pub fn snapshot(&self) -> HookSnapshot {
+        let key_events = self.key_events.load(Ordering::Relaxed);
+        let total = self.callback_nanos.load(Ordering::Relaxed);
+        let corrections = self.corrections.load(Ordering::Relaxed);
+        let correction_total = self.correction_nanos.load(Ordering::Relaxed);
+
+        HookSnapshot {
+            key_events,
+            average_callback: Duration::from_nanos(total.checked_div(key_events).unwrap_or(0)),
+            max_callback: Duration::from_nanos(self.max_callback_nanos.load(Ordering::Relaxed)),
+            injection_failures: self.injection_failures.load(Ordering::Relaxed),
+            hook_reinstalls: self.hook_installs.load(Ordering::Relaxed).saturating_sub(1),
+            average_correction: Duration::from_nanos(
+                correction_total.checked_div(corrections).unwrap_or(0),
+            ),
+            max_correction: Duration::from_nanos(self.max_correction_nanos.load(Ordering::Relaxed)),
+            slow_corrections: self.slow_corrections.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A point-in-time copy of [`HookCounters`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HookSnapshot {
+    pub key_events: u64,
+    pub average_callback: Duration,
+    pub max_callback: Duration,
+    pub injection_failures: u64,
+    pub hook_reinstalls: u64,
+    pub average_correction: Duration,
+    pub max_correction: Duration,
+    /// Corrections that took longer than the configured latency budget.
+    pub slow_corrections: u64,
+}
+
+impl HookSnapshot {
+    /// Multi-line summary for the stats dialog and status command.
+    pub fn summary(&self) -> String {
+        format!(
+            "Key events: {}\nHook callback: {:.1} µs average, {:.1} µs max\nInjection failures: {}\nHook reinstalls: {}\nCorrection latency: {:.1} ms average, {:.1} ms max, {} over budget",
+            self.key_events,
+            self.average_callback.as_secs_f64() * 1_000_000.0,
+            self.max_callback.as_secs_f64() * 1_000_000.0,
+            self.injection_failures,
+            self.hook_reinstalls,
+            self.average_correction.as_secs_f64() * 1_000.0,
+            self.max_correction.as_secs_f64() * 1_000.0,
+            self.slow_corrections
+        )
+    }
+}
+
+/// Counters for the current session.
+#[derive(Debug, Clone)]
+pub struct SessionStats {
+    started: Instant,
+    /// Finished words that were checked against the dictionary.
+    pub words_checked: u64,
+    /// Corrections typed into the focused application.
+    pub corrections: u64,
+    /// Corrections reverted with Ctrl+Z.
+    pub undos: u64,
+    /// Corrections found but not typed because of dry-run mode.
+    pub dry_run_corrections: u64,
+    /// Day (see `tutor::today`) counted by `corrections_on_day`.
+    day: u64,
+    /// Corrections typed on `day`, for the widget.
+    corrections_on_day: u64,
+}
+
+impl SessionStats {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            words_checked: 0,
+            corrections: 0,
+            undos: 0,
+            dry_run_corrections: 0,
+            day: crate::tutor::today(),
+            corrections_on_day: 0,
+        }
+    }
+
+    /// Count a finished word and whether it was corrected.
+    ///
+    /// Nothing is counted while a sensitive app is in the foreground.
+    pub fn record_word(&mut self, corrected: bool) {
+        if !crate::privacy::recording_allowed() {
+            return;
+        }
+        self.words_checked += 1;
+        if corrected {
+            self.corrections += 1;
+
+            let today = crate::tutor::today();
+            if self.day != today {
+                self.day = today;
+                self.corrections_on_day = 0;
+            }
+            self.corrections_on_day += 1;
+        }
+    }
+
+    /// Corrections typed today while running.
+    pub fn corrections_today(&self) -> u64 {
+        if self.day == crate::tutor::today() {
+            self.corrections_on_day
+        } else {
+            0
+        }
+    }
+
+    /// Count a finished word that would have been corrected outside dry-run
+    /// mode.
+    pub fn record_dry_run_correction(&mut self) {
+        if !crate::privacy::recording_allowed() {
+            return;
+        }
+        self.words_checked += 1;
+        self.dry_run_corrections += 1;
+    }
+
+    /// Count an undone correction.
+    pub fn record_undo(&mut self) {
+        if !crate::privacy::recording_allowed() {
+            return;
+        }
+        self.undos += 1;
+    }
+
+    /// Multi-line summary for the stats dialog.
+    pub fn summary(&self) -> String {
+        let rate = if self.words_checked > 0 {
+            self.corrections as f64 * 100.0 / self.words_checked as f64
+        } else {
+            0.0
+        };
+
+        let mut summary = format!(
+            "Running for: {}\nWords checked: {}\nCorrections: {} ({:.1}%)\nUndone: {}",
+            format_duration(self.started.elapsed()),
+            self.words_checked,
+            self.corrections,
+            rate,
+            self.undos
+        );
+        if self.dry_run_corrections > 0 {
+            summary.push_str(&format!(
+                "\nNot applied (dry run): {}",
+                self.dry_run_corrections
+            ));
+        }
+        summary
+    }
+}
+
+/// Path of the status snapshot file.
+pub fn status_file() -> PathBuf {
+    crate::paths::local_file(crate::paths::STATUS_FILE)
+}
+
+/// Write the session and hook counters for `autocorrect status`.
+///
+/// # Errors
+/// Returns an error if the status file cannot be written.
+pub fn write_status_file(session: &SessionStats) -> std::io::Result<()> {
+    let updated = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    std::fs::write(
+        status_file(),
+        format!(
+            "updated {}\n{}\n{}\n",
+            updated,
+            session.summary(),
+            HOOK_COUNTERS.snapshot().summary()
+        ),
+    )
+}
+
+/// Remove the status file on shutdown, so `autocorrect status` knows we stopped.
+pub fn remove_status_file() {
+    let _ = std::fs::remove_file(status_file());
+}
+
+/// Read the status file written by a running instance.
+///
+/// Returns the counters text and how long ago it was written, or `None` if
+/// no instance has written one.
+pub fn read_status_file() -> Option<(String, Duration)> {
+    let contents = std::fs::read_to_string(status_file()).ok()?;
+    let (first_line, rest) = contents.split_once('\n')?;
+    let updated = first_line.strip_prefix("updated ")?.parse::<u64>().ok()?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some((
+        rest.trim_end().to_string(),
+        Duration::from_secs(now.saturating_sub(updated)),
+    ))
+}
+
+/// Format a duration as e.g. `2h 05m` or `7m`.
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_counts() {
+        let mut stats = SessionStats::new();
+        stats.record_word(true);
+        stats.record_word(false);
+        stats.record_word(false);
+        stats.record_word(false);
+        stats.record_undo();
+
+        let summary = stats.summary();
+        assert!(summary.contains("Words checked: 4"));
+        assert!(summary.contains("Corrections: 1 (25.0%)"));
+        assert!(summary.contains("Undone: 1"));
+        assert_eq!(stats.corrections_today(), 1);
+    }
+
+    #[test]
+    fn test_hook_counters() {
+        let counters = HookCounters::new();
+        counters.record_callback(Duration::from_micros(10));
+        counters.record_callback(Duration::from_micros(30));
+        counters.record_injection_failure();
+        counters.record_hook_install();
+        counters.record_hook_install();
+        let budget = Duration::from_millis(100);
+        assert!(!counters.record_correction(Duration::from_millis(40), budget));
+        assert!(counters.record_correction(Duration::from_millis(160), budget));
+
+        let snapshot = counters.snapshot();
+        assert_eq!(snapshot.key_events, 2);
+        assert_eq!(snapshot.average_callback, Duration::from_micros(20));
+        assert_eq!(snapshot.max_callback, Duration::from_micros(30));
+        assert_eq!(snapshot.injection_failures, 1);
+        assert_eq!(snapshot.hook_reinstalls, 1);
+        assert_eq!(snapshot.average_correction, Duration::from_millis(100));
+        assert_eq!(snapshot.max_correction, Duration::from_millis(160));
+        assert_eq!(snapshot.slow_corrections, 1);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(59)), "0m");
+        assert_eq!(format_duration(Duration::from_secs(7 * 60)), "7m");
+        assert_eq!(format_duration(Duration::from_secs(125 * 60)), "2h 05m");
+    }
+}
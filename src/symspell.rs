@@ -26,6 +26,11 @@
 //! - Lookup: O(1) average - constant time delete dictionary lookup
 //! - Space: O(n * edits) - stores delete mappings
 //!
+//! Candidates are reached through a delete of the input, so their length is
+//! always within `max_edit_distance` of the input's. Sharding the delete index
+//! by word length therefore skips nothing; `bench_lookup` in the tests
+//! measures lookup cost if the index layout changes.
+//!
 //! Supports Damerau-Levenshtein distance (includes transpositions).
 
 use crate::trigram::TrigramModel;
@@ -35,22 +40,85 @@ use std::cmp::Ordering;
 /// A spelling suggestion with edit distance and frequency information.
 #[derive(Debug, Clone)]
 pub struct SuggestItem {
-    /// The suggested (corrected) word.
+    /// The suggested (corrected) word, in dictionary (canonical) casing.
     pub term: String,
+    /// The term as it should be shown or typed: its canonical casing
+    /// ("iPhone", see [`SymSpell::set_casing`]), with the input's
+    /// capitalization once [`SuggestItem::transfer_case`] has applied it.
+    pub display_term: String,
     /// Edit distance from the input word (Damerau-Levenshtein).
     pub distance: i32,
     /// Frequency of this word in the dictionary (higher = more common).
     pub frequency: u64,
+    /// Edit distance with slips onto neighboring keys counted as part of an
+    /// edit (see [`crate::keyboard`]); `distance` until a layout scores it.
+    pub typing_distance: f64,
 }
 
 impl SuggestItem {
     fn new(term: String, distance: i32, frequency: u64) -> Self {
         Self {
+            display_term: term.clone(),
             term,
             distance,
             frequency,
+            typing_distance: distance as f64,
         }
     }
+
+    /// Frequency, boosted by how much of the edit distance is slips onto
+    /// neighboring keys. Orders suggestions at the same distance.
+    pub fn score(&self) -> f64 {
+        self.frequency as f64
+            * crate::keyboard::near_miss_boost(self.distance, self.typing_distance)
+    }
+
+    /// Order by distance, then by [`SuggestItem::score`], best first.
+    pub fn rank_order(a: &SuggestItem, b: &SuggestItem) -> Ordering {
+        a.distance
+            .cmp(&b.distance)
+            .then_with(|| b.score().total_cmp(&a.score()))
+    }
+
+    /// Apply the capitalization pattern of the typed word to `display_term`.
+    ///
+    /// "teh" keeps the canonical form, "Teh" capitalizes its first letter and
+    /// "TEH" uppercases it entirely.
+    pub fn transfer_case(&mut self, typed: &str) {
+        self.display_term = match_case(typed, &self.display_term);
+    }
+}
+
+/// Apply the capitalization pattern of `original` (lower, Title, UPPER) to `term`.
+///
+/// A term with capitals of its own ("iPhone", "McDonald") is canonical
+/// casing and is kept, unless `original` is all uppercase.
+pub fn match_case(original: &str, term: &str) -> String {
+    let mut chars = original.chars();
+    let first_upper = chars.next().is_some_and(|ch| ch.is_uppercase());
+
+    if first_upper && original.chars().count() > 1 && chars.all(|ch| ch.is_uppercase()) {
+        return term.to_uppercase();
+    }
+
+    if term.chars().any(char::is_uppercase) {
+        return term.to_string();
+    }
+
+    if first_upper {
+        let mut rest = term.chars();
+        return rest
+            .next()
+            .map(|first| first.to_uppercase().chain(rest).collect())
+            .unwrap_or_default();
+    }
+
+    term.to_string()
+}
+
+/// `word` with its accented letters typed plain ("über" -> "uber").
+fn strip_accents(word: &str) -> String {
+    word.chars().map(crate::keymap::strip_accent).collect()
 }
 
 /// SymSpell spell checker with pre-computed delete index.
@@ -68,6 +136,16 @@ pub struct SymSpell {
     deletes: AHashMap<String, Vec<String>>,
     /// Maximum edit distance to consider for corrections.
     max_edit_distance: i32,
+    /// Sum of all word frequencies, for word probabilities.
+    total_frequency: u64,
+    /// Length of the longest word, in characters.
+    max_word_length: usize,
+    /// Canonical casing of words that have one ("iphone" -> "iPhone"),
+    /// by their lowercase form.
+    casings: AHashMap<String, String>,
+    /// Most common accented word by its spelling without accents
+    /// ("cafe" -> "café"), for restoring diacritics.
+    unaccented: AHashMap<String, String>,
     /// Optional trigram model for context-aware scoring.
     pub trigram_model: Option<TrigramModel>,
 }
@@ -88,6 +166,10 @@ impl SymSpell {
             trigram_model: None,
             deletes: AHashMap::new(),
             max_edit_distance,
+            total_frequency: 0,
+            max_word_length: 0,
+            unaccented: AHashMap::new(),
+            casings: AHashMap::new(),
         }
     }
 
@@ -107,16 +189,62 @@ impl SymSpell {
     /// symspell.insert("hello".to_string(), 1000);
     /// ```
     pub fn insert(&mut self, word: String, frequency: u64) {
-        // Store the word
-        self.words.insert(word.clone(), frequency);
+        self.index(&word);
+        self.add_word(word, frequency);
+    }
+
+    /// Add a word without indexing its deletes.
+    ///
+    /// The word is recognized as correctly spelled, but can't be suggested
+    /// until [`SymSpell::index`] is called for it. Lets large dictionaries be
+    /// made usable before the (much slower) delete index is complete.
+    pub fn add_word(&mut self, word: String, frequency: u64) {
+        self.max_word_length = self.max_word_length.max(word.chars().count());
+        self.total_frequency += frequency;
+        let unaccented = strip_accents(&word);
+        if unaccented != word {
+            let more_common = self
+                .unaccented
+                .get(&unaccented)
+                .and_then(|accented| self.words.get(accented))
+                .is_none_or(|&current| frequency > current);
+            if more_common {
+                self.unaccented.insert(unaccented, word.clone());
+            }
+        }
+        if let Some(previous) = self.words.insert(word, frequency) {
+            self.total_frequency -= previous;
+        }
+    }
+
+    /// Record how `word` (lowercase) is written ("iPhone"); suggestions of
+    /// it are typed that way.
+    pub fn set_casing(&mut self, word: &str, casing: &str) {
+        self.casings.insert(word.to_string(), casing.to_string());
+    }
 
-        // Generate deletes for this word
-        let deletes = Self::generate_deletes(&word, self.max_edit_distance);
+    /// The canonical casing of `word` (lowercase), if it has one.
+    pub fn casing(&self, word: &str) -> Option<&str> {
+        self.casings.get(word).map(String::as_str)
+    }
+
+    /// A suggestion of `term`, displayed in its canonical casing.
+    fn suggestion(&self, term: String, distance: i32, frequency: u64) -> SuggestItem {
+        let mut item = SuggestItem::new(term, distance, frequency);
+        if let Some(casing) = self.casing(&item.term) {
+            item.display_term = casing.to_string();
+        }
+        item
+    }
+
+    /// Add a word's delete variations to the index so it can be suggested.
+    pub fn index(&mut self, word: &str) {
+        let deletes = Self::generate_deletes(word, self.max_edit_distance);
         for delete in deletes {
             self.deletes
                 .entry(delete)
                 .or_insert_with(Vec::new)
-                .push(word.clone());
+                .push(word.to_string());
         }
     }
 
@@ -149,7 +277,7 @@ impl SymSpell {
 
         // Check if input is in dictionary
         if let Some(&frequency) = self.words.get(input) {
-            suggestions.push(SuggestItem::new(input.to_string(), 0, frequency));
+            suggestions.push(self.suggestion(input.to_string(), 0, frequency));
             if max_edit_distance == 0 {
                 return suggestions;
             }
@@ -173,7 +301,7 @@ impl SymSpell {
 
                     if distance >= 0 && distance <= max_edit_distance {
                         if let Some(&frequency) = self.words.get(original) {
-                            suggestions.push(SuggestItem::new(
+                            suggestions.push(self.suggestion(
                                 original.clone(),
                                 distance,
                                 frequency,
@@ -203,6 +331,108 @@ impl SymSpell {
         suggestions
     }
 
+    /// Split a word typed without its space ("inthe") into two words.
+    ///
+    /// Each part is corrected on its own, and together they may take at most
+    /// `max_edit_distance` edits. The split with the fewest edits wins, then
+    /// the one whose words are most common. A one-letter part ("a lot") must
+    /// be at least as common as the other word, so a stray letter isn't split
+    /// off ("bcool" isn't "b cool").
+    ///
+    /// # Returns
+    /// The two words separated by a space, or `None` if no split works.
+    pub fn lookup_compound(&self, input: &str, max_edit_distance: i32) -> Option<SuggestItem> {
+        let mut best: Option<(SuggestItem, f64)> = None;
+
+        for (split, _) in input.char_indices().skip(1) {
+            let (left, right) = input.split_at(split);
+            let Some(left) = self
+                .lookup(left, max_edit_distance, None)
+                .into_iter()
+                .next()
+            else {
+                continue;
+            };
+            let Some(right) = self
+                .lookup(right, max_edit_distance, None)
+                .into_iter()
+                .next()
+            else {
+                continue;
+            };
+
+            let stray = |part: &SuggestItem, other: &SuggestItem| {
+                part.term.chars().count() == 1 && part.frequency < other.frequency
+            };
+            let distance = left.distance + right.distance;
+            if distance > max_edit_distance || stray(&left, &right) || stray(&right, &left) {
+                continue;
+            }
+
+            let score = left.frequency as f64 * right.frequency as f64;
+            let better = best.as_ref().is_none_or(|(item, best_score)| {
+                distance < item.distance || (distance == item.distance && score > *best_score)
+            });
+            if better {
+                let term = format!("{} {}", left.term, right.term);
+                let frequency = left.frequency.min(right.frequency);
+                let mut item = SuggestItem::new(term, distance, frequency);
+                item.display_term = format!("{} {}", left.display_term, right.display_term);
+                best = Some((item, score));
+            }
+        }
+
+        best.map(|(item, _)| item)
+    }
+
+    /// Split text typed without spaces into its most probable words.
+    ///
+    /// Each split point is chosen so the product of the words' probabilities
+    /// (frequency over the total) is highest, found left to right in one pass
+    /// (Viterbi). Text that isn't made of dictionary words is kept in chunks
+    /// whose probability falls with their length, so long unknown runs stay
+    /// whole rather than being cut into rare one-letter words.
+    ///
+    /// # Example
+    /// ```rust
+    /// let words = symspell.word_segmentation("throwbackthursday");
+    /// // Returns ["throwback", "thursday"]
+    /// ```
+    pub fn word_segmentation(&self, input: &str) -> Vec<String> {
+        let chars: Vec<char> = input.chars().collect();
+        let total = self.total_frequency.max(1) as f64;
+        let unknown = |length: usize| (10.0 / total).ln() - length as f64 * 10f64.ln();
+
+        // best[i]: log probability of the best split of the first i chars,
+        // and where its last word starts
+        let mut best: Vec<(f64, usize)> = vec![(f64::NEG_INFINITY, 0); chars.len() + 1];
+        best[0].0 = 0.0;
+        for end in 1..=chars.len() {
+            let first_start = end.saturating_sub(self.max_word_length.max(1));
+            for start in first_start..end {
+                let word: String = chars[start..end].iter().collect();
+                let log_probability = match self.words.get(&word) {
+                    Some(&frequency) => (frequency as f64 / total).ln(),
+                    None => unknown(end - start),
+                };
+                let score = best[start].0 + log_probability;
+                if score > best[end].0 {
+                    best[end] = (score, start);
+                }
+            }
+        }
+
+        let mut words = Vec::new();
+        let mut end = chars.len();
+        while end > 0 {
+            let start = best[end].1;
+            words.push(chars[start..end].iter().collect());
+            end = start;
+        }
+        words.reverse();
+        words
+    }
+
     /// Generate all possible delete variations of a word.
     ///
     /// Creates all strings that can be formed by deleting up to
@@ -351,6 +581,27 @@ impl SymSpell {
         }
     }
 
+    /// Check whether a word is in the dictionary (exact match).
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains_key(word)
+    }
+
+    /// The accented word `input` is typed without its accents ("cafe" ->
+    /// "café"), unless `input` is a word as it is ("como" next to "cómo").
+    pub fn restore_diacritics(&self, input: &str) -> Option<&str> {
+        if self.words.contains_key(input) {
+            return None;
+        }
+        self.unaccented
+            .get(&strip_accents(input))
+            .map(String::as_str)
+    }
+
+    /// A word's frequency, if it's in the dictionary (exact match).
+    pub fn frequency(&self, word: &str) -> Option<u64> {
+        self.words.get(word).copied()
+    }
+
     /// Get the number of words in the dictionary.
     ///
     /// # Returns
@@ -386,6 +637,155 @@ mod tests {
         assert_eq!(suggestions[0].distance, 1);
     }
 
+    #[test]
+    fn test_transfer_case() {
+        let mut item = SuggestItem::new("the".to_string(), 1, 100);
+        item.transfer_case("teh");
+        assert_eq!(item.display_term, "the");
+        item.transfer_case("Teh");
+        assert_eq!(item.display_term, "The");
+        item.transfer_case("TEH");
+        assert_eq!(item.display_term, "THE");
+        assert_eq!(item.term, "the");
+    }
+
+    #[test]
+    fn test_lookup_across_lengths() {
+        let mut symspell = SymSpell::new(2);
+        symspell.insert("hell".to_string(), 10);
+        symspell.insert("hello".to_string(), 10);
+        symspell.insert("helloo".to_string(), 10);
+        symspell.insert("hellooo".to_string(), 10);
+
+        let mut terms: Vec<String> = symspell
+            .lookup("hellp", 2, None)
+            .into_iter()
+            .map(|s| s.term)
+            .collect();
+        terms.sort();
+        assert_eq!(terms, ["hell", "hello", "helloo"]);
+    }
+
+    #[test]
+    fn test_lookup_compound() {
+        let mut symspell = SymSpell::new(2);
+        for (word, frequency) in [
+            ("in", 900),
+            ("int", 5),
+            ("the", 1000),
+            ("he", 800),
+            ("a", 950),
+            ("b", 10),
+            ("lot", 300),
+            ("cool", 200),
+        ] {
+            symspell.insert(word.to_string(), frequency);
+        }
+
+        let term = |input| symspell.lookup_compound(input, 0).map(|item| item.term);
+        assert_eq!(term("inthe").as_deref(), Some("in the"));
+        assert_eq!(term("alot").as_deref(), Some("a lot"));
+        assert_eq!(term("bcool"), None);
+        assert_eq!(term("inteh"), None);
+        assert_eq!(
+            symspell.lookup_compound("inteh", 1).map(|item| item.term),
+            Some("in the".to_string())
+        );
+    }
+
+    #[test]
+    fn test_word_segmentation() {
+        let mut symspell = SymSpell::new(2);
+        for (word, frequency) in [
+            ("throw", 50),
+            ("back", 300),
+            ("throwback", 20),
+            ("thursday", 40),
+            ("in", 900),
+            ("the", 1000),
+            ("a", 950),
+            ("b", 10),
+        ] {
+            symspell.insert(word.to_string(), frequency);
+        }
+
+        assert_eq!(
+            symspell.word_segmentation("throwbackthursday"),
+            ["throwback", "thursday"]
+        );
+        assert_eq!(symspell.word_segmentation("inthe"), ["in", "the"]);
+        assert_eq!(symspell.word_segmentation("the"), ["the"]);
+        assert!(symspell.word_segmentation("").is_empty());
+        // Unknown text stays in one piece
+        assert_eq!(symspell.word_segmentation("xyzzy"), ["xyzzy"]);
+    }
+
+    #[test]
+    fn test_restore_diacritics() {
+        let mut symspell = SymSpell::new(2);
+        for (word, frequency) in [
+            ("café", 50),
+            ("cafè", 1),
+            ("über", 80),
+            ("como", 90),
+            ("cómo", 70),
+        ] {
+            symspell.insert(word.to_string(), frequency);
+        }
+
+        assert_eq!(symspell.restore_diacritics("cafe"), Some("café"));
+        assert_eq!(symspell.restore_diacritics("uber"), Some("über"));
+        // A word as typed is left alone
+        assert_eq!(symspell.restore_diacritics("como"), None);
+        assert_eq!(symspell.restore_diacritics("cat"), None);
+    }
+
+    /// Lookup throughput over the embedded dictionary.
+    ///
+    /// Run with `cargo test --release bench_lookup -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_lookup() {
+        let words: Vec<&str> = include_str!("../dictionary/words.txt")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_whitespace().next())
+            .collect();
+
+        let mut symspell = SymSpell::new(2);
+        let start = std::time::Instant::now();
+        for (rank, word) in words.iter().enumerate() {
+            symspell.insert(word.to_string(), (words.len() - rank) as u64);
+        }
+        println!("insert: {} words in {:?}", words.len(), start.elapsed());
+
+        // Deterministic typos: drop the second letter of every 7th word
+        let typos: Vec<String> = words
+            .iter()
+            .step_by(7)
+            .filter(|word| word.chars().count() > 3)
+            .map(|word| {
+                let mut chars: Vec<char> = word.chars().collect();
+                chars.remove(1);
+                chars.into_iter().collect()
+            })
+            .collect();
+
+        let start = std::time::Instant::now();
+        let mut found = 0;
+        for typo in &typos {
+            found += symspell.lookup(typo, 2, None).len();
+        }
+        let elapsed = start.elapsed();
+        println!(
+            "lookup: {} typos in {:?} ({:?} each, {} suggestions)",
+            typos.len(),
+            elapsed,
+            elapsed / typos.len() as u32,
+            found
+        );
+    }
+
     #[test]
     fn test_distance() {
         let dist = SymSpell::damerau_levenshtein_distance("hello", "helo", 2);
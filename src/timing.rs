@@ -0,0 +1,82 @@
+//! Time-based rules for undo and corrections, configured under `[timing]`.
+//!
+//! - **Undo timeout**: how long after a correction Ctrl+Z can revert it
+//! - **Undo typing grace**: once this long has passed since a correction,
+//!   typing the next letter gives up the chance to undo it, so Ctrl+Z in the
+//!   middle of a new word goes to the application instead
+//! - **Correction cooldown**: how long after a correction the next word is
+//!   left alone, for people who type fast enough that back-to-back
+//!   corrections land out of order (off by default)
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// All time-based rules in one place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimingPolicy {
+    /// Seconds after a correction during which it can be undone.
+    pub undo_timeout_seconds: u64,
+    /// Seconds after a correction after which typing a letter forgets it.
+    pub undo_typing_grace_seconds: u64,
+    /// Milliseconds after a correction during which words aren't corrected.
+    pub correction_cooldown_ms: u64,
+}
+
+impl Default for TimingPolicy {
+    fn default() -> Self {
+        Self {
+            undo_timeout_seconds: 5,
+            undo_typing_grace_seconds: 2,
+            correction_cooldown_ms: 0,
+        }
+    }
+}
+
+impl TimingPolicy {
+    /// Whether a correction made at `corrected_at` can still be undone.
+    pub fn undo_allowed(&self, corrected_at: Instant, now: Instant) -> bool {
+        now.saturating_duration_since(corrected_at) < Duration::from_secs(self.undo_timeout_seconds)
+    }
+
+    /// Whether typing a letter now should forget a correction made at
+    /// `corrected_at`.
+    pub fn typing_expires_undo(&self, corrected_at: Instant, now: Instant) -> bool {
+        now.saturating_duration_since(corrected_at)
+            > Duration::from_secs(self.undo_typing_grace_seconds)
+    }
+
+    /// Whether a word finished now is too soon after the last correction.
+    pub fn in_cooldown(&self, last_correction: Option<Instant>, now: Instant) -> bool {
+        last_correction.is_some_and(|corrected_at| {
+            now.saturating_duration_since(corrected_at)
+                < Duration::from_millis(self.correction_cooldown_ms)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_rules() {
+        let policy = TimingPolicy {
+            correction_cooldown_ms: 300,
+            ..TimingPolicy::default()
+        };
+        let corrected = Instant::now();
+        let at = |millis| corrected + Duration::from_millis(millis);
+
+        assert!(policy.undo_allowed(corrected, at(4_900)));
+        assert!(!policy.undo_allowed(corrected, at(5_000)));
+
+        assert!(!policy.typing_expires_undo(corrected, at(1_500)));
+        assert!(policy.typing_expires_undo(corrected, at(2_500)));
+
+        assert!(policy.in_cooldown(Some(corrected), at(100)));
+        assert!(!policy.in_cooldown(Some(corrected), at(300)));
+        assert!(!policy.in_cooldown(None, at(100)));
+        assert!(!TimingPolicy::default().in_cooldown(Some(corrected), corrected));
+    }
+}
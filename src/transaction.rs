@@ -0,0 +1,287 @@
+//! Journal of a replacement being typed, so a failure halfway can be undone.
+//!
+//! A replacement is a run of backspaces over the original text followed by
+//! the new text, one key at a time. If injection fails partway (`SendInput`
+//! blocked by a higher-integrity window, the input desktop switching), the
+//! app would be left with a half-deleted word. Each key is journaled as it's
+//! sent; on failure, the keys that went through are reversed by deleting
+//! what was typed and re-typing what was deleted.
+//!
+//! With `SendInput`, all keys go out as one batch of [`Stroke`]s, so the
+//! user's own keystrokes can't land in the middle of a replacement. If only
+//! part of the batch is inserted, the keys whose strokes all went through are
+//! journaled and rolled back the same way.
+//!
+//! Apps that support it can have the original word deleted with a single
+//! Ctrl+Backspace instead of one backspace per character, which flickers
+//! less. It deletes the whole word or nothing, so it's journaled as such.
+//!
+//! If the foreground window changed instead, nothing is rolled back: the
+//! keys would go to the wrong window. The replacement just stops there.
+
+/// `VK_BACK`.
+const VK_BACK: u16 = 0x08;
+/// `VK_SHIFT`.
+const VK_SHIFT: u16 = 0x10;
+/// `VK_CONTROL`.
+const VK_CONTROL: u16 = 0x11;
+
+/// One key of a replacement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Backspace,
+    /// Ctrl+Backspace, deleting the whole original word.
+    DeleteWord,
+    Char(char),
+}
+
+/// A key press or release, as submitted to `SendInput`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stroke {
+    /// A virtual key.
+    Vk { vk: u16, up: bool },
+    /// A UTF-16 code unit, typed independently of the keyboard layout.
+    Unicode { unit: u16, up: bool },
+}
+
+impl Key {
+    /// The presses and releases that type this key.
+    ///
+    /// ASCII letters are typed as their virtual keys, with Shift for
+    /// capitals, so apps see ordinary key presses; anything else is sent as
+    /// Unicode, one code unit (two for characters outside the BMP) at a time.
+    pub fn strokes(self) -> Vec<Stroke> {
+        let tap = |vk| [Stroke::Vk { vk, up: false }, Stroke::Vk { vk, up: true }];
+        match self {
+            Key::Backspace => tap(VK_BACK).to_vec(),
+            Key::DeleteWord => {
+                let mut strokes = vec![Stroke::Vk {
+                    vk: VK_CONTROL,
+                    up: false,
+                }];
+                strokes.extend(tap(VK_BACK));
+                strokes.push(Stroke::Vk {
+                    vk: VK_CONTROL,
+                    up: true,
+                });
+                strokes
+            }
+            Key::Char(ch) if ch.is_ascii_alphabetic() => {
+                let letter = tap(ch.to_ascii_uppercase() as u16);
+                if ch.is_ascii_uppercase() {
+                    let mut strokes = vec![Stroke::Vk {
+                        vk: VK_SHIFT,
+                        up: false,
+                    }];
+                    strokes.extend(letter);
+                    strokes.push(Stroke::Vk {
+                        vk: VK_SHIFT,
+                        up: true,
+                    });
+                    strokes
+                } else {
+                    letter.to_vec()
+                }
+            }
+            Key::Char(ch) => {
+                let mut units = [0u16; 2];
+                ch.encode_utf16(&mut units)
+                    .iter()
+                    .flat_map(|&unit| {
+                        [
+                            Stroke::Unicode { unit, up: false },
+                            Stroke::Unicode { unit, up: true },
+                        ]
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Number of leading `keys` whose strokes were all inserted, when only the
+/// first `inserted` strokes of a batch went through.
+pub fn keys_completed(keys: &[Key], inserted: usize) -> usize {
+    let mut strokes = 0;
+    keys.iter()
+        .take_while(|key| {
+            strokes += key.strokes().len();
+            strokes <= inserted
+        })
+        .count()
+}
+
+/// Releases for the keys still held down after `strokes`, last pressed
+/// first: what a batch cut short has to send so Shift or Ctrl isn't left
+/// down.
+pub fn held_keys_released(strokes: &[Stroke]) -> Vec<Stroke> {
+    let mut held: Vec<Stroke> = Vec::new();
+    for &stroke in strokes {
+        let (pressed, up) = match stroke {
+            Stroke::Vk { vk, up } => (Stroke::Vk { vk, up: false }, up),
+            Stroke::Unicode { unit, up } => (Stroke::Unicode { unit, up: false }, up),
+        };
+        if up {
+            held.retain(|&key| key != pressed);
+        } else {
+            held.push(pressed);
+        }
+    }
+    held.into_iter()
+        .rev()
+        .map(|stroke| match stroke {
+            Stroke::Vk { vk, .. } => Stroke::Vk { vk, up: true },
+            Stroke::Unicode { unit, .. } => Stroke::Unicode { unit, up: true },
+        })
+        .collect()
+}
+
+/// A replacement of `original` (just before the caret) by `replacement`.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    original: &'a str,
+    replacement: &'a str,
+    /// Backspaces that went through.
+    deleted: usize,
+    /// Characters of the replacement that went through.
+    typed: usize,
+    /// Delete the original with Ctrl+Backspace.
+    delete_word: bool,
+}
+
+impl<'a> Transaction<'a> {
+    pub fn new(original: &'a str, replacement: &'a str) -> Self {
+        Self {
+            original,
+            replacement,
+            deleted: 0,
+            typed: 0,
+            delete_word: false,
+        }
+    }
+
+    /// A replacement that deletes `original` with one Ctrl+Backspace.
+    ///
+    /// Only right if the app's idea of a word covers all of `original`
+    /// (letters and digits only) and nothing else.
+    pub fn deleting_word(original: &'a str, replacement: &'a str) -> Self {
+        Self {
+            delete_word: true,
+            ..Self::new(original, replacement)
+        }
+    }
+
+    /// Every key of the replacement, in order.
+    pub fn keys(&self) -> Vec<Key> {
+        let deletion = if self.delete_word {
+            vec![Key::DeleteWord]
+        } else {
+            vec![Key::Backspace; self.original.chars().count()]
+        };
+        deletion
+            .into_iter()
+            .chain(self.replacement.chars().map(Key::Char))
+            .collect()
+    }
+
+    /// Journal a key that was sent successfully.
+    pub fn record(&mut self, key: Key) {
+        match key {
+            Key::Backspace => self.deleted += 1,
+            Key::DeleteWord => self.deleted = self.original.chars().count(),
+            Key::Char(_) => self.typed += 1,
+        }
+    }
+
+    /// Keys that restore the original text after the journaled ones.
+    pub fn rollback(&self) -> Vec<Key> {
+        let original_len = self.original.chars().count();
+        let restore = self
+            .original
+            .chars()
+            .skip(original_len - self.deleted.min(original_len))
+            .map(Key::Char);
+        std::iter::repeat_n(Key::Backspace, self.typed)
+            .chain(restore)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> Vec<Key> {
+        text.chars().map(Key::Char).collect()
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut transaction = Transaction::new("teh", "the");
+        let keys = transaction.keys();
+        assert_eq!(keys.len(), 6);
+
+        // Failed on the second backspace: put back the deleted "h"
+        transaction.record(keys[0]);
+        assert_eq!(transaction.rollback(), chars("h"));
+
+        // Failed while typing "th": remove it and retype the whole word
+        for key in &keys[1..5] {
+            transaction.record(*key);
+        }
+        let mut expected = vec![Key::Backspace; 2];
+        expected.extend(chars("teh"));
+        assert_eq!(transaction.rollback(), expected);
+
+        assert!(Transaction::new("teh", "the").rollback().is_empty());
+    }
+
+    #[test]
+    fn test_strokes() {
+        let vk = |vk, up| Stroke::Vk { vk, up };
+        assert_eq!(
+            Key::Char('T').strokes(),
+            vec![
+                vk(VK_SHIFT, false),
+                vk(0x54, false),
+                vk(0x54, true),
+                vk(VK_SHIFT, true)
+            ]
+        );
+        assert_eq!(Key::Char('é').strokes().len(), 2);
+        assert_eq!(Key::Char('😀').strokes().len(), 4);
+
+        // "teh" -> "The": 3 backspaces (2 strokes each), then 4 + 2 + 2
+        let keys = Transaction::new("teh", "The").keys();
+        assert_eq!(keys_completed(&keys, 0), 0);
+        assert_eq!(keys_completed(&keys, 5), 2);
+        assert_eq!(keys_completed(&keys, 9), 3);
+        assert_eq!(keys_completed(&keys, 10), 4);
+        assert_eq!(keys_completed(&keys, 14), 6);
+
+        // Cut short inside "T" (Shift and T down) or Ctrl+Backspace
+        let strokes: Vec<Stroke> = keys.iter().flat_map(|key| key.strokes()).collect();
+        assert!(held_keys_released(&strokes[..6]).is_empty());
+        assert_eq!(
+            held_keys_released(&strokes[..8]),
+            vec![vk(0x54, true), vk(VK_SHIFT, true)]
+        );
+        assert_eq!(
+            held_keys_released(&Key::DeleteWord.strokes()[..3]),
+            vec![vk(VK_CONTROL, true)]
+        );
+    }
+
+    #[test]
+    fn test_deleting_word() {
+        let mut transaction = Transaction::deleting_word("teh", "the");
+        let keys = transaction.keys();
+        assert_eq!(keys[0], Key::DeleteWord);
+        assert_eq!(keys.len(), 4);
+        assert_eq!(Key::DeleteWord.strokes().len(), 4);
+
+        // Failed after the word was deleted: type it back in full
+        transaction.record(keys[0]);
+        assert_eq!(transaction.rollback(), chars("teh"));
+    }
+}
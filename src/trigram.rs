@@ -24,8 +24,28 @@
 //! let mut model = TrigramModel::new();
 //! model.train(&["the quick brown fox", "the lazy dog"]);
 //! ```
+//!
+//! `autocorrect train-lm --input <folder>` trains it on the user's own
+//! `.txt` and `.md` files (see [`sentences`]) and saves it to
+//! `%LOCALAPPDATA%/Autocorrect/language_model.bin`, which the dictionary
+//! loads on startup.
+//!
+//! # File Format
+//!
+//! Little-endian binary: the magic bytes `ACLM`, a `u32` format version and
+//! the `u64` word total, then the unigram, bigram and trigram tables. Each
+//! table is a `u64` entry count followed by its entries; an entry is its
+//! words (each a `u32` byte length and UTF-8 bytes) and a `u64` count.
 
 use ahash::AHashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Start of a saved model file.
+const MAGIC: &[u8; 4] = b"ACLM";
+
+/// Version of the saved model format.
+const FORMAT_VERSION: u32 = 1;
 
 /// A trigram language model with backoff smoothing.
 ///
@@ -143,6 +163,207 @@ impl TrigramModel {
 
         1e-9 // Smoothing for unseen words
     }
+
+    /// Times `word` was seen right after `prev` (both lowercase).
+    pub fn bigram_count(&self, prev: &str, word: &str) -> u64 {
+        self.bigram_counts
+            .get(&(prev.to_string(), word.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Number of word tokens the model was trained on.
+    pub fn total_words(&self) -> u64 {
+        self.total_words
+    }
+
+    /// Save the model in the binary format described in the module docs.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut out = Vec::new();
+        out.write_all(MAGIC)?;
+        out.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        out.write_all(&self.total_words.to_le_bytes())?;
+
+        write_table(
+            &mut out,
+            self.unigram_counts.iter().map(|(w, &c)| ([w.as_str()], c)),
+        )?;
+        write_table(
+            &mut out,
+            self.bigram_counts
+                .iter()
+                .map(|((a, b), &c)| ([a.as_str(), b.as_str()], c)),
+        )?;
+        write_table(
+            &mut out,
+            self.trigram_counts
+                .iter()
+                .map(|((a, b, w), &c)| ([a.as_str(), b.as_str(), w.as_str()], c)),
+        )?;
+
+        crate::paths::write_atomically(path, &out)?;
+        Ok(())
+    }
+
+    /// Load a model saved by [`TrigramModel::save`].
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or isn't a model file of
+    /// a supported version.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let bytes = std::fs::read(path)?;
+        let mut input = bytes.as_slice();
+
+        let mut magic = [0u8; 4];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err("not a language model file".into());
+        }
+        let version = read_u32(&mut input)?;
+        if version != FORMAT_VERSION {
+            return Err(format!("unsupported language model version {}", version).into());
+        }
+
+        let mut model = Self::new();
+        model.total_words = read_u64(&mut input)?;
+        for _ in 0..read_u64(&mut input)? {
+            let [word] = read_words(&mut input)?;
+            model.unigram_counts.insert(word, read_u64(&mut input)?);
+        }
+        for _ in 0..read_u64(&mut input)? {
+            let [a, b] = read_words(&mut input)?;
+            model.bigram_counts.insert((a, b), read_u64(&mut input)?);
+        }
+        for _ in 0..read_u64(&mut input)? {
+            let [a, b, w] = read_words(&mut input)?;
+            model
+                .trigram_counts
+                .insert((a, b, w), read_u64(&mut input)?);
+        }
+        Ok(model)
+    }
+}
+
+/// Split text into sentences of lowercase words, for training.
+///
+/// Sentences end at `.`, `!`, `?` and line breaks (so Markdown headings and
+/// list items don't run into the next line). Words are runs of letters and
+/// apostrophes; digits, punctuation and markup separate them.
+pub fn sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+
+    for c in text.chars() {
+        if c.is_alphabetic() || is_apostrophe(c) {
+            word.push(c);
+        } else {
+            finish_word(&mut word, &mut words);
+        }
+
+        if matches!(c, '.' | '!' | '?' | '\n') && !words.is_empty() {
+            sentences.push(words.join(" "));
+            words.clear();
+        }
+    }
+
+    finish_word(&mut word, &mut words);
+    if !words.is_empty() {
+        sentences.push(words.join(" "));
+    }
+    sentences
+}
+
+/// `.txt` and `.md` files in `folder` and its subfolders, skipping hidden
+/// ones (such as `.git`).
+///
+/// # Errors
+/// Returns an error if a folder cannot be listed.
+pub fn corpus_files(folder: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![folder.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if hidden {
+                continue;
+            }
+
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| {
+                ext.eq_ignore_ascii_case("txt") || ext.eq_ignore_ascii_case("md")
+            }) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '’'
+}
+
+/// Move a word being built into `words`, without surrounding quotes.
+fn finish_word(word: &mut String, words: &mut Vec<String>) {
+    let trimmed = word.trim_matches(is_apostrophe);
+    if !trimmed.is_empty() {
+        words.push(trimmed.replace('’', "'").to_lowercase());
+    }
+    word.clear();
+}
+
+fn write_table<'a, const N: usize>(
+    out: &mut Vec<u8>,
+    entries: impl ExactSizeIterator<Item = ([&'a str; N], u64)>,
+) -> std::io::Result<()> {
+    out.write_all(&(entries.len() as u64).to_le_bytes())?;
+    for (words, count) in entries {
+        for word in words {
+            out.write_all(&(word.len() as u32).to_le_bytes())?;
+            out.write_all(word.as_bytes())?;
+        }
+        out.write_all(&count.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_u32(input: &mut &[u8]) -> std::io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(input: &mut &[u8]) -> std::io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_words<const N: usize>(
+    input: &mut &[u8],
+) -> Result<[String; N], Box<dyn std::error::Error>> {
+    let mut words: [String; N] = std::array::from_fn(|_| String::new());
+    for word in &mut words {
+        let len = read_u32(input)? as usize;
+        if len > input.len() {
+            return Err("truncated language model file".into());
+        }
+        let (bytes, rest) = input.split_at(len);
+        *word = String::from_utf8(bytes.to_vec())?;
+        *input = rest;
+    }
+    Ok(words)
 }
 
 #[cfg(test)]
@@ -163,4 +384,35 @@ mod tests {
         let p = model.trigram_probability("fox", "quick", "the");
         assert!(p > 0.0);
     }
+
+    #[test]
+    fn test_train_save_load() {
+        let text = "# Release notes\nThe build’s fixed. Deploy the build!\n\n- rerun 2 tests";
+        assert_eq!(
+            sentences(text),
+            [
+                "release notes",
+                "the build's fixed",
+                "deploy the build",
+                "rerun tests"
+            ]
+        );
+
+        let mut model = TrigramModel::new();
+        let corpus = sentences(text);
+        model.train(&corpus.iter().map(String::as_str).collect::<Vec<_>>());
+
+        let path =
+            std::env::temp_dir().join(format!("autocorrect-model-{}.bin", std::process::id()));
+        model.save(&path).unwrap();
+        let loaded = TrigramModel::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded.total_words(), 10);
+        assert_eq!(
+            loaded.trigram_probability("build", "the", "deploy"),
+            model.trigram_probability("build", "the", "deploy")
+        );
+        assert_eq!(loaded.trigram_probability("build", "the", "deploy"), 1.0);
+    }
 }
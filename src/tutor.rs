@@ -0,0 +1,325 @@
+//! Weekly "typing tutor" report of the user's systematic misspellings.
+//!
+//! Every correction that isn't undone is counted per day in a mistake log.
+//! Once a week, words corrected at least [`MIN_REPEATS`] times in the past
+//! seven days are written to an HTML report in the local data directory, grouped
+//! by the correct spelling, so users can learn them instead of relying on
+//! corrections.
+//!
+//! # File Format
+//!
+//! The log is stored in `%LOCALAPPDATA%/Autocorrect/mistakes.txt` and keeps
+//! [`HISTORY_DAYS`] days:
+//! ```text
+//! # typo correction day count
+//! teh the 20371 3
+//! ```
+//! `day` counts days since the Unix epoch.
+
+use ahash::AHashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Days of history kept in the log.
+const HISTORY_DAYS: u64 = 56;
+
+/// Days covered by a report, and between reports.
+pub const REPORT_PERIOD_DAYS: u64 = 7;
+
+/// Corrections of the same word needed in a week to appear in the report.
+pub const MIN_REPEATS: u32 = 2;
+
+/// Most correct forms listed in a report.
+const MAX_REPORTED_WORDS: usize = 25;
+
+/// Number of recorded corrections after which the log is due to be flushed
+/// to disk (see `flush_if_due`).
+const FLUSH_EVERY: u32 = 20;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// A word the user keeps misspelling.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mistake {
+    /// The correct spelling.
+    pub correction: String,
+    /// How it was typed, most frequent first, with counts.
+    pub typos: Vec<(String, u32)>,
+    /// Total corrections to this word.
+    pub total: u32,
+}
+
+/// Persistent per-day counts of corrections.
+pub struct MistakeLog {
+    /// `(typo, correction, day)` to count.
+    entries: AHashMap<(String, String, u64), u32>,
+    path: PathBuf,
+    /// Corrections recorded since the last flush.
+    unsaved: u32,
+}
+
+impl MistakeLog {
+    /// Create an empty log backed by the default file location.
+    pub fn new() -> Self {
+        Self::with_path(crate::paths::local_file(crate::paths::MISTAKES_FILE))
+    }
+
+    /// Create an empty log backed by a specific file.
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            entries: AHashMap::new(),
+            path,
+            unsaved: 0,
+        }
+    }
+
+    /// Load the log from disk. A missing file is not an error.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read.
+    pub fn load(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+
+        let reader = BufReader::new(File::open(&self.path)?);
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let [typo, correction, day, count] = parts[..] else {
+                continue;
+            };
+            let (Ok(day), Ok(count)) = (day.parse::<u64>(), count.parse::<u32>()) else {
+                continue;
+            };
+            self.entries
+                .insert((typo.to_string(), correction.to_string(), day), count);
+        }
+
+        Ok(())
+    }
+
+    /// Count a correction made today, in memory (see `flush_if_due`).
+    ///
+    /// Nothing is recorded while a sensitive app is in the foreground.
+    pub fn record(&mut self, typo: &str, correction: &str) {
+        if !crate::privacy::recording_allowed() {
+            return;
+        }
+
+        let key = (typo.to_lowercase(), correction.to_lowercase(), today());
+        *self.entries.entry(key).or_insert(0) += 1;
+
+        self.unsaved += 1;
+    }
+
+    /// Write the log to disk once `FLUSH_EVERY` corrections have been
+    /// counted since the last write. Returns `true` if written.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn flush_if_due(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        if self.unsaved < FLUSH_EVERY {
+            return Ok(false);
+        }
+        self.flush()?;
+        Ok(true)
+    }
+
+    /// Take back a correction counted today (it was undone, so it wasn't a
+    /// misspelling).
+    pub fn forget(&mut self, typo: &str, correction: &str) {
+        let key = (typo.to_lowercase(), correction.to_lowercase(), today());
+        if let Some(count) = self.entries.get_mut(&key) {
+            *count -= 1;
+            if *count == 0 {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    /// Drop old days and write the log to disk.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let oldest = today().saturating_sub(HISTORY_DAYS);
+        self.entries.retain(|(_, _, day), _| *day >= oldest);
+
+        let mut contents = Vec::new();
+        writeln!(contents, "# Corrections per day (managed by Autocorrect)")?;
+        writeln!(contents, "# typo correction day count")?;
+        for ((typo, correction, day), count) in &self.entries {
+            writeln!(contents, "{} {} {} {}", typo, correction, day, count)?;
+        }
+        crate::paths::write_atomically(&self.path, &contents)?;
+
+        self.unsaved = 0;
+        Ok(())
+    }
+
+    /// The first day with any recorded correction.
+    pub fn first_day(&self) -> Option<u64> {
+        self.entries.keys().map(|(_, _, day)| *day).min()
+    }
+
+    /// Words corrected at least [`MIN_REPEATS`] times in the report period
+    /// ending on `today`, most corrected first.
+    pub fn weekly_mistakes(&self, today: u64) -> Vec<Mistake> {
+        let start = today.saturating_sub(REPORT_PERIOD_DAYS - 1);
+        let mut by_correction: AHashMap<&str, AHashMap<&str, u32>> = AHashMap::new();
+
+        for ((typo, correction, day), count) in &self.entries {
+            if (start..=today).contains(day) {
+                *by_correction
+                    .entry(correction.as_str())
+                    .or_default()
+                    .entry(typo.as_str())
+                    .or_insert(0) += count;
+            }
+        }
+
+        let mut mistakes: Vec<Mistake> = by_correction
+            .into_iter()
+            .map(|(correction, typos)| {
+                let mut typos: Vec<(String, u32)> = typos
+                    .into_iter()
+                    .map(|(typo, count)| (typo.to_string(), count))
+                    .collect();
+                typos.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                Mistake {
+                    correction: correction.to_string(),
+                    total: typos.iter().map(|(_, count)| count).sum(),
+                    typos,
+                }
+            })
+            .filter(|mistake| mistake.total >= MIN_REPEATS)
+            .collect();
+
+        mistakes.sort_by(|a, b| {
+            b.total
+                .cmp(&a.total)
+                .then_with(|| a.correction.cmp(&b.correction))
+        });
+        mistakes.truncate(MAX_REPORTED_WORDS);
+        mistakes
+    }
+}
+
+/// Today, in days since the Unix epoch.
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or(0)
+}
+
+/// Path of the HTML report.
+pub fn report_path() -> PathBuf {
+    crate::paths::local_file(crate::paths::TYPING_REPORT_FILE)
+}
+
+/// Whether a new report is due on `today`.
+///
+/// Reports are a week apart. Before the first report, the log must cover a
+/// full week.
+pub fn report_due(report: &Path, log: &MistakeLog, today: u64) -> bool {
+    let last_report_day = std::fs::metadata(report)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|age| age.as_secs() / SECONDS_PER_DAY);
+
+    match last_report_day.or_else(|| log.first_day()) {
+        Some(day) => today >= day + REPORT_PERIOD_DAYS,
+        None => false,
+    }
+}
+
+/// Render a report as a standalone HTML page.
+pub fn render_html(mistakes: &[Mistake]) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Your weekly typing report</title>\n\
+         <style>body{font-family:Segoe UI,sans-serif;max-width:40em;margin:2em auto}\
+         td,th{padding:.3em 1em;text-align:left}.typo{color:#b00;text-decoration:line-through}</style>\n\
+         </head><body>\n<h1>Your weekly typing report</h1>\n",
+    );
+
+    if mistakes.is_empty() {
+        html.push_str("<p>No repeated misspellings this week. Nice typing!</p>\n");
+    } else {
+        html.push_str(
+            "<p>These words were corrected most often in the past seven days. \
+             Practising them helps you need fewer corrections.</p>\n\
+             <table>\n<tr><th>Correct spelling</th><th>You typed</th><th>Times</th></tr>\n",
+        );
+        for mistake in mistakes {
+            let typos: Vec<String> = mistake
+                .typos
+                .iter()
+                .map(|(typo, count)| {
+                    format!(
+                        "<span class=\"typo\">{}</span> ({})",
+                        escape_html(typo),
+                        count
+                    )
+                })
+                .collect();
+            html.push_str(&format!(
+                "<tr><td><b>{}</b></td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&mistake.correction),
+                typos.join(", "),
+                mistake.total
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body></html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weekly_mistakes() {
+        let mut log = MistakeLog::with_path(PathBuf::new());
+        let today = 20_000;
+        for (typo, correction, day, count) in [
+            ("teh", "the", today, 3),
+            ("hte", "the", today - 2, 1),
+            ("recieve", "receive", today, 2),
+            ("adn", "and", today, 1),
+            ("wierd", "weird", today - 10, 5),
+        ] {
+            log.entries
+                .insert((typo.to_string(), correction.to_string(), day), count);
+        }
+
+        let mistakes = log.weekly_mistakes(today);
+        let corrections: Vec<&str> = mistakes.iter().map(|m| m.correction.as_str()).collect();
+        assert_eq!(corrections, ["the", "receive"]);
+        assert_eq!(mistakes[0].total, 4);
+        assert_eq!(mistakes[0].typos[0], ("teh".to_string(), 3));
+
+        let html = render_html(&mistakes);
+        assert!(html.contains("<b>receive</b>"));
+        assert!(!html.contains("wierd"));
+    }
+}
@@ -0,0 +1,80 @@
+//! Typographic replacements typed as you go: curly quotes, em dashes and
+//! ellipses.
+//!
+//! With `typography = true`, a straight quote is typed as the curly quote
+//! that fits where it is (“opening” after a space or bracket, ”closing”
+//! otherwise, and ’ as an apostrophe), `--` becomes an em dash and `...` an
+//! ellipsis. The key's own character is swallowed and the replacement typed
+//! instead, before the word it ends reaches the dictionary, so "don’t" is
+//! tracked (and looked up) as typed.
+//!
+//! Code needs its quotes straight: editors and terminals are listed in
+//! `[apps] no_typography`.
+
+/// Code editors and terminals, where straight quotes and `--` are code
+/// (`[apps] no_typography`).
+pub const DEFAULT_NO_TYPOGRAPHY_APPS: &[&str] = &[
+    "code.exe",
+    "cursor.exe",
+    "devenv.exe",
+    "idea64.exe",
+    "pycharm64.exe",
+    "rider64.exe",
+    "sublime_text.exe",
+    "notepad++.exe",
+    "zed.exe",
+    "windowsterminal.exe",
+    "cmd.exe",
+    "powershell.exe",
+    "pwsh.exe",
+    "wezterm-gui.exe",
+];
+
+/// Characters after which a quote opens.
+const OPENS_AFTER: &str = "([{<“‘—–-/";
+
+/// The typographic form of `typed`, typed after `before` (the text since
+/// the last space): the straight characters at the end of `before` it
+/// replaces, and the character that replaces them and `typed`.
+pub fn replace(before: &str, typed: char) -> Option<(&'static str, char)> {
+    let opens = before
+        .chars()
+        .last()
+        .is_none_or(|previous| previous.is_whitespace() || OPENS_AFTER.contains(previous));
+    match typed {
+        '"' if opens => Some(("", '“')),
+        '"' => Some(("", '”')),
+        '\'' if opens => Some(("", '‘')),
+        '\'' => Some(("", '’')),
+        '-' if before.ends_with('-') => Some(("-", '—')),
+        '.' if before.ends_with("..") => Some(("..", '…')),
+        _ => None,
+    }
+}
+
+/// `text` with its straight apostrophes curled, for corrections typed where
+/// typography is on ("dont" -> "don’t").
+pub fn curl_apostrophes(text: &str) -> String {
+    text.replace('\'', "’")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace() {
+        assert_eq!(replace("", '"'), Some(("", '“')));
+        assert_eq!(replace("(", '"'), Some(("", '“')));
+        assert_eq!(replace("“Hello", '"'), Some(("", '”')));
+        assert_eq!(replace("", '\''), Some(("", '‘')));
+        assert_eq!(replace("don", '\''), Some(("", '’')));
+        assert_eq!(replace("well-", '-'), Some(("-", '—')));
+        assert_eq!(replace("well", '-'), None);
+        assert_eq!(replace("wait..", '.'), Some(("..", '…')));
+        assert_eq!(replace("wait.", '.'), None);
+        assert_eq!(replace("a", 'b'), None);
+
+        assert_eq!(curl_apostrophes("don't"), "don’t");
+    }
+}
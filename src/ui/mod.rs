@@ -0,0 +1,6 @@
+//! Shared helpers for popup and overlay windows.
+
+#[cfg(windows)]
+pub mod notify;
+pub mod positioning;
+pub mod widget;
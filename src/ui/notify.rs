@@ -0,0 +1,135 @@
+//! Notifications that don't interrupt typing: a balloon (a toast on Windows
+//! 10 and later) from a short-lived notification area icon.
+//!
+//! Unlike a message box, nothing waits for the user and no window takes
+//! focus. Each notification has its own thread and hidden window, and its
+//! icon is removed once the balloon is clicked, dismissed or times out.
+
+use std::cell::RefCell;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr::{null, null_mut};
+use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::HWND;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::shellapi::*;
+use winapi::um::winuser::*;
+
+/// What a click on the notification does.
+pub type OnClick = Box<dyn FnOnce() + Send>;
+
+const CLASS_NAME: &str = "AutocorrectNotification";
+
+/// Message the notification icon sends its window.
+const CALLBACK_MESSAGE: UINT = WM_APP + 1;
+
+/// How long the icon stays if the balloon never reports back.
+const LIFETIME_MS: UINT = 60_000;
+
+thread_local! {
+    /// The click action of the notification shown on this thread.
+    static ON_CLICK: RefCell<Option<OnClick>> = const { RefCell::new(None) };
+}
+
+/// Show `message` in a balloon titled `title`, running `on_click` if the
+/// user clicks it. Returns at once.
+pub fn show(title: &str, message: &str, on_click: Option<OnClick>) {
+    let (title, message) = (title.to_string(), message.to_string());
+    std::thread::spawn(move || unsafe { run(&title, &message, on_click) });
+}
+
+/// Copy `text` into a fixed-size UTF-16 buffer, truncated to fit with its
+/// terminating nul.
+fn copy_wide(buffer: &mut [u16], text: &str) {
+    let units: Vec<u16> = OsStr::new(text).encode_wide().collect();
+    let len = units.len().min(buffer.len() - 1);
+    buffer[..len].copy_from_slice(&units[..len]);
+    buffer[len] = 0;
+}
+
+unsafe fn run(title: &str, message: &str, on_click: Option<OnClick>) {
+    let class_name: Vec<u16> = OsStr::new(CLASS_NAME)
+        .encode_wide()
+        .chain(Some(0))
+        .collect();
+    let instance = GetModuleHandleW(null());
+    let mut class: WNDCLASSW = std::mem::zeroed();
+    class.lpfnWndProc = Some(window_proc);
+    class.hInstance = instance;
+    class.lpszClassName = class_name.as_ptr();
+    // Fails harmlessly once the class is registered
+    RegisterClassW(&class);
+
+    let hwnd = CreateWindowExW(
+        0,
+        class_name.as_ptr(),
+        null(),
+        0,
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE,
+        null_mut(),
+        instance,
+        null_mut(),
+    );
+    if hwnd.is_null() {
+        eprintln!("Failed to show notification: {}", message);
+        return;
+    }
+    ON_CLICK.with(|action| *action.borrow_mut() = on_click);
+
+    let mut data: NOTIFYICONDATAW = std::mem::zeroed();
+    data.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+    data.hWnd = hwnd;
+    data.uID = 1;
+    data.uFlags = NIF_MESSAGE | NIF_ICON | NIF_TIP | NIF_INFO;
+    data.uCallbackMessage = CALLBACK_MESSAGE;
+    data.hIcon = LoadIconW(null_mut(), IDI_INFORMATION);
+    data.dwInfoFlags = NIIF_INFO;
+    copy_wide(&mut data.szTip, title);
+    copy_wide(&mut data.szInfoTitle, title);
+    copy_wide(&mut data.szInfo, message);
+
+    if Shell_NotifyIconW(NIM_ADD, &mut data) == 0 {
+        eprintln!("Failed to show notification: {}", message);
+    } else {
+        SetTimer(hwnd, 1, LIFETIME_MS, None);
+        let mut msg: MSG = std::mem::zeroed();
+        while GetMessageW(&mut msg, null_mut(), 0, 0) > 0 {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+        Shell_NotifyIconW(NIM_DELETE, &mut data);
+    }
+    DestroyWindow(hwnd);
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        CALLBACK_MESSAGE => {
+            match lparam as UINT {
+                NIN_BALLOONUSERCLICK | WM_LBUTTONUP => {
+                    if let Some(action) = ON_CLICK.with(|action| action.borrow_mut().take()) {
+                        action();
+                    }
+                    PostQuitMessage(0);
+                }
+                NIN_BALLOONTIMEOUT | NIN_BALLOONHIDE => PostQuitMessage(0),
+                _ => {}
+            }
+            0
+        }
+        WM_TIMER => {
+            PostQuitMessage(0);
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
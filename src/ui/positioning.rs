@@ -0,0 +1,210 @@
+//! DPI- and monitor-aware placement of popups and overlays.
+//!
+//! Every overlay should be positioned through these helpers so it behaves
+//! the same on multi-monitor setups with mixed scaling:
+//!
+//! 1. Look up the monitor it belongs on, its work area and effective DPI
+//!    ([`monitor_at`])
+//! 2. Scale the overlay's logical size to that DPI
+//! 3. Place it inside the work area, never under the taskbar
+//!    ([`place_in_corner`])
+//!
+//! The caret of the focused window, in the same coordinates, is
+//! [`system_caret_rect`].
+//!
+//! All coordinates are physical pixels. This is only correct when the
+//! process is per-monitor DPI aware, which [`enable_per_monitor_dpi_awareness`]
+//! sets up at startup.
+
+#[cfg(windows)]
+use std::ptr::null_mut;
+#[cfg(windows)]
+use winapi::shared::windef::{POINT, RECT};
+#[cfg(windows)]
+use winapi::um::shellscalingapi::{
+    GetDpiForMonitor, SetProcessDpiAwareness, MDT_EFFECTIVE_DPI, PROCESS_PER_MONITOR_DPI_AWARE,
+};
+#[cfg(windows)]
+use winapi::um::winuser::*;
+
+/// DPI at which logical sizes equal physical pixels (100% scaling).
+pub const BASE_DPI: u32 = 96;
+
+/// Logical gap between a corner overlay and the edges of the work area.
+const CORNER_MARGIN: i32 = 12;
+
+/// A rectangle in physical screen coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScreenRect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl ScreenRect {
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+}
+
+/// The parts of a monitor that matter for placing a popup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorMetrics {
+    /// Monitor area excluding the taskbar and docked toolbars.
+    pub work_area: ScreenRect,
+    /// Effective DPI (96 = 100% scaling).
+    pub dpi: u32,
+}
+
+/// Convert a logical size (designed at 96 DPI) to physical pixels.
+pub fn scale(logical: i32, dpi: u32) -> i32 {
+    (logical as i64 * dpi as i64 / BASE_DPI as i64) as i32
+}
+
+/// Place an overlay of `logical_size` in the bottom-right corner of the
+/// monitor's work area, clear of the taskbar.
+pub fn place_in_corner(logical_size: (i32, i32), monitor: &MonitorMetrics) -> ScreenRect {
+    let area = monitor.work_area;
+    let margin = scale(CORNER_MARGIN, monitor.dpi);
+    let width = scale(logical_size.0, monitor.dpi).min(area.width() - margin * 2);
+    let height = scale(logical_size.1, monitor.dpi).min(area.height() - margin * 2);
+
+    let right = area.right - margin;
+    let bottom = area.bottom - margin;
+    ScreenRect {
+        left: right - width,
+        top: bottom - height,
+        right,
+        bottom,
+    }
+}
+
+/// Make window coordinates physical pixels on every monitor.
+///
+/// Must be called before any window is created. Failure (e.g. awareness
+/// already set by a manifest) is harmless and ignored.
+#[cfg(windows)]
+pub fn enable_per_monitor_dpi_awareness() {
+    unsafe {
+        SetProcessDpiAwareness(PROCESS_PER_MONITOR_DPI_AWARE);
+    }
+}
+
+/// Screen rectangle of the foreground window's system caret, or `None` if
+/// the app doesn't report one.
+#[cfg(windows)]
+pub fn system_caret_rect() -> Option<ScreenRect> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+
+        let thread_id = GetWindowThreadProcessId(hwnd, null_mut());
+        let mut info: GUITHREADINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<GUITHREADINFO>() as u32;
+        if GetGUIThreadInfo(thread_id, &mut info) == 0 || info.hwndCaret.is_null() {
+            return None;
+        }
+
+        let mut top_left = POINT {
+            x: info.rcCaret.left,
+            y: info.rcCaret.top,
+        };
+        let mut bottom_right = POINT {
+            x: info.rcCaret.right,
+            y: info.rcCaret.bottom,
+        };
+        ClientToScreen(info.hwndCaret, &mut top_left);
+        ClientToScreen(info.hwndCaret, &mut bottom_right);
+
+        Some(ScreenRect {
+            left: top_left.x,
+            top: top_left.y,
+            right: bottom_right.x,
+            bottom: bottom_right.y,
+        })
+    }
+}
+
+/// Work area and DPI of the monitor nearest to (`x`, `y`).
+#[cfg(windows)]
+pub fn monitor_at(x: i32, y: i32) -> MonitorMetrics {
+    unsafe {
+        let monitor = MonitorFromPoint(POINT { x, y }, MONITOR_DEFAULTTONEAREST);
+
+        let mut info: MONITORINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+        let RECT {
+            left,
+            top,
+            right,
+            bottom,
+        } = if GetMonitorInfoW(monitor, &mut info) != 0 {
+            info.rcWork
+        } else {
+            RECT {
+                left: 0,
+                top: 0,
+                right: GetSystemMetrics(SM_CXSCREEN),
+                bottom: GetSystemMetrics(SM_CYSCREEN),
+            }
+        };
+
+        let (mut dpi_x, mut dpi_y) = (BASE_DPI, BASE_DPI);
+        if GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) < 0 {
+            dpi_x = BASE_DPI;
+        }
+
+        MonitorMetrics {
+            work_area: ScreenRect {
+                left,
+                top,
+                right,
+                bottom,
+            },
+            dpi: dpi_x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(left: i32, top: i32, right: i32, bottom: i32, dpi: u32) -> MonitorMetrics {
+        MonitorMetrics {
+            work_area: ScreenRect {
+                left,
+                top,
+                right,
+                bottom,
+            },
+            dpi,
+        }
+    }
+
+    #[test]
+    fn test_corner_clears_taskbar() {
+        // Taskbar at the bottom of a 150% monitor
+        let rect = place_in_corner((200, 28), &monitor(0, 0, 1920, 1032, 144));
+        assert_eq!(rect.right, 1920 - 18);
+        assert_eq!(rect.bottom, 1032 - 18);
+        assert_eq!(rect.width(), 300);
+        assert_eq!(rect.height(), 42);
+    }
+
+    #[test]
+    fn test_corner_of_secondary_monitor() {
+        // Monitor to the left of the primary, with negative coordinates
+        let rect = place_in_corner((200, 50), &monitor(-2560, 0, 0, 1400, 192));
+        assert_eq!(rect.right, -24);
+        assert_eq!(rect.left, -424);
+        assert_eq!(rect.bottom, 1400 - 24);
+    }
+}
@@ -0,0 +1,243 @@
+//! The optional always-on-top widget (`show_widget`): today's correction
+//! count at a glance, and a click to pause or resume.
+//!
+//! It sits in the bottom-right corner of the primary monitor's work area
+//! (see [`place_in_corner`](super::positioning::place_in_corner)), never
+//! takes focus, so typing continues in the app underneath, and is left out of
+//! the taskbar and Alt+Tab.
+
+/// Logical size of the widget.
+pub const WIDGET_SIZE: (i32, i32) = (200, 28);
+
+/// Whether corrections are being made, as far as the widget is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidgetStatus {
+    Active,
+    /// Paused for any reason (the widget, a hotkey, an excluded window...).
+    Paused,
+    /// Disabled from the tray or at startup.
+    Off,
+}
+
+/// What the widget shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WidgetState {
+    pub status: WidgetStatus,
+    pub corrections_today: u64,
+}
+
+impl WidgetState {
+    pub fn label(&self) -> String {
+        let count = self.corrections_today;
+        match self.status {
+            WidgetStatus::Active if count == 1 => "1 correction today".to_string(),
+            WidgetStatus::Active => format!("{} corrections today", count),
+            WidgetStatus::Paused => format!("Paused · {} today", count),
+            WidgetStatus::Off => format!("Off · {} today", count),
+        }
+    }
+}
+
+#[cfg(windows)]
+pub use window::Widget;
+
+#[cfg(windows)]
+mod window {
+    use super::super::positioning::{monitor_at, place_in_corner};
+    use super::{WidgetState, WidgetStatus, WIDGET_SIZE};
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use std::ptr::{null, null_mut};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
+    use winapi::shared::windef::{HWND, RECT};
+    use winapi::um::libloaderapi::GetModuleHandleW;
+    use winapi::um::wingdi::*;
+    use winapi::um::winuser::*;
+
+    const CLASS_NAME: &str = "AutocorrectWidget";
+
+    /// Opacity, out of 255.
+    const ALPHA: u8 = 230;
+
+    const ACTIVE_COLOR: (u8, u8, u8) = (30, 120, 30);
+    const PAUSED_COLOR: (u8, u8, u8) = (90, 90, 90);
+
+    /// Whether the widget is drawn in the active color.
+    static ACTIVE: AtomicBool = AtomicBool::new(true);
+
+    fn wide(text: &str) -> Vec<u16> {
+        OsStr::new(text).encode_wide().chain(Some(0)).collect()
+    }
+
+    /// The widget window. Destroyed when dropped.
+    pub struct Widget {
+        hwnd: HWND,
+        shown: Option<WidgetState>,
+    }
+
+    impl Widget {
+        /// Create and show the widget on this thread, which must run a
+        /// message loop.
+        pub fn create() -> Result<Self, String> {
+            let class_name = wide(CLASS_NAME);
+            let rect = place_in_corner(WIDGET_SIZE, &monitor_at(0, 0));
+
+            unsafe {
+                let instance = GetModuleHandleW(null());
+                let class = WNDCLASSW {
+                    style: 0,
+                    lpfnWndProc: Some(window_proc),
+                    cbClsExtra: 0,
+                    cbWndExtra: 0,
+                    hInstance: instance,
+                    hIcon: null_mut(),
+                    hCursor: LoadCursorW(null_mut(), IDC_HAND),
+                    hbrBackground: null_mut(),
+                    lpszMenuName: null(),
+                    lpszClassName: class_name.as_ptr(),
+                };
+                RegisterClassW(&class);
+
+                let hwnd = CreateWindowExW(
+                    WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE | WS_EX_LAYERED,
+                    class_name.as_ptr(),
+                    null(),
+                    WS_POPUP,
+                    rect.left,
+                    rect.top,
+                    rect.width(),
+                    rect.height(),
+                    null_mut(),
+                    null_mut(),
+                    instance,
+                    null_mut(),
+                );
+                if hwnd.is_null() {
+                    return Err("could not create widget window".to_string());
+                }
+
+                SetLayeredWindowAttributes(hwnd, 0, ALPHA, LWA_ALPHA);
+                ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+                Ok(Self { hwnd, shown: None })
+            }
+        }
+
+        /// Show `state`, repainting only if it changed.
+        pub fn update(&mut self, state: WidgetState) {
+            if self.shown == Some(state) {
+                return;
+            }
+            self.shown = Some(state);
+
+            ACTIVE.store(state.status == WidgetStatus::Active, Ordering::Relaxed);
+            let label = wide(&state.label());
+            unsafe {
+                SetWindowTextW(self.hwnd, label.as_ptr());
+                InvalidateRect(self.hwnd, null(), 1);
+            }
+        }
+
+        /// Whether `message` is a left-click on the widget.
+        pub fn is_click(&self, message: &MSG) -> bool {
+            message.hwnd == self.hwnd && message.message == WM_LBUTTONUP
+        }
+    }
+
+    impl Drop for Widget {
+        fn drop(&mut self) {
+            unsafe {
+                DestroyWindow(self.hwnd);
+            }
+        }
+    }
+
+    unsafe extern "system" fn window_proc(
+        hwnd: HWND,
+        message: UINT,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        match message {
+            // Clicks must leave the focus in the app being typed into
+            WM_MOUSEACTIVATE => MA_NOACTIVATE as LRESULT,
+            WM_PAINT => {
+                paint(hwnd);
+                0
+            }
+            _ => DefWindowProcW(hwnd, message, wparam, lparam),
+        }
+    }
+
+    unsafe fn paint(hwnd: HWND) {
+        let mut paint: PAINTSTRUCT = std::mem::zeroed();
+        let hdc = BeginPaint(hwnd, &mut paint);
+        let mut rect: RECT = std::mem::zeroed();
+        GetClientRect(hwnd, &mut rect);
+
+        let (r, g, b) = if ACTIVE.load(Ordering::Relaxed) {
+            ACTIVE_COLOR
+        } else {
+            PAUSED_COLOR
+        };
+        let brush = CreateSolidBrush(RGB(r, g, b));
+        FillRect(hdc, &rect, brush);
+        DeleteObject(brush as _);
+
+        // The window is already sized for the monitor's DPI
+        let face = wide("Segoe UI");
+        let font = CreateFontW(
+            -(rect.bottom - rect.top) / 2,
+            0,
+            0,
+            0,
+            FW_SEMIBOLD,
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET,
+            OUT_DEFAULT_PRECIS,
+            CLIP_DEFAULT_PRECIS,
+            CLEARTYPE_QUALITY,
+            DEFAULT_PITCH | FF_SWISS,
+            face.as_ptr(),
+        );
+        let previous_font = SelectObject(hdc, font as _);
+        SetBkMode(hdc, TRANSPARENT as i32);
+        SetTextColor(hdc, RGB(255, 255, 255));
+
+        let mut text = [0u16; 64];
+        let len = GetWindowTextW(hwnd, text.as_mut_ptr(), text.len() as i32);
+        DrawTextW(
+            hdc,
+            text.as_ptr(),
+            len,
+            &mut rect,
+            DT_CENTER | DT_VCENTER | DT_SINGLELINE | DT_END_ELLIPSIS,
+        );
+
+        SelectObject(hdc, previous_font);
+        DeleteObject(font as _);
+        EndPaint(hwnd, &paint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label() {
+        let state = |status, corrections_today| WidgetState {
+            status,
+            corrections_today,
+        };
+        assert_eq!(state(WidgetStatus::Active, 1).label(), "1 correction today");
+        assert_eq!(
+            state(WidgetStatus::Active, 12).label(),
+            "12 corrections today"
+        );
+        assert_eq!(state(WidgetStatus::Paused, 12).label(), "Paused · 12 today");
+        assert_eq!(state(WidgetStatus::Off, 0).label(), "Off · 0 today");
+    }
+}
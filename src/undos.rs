@@ -0,0 +1,135 @@
+//! Corrections the user keeps undoing.
+//!
+//! Each undo of a correction is counted per pair (typed word, correction).
+//! Undoing the same correction again and again means the typed word is
+//! right, so after a few undos (`learn_after_undos`) the dictionary adds it
+//! to the personal dictionary and it's never corrected again.
+//!
+//! # File Format
+//!
+//! Counts are stored in `%APPDATA%/Autocorrect/undone_corrections.txt`:
+//! ```text
+//! # original correction undos
+//! notin nothing 1
+//! ```
+
+use ahash::AHashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+/// Persistent undo counts per correction.
+pub struct UndoCounts {
+    counts: AHashMap<(String, String), u32>,
+    path: PathBuf,
+    /// Counts changed since the last flush.
+    unsaved: bool,
+}
+
+impl UndoCounts {
+    /// Create an empty store backed by the default file location.
+    pub fn new() -> Self {
+        Self::with_path(crate::paths::data_file(
+            crate::paths::UNDONE_CORRECTIONS_FILE,
+        ))
+    }
+
+    /// Create an empty store backed by a specific file.
+    pub fn with_path(path: PathBuf) -> Self {
+        Self {
+            counts: AHashMap::new(),
+            path,
+            unsaved: false,
+        }
+    }
+
+    /// Load counts from disk. A missing file is not an error.
+    ///
+    /// # Errors
+    /// Returns an error if the file exists but cannot be read.
+    pub fn load(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+
+        let reader = BufReader::new(File::open(&self.path)?);
+        for line in reader.lines() {
+            let line = line?;
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if let [original, correction, count] = parts[..] {
+                if let Ok(count) = count.parse() {
+                    self.counts
+                        .insert((original.to_string(), correction.to_string()), count);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Count an undo of `original` corrected to `correction`, and return how
+    /// many times it has been undone. Nothing is counted (0) while a
+    /// sensitive app is in the foreground.
+    ///
+    /// Undo runs in the keyboard hook, so counts are written by `flush`,
+    /// off it.
+    pub fn record(&mut self, original: &str, correction: &str) -> u32 {
+        if !crate::privacy::recording_allowed() {
+            return 0;
+        }
+
+        let key = (original.to_lowercase(), correction.to_lowercase());
+        let count = self.counts.entry(key).or_insert(0);
+        *count += 1;
+        self.unsaved = true;
+        *count
+    }
+
+    /// Drop the counts for `original`, once it's been learned.
+    pub fn forget(&mut self, original: &str) {
+        let original = original.to_lowercase();
+        self.counts.retain(|(word, _), _| *word != original);
+        self.unsaved = true;
+    }
+
+    /// Write the counts to disk, if they changed since the last flush.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.unsaved {
+            return Ok(());
+        }
+
+        let mut contents = Vec::new();
+        writeln!(contents, "# original correction undos")?;
+        for ((original, correction), count) in &self.counts {
+            writeln!(contents, "{} {} {}", original, correction, count)?;
+        }
+        crate::paths::write_atomically(&self.path, &contents)?;
+        self.unsaved = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_persist() {
+        let path = std::env::temp_dir().join(format!("autocorrect-undos-{}", std::process::id()));
+        let mut counts = UndoCounts::with_path(path.clone());
+        assert_eq!(counts.record("notin", "nothing"), 1);
+        assert_eq!(counts.record("Notin", "Nothing"), 2);
+        assert_eq!(counts.record("notin", "noting"), 1);
+        counts.flush().unwrap();
+
+        let mut reloaded = UndoCounts::with_path(path.clone());
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.record("notin", "nothing"), 3);
+
+        reloaded.forget("NOTIN");
+        assert_eq!(reloaded.record("notin", "noting"), 1);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+//! Detecting a keyboard hook that was removed behind our back.
+//!
+//! Windows silently removes a low-level hook whose callback is too slow too
+//! often, and anti-cheat or policy software may remove it on purpose. Nothing
+//! tells the application: corrections just stop. The watchdog notices by
+//! comparing the hook's key event count with the user's input:
+//!
+//! 1. If the user was active since the last check but the hook saw nothing,
+//!    it sends a probe (a key release of an unassigned key, which apps ignore)
+//! 2. If the hook doesn't see the probe either, the hook is reinstalled
+//! 3. After [`MAX_REINSTALLS`] reinstalls that didn't bring events back, the
+//!    hook is considered lost: the tray shows it and the user is told once.
+//!    Reinstalling continues, and the warning clears if the hook recovers.
+//!
+//! Probes are only sent after user input, so an idle machine still locks and
+//! sleeps as usual.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Reinstalls without a key event before the hook is reported as lost.
+pub const MAX_REINSTALLS: u32 = 3;
+
+/// Unassigned virtual key used for probes.
+#[cfg(windows)]
+const PROBE_VK: u16 = 0xE8;
+
+/// Whether the hook is currently considered lost.
+static HOOK_LOST: AtomicBool = AtomicBool::new(false);
+
+/// Whether the keyboard hook is lost and corrections are inactive.
+pub fn hook_lost() -> bool {
+    HOOK_LOST.load(Ordering::Relaxed)
+}
+
+/// What the caller should do after a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogAction {
+    None,
+    /// Send a probe key to see whether the hook still receives input.
+    Probe,
+    /// Reinstall the hook.
+    Reinstall,
+    /// Reinstall the hook, and tell the user it's lost.
+    Lost,
+    /// Key events arrived again after the hook was reported lost.
+    Recovered,
+}
+
+/// Hook health, checked periodically from the message loop.
+#[derive(Debug)]
+pub struct HookWatchdog {
+    last_key_events: u64,
+    /// Time of the last input seen (`GetLastInputInfo` ticks).
+    last_input: u32,
+    probe_pending: bool,
+    /// Reinstalls since the hook last saw a key event.
+    reinstalls: u32,
+}
+
+impl HookWatchdog {
+    pub fn new(key_events: u64, last_input: u32) -> Self {
+        Self {
+            last_key_events: key_events,
+            last_input,
+            probe_pending: false,
+            reinstalls: 0,
+        }
+    }
+
+    /// Check the hook given its key event count and the time of the user's
+    /// last input.
+    pub fn tick(&mut self, key_events: u64, last_input: u32) -> WatchdogAction {
+        let user_active = last_input != self.last_input;
+        self.last_input = last_input;
+
+        if key_events != self.last_key_events {
+            self.last_key_events = key_events;
+            self.probe_pending = false;
+            self.reinstalls = 0;
+            if HOOK_LOST.swap(false, Ordering::Relaxed) {
+                return WatchdogAction::Recovered;
+            }
+            return WatchdogAction::None;
+        }
+
+        if self.probe_pending {
+            self.probe_pending = false;
+            self.reinstalls += 1;
+            if self.reinstalls > MAX_REINSTALLS && !HOOK_LOST.swap(true, Ordering::Relaxed) {
+                return WatchdogAction::Lost;
+            }
+            return WatchdogAction::Reinstall;
+        }
+
+        if user_active {
+            self.probe_pending = true;
+            return WatchdogAction::Probe;
+        }
+
+        WatchdogAction::None
+    }
+}
+
+/// Time of the user's last input, in `GetTickCount` milliseconds.
+#[cfg(windows)]
+pub fn last_input_time() -> u32 {
+    use winapi::um::winuser::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    unsafe { GetLastInputInfo(&mut info) };
+    info.dwTime
+}
+
+/// Send a key release of an unassigned key, tagged as ours, for the hook to see.
+#[cfg(windows)]
+pub fn send_probe() {
+    use winapi::um::winuser::{SendInput, INPUT, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP};
+
+    unsafe {
+        let mut input = INPUT {
+            type_: INPUT_KEYBOARD,
+            u: std::mem::zeroed(),
+        };
+        *input.u.ki_mut() = KEYBDINPUT {
+            wVk: PROBE_VK,
+            wScan: 0,
+            dwFlags: KEYEVENTF_KEYUP,
+            time: 0,
+            dwExtraInfo: crate::corrector::INJECTION_MARKER,
+        };
+        SendInput(1, &mut input, std::mem::size_of::<INPUT>() as i32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lost_and_recovered() {
+        let mut watchdog = HookWatchdog::new(10, 100);
+
+        // Nothing typed: nothing to check
+        assert_eq!(watchdog.tick(10, 100), WatchdogAction::None);
+        // Typing seen by the hook
+        assert_eq!(watchdog.tick(15, 200), WatchdogAction::None);
+
+        // Input the hook missed: probe, then reinstall when the probe is missed too
+        let mut input = 200;
+        for _ in 0..MAX_REINSTALLS {
+            input += 100;
+            assert_eq!(watchdog.tick(15, input), WatchdogAction::Probe);
+            assert_eq!(watchdog.tick(15, input), WatchdogAction::Reinstall);
+            assert_eq!(watchdog.tick(15, input), WatchdogAction::None);
+        }
+        assert_eq!(watchdog.tick(15, input + 100), WatchdogAction::Probe);
+        assert_eq!(watchdog.tick(15, input + 100), WatchdogAction::Lost);
+        assert!(hook_lost());
+
+        // Reported once; reinstalls continue quietly
+        assert_eq!(watchdog.tick(15, input + 200), WatchdogAction::Probe);
+        assert_eq!(watchdog.tick(15, input + 200), WatchdogAction::Reinstall);
+
+        assert_eq!(watchdog.tick(16, input + 300), WatchdogAction::Recovered);
+        assert!(!hook_lost());
+    }
+}